@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- カレンダーアプリに登録する iCalendar フィード用のトークン
+-- ログイン用のアクセストークンとは別物で、ユーザーごとに1つだけ発行する
+CREATE TABLE IF NOT EXISTS calendar_feed_tokens (
+  user_id UUID PRIMARY KEY,
+  token_hash VARCHAR(255) NOT NULL UNIQUE,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
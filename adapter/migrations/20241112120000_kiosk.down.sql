@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS kiosk_devices;
+ALTER TABLE users DROP COLUMN IF EXISTS badge_code;
+ALTER TABLE books DROP COLUMN IF EXISTS barcode;
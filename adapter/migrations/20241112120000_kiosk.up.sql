@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- 蔵書1冊ごとに貼り付ける図書館独自のバーコード
+ALTER TABLE books ADD COLUMN IF NOT EXISTS barcode VARCHAR(255) UNIQUE;
+
+-- 利用者カードのコード
+ALTER TABLE users ADD COLUMN IF NOT EXISTS badge_code VARCHAR(255) UNIQUE;
+
+-- 貸出・返却用の端末。ユーザーとは別の認証情報を持つ
+CREATE TABLE IF NOT EXISTS kiosk_devices (
+  kiosk_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name VARCHAR(255) NOT NULL,
+  secret_hash VARCHAR(255) NOT NULL UNIQUE,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+  last_used_at TIMESTAMP(3) WITH TIME ZONE
+);
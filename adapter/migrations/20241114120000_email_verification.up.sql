@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- メールアドレスの確認日時。セルフサインアップしたユーザーは確認が済むまで NULL とする
+-- 既存のユーザーと管理者が作成したユーザーは確認済みとして扱う
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMP(3) WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP(3);
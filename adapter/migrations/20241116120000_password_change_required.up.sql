@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- 管理者がパスワードをリセットしたユーザーは、次回ログイン時にパスワードの再設定を求める
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS password_change_required BOOLEAN NOT NULL DEFAULT false;
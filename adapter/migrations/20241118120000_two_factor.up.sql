@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- TOTP による 2 段階認証の共有鍵。enabled_at が NULL の間は登録の途中で、コードの確認後に有効になる
+-- last_used_step には最後に使ったコードの時間ステップを保存し、同じコードの再利用を防ぐ
+CREATE TABLE IF NOT EXISTS user_totp (
+  user_id UUID PRIMARY KEY,
+  secret BYTEA NOT NULL,
+  last_used_step BIGINT NOT NULL DEFAULT 0,
+  enabled_at TIMESTAMP(3) WITH TIME ZONE,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+
+-- TOTP を使えない場合の一度限りのリカバリーコード。ハッシュ値のみを保存し、使ったら削除する
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+  user_id UUID NOT NULL,
+  code_hash VARCHAR(255) NOT NULL,
+
+  PRIMARY KEY (user_id, code_hash),
+  FOREIGN KEY (user_id) REFERENCES user_totp(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
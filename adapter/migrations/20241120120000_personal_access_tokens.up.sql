@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- スクリプトなどから API を利用するためのパーソナルアクセストークン
+-- トークンの値はハッシュ値のみを保存する。expires_at が NULL の場合は無期限とする
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+  token_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL,
+  name VARCHAR(255) NOT NULL,
+  token_hash VARCHAR(255) NOT NULL UNIQUE,
+  scopes VARCHAR(64)[] NOT NULL,
+  expires_at TIMESTAMP(3) WITH TIME ZONE,
+  last_used_at TIMESTAMP(3) WITH TIME ZONE,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
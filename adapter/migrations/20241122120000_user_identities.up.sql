@@ -0,0 +1,13 @@
+-- Add up migration script here
+-- シングルサインオンで使う、IdP 上のユーザー（issuer と subject の組）とユーザーの紐付け
+CREATE TABLE IF NOT EXISTS user_identities (
+  issuer VARCHAR(255) NOT NULL,
+  subject VARCHAR(255) NOT NULL,
+  user_id UUID NOT NULL,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  PRIMARY KEY (issuer, subject),
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
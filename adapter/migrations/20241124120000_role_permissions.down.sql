@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS role_permissions;
+-- users.role_id は ON DELETE CASCADE のため、ロールを削除する前に一般のユーザー権限に移しておく
+UPDATE users
+  SET role_id = (SELECT role_id FROM roles WHERE name = 'User')
+  WHERE role_id = (SELECT role_id FROM roles WHERE name = 'Librarian');
+DELETE FROM roles WHERE name = 'Librarian';
+ALTER TABLE roles DROP COLUMN IF EXISTS built_in;
@@ -0,0 +1,46 @@
+-- Add up migration script here
+-- 組み込みのロールは削除できないようにする
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS built_in BOOLEAN NOT NULL DEFAULT FALSE;
+
+INSERT INTO roles (name, built_in)
+VALUES ('Admin', TRUE), ('Librarian', TRUE), ('User', TRUE)
+ON CONFLICT (name) DO UPDATE SET built_in = TRUE;
+
+-- ロールに割り当てた権限
+CREATE TABLE IF NOT EXISTS role_permissions (
+  role_id UUID NOT NULL,
+  permission VARCHAR(64) NOT NULL,
+
+  PRIMARY KEY (role_id, permission),
+  FOREIGN KEY (role_id) REFERENCES roles(role_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+
+-- Admin はすべての権限を持つ
+INSERT INTO role_permissions (role_id, permission)
+SELECT role_id, p.permission FROM roles
+CROSS JOIN (
+  VALUES
+    ('book:update:any'),
+    ('checkout:return:any'),
+    ('kiosk:manage'),
+    ('statistics:read'),
+    ('user:manage'),
+    ('role:manage')
+) AS p(permission)
+WHERE name = 'Admin'
+ON CONFLICT DO NOTHING;
+
+-- Librarian は蔵書と貸出を管理できるが、ユーザーとロールは管理できない
+INSERT INTO role_permissions (role_id, permission)
+SELECT role_id, p.permission FROM roles
+CROSS JOIN (
+  VALUES
+    ('book:update:any'),
+    ('checkout:return:any'),
+    ('kiosk:manage'),
+    ('statistics:read')
+) AS p(permission)
+WHERE name = 'Librarian'
+ON CONFLICT DO NOTHING;
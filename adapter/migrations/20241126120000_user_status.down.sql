@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS user_status_changes;
+ALTER TABLE users
+  DROP COLUMN IF EXISTS status_changed_at,
+  DROP COLUMN IF EXISTS status;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+-- ユーザーの状態。ユーザーは削除せずに無効化し、蔵書や貸出の記録を残す
+-- 既存のユーザーは利用中として扱う
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS status VARCHAR(32) NOT NULL DEFAULT 'active'
+    CHECK (status IN ('invited', 'active', 'suspended', 'deactivated')),
+  ADD COLUMN IF NOT EXISTS status_changed_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3);
+
+-- 状態の変更履歴。変更したユーザーが削除された場合も履歴は残す
+CREATE TABLE IF NOT EXISTS user_status_changes (
+  user_status_change_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL,
+  from_status VARCHAR(32) NOT NULL,
+  to_status VARCHAR(32) NOT NULL,
+  changed_by UUID,
+  changed_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE,
+  FOREIGN KEY (changed_by) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS user_status_changes_user_id_idx
+  ON user_status_changes(user_id, changed_at);
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- 管理者による招待。招待したユーザーは status が 'invited' の状態で users に作成する
+-- トークンの値はハッシュ値のみを保存する。招待を受け入れると行を削除する
+CREATE TABLE IF NOT EXISTS user_invitations (
+  user_id UUID PRIMARY KEY,
+  token_hash VARCHAR(255) NOT NULL UNIQUE,
+  invited_by UUID,
+  expires_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE,
+  FOREIGN KEY (invited_by) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE SET NULL
+);
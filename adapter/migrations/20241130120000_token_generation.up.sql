@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- ユーザーごとのアクセストークン（JWT）の世代。ユーザー単位でトークンを無効にするたびに 1 増やす
+-- 発行時の世代をクレームに含め、無効にした世代より前のトークンを拒否する
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS token_generation BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- 利用統計の「よく借りている利用者」に名前と貸出数を載せることに同意したかどうか
+-- 同意したユーザーのみを集計の対象とするため、既存のユーザーは同意していないものとして扱う
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS share_loan_statistics BOOLEAN NOT NULL DEFAULT false;
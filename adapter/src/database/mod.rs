@@ -5,6 +5,10 @@ use shared::{
 use sqlx::{postgres::PgConnectOptions, PgPool};
 
 pub mod model;
+pub mod transaction;
+
+// トランザクションのリトライ回数の上限のデフォルト値
+const DEFAULT_MAX_TRANSACTION_RETRIES: u32 = 3;
 
 fn make_pg_connect_options(cfg: &DatabaseConfig) -> PgConnectOptions {
     PgConnectOptions::new()
@@ -16,22 +20,46 @@ fn make_pg_connect_options(cfg: &DatabaseConfig) -> PgConnectOptions {
 }
 
 #[derive(Clone)]
-pub struct ConnectionPool(PgPool);
+pub struct ConnectionPool {
+    pool: PgPool,
+    max_transaction_retries: u32,
+}
 
 impl ConnectionPool {
     pub fn new(pool: PgPool) -> Self {
-        Self(pool)
+        Self {
+            pool,
+            max_transaction_retries: DEFAULT_MAX_TRANSACTION_RETRIES,
+        }
+    }
+
+    pub fn with_max_transaction_retries(mut self, max_transaction_retries: u32) -> Self {
+        self.max_transaction_retries = max_transaction_retries;
+        self
     }
 
     pub fn inner_ref(&self) -> &PgPool {
-        &self.0
+        &self.pool
     }
 
     pub async fn begin(&self) -> AppResult<sqlx::Transaction<'_, sqlx::Postgres>> {
-        self.0.begin().await.map_err(AppError::TransactionError)
+        self.pool.begin().await.map_err(AppError::TransactionError)
+    }
+}
+
+// 一意制約に違反した場合は競合として扱い、それ以外は従来どおりのエラーにする
+pub(crate) fn conflict_on_unique_violation(
+    message: &'static str,
+) -> impl FnOnce(sqlx::Error) -> AppError {
+    move |e| match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => {
+            AppError::EntityConflict(message.into())
+        }
+        _ => AppError::SpecificOperationError(e),
     }
 }
 
 pub fn connect_database_with(cfg: &DatabaseConfig) -> ConnectionPool {
-    ConnectionPool(PgPool::connect_lazy_with(make_pg_connect_options(cfg)))
+    ConnectionPool::new(PgPool::connect_lazy_with(make_pg_connect_options(cfg)))
+        .with_max_transaction_retries(cfg.max_transaction_retries)
 }
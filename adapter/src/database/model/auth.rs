@@ -1,25 +1,37 @@
+use serde::{Deserialize, Serialize};
 use shared::error::{AppError, AppResult};
+use sqlx::types::chrono::{DateTime, Utc};
 use std::str::FromStr;
 
 use kernel::model::{
-    auth::{event::CreateToken, AccessToken},
-    id::UserId,
+    auth::{event::CreateToken, AccessToken, RefreshToken, Session},
+    id::{SessionId, UserId},
 };
 
-use crate::redis::model::{RedisKey, RedisValue};
+use crate::{
+    redis::model::{RedisKey, RedisSetKey, RedisValue},
+    token::hash_token,
+};
 
 pub struct UserItem {
     pub user_id: UserId,
     pub password_hash: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
 }
 
 pub struct AuthorizationKey(String);
-pub struct AuthorizedUserId(UserId);
+pub struct AuthorizedSession {
+    pub user_id: UserId,
+    pub session_id: SessionId,
+}
 
-pub fn from(event: CreateToken) -> (AuthorizationKey, AuthorizedUserId) {
+pub fn from(event: CreateToken) -> (AuthorizationKey, AuthorizedSession) {
     (
         AuthorizationKey(event.access_token),
-        AuthorizedUserId(event.user_id),
+        AuthorizedSession {
+            user_id: event.user_id,
+            session_id: event.session_id,
+        },
     )
 }
 
@@ -41,32 +53,251 @@ impl From<&AccessToken> for AuthorizationKey {
     }
 }
 
+impl AuthorizationKey {
+    pub fn token(&self) -> &str {
+        &self.0
+    }
+}
+
 impl RedisKey for AuthorizationKey {
-    type Value = AuthorizedUserId;
+    type Value = AuthorizedSession;
 
     fn inner(&self) -> String {
         self.0.clone()
     }
 }
 
-impl RedisValue for AuthorizedUserId {
+impl RedisValue for AuthorizedSession {
     fn inner(&self) -> String {
-        self.0.to_string()
+        format!("{}:{}", self.user_id, self.session_id)
     }
 }
 
-impl TryFrom<String> for AuthorizedUserId {
+impl TryFrom<String> for AuthorizedSession {
     type Error = AppError;
 
     fn try_from(s: String) -> AppResult<Self> {
-        Ok(Self(UserId::from_str(&s).map_err(|e| {
-            AppError::ConversionEntityError(e.to_string())
-        })?))
+        let (user_id, session_id) = s.split_once(':').ok_or_else(|| {
+            AppError::ConversionEntityError(format!("invalid authorized session: {}", s))
+        })?;
+        Ok(Self {
+            user_id: UserId::from_str(user_id)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            session_id: SessionId::from_str(session_id)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+        })
+    }
+}
+
+// セッションごとに発行済みのアクセストークンを管理する集合のキー
+// セッションを終了する際に、そのセッションのアクセストークンをまとめて無効にする
+pub struct SessionTokensKey(SessionId);
+
+impl From<SessionId> for SessionTokensKey {
+    fn from(session_id: SessionId) -> Self {
+        Self(session_id)
+    }
+}
+
+impl RedisSetKey for SessionTokensKey {
+    fn inner(&self) -> String {
+        format!("session-tokens:{}", self.0)
+    }
+}
+
+// セッションの情報。セッションの一覧の表示に使う
+pub struct SessionKey(SessionId);
+
+#[derive(Serialize, Deserialize)]
+pub struct SessionValue {
+    pub user_id: UserId,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl From<SessionId> for SessionKey {
+    fn from(session_id: SessionId) -> Self {
+        Self(session_id)
+    }
+}
+
+impl RedisKey for SessionKey {
+    type Value = SessionValue;
+
+    fn inner(&self) -> String {
+        format!("session:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionValue {
+    fn inner(&self) -> String {
+        // SessionValue はシリアライズに失敗しうる型を含まない
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for SessionValue {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl SessionValue {
+    pub fn into_session(self, id: SessionId) -> Session {
+        let SessionValue {
+            user_id,
+            created_at,
+            last_used_at,
+            ip_address,
+            user_agent,
+        } = self;
+        Session {
+            id,
+            user_id,
+            created_at,
+            last_used_at,
+            ip_address,
+            user_agent,
+        }
+    }
+}
+
+// ユーザーごとのセッションの集合のキー
+pub struct UserSessionsKey(UserId);
+
+impl From<UserId> for UserSessionsKey {
+    fn from(user_id: UserId) -> Self {
+        Self(user_id)
+    }
+}
+
+impl RedisSetKey for UserSessionsKey {
+    fn inner(&self) -> String {
+        format!("user-sessions:{}", self.0)
+    }
+}
+
+// リフレッシュトークンのハッシュ値をキーとし、トークンが属するセッションとユーザーを保存する
+// 置き換え済みのトークンも有効期限までは残しておき、再利用の検知に使う
+pub struct RefreshTokenKey(String);
+
+pub struct RefreshTokenEntry {
+    pub session_id: SessionId,
+    pub user_id: UserId,
+}
+
+impl From<&RefreshToken> for RefreshTokenKey {
+    fn from(token: &RefreshToken) -> Self {
+        Self(hash_token(&token.0))
     }
 }
 
-impl AuthorizedUserId {
-    pub fn into_inner(self) -> UserId {
-        self.0
+impl RedisKey for RefreshTokenKey {
+    type Value = RefreshTokenEntry;
+
+    fn inner(&self) -> String {
+        format!("refresh-token:{}", self.0)
+    }
+}
+
+impl RedisValue for RefreshTokenEntry {
+    fn inner(&self) -> String {
+        format!("{}:{}", self.session_id, self.user_id)
+    }
+}
+
+impl TryFrom<String> for RefreshTokenEntry {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        let (session_id, user_id) = s.split_once(':').ok_or_else(|| {
+            AppError::ConversionEntityError(format!("invalid refresh token entry: {}", s))
+        })?;
+        Ok(Self {
+            session_id: SessionId::from_str(session_id)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            user_id: UserId::from_str(user_id)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+        })
+    }
+}
+
+// セッション（トークンファミリー）ごとに、現在有効なリフレッシュトークンのハッシュ値を保存する
+// キーが存在しないファミリーは無効になっている
+pub struct RefreshTokenFamilyKey(SessionId);
+pub struct CurrentRefreshToken(String);
+
+impl From<SessionId> for RefreshTokenFamilyKey {
+    fn from(session_id: SessionId) -> Self {
+        Self(session_id)
+    }
+}
+
+impl RedisKey for RefreshTokenFamilyKey {
+    type Value = CurrentRefreshToken;
+
+    fn inner(&self) -> String {
+        format!("refresh-token-family:{}", self.0)
+    }
+}
+
+impl From<&RefreshTokenKey> for CurrentRefreshToken {
+    fn from(key: &RefreshTokenKey) -> Self {
+        Self(key.0.clone())
+    }
+}
+
+impl RedisValue for CurrentRefreshToken {
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl TryFrom<String> for CurrentRefreshToken {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s))
+    }
+}
+
+// 無効にした JWT の一覧（denylist）のキー
+// セッションの終了時はセッション単位で、パスワードの再設定時などはユーザー単位で無効にする
+// 値には、セッション単位では無効にした日時（UNIX 時間）を、ユーザー単位では無効にしたトークンの世代を保存する
+pub enum JwtDenylistKey {
+    Session(SessionId),
+    User(UserId),
+}
+
+pub struct Revocation(pub i64);
+
+impl RedisKey for JwtDenylistKey {
+    type Value = Revocation;
+
+    fn inner(&self) -> String {
+        match self {
+            Self::Session(session_id) => format!("jwt-denylist:session:{}", session_id),
+            Self::User(user_id) => format!("jwt-denylist:user:{}", user_id),
+        }
+    }
+}
+
+impl RedisValue for Revocation {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for Revocation {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        s.parse()
+            .map(Self)
+            .map_err(|e: std::num::ParseIntError| AppError::ConversionEntityError(e.to_string()))
     }
 }
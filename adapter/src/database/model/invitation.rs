@@ -0,0 +1,38 @@
+use std::str::FromStr;
+
+use kernel::model::{id::UserId, invitation::Invitation, role::Role};
+use shared::error::{AppError, AppResult};
+use sqlx::types::chrono::{DateTime, Utc};
+
+pub struct InvitationRow {
+    pub user_id: UserId,
+    pub email: String,
+    pub role_name: String,
+    pub invited_by: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<InvitationRow> for Invitation {
+    type Error = AppError;
+
+    fn try_from(value: InvitationRow) -> AppResult<Self> {
+        let InvitationRow {
+            user_id,
+            email,
+            role_name,
+            invited_by,
+            expires_at,
+            created_at,
+        } = value;
+        Ok(Self {
+            user_id,
+            email,
+            role: Role::from_str(&role_name)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            invited_by,
+            expires_at,
+            created_at,
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use kernel::model::{
+    id::{BookId, CheckoutId, KioskId, UserId},
+    kiosk::KioskDevice,
+};
+use sqlx::types::chrono::{DateTime, Utc};
+
+pub struct KioskDeviceRow {
+    pub kiosk_id: KioskId,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<KioskDeviceRow> for KioskDevice {
+    fn from(value: KioskDeviceRow) -> Self {
+        let KioskDeviceRow {
+            kiosk_id,
+            name,
+            created_at,
+            last_used_at,
+        } = value;
+        Self {
+            id: kiosk_id,
+            name,
+            created_at,
+            last_used_at,
+        }
+    }
+}
+
+// 読み取ったコードに一致する蔵書と、その貸出状況
+pub struct KioskBookRow {
+    pub book_id: BookId,
+    pub title: String,
+    pub checkout_id: Option<CheckoutId>,
+    pub user_id: Option<UserId>,
+}
@@ -0,0 +1,66 @@
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    redis::model::{RedisCounterKey, RedisKey, RedisValue},
+    token::hash_token,
+};
+
+// ログインの失敗回数を数える対象。メールアドレスはハッシュ値にして保存する
+pub enum LoginThrottleTarget {
+    Email(String),
+    Ip(String),
+}
+
+impl LoginThrottleTarget {
+    pub fn email(email: &str) -> Self {
+        Self::Email(hash_token(&email.trim().to_lowercase()))
+    }
+
+    pub fn ip(ip_address: &str) -> Self {
+        Self::Ip(ip_address.to_string())
+    }
+
+    fn inner(&self) -> String {
+        match self {
+            Self::Email(hash) => format!("email:{}", hash),
+            Self::Ip(ip_address) => format!("ip:{}", ip_address),
+        }
+    }
+}
+
+// 続けて失敗した回数
+pub struct LoginFailuresKey<'a>(pub &'a LoginThrottleTarget);
+
+impl RedisCounterKey for LoginFailuresKey<'_> {
+    fn inner(&self) -> String {
+        format!("login-failures:{}", self.0.inner())
+    }
+}
+
+// 次にログインを試行できる日時（UNIX 時間）。キーが存在しない場合はすぐに試行できる
+pub struct LoginBlockKey<'a>(pub &'a LoginThrottleTarget);
+pub struct BlockedUntil(pub i64);
+
+impl RedisKey for LoginBlockKey<'_> {
+    type Value = BlockedUntil;
+
+    fn inner(&self) -> String {
+        format!("login-block:{}", self.0.inner())
+    }
+}
+
+impl RedisValue for BlockedUntil {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for BlockedUntil {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        s.parse()
+            .map(Self)
+            .map_err(|e: std::num::ParseIntError| AppError::ConversionEntityError(e.to_string()))
+    }
+}
@@ -1,4 +1,14 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod invitation;
+pub mod kiosk;
+pub mod login_throttle;
+pub mod oidc;
+pub mod password_reset;
+pub mod personal_access_token;
+pub mod role;
+pub mod signup;
+pub mod statistics;
+pub mod two_factor;
 pub mod user;
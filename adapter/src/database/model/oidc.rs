@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    redis::model::{RedisKey, RedisValue},
+    token::hash_token,
+};
+
+// 認可リクエストの state のハッシュ値をキーとし、認可コードの交換に必要な値を保存する
+pub struct OidcStateKey(String);
+
+#[derive(Serialize, Deserialize)]
+pub struct PendingOidcLogin {
+    pub code_verifier: String,
+    pub nonce: String,
+    // ログインを開始したブラウザに Cookie として保存させた値のハッシュ値
+    #[serde(default)]
+    pub browser_binding: String,
+}
+
+impl From<&str> for OidcStateKey {
+    fn from(state: &str) -> Self {
+        Self(hash_token(state))
+    }
+}
+
+impl RedisKey for OidcStateKey {
+    type Value = PendingOidcLogin;
+
+    fn inner(&self) -> String {
+        format!("oidc-state:{}", self.0)
+    }
+}
+
+impl RedisValue for PendingOidcLogin {
+    fn inner(&self) -> String {
+        // PendingOidcLogin はシリアライズに失敗しうる型を含まない
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for PendingOidcLogin {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
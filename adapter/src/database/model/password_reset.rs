@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use kernel::model::{id::UserId, password_reset::PasswordResetToken};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    redis::model::{RedisKey, RedisSetKey, RedisValue},
+    token::hash_token,
+};
+
+// Redis にはトークンそのものではなくハッシュ値をキーとして保存する
+pub struct PasswordResetKey(String);
+pub struct ResettingUserId(pub UserId);
+
+impl From<&PasswordResetToken> for PasswordResetKey {
+    fn from(token: &PasswordResetToken) -> Self {
+        Self(hash_token(&token.0))
+    }
+}
+
+impl PasswordResetKey {
+    pub fn from_hash(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.0
+    }
+}
+
+impl RedisKey for PasswordResetKey {
+    type Value = ResettingUserId;
+
+    fn inner(&self) -> String {
+        format!("password-reset:{}", self.0)
+    }
+}
+
+impl RedisValue for ResettingUserId {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for ResettingUserId {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(UserId::from_str(&s).map_err(|e| {
+            AppError::ConversionEntityError(e.to_string())
+        })?))
+    }
+}
+
+// ユーザーごとに、発行済みで未使用のトークンのハッシュ値を管理する集合のキー
+// パスワードを再設定した際に、他のトークンをまとめて無効にする
+pub struct UserPasswordResetsKey(UserId);
+
+impl From<UserId> for UserPasswordResetsKey {
+    fn from(user_id: UserId) -> Self {
+        Self(user_id)
+    }
+}
+
+impl RedisSetKey for UserPasswordResetsKey {
+    fn inner(&self) -> String {
+        format!("user-password-resets:{}", self.0)
+    }
+}
@@ -0,0 +1,48 @@
+use std::str::FromStr;
+
+use kernel::model::{
+    id::{PersonalAccessTokenId, UserId},
+    personal_access_token::{PersonalAccessToken, Scope},
+};
+use shared::error::{AppError, AppResult};
+use sqlx::types::chrono::{DateTime, Utc};
+
+pub struct PersonalAccessTokenRow {
+    pub token_id: PersonalAccessTokenId,
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<PersonalAccessTokenRow> for PersonalAccessToken {
+    type Error = AppError;
+
+    fn try_from(value: PersonalAccessTokenRow) -> AppResult<Self> {
+        let PersonalAccessTokenRow {
+            token_id,
+            user_id,
+            name,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        } = value;
+        let scopes = scopes
+            .iter()
+            .map(|scope| Scope::from_str(scope))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        Ok(Self {
+            id: token_id,
+            user_id,
+            name,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use kernel::model::role::{Permission, Role, RolePermissions};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct RoleRow {
+    pub name: String,
+    pub built_in: bool,
+    pub permissions: Vec<String>,
+}
+
+impl TryFrom<RoleRow> for RolePermissions {
+    type Error = AppError;
+
+    fn try_from(value: RoleRow) -> AppResult<Self> {
+        let RoleRow {
+            name,
+            built_in,
+            permissions,
+        } = value;
+        Ok(Self {
+            role: Role::from_str(&name)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            built_in,
+            permissions: parse_permissions(&permissions)?,
+        })
+    }
+}
+
+pub fn parse_permissions(permissions: &[String]) -> AppResult<Vec<Permission>> {
+    permissions
+        .iter()
+        .map(|permission| Permission::from_str(permission))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+// リクエストごとにデータベースを参照しないよう、ロールの権限をキャッシュする
+pub struct RolePermissionsKey(String);
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedPermissions(pub Vec<String>);
+
+impl From<&Role> for RolePermissionsKey {
+    fn from(role: &Role) -> Self {
+        Self(role.as_ref().to_string())
+    }
+}
+
+impl RedisKey for RolePermissionsKey {
+    type Value = CachedPermissions;
+
+    fn inner(&self) -> String {
+        format!("role-permissions:{}", self.0)
+    }
+}
+
+impl RedisValue for CachedPermissions {
+    fn inner(&self) -> String {
+        // 文字列の配列はシリアライズに失敗しない
+        serde_json::to_string(&self.0).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for CachedPermissions {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s)
+            .map(Self)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
@@ -0,0 +1,82 @@
+use kernel::model::{id::UserId, signup::EmailVerificationToken};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    redis::model::{RedisKey, RedisValue},
+    token::hash_token,
+};
+
+// Redis にはトークンそのものではなくハッシュ値をキーとして保存する
+pub struct EmailVerificationKey(String);
+
+// 登録の申し込みの内容。確認が済むまではユーザーの名前とパスワードに反映しない
+#[derive(Serialize, Deserialize)]
+pub struct PendingRegistration {
+    pub user_id: UserId,
+    pub name: String,
+    pub password_hash: String,
+}
+
+// ユーザーごとに、最後に発行した確認用のトークンを覚えておく。
+// 登録し直した場合は、それより前に発行したトークンを使えなくする
+pub struct EmailVerificationAttemptKey(pub UserId);
+#[derive(PartialEq, Eq)]
+pub struct EmailVerificationAttempt(String);
+
+impl From<&EmailVerificationToken> for EmailVerificationKey {
+    fn from(token: &EmailVerificationToken) -> Self {
+        Self(format!("email-verification:{}", hash_token(&token.0)))
+    }
+}
+
+impl RedisKey for EmailVerificationKey {
+    type Value = PendingRegistration;
+
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl RedisValue for PendingRegistration {
+    fn inner(&self) -> String {
+        // PendingRegistration はシリアライズに失敗しうる型を含まない
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for PendingRegistration {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl From<&EmailVerificationToken> for EmailVerificationAttempt {
+    fn from(token: &EmailVerificationToken) -> Self {
+        Self(hash_token(&token.0))
+    }
+}
+
+impl RedisKey for EmailVerificationAttemptKey {
+    type Value = EmailVerificationAttempt;
+
+    fn inner(&self) -> String {
+        format!("email-verification-attempt:{}", self.0)
+    }
+}
+
+impl RedisValue for EmailVerificationAttempt {
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl TryFrom<String> for EmailVerificationAttempt {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s))
+    }
+}
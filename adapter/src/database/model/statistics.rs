@@ -0,0 +1,149 @@
+use kernel::model::{
+    id::{BookId, UserId},
+    statistics::{
+        BookLoanCount, BorrowerLoanCount, LibraryStatistics, MonthlyLoanCount, StatisticsBook,
+        StatisticsOptions,
+    },
+};
+use shared::error::{AppError, AppResult};
+use sqlx::types::chrono::{DateTime, Utc};
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct BookLoanCountRow {
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+    pub loan_count: i64,
+}
+
+impl From<BookLoanCountRow> for BookLoanCount {
+    fn from(value: BookLoanCountRow) -> Self {
+        let BookLoanCountRow {
+            book_id,
+            title,
+            author,
+            loan_count,
+        } = value;
+        Self {
+            book: StatisticsBook {
+                book_id,
+                title,
+                author,
+            },
+            loan_count,
+        }
+    }
+}
+
+pub struct BorrowerLoanCountRow {
+    pub user_id: UserId,
+    pub user_name: String,
+    pub loan_count: i64,
+}
+
+impl From<BorrowerLoanCountRow> for BorrowerLoanCount {
+    fn from(value: BorrowerLoanCountRow) -> Self {
+        let BorrowerLoanCountRow {
+            user_id,
+            user_name,
+            loan_count,
+        } = value;
+        Self {
+            user_id,
+            user_name,
+            loan_count,
+        }
+    }
+}
+
+pub struct MonthlyLoanCountRow {
+    pub month: DateTime<Utc>,
+    pub loan_count: i64,
+}
+
+impl From<MonthlyLoanCountRow> for MonthlyLoanCount {
+    fn from(value: MonthlyLoanCountRow) -> Self {
+        let MonthlyLoanCountRow { month, loan_count } = value;
+        Self { month, loan_count }
+    }
+}
+
+pub struct StatisticsBookRow {
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+}
+
+impl From<StatisticsBookRow> for StatisticsBook {
+    fn from(value: StatisticsBookRow) -> Self {
+        let StatisticsBookRow {
+            book_id,
+            title,
+            author,
+        } = value;
+        Self {
+            book_id,
+            title,
+            author,
+        }
+    }
+}
+
+// 集計結果のキャッシュは集計条件ごとに保持する
+pub struct StatisticsCacheKey(String);
+// シリアライズに失敗した場合にエラーを返せるよう、集計結果は JSON に変換した状態で保持する
+pub struct CachedStatistics(String);
+
+impl CachedStatistics {
+    pub fn new(statistics: &LibraryStatistics) -> AppResult<Self> {
+        serde_json::to_string(statistics)
+            .map(Self)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+
+    pub fn into_statistics(self) -> AppResult<LibraryStatistics> {
+        serde_json::from_str(&self.0).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl From<&StatisticsOptions> for StatisticsCacheKey {
+    fn from(options: &StatisticsOptions) -> Self {
+        let StatisticsOptions {
+            from,
+            to,
+            include_borrowers,
+            limit,
+        } = options;
+        let bound = |d: &Option<DateTime<Utc>>| d.map(|d| d.to_rfc3339()).unwrap_or_default();
+        Self(format!(
+            "statistics:{}:{}:{}:{}",
+            bound(from),
+            bound(to),
+            include_borrowers,
+            limit
+        ))
+    }
+}
+
+impl RedisKey for StatisticsCacheKey {
+    type Value = CachedStatistics;
+
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl RedisValue for CachedStatistics {
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl TryFrom<String> for CachedStatistics {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s))
+    }
+}
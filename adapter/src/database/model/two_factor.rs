@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use kernel::model::{
+    id::UserId,
+    two_factor::{TwoFactorChallenge, TwoFactorChallengePurpose},
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    redis::model::{RedisCounterKey, RedisKey, RedisValue},
+    token::hash_token,
+};
+
+// Redis にはチャレンジそのものではなくハッシュ値をキーとして保存する
+pub struct TwoFactorChallengeKey(String);
+
+pub struct ChallengedUser {
+    pub purpose: TwoFactorChallengePurpose,
+    pub user_id: UserId,
+}
+
+impl From<&TwoFactorChallenge> for TwoFactorChallengeKey {
+    fn from(challenge: &TwoFactorChallenge) -> Self {
+        Self(hash_token(&challenge.0))
+    }
+}
+
+impl RedisKey for TwoFactorChallengeKey {
+    type Value = ChallengedUser;
+
+    fn inner(&self) -> String {
+        format!("two-factor-challenge:{}", self.0)
+    }
+}
+
+impl RedisValue for ChallengedUser {
+    fn inner(&self) -> String {
+        let purpose = match self.purpose {
+            TwoFactorChallengePurpose::Verify => "verify",
+            TwoFactorChallengePurpose::Enroll => "enroll",
+        };
+        format!("{}:{}", purpose, self.user_id)
+    }
+}
+
+impl TryFrom<String> for ChallengedUser {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        let invalid = || AppError::ConversionEntityError(format!("invalid challenge: {}", s));
+        let (purpose, user_id) = s.split_once(':').ok_or_else(invalid)?;
+        let purpose = match purpose {
+            "verify" => TwoFactorChallengePurpose::Verify,
+            "enroll" => TwoFactorChallengePurpose::Enroll,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            purpose,
+            user_id: UserId::from_str(user_id)?,
+        })
+    }
+}
+
+// チャレンジごとの、コードの検証に失敗した回数
+pub struct TwoFactorAttemptsKey<'a>(pub &'a TwoFactorChallengeKey);
+
+impl RedisCounterKey for TwoFactorAttemptsKey<'_> {
+    fn inner(&self) -> String {
+        format!("two-factor-attempts:{}", self.0 .0)
+    }
+}
+
+// ユーザーごとの、チャレンジをまたいでコードの検証に失敗した回数
+pub struct TwoFactorFailuresKey(pub UserId);
+
+impl RedisCounterKey for TwoFactorFailuresKey {
+    fn inner(&self) -> String {
+        format!("two-factor-failures:{}", self.0)
+    }
+}
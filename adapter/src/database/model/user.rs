@@ -1,17 +1,63 @@
-use kernel::model::{id::UserId, role::Role, user::User};
-use shared::error::AppError;
+use kernel::model::{
+    id::UserId,
+    role::Role,
+    user::{EmailChangeToken, User, UserStatus},
+};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
 use sqlx::types::chrono::{DateTime, Utc};
 use std::str::FromStr;
 
+use crate::{
+    redis::model::{RedisKey, RedisValue},
+    token::hash_token,
+};
+
 pub struct UserRow {
     pub user_id: UserId,
     pub name: String,
     pub email: String,
     pub role_name: String,
+    pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+pub struct PaginatedUserRow {
+    pub total: i64,
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+    pub role_name: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PaginatedUserRow> for UserRow {
+    fn from(value: PaginatedUserRow) -> Self {
+        let PaginatedUserRow {
+            user_id,
+            name,
+            email,
+            role_name,
+            status,
+            created_at,
+            updated_at,
+            ..
+        } = value;
+        Self {
+            user_id,
+            name,
+            email,
+            role_name,
+            status,
+            created_at,
+            updated_at,
+        }
+    }
+}
+
 impl TryFrom<UserRow> for User {
     type Error = AppError;
 
@@ -21,6 +67,7 @@ impl TryFrom<UserRow> for User {
             name,
             email,
             role_name,
+            status,
             ..
         } = value;
         Ok(User {
@@ -29,6 +76,47 @@ impl TryFrom<UserRow> for User {
             email,
             role: Role::from_str(role_name.as_str())
                 .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            status: UserStatus::from_str(status.as_str())
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
         })
     }
 }
+
+// Redis にはトークンそのものではなくハッシュ値をキーとして保存する
+pub struct EmailChangeKey(String);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailChangeRequest {
+    pub user_id: UserId,
+    pub new_email: String,
+}
+
+impl From<&EmailChangeToken> for EmailChangeKey {
+    fn from(token: &EmailChangeToken) -> Self {
+        Self(format!("email-change:{}", hash_token(&token.0)))
+    }
+}
+
+impl RedisKey for EmailChangeKey {
+    type Value = EmailChangeRequest;
+
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl RedisValue for EmailChangeRequest {
+    fn inner(&self) -> String {
+        // ID と文字列のみの構造体はシリアライズに失敗しない
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for EmailChangeRequest {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
@@ -0,0 +1,198 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use rand::Rng;
+use shared::error::{AppError, AppResult};
+use sqlx::PgConnection;
+
+use super::ConnectionPool;
+
+// リトライ間隔の基準値と上限
+const BASE_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+// シリアライズ失敗 (serialization_failure) とデッドロック検出 (deadlock_detected)
+const RETRYABLE_SQLSTATES: [&str; 2] = ["40001", "40P01"];
+
+// run_in_transaction に渡す処理が返す Future の型
+pub type TransactionFuture<'t, T> = Pin<Box<dyn Future<Output = AppResult<T>> + Send + 't>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    Serializable,
+}
+
+impl ConnectionPool {
+    // 渡された処理を1つのトランザクション内で実行し、成功したらコミットする。
+    // シリアライズ失敗やデッドロックで失敗した場合は、間隔をあけてトランザクションごとやり直す。
+    // そのため、処理は何度呼び出されてもよいように作る必要がある
+    #[tracing::instrument(name = "db.transaction", skip(self, f), fields(retries = 0))]
+    pub async fn run_in_transaction<T, F>(
+        &self,
+        isolation_level: IsolationLevel,
+        f: F,
+    ) -> AppResult<T>
+    where
+        T: Send,
+        F: for<'t> Fn(&'t mut PgConnection) -> TransactionFuture<'t, T> + Send + Sync,
+    {
+        let mut retries = 0;
+        loop {
+            match self.try_transaction(isolation_level, &f).await {
+                Err(e) if is_retryable_error(&e) => {
+                    if retries >= self.max_transaction_retries {
+                        tracing::warn!(
+                            retries,
+                            error.message = %e,
+                            "Transaction retry limit exceeded"
+                        );
+                        return Err(AppError::TransactionConflict);
+                    }
+                    retries += 1;
+                    tracing::Span::current().record("retries", retries);
+
+                    let delay = backoff(retries);
+                    tracing::debug!(
+                        retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error.message = %e,
+                        "Retrying transaction"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn try_transaction<T, F>(&self, isolation_level: IsolationLevel, f: &F) -> AppResult<T>
+    where
+        F: for<'t> Fn(&'t mut PgConnection) -> TransactionFuture<'t, T>,
+    {
+        // 途中でエラーになった場合、tx がドロップされる際にロールバックされる
+        let mut tx = self.begin().await?;
+
+        if isolation_level == IsolationLevel::Serializable {
+            sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::SpecificOperationError)?;
+        }
+
+        let value = f(&mut *tx).await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(value)
+    }
+}
+
+// トランザクションをやり直せば成功する可能性のあるエラーかどうか
+pub fn is_retryable_error(e: &AppError) -> bool {
+    match e {
+        AppError::TransactionError(sqlx::Error::Database(db))
+        | AppError::SpecificOperationError(sqlx::Error::Database(db)) => db
+            .code()
+            .is_some_and(|code| RETRYABLE_SQLSTATES.contains(&code.as_ref())),
+        _ => false,
+    }
+}
+
+// 指数バックオフの上限までの範囲でランダムに待ち時間を決める (Full Jitter)
+fn backoff(retries: u32) -> Duration {
+    let cap = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retries.saturating_sub(1)))
+        .min(MAX_BACKOFF);
+    let millis = rand::thread_rng().gen_range(0..=cap.as_millis() as u64);
+    Duration::from_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    // シリアライズ失敗 (40001) を発生させる
+    async fn raise_serialization_failure(conn: &mut PgConnection) -> AppResult<()> {
+        sqlx::query("DO $$ BEGIN RAISE EXCEPTION USING ERRCODE = '40001'; END $$")
+            .execute(conn)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_retry_until_success(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let db = ConnectionPool::new(pool).with_max_transaction_retries(3);
+        let attempts = AtomicU32::new(0);
+
+        // 2 回失敗した後、3 回目で成功する
+        let value = db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if attempt < 2 {
+                        raise_serialization_failure(conn).await?;
+                    }
+                    Ok(attempt)
+                })
+            })
+            .await?;
+        assert_eq!(value, 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_retry_limit_exceeded(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let db = ConnectionPool::new(pool).with_max_transaction_retries(2);
+        let attempts = AtomicU32::new(0);
+
+        // 最初の 1 回と、上限の 2 回のやり直しがすべて失敗すると競合として扱う
+        let res = db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Box::pin(raise_serialization_failure(conn))
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::TransactionConflict)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_non_retryable_error_is_returned(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let db = ConnectionPool::new(pool);
+        let attempts = AtomicU32::new(0);
+
+        let res: AppResult<()> = db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Err(AppError::UnauthenticatedError) })
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        for retries in 1..=32 {
+            assert!(backoff(retries) <= MAX_BACKOFF);
+        }
+        assert!(backoff(1) <= BASE_BACKOFF);
+    }
+
+    #[test]
+    fn test_non_database_error_is_not_retryable() {
+        assert!(!is_retryable_error(&AppError::TransactionError(
+            sqlx::Error::PoolTimedOut
+        )));
+        assert!(!is_retryable_error(&AppError::TransactionConflict));
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use kernel::{model::mail::Mail, repository::mailer::Mailer};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use shared::error::{AppError, AppResult};
+
+// 送信するメールを1通ずつファイルに書き出す
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: String, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            from,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        let message = build_message(&self.from, mail)?;
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(delivery_error)?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%3f"),
+            uuid::Uuid::new_v4().simple()
+        ));
+        tokio::fs::write(&path, message.formatted())
+            .await
+            .map_err(delivery_error)?;
+
+        tracing::info!(path = %path.display(), "Mail has been written to file");
+        Ok(())
+    }
+}
+
+// ローカルの SMTP サーバーに送信する
+pub struct SmtpMailer {
+    from: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(from: String, host: &str, port: u16) -> Self {
+        // 開発用のサーバーを想定しているため、TLS と認証は使わない
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+            .port(port)
+            .build();
+        Self { from, transport }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        let message = build_message(&self.from, mail)?;
+        self.transport.send(message).await.map_err(delivery_error)?;
+        Ok(())
+    }
+}
+
+fn build_message(from: &str, mail: Mail) -> AppResult<Message> {
+    let Mail { to, subject, body } = mail;
+    Message::builder()
+        .from(from.parse::<Mailbox>().map_err(delivery_error)?)
+        .to(to.parse::<Mailbox>().map_err(delivery_error)?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(delivery_error)
+}
+
+fn delivery_error(e: impl ToString) -> AppError {
+    AppError::MailDeliveryError(e.to_string())
+}
@@ -0,0 +1,196 @@
+use std::{collections::HashMap, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use kernel::model::role::Role;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use shared::{
+    config::OidcConfig,
+    error::{AppError, AppResult},
+};
+
+// ディスカバリーで取得する IdP のメタデータのうち、認可コードフローに必要なもの
+#[derive(Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+// トークンエンドポイントのレスポンス。IdP のアクセストークンは使わない
+#[derive(Deserialize)]
+pub struct TokenResponse {
+    pub id_token: String,
+}
+
+// ID トークンのクレーム。iss, aud, exp は jsonwebtoken で検証する
+#[derive(Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    // グループのクレーム名は IdP によって異なるため、残りのクレームから設定した名前で取り出す
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl IdTokenClaims {
+    // グループは文字列の配列とするが、1 つだけの場合に文字列で返す IdP もある
+    pub fn groups(&self, claim: &str) -> Vec<String> {
+        match self.other.get(claim) {
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            Some(Value::String(value)) => vec![value.clone()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+// PKCE の S256 方式の code_challenge (RFC 7636)
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+pub fn authorization_url(
+    endpoint: &str,
+    config: &OidcConfig,
+    state: &str,
+    nonce: &str,
+    code_verifier: &str,
+) -> AppResult<String> {
+    let mut url = Url::parse(endpoint).map_err(|e| AppError::OidcError(e.to_string()))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_uri)
+        .append_pair("scope", &config.scopes)
+        .append_pair("state", state)
+        .append_pair("nonce", nonce)
+        .append_pair("code_challenge", &code_challenge(code_verifier))
+        .append_pair("code_challenge_method", "S256");
+    Ok(url.into())
+}
+
+// 所属するグループに対応するロールのうち、設定で先に書かれたものを返す
+// ロールは任意に作成できるため強さを比較できず、優先順位は設定の順とする
+// 対応を設定している場合、どのグループにも属さないユーザーは一般のユーザーとする
+pub fn role_for_groups(group_roles: &[(String, String)], groups: &[String]) -> Option<Role> {
+    if group_roles.is_empty() {
+        return None;
+    }
+    let role = group_roles
+        .iter()
+        .find(|(group, _)| groups.contains(group))
+        .and_then(|(_, role)| Role::from_str(role).ok())
+        .unwrap_or_default();
+    Some(role)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            issuer_url: "http://localhost:8090/default".into(),
+            client_id: "book-manager".into(),
+            client_secret: None,
+            redirect_uri: "http://localhost:3000/auth/oidc/callback".into(),
+            scopes: "openid email profile".into(),
+            groups_claim: "groups".into(),
+            group_roles: vec![],
+            auto_provision: true,
+            state_ttl: 600,
+        }
+    }
+
+    #[test]
+    fn test_code_challenge() {
+        // RFC 7636 Appendix B の例
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+        assert!(crate::token::random_token().len() >= 43);
+    }
+
+    #[test]
+    fn test_authorization_url() -> anyhow::Result<()> {
+        let url = authorization_url(
+            "http://localhost:8090/default/authorize",
+            &config(),
+            "state",
+            "nonce",
+            "verifier",
+        )?;
+        let url = Url::parse(&url)?;
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], "book-manager");
+        assert_eq!(
+            params["redirect_uri"],
+            "http://localhost:3000/auth/oidc/callback"
+        );
+        assert_eq!(params["scope"], "openid email profile");
+        assert_eq!(params["state"], "state");
+        assert_eq!(params["nonce"], "nonce");
+        assert_eq!(params["code_challenge"], code_challenge("verifier"));
+        assert_eq!(params["code_challenge_method"], "S256");
+        Ok(())
+    }
+
+    #[test]
+    fn test_groups() -> anyhow::Result<()> {
+        let claims: IdTokenClaims = serde_json::from_str(
+            r#"{"sub": "alice", "groups": ["staff", "library-admins"], "role": "admin"}"#,
+        )?;
+        assert_eq!(claims.groups("groups"), vec!["staff", "library-admins"]);
+        assert_eq!(claims.groups("role"), vec!["admin"]);
+        assert!(claims.groups("missing").is_empty());
+        assert!(!claims.email_verified);
+        Ok(())
+    }
+
+    #[test]
+    fn test_role_for_groups() {
+        let group_roles = vec![
+            ("library-admins".to_string(), "Admin".to_string()),
+            ("librarians".to_string(), "Librarian".to_string()),
+            ("curators".to_string(), "Curator".to_string()),
+            ("staff".to_string(), "User".to_string()),
+        ];
+        let groups = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            role_for_groups(&group_roles, &groups(&["staff", "library-admins"])),
+            Some(Role::Admin)
+        );
+        assert_eq!(
+            role_for_groups(&group_roles, &groups(&["staff", "librarians"])),
+            Some(Role::Librarian)
+        );
+        assert_eq!(
+            role_for_groups(&group_roles, &groups(&["curators"])),
+            Some(Role::Custom("Curator".into()))
+        );
+        assert_eq!(
+            role_for_groups(&group_roles, &groups(&["staff"])),
+            Some(Role::User)
+        );
+        assert_eq!(
+            role_for_groups(&group_roles, &groups(&["other"])),
+            Some(Role::User)
+        );
+        // 対応を設定していない場合は権限を同期しない
+        assert_eq!(role_for_groups(&[], &groups(&["library-admins"])), None);
+    }
+}
@@ -0,0 +1,241 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use shared::error::{AppError, AppResult};
+use tokio::task::spawn_blocking;
+
+// パスワードハッシュの方式。ハッシュの文字列から、どの方式で作ったものかを判別できる
+pub trait PasswordScheme: Send + Sync {
+    // この方式で作ったハッシュかどうか
+    fn matches(&self, hash: &str) -> bool;
+    fn hash(&self, password: &str) -> AppResult<String>;
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool>;
+    // 同じ方式でも、現在とは異なるパラメータで作ったハッシュは作り直す
+    fn needs_rehash(&self, _hash: &str) -> bool {
+        false
+    }
+}
+
+// RFC 9106 の Argon2id。パラメータは OWASP の推奨値（argon2 クレートの既定値）を使う
+pub struct Argon2id {
+    params: Params,
+}
+
+impl Default for Argon2id {
+    fn default() -> Self {
+        Self {
+            params: Params::default(),
+        }
+    }
+}
+
+impl Argon2id {
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordScheme for Argon2id {
+    fn matches(&self, hash: &str) -> bool {
+        hash.starts_with("$argon2id$")
+    }
+
+    fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+            .map_err(|e| AppError::PasswordHashError(e.to_string()))?;
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::PasswordHashError(e.to_string()))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        let parsed =
+            PasswordHash::new(hash).map_err(|e| AppError::PasswordHashError(e.to_string()))?;
+        match self.argon2().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(AppError::PasswordHashError(e.to_string())),
+        }
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+// 以前のバージョンで保存したパスワードハッシュの検証に使う
+pub struct Bcrypt;
+
+impl PasswordScheme for Bcrypt {
+    fn matches(&self, hash: &str) -> bool {
+        ["$2a$", "$2b$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+    }
+
+    fn hash(&self, password: &str) -> AppResult<String> {
+        bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(AppError::from)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        bcrypt::verify(password, hash).map_err(AppError::from)
+    }
+}
+
+// パスワードでのログインを許可しないユーザーに設定するハッシュ
+// どの方式のハッシュの形式にも当てはまらないため、どのようなパスワードでも検証に失敗する
+pub const UNUSABLE_PASSWORD_HASH: &str = "!";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Invalid,
+    Valid,
+    // パスワードは正しいが、現在の方式・パラメータでハッシュを作り直す必要がある
+    ValidNeedsRehash,
+}
+
+impl PasswordVerification {
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, Self::Invalid)
+    }
+}
+
+// 新しいパスワードは current の方式でハッシュ化し、検証は legacy の方式にも対応する
+pub struct PasswordHasher {
+    current: Box<dyn PasswordScheme>,
+    legacy: Vec<Box<dyn PasswordScheme>>,
+}
+
+impl PasswordHasher {
+    pub fn new(current: Box<dyn PasswordScheme>, legacy: Vec<Box<dyn PasswordScheme>>) -> Self {
+        Self { current, legacy }
+    }
+
+    pub fn hash(&self, password: &str) -> AppResult<String> {
+        self.current.hash(password)
+    }
+
+    pub fn verify(&self, password: &str, hash: &str) -> AppResult<PasswordVerification> {
+        if hash == UNUSABLE_PASSWORD_HASH {
+            return Ok(PasswordVerification::Invalid);
+        }
+        if self.current.matches(hash) {
+            return Ok(match self.current.verify(password, hash)? {
+                false => PasswordVerification::Invalid,
+                true if self.current.needs_rehash(hash) => PasswordVerification::ValidNeedsRehash,
+                true => PasswordVerification::Valid,
+            });
+        }
+        let Some(scheme) = self.legacy.iter().find(|scheme| scheme.matches(hash)) else {
+            return Err(AppError::PasswordHashError(
+                "unknown password hash format".into(),
+            ));
+        };
+        Ok(match scheme.verify(password, hash)? {
+            false => PasswordVerification::Invalid,
+            true => PasswordVerification::ValidNeedsRehash,
+        })
+    }
+}
+
+impl Default for PasswordHasher {
+    fn default() -> Self {
+        Self::new(Box::new(Argon2id::default()), vec![Box::new(Bcrypt)])
+    }
+}
+
+static PASSWORD_HASHER: OnceLock<PasswordHasher> = OnceLock::new();
+
+fn password_hasher() -> &'static PasswordHasher {
+    PASSWORD_HASHER.get_or_init(PasswordHasher::default)
+}
+
+// Argon2id の計算は CPU を長く占有するため、非同期ランタイムのワーカースレッドを
+// 止めないようにブロッキング処理用のスレッドで実行する
+pub async fn hash_password(password: &str) -> AppResult<String> {
+    let password = password.to_owned();
+    spawn_blocking(move || password_hasher().hash(&password))
+        .await
+        .map_err(|e| AppError::PasswordHashError(e.to_string()))?
+}
+
+pub async fn verify_password(password: &str, hash: &str) -> AppResult<PasswordVerification> {
+    let password = password.to_owned();
+    let hash = hash.to_owned();
+    spawn_blocking(move || password_hasher().verify(&password, &hash))
+        .await
+        .map_err(|e| AppError::PasswordHashError(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hash_with_argon2id() -> anyhow::Result<()> {
+        let hash = hash_password("correct horse").await?;
+        assert!(hash.starts_with("$argon2id$"));
+        assert_eq!(
+            verify_password("correct horse", &hash).await?,
+            PasswordVerification::Valid
+        );
+        assert_eq!(
+            verify_password("wrong horse", &hash).await?,
+            PasswordVerification::Invalid
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_legacy_bcrypt_hash() -> anyhow::Result<()> {
+        let hash = bcrypt::hash("correct horse", 4)?;
+        assert_eq!(
+            verify_password("correct horse", &hash).await?,
+            PasswordVerification::ValidNeedsRehash
+        );
+        assert_eq!(
+            verify_password("wrong horse", &hash).await?,
+            PasswordVerification::Invalid
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unusable_hash_never_verifies() -> anyhow::Result<()> {
+        for password in ["", "!", "correct horse"] {
+            assert_eq!(
+                verify_password(password, UNUSABLE_PASSWORD_HASH).await?,
+                PasswordVerification::Invalid
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rehash_when_params_change() -> anyhow::Result<()> {
+        let weak = Argon2id {
+            params: Params::new(8 * 1024, 1, 1, None)
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?,
+        };
+        let hash = weak.hash("correct horse")?;
+        assert_eq!(
+            verify_password("correct horse", &hash).await?,
+            PasswordVerification::ValidNeedsRehash
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 pub mod model;
 
-use self::model::{RedisKey, RedisValue};
+use self::model::{CompareAndSet, RedisCounterKey, RedisKey, RedisSetKey, RedisValue};
 use redis::{AsyncCommands, Client};
 use shared::{config::RedisConfig, error::AppResult};
 
@@ -26,12 +26,147 @@ impl RedisClient {
         result.map(T::Value::try_from).transpose()
     }
 
+    // 値を取得すると同時にキーを削除する。一度しか使えないトークンの検証に使う
+    pub async fn get_del<T: RedisKey>(&self, key: &T) -> AppResult<Option<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let result: Option<String> = conn.get_del(key.inner()).await?;
+        result.map(T::Value::try_from).transpose()
+    }
+
+    // 複数のキーの値をまとめて取得する。結果はキーと同じ順に並ぶ
+    pub async fn get_many<T: RedisKey>(&self, keys: &[T]) -> AppResult<Vec<Option<T::Value>>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let keys: Vec<String> = keys.iter().map(RedisKey::inner).collect();
+        let result: Vec<Option<String>> =
+            redis::cmd("MGET").arg(keys).query_async(&mut conn).await?;
+        result
+            .into_iter()
+            .map(|value| value.map(T::Value::try_from).transpose())
+            .collect()
+    }
+
     pub async fn delete<T: RedisKey>(&self, key: &T) -> AppResult<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         conn.del(key.inner()).await?;
         Ok(())
     }
 
+    // 現在の値が expected と一致する場合のみ、値を置き換えて有効期限を設定し直す
+    // 比較と置き換えは Lua スクリプトでアトミックに行う
+    pub async fn compare_and_set<T: RedisKey>(
+        &self,
+        key: &T,
+        expected: &T::Value,
+        value: &T::Value,
+        ttl: u64,
+    ) -> AppResult<CompareAndSet> {
+        let script = redis::Script::new(
+            r"
+                local current = redis.call('GET', KEYS[1])
+                if not current then
+                    return 0
+                end
+                if current ~= ARGV[1] then
+                    return -1
+                end
+                redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+                return 1
+            ",
+        );
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let result: i64 = script
+            .key(key.inner())
+            .arg(expected.inner())
+            .arg(value.inner())
+            .arg(ttl)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(match result {
+            1 => CompareAndSet::Swapped,
+            0 => CompareAndSet::Missing,
+            _ => CompareAndSet::Mismatched,
+        })
+    }
+
+    // 集合にメンバーを追加し、集合全体の有効期限を延長する
+    pub async fn add_to_set<T: RedisSetKey>(
+        &self,
+        key: &T,
+        member: &str,
+        ttl: u64,
+    ) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        redis::pipe()
+            .atomic()
+            .sadd(key.inner(), member)
+            .ignore()
+            .expire(key.inner(), ttl as i64)
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_from_set<T: RedisSetKey>(&self, key: &T, member: &str) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.srem(key.inner(), member).await?;
+        Ok(())
+    }
+
+    pub async fn set_members<T: RedisSetKey>(&self, key: &T) -> AppResult<Vec<String>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let members: Vec<String> = conn.smembers(key.inner()).await?;
+        Ok(members)
+    }
+
+    pub async fn delete_set<T: RedisSetKey>(&self, key: &T) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.del(key.inner()).await?;
+        Ok(())
+    }
+
+    // カウンタを 1 増やして増やした後の値を返し、有効期限を設定し直す
+    pub async fn increment<T: RedisCounterKey>(&self, key: &T, ttl: u64) -> AppResult<u64> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(key.inner(), 1)
+            .expire(key.inner(), ttl as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
+    // カウンタの現在の値を返す。キーが存在しない場合は 0 とする
+    pub async fn counter<T: RedisCounterKey>(&self, key: &T) -> AppResult<u64> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let count: Option<u64> = conn.get(key.inner()).await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    // カウンタを 1 減らす。有効期限は変えず、0 を下回らないようにする
+    pub async fn decrement<T: RedisCounterKey>(&self, key: &T) -> AppResult<()> {
+        let script = redis::Script::new(
+            r"
+                local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+                if current > 0 then
+                    redis.call('DECR', KEYS[1])
+                end
+                return 0
+            ",
+        );
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let _: i64 = script.key(key.inner()).invoke_async(&mut conn).await?;
+        Ok(())
+    }
+
+    pub async fn delete_counter<T: RedisCounterKey>(&self, key: &T) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.del(key.inner()).await?;
+        Ok(())
+    }
+
     pub async fn try_connect(&self) -> AppResult<()> {
         let _ = self.client.get_multiplexed_async_connection().await?;
         Ok(())
@@ -114,4 +249,108 @@ mod tests {
 
         Ok(())
     }
+
+    pub struct TestSetKey(String);
+
+    impl RedisSetKey for TestSetKey {
+        fn inner(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_set() -> anyhow::Result<()> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let client = RedisClient::new(&config)?;
+        let key = TestSetKey("redis:set".to_string());
+
+        // 要素を追加すると取得できる
+        client.add_to_set(&key, "aaa", 1000).await?;
+        client.add_to_set(&key, "bbb", 1000).await?;
+        let mut members = client.set_members(&key).await?;
+        members.sort();
+        assert_eq!(members, vec!["aaa".to_string(), "bbb".to_string()]);
+
+        // 要素を取り除く
+        client.remove_from_set(&key, "aaa").await?;
+        assert_eq!(client.set_members(&key).await?, vec!["bbb".to_string()]);
+
+        // 集合を削除すると空になる
+        client.delete_set(&key).await?;
+        assert!(client.set_members(&key).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_compare_and_set() -> anyhow::Result<()> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let client = RedisClient::new(&config)?;
+        let key = TestContentKey("redis:cas".to_string());
+        let content = |name: &str| TestContent {
+            name: name.to_string(),
+        };
+
+        // キーが存在しない場合は置き換えない
+        let res = client
+            .compare_and_set(&key, &content("aaa"), &content("bbb"), 1000)
+            .await?;
+        assert_eq!(res, CompareAndSet::Missing);
+        assert!(client.get(&key).await?.is_none());
+
+        // 値が一致する場合は置き換える
+        client.set_ex(&key, &content("aaa"), 1000).await?;
+        let res = client
+            .compare_and_set(&key, &content("aaa"), &content("bbb"), 1000)
+            .await?;
+        assert_eq!(res, CompareAndSet::Swapped);
+        assert_eq!(client.get(&key).await?, Some(content("bbb")));
+
+        // 値が一致しない場合は置き換えない
+        let res = client
+            .compare_and_set(&key, &content("aaa"), &content("ccc"), 1000)
+            .await?;
+        assert_eq!(res, CompareAndSet::Mismatched);
+        assert_eq!(client.get(&key).await?, Some(content("bbb")));
+
+        client.delete(&key).await?;
+
+        Ok(())
+    }
+
+    pub struct TestCounterKey(String);
+
+    impl RedisCounterKey for TestCounterKey {
+        fn inner(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_counter() -> anyhow::Result<()> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let client = RedisClient::new(&config)?;
+        let key = TestCounterKey("redis:counter".to_string());
+
+        // 存在しないカウンタは 0 から数える
+        assert_eq!(client.increment(&key, 1000).await?, 1);
+        assert_eq!(client.increment(&key, 1000).await?, 2);
+
+        // 削除するとやり直しになる
+        client.delete_counter(&key).await?;
+        assert_eq!(client.increment(&key, 1000).await?, 1);
+
+        client.delete_counter(&key).await?;
+
+        Ok(())
+    }
 }
@@ -8,3 +8,24 @@ pub trait RedisKey {
 pub trait RedisValue {
     fn inner(&self) -> String;
 }
+
+// 文字列の集合を値として持つキー
+pub trait RedisSetKey {
+    fn inner(&self) -> String;
+}
+
+// 整数のカウンタを値として持つキー
+pub trait RedisCounterKey {
+    fn inner(&self) -> String;
+}
+
+// RedisClient::compare_and_set の結果
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompareAndSet {
+    // 値が一致したため、新しい値に置き換えた
+    Swapped,
+    // キーは存在するが、値が一致しなかった
+    Mismatched,
+    // キーが存在しなかった
+    Missing,
+}
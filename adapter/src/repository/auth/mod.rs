@@ -0,0 +1,255 @@
+pub mod jwt;
+mod session;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::{CreateSession, CreateToken, RotateRefreshToken},
+            AccessToken, IssuedRefreshToken, Session, VerifyingKey,
+        },
+        id::{SessionId, UserId},
+        user::User,
+    },
+    repository::auth::AuthRepository,
+};
+use shared::error::{AppError, AppResult};
+use tokio::sync::OnceCell;
+
+use session::Rotation;
+pub use session::SessionStore;
+
+use crate::{
+    database::{
+        model::auth::{from, AuthorizationKey, SessionTokensKey, UserItem},
+        ConnectionPool,
+    },
+    password::{hash_password, verify_password, PasswordVerification, UNUSABLE_PASSWORD_HASH},
+    redis::RedisClient,
+};
+
+// アクセストークンとしてランダムな文字列を発行し、Redis でユーザーとの対応を管理する
+#[derive(new)]
+pub struct AuthRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    ttl: u64,
+    sessions: SessionStore,
+}
+
+impl AuthRepositoryImpl {
+    // セッションを終了し、そのセッションで発行したアクセストークンをすべて無効にする
+    async fn end_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        self.delete_session_tokens(session_id).await?;
+        self.sessions.delete(user_id, session_id).await
+    }
+
+    async fn delete_session_tokens(&self, session_id: SessionId) -> AppResult<()> {
+        let tokens_key = SessionTokensKey::from(session_id);
+        for token in self.kv.set_members(&tokens_key).await? {
+            self.kv
+                .delete(&AuthorizationKey::from(&AccessToken(token)))
+                .await?;
+        }
+        self.kv.delete_set(&tokens_key).await
+    }
+}
+
+#[async_trait]
+impl AuthRepository for AuthRepositoryImpl {
+    async fn fetch_user_id_from_token(
+        &self,
+        access_token: &AccessToken,
+    ) -> AppResult<Option<UserId>> {
+        let key: AuthorizationKey = access_token.into();
+        self.kv
+            .get(&key)
+            .await
+            .map(|x| x.map(|session| session.user_id))
+    }
+
+    // アクセストークン自体にはユーザーの情報を含まない
+    async fn fetch_user_from_claims(&self, _access_token: &AccessToken) -> AppResult<Option<User>> {
+        Ok(None)
+    }
+
+    async fn fetch_session_id_from_token(
+        &self,
+        access_token: &AccessToken,
+    ) -> AppResult<Option<SessionId>> {
+        let key: AuthorizationKey = access_token.into();
+        self.kv
+            .get(&key)
+            .await
+            .map(|x| x.map(|session| session.session_id))
+    }
+
+    async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId> {
+        verify_user(&self.db, email, password).await
+    }
+
+    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken> {
+        let (key, value) = from(event);
+        self.kv.set_ex(&key, &value, self.ttl).await?;
+        self.kv
+            .add_to_set(
+                &SessionTokensKey::from(value.session_id),
+                key.token(),
+                self.ttl,
+            )
+            .await?;
+        Ok(key.into())
+    }
+
+    async fn delete_token(&self, access_token: AccessToken) -> AppResult<()> {
+        let key: AuthorizationKey = access_token.into();
+        if let Some(session) = self.kv.get(&key).await? {
+            self.end_session(session.user_id, session.session_id)
+                .await?;
+        }
+        self.kv.delete(&key).await
+    }
+
+    async fn delete_all_tokens(&self, user_id: UserId) -> AppResult<()> {
+        for session_id in self.sessions.delete_all(user_id).await? {
+            self.delete_session_tokens(session_id).await?;
+        }
+        delete_personal_access_tokens(&self.db, user_id).await
+    }
+
+    async fn create_session(&self, event: CreateSession) -> AppResult<IssuedRefreshToken> {
+        self.sessions.create(event).await
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        event: RotateRefreshToken,
+    ) -> AppResult<IssuedRefreshToken> {
+        match self.sessions.rotate(event).await? {
+            Rotation::Rotated(issued) => Ok(issued),
+            // ログアウトと同じく、そのセッションで発行したアクセストークンも無効にする
+            Rotation::Reused {
+                user_id,
+                session_id,
+            } => {
+                self.end_session(user_id, session_id).await?;
+                Err(AppError::UnauthorizedError)
+            }
+        }
+    }
+
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>> {
+        self.sessions.find_by_user(user_id).await
+    }
+
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        let session = self.sessions.find(user_id, session_id).await?;
+        self.end_session(session.user_id, session.id).await
+    }
+
+    async fn delete_other_sessions(&self, user_id: UserId, keep: SessionId) -> AppResult<()> {
+        for session in self.sessions.find_by_user(user_id).await? {
+            if session.id != keep {
+                self.end_session(session.user_id, session.id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn verifying_keys(&self) -> Vec<VerifyingKey> {
+        Vec::new()
+    }
+}
+
+// ユーザーのパーソナルアクセストークンをすべて削除する
+// アクセストークンの形式によらず、ユーザー単位でトークンを無効にする際に合わせて行う
+pub(super) async fn delete_personal_access_tokens(
+    db: &ConnectionPool,
+    user_id: UserId,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            DELETE FROM personal_access_tokens WHERE user_id = $1;
+        "#,
+        user_id as _
+    )
+    .execute(db.inner_ref())
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
+
+// 存在しないメールアドレスでも、パスワードの検証と同じだけ時間がかかるようにするためのハッシュ
+static DUMMY_PASSWORD_HASH: OnceCell<String> = OnceCell::const_new();
+
+// メールアドレスとパスワードでユーザーを認証する
+// アクセストークンの形式によらず共通の処理
+// メールアドレスが存在しない場合も、パスワードが誤っている場合と同じエラーを返す
+pub(super) async fn verify_user(
+    db: &ConnectionPool,
+    email: &str,
+    password: &str,
+) -> AppResult<UserId> {
+    let user_item = sqlx::query_as!(
+        UserItem,
+        r#"
+            SELECT user_id, password_hash, email_verified_at FROM users
+            WHERE email = $1;
+        "#,
+        email
+    )
+    .fetch_optional(db.inner_ref())
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    // パスワードが設定されていないユーザーも、存在しない場合と区別できないようにする
+    let Some(user_item) =
+        user_item.filter(|user_item| user_item.password_hash != UNUSABLE_PASSWORD_HASH)
+    else {
+        let dummy_hash = DUMMY_PASSWORD_HASH
+            .get_or_init(|| async { hash_password("dummy-password").await.unwrap_or_default() })
+            .await;
+        let _ = verify_password(password, dummy_hash).await;
+        return Err(AppError::UnauthenticatedError);
+    };
+    let verification = verify_password(password, &user_item.password_hash).await?;
+    if !verification.is_valid() {
+        return Err(AppError::UnauthenticatedError);
+    }
+    // メールアドレスの確認が済んでいないユーザーはログインできない
+    if user_item.email_verified_at.is_none() {
+        return Err(AppError::UnauthenticatedError);
+    }
+    // 以前の方式で保存したハッシュは、ログインに成功した時点で現在の方式に置き換える
+    // 置き換えに失敗しても、次回のログインで再度試みればよいので、ログインは失敗させない
+    if verification == PasswordVerification::ValidNeedsRehash {
+        if let Err(e) = rehash_password(db, &user_item, password).await {
+            tracing::warn!(error.message = %e, "failed to rehash password");
+        }
+    }
+    Ok(user_item.user_id)
+}
+
+async fn rehash_password(
+    db: &ConnectionPool,
+    user_item: &UserItem,
+    password: &str,
+) -> AppResult<()> {
+    let new_password_hash = hash_password(password).await?;
+    // 検証している間にパスワードが変更されていた場合は、変更後のハッシュを上書きしない
+    sqlx::query!(
+        r#"
+            UPDATE users SET password_hash = $3
+            WHERE user_id = $1 AND password_hash = $2;
+        "#,
+        user_item.user_id as _,
+        user_item.password_hash,
+        new_password_hash,
+    )
+    .execute(db.inner_ref())
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
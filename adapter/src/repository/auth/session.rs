@@ -0,0 +1,438 @@
+use std::{str::FromStr, sync::Arc};
+
+use chrono::Utc;
+use derive_new::new;
+use kernel::model::{
+    auth::{
+        event::{CreateSession, RotateRefreshToken},
+        IssuedRefreshToken, RefreshToken, Session,
+    },
+    id::{SessionId, UserId},
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::model::auth::{
+        CurrentRefreshToken, RefreshTokenEntry, RefreshTokenFamilyKey, RefreshTokenKey, SessionKey,
+        SessionValue, UserSessionsKey,
+    },
+    redis::{model::CompareAndSet, RedisClient},
+};
+
+// セッションの情報と、セッションごとのリフレッシュトークンを管理する
+// アクセストークンの形式によらず共通のため、各 AuthRepository の実装から利用する
+#[derive(new)]
+pub struct SessionStore {
+    kv: Arc<RedisClient>,
+    ttl: u64,
+}
+
+// リフレッシュトークンの置き換えの結果
+pub enum Rotation {
+    Rotated(IssuedRefreshToken),
+    // 置き換え済みのトークンが再び使われた場合。盗用されたとみなし、呼び出し側でセッションごと終了する
+    // アクセストークンの無効化の方法は AuthRepository の実装ごとに異なるため、ここではセッションを削除しない
+    Reused {
+        user_id: UserId,
+        session_id: SessionId,
+    },
+}
+
+impl SessionStore {
+    // セッションを作成し、最初のリフレッシュトークンを発行する
+    pub async fn create(&self, event: CreateSession) -> AppResult<IssuedRefreshToken> {
+        let CreateSession {
+            user_id,
+            ip_address,
+            user_agent,
+        } = event;
+        let session_id = SessionId::new();
+        let now = Utc::now();
+        self.kv
+            .set_ex(
+                &SessionKey::from(session_id),
+                &SessionValue {
+                    user_id,
+                    created_at: now,
+                    last_used_at: now,
+                    ip_address,
+                    user_agent,
+                },
+                self.ttl,
+            )
+            .await?;
+
+        let refresh_token = RefreshToken::new();
+        let key = RefreshTokenKey::from(&refresh_token);
+        self.kv
+            .set_ex(
+                &key,
+                &RefreshTokenEntry {
+                    session_id,
+                    user_id,
+                },
+                self.ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(
+                &RefreshTokenFamilyKey::from(session_id),
+                &CurrentRefreshToken::from(&key),
+                self.ttl,
+            )
+            .await?;
+        self.kv
+            .add_to_set(
+                &UserSessionsKey::from(user_id),
+                &session_id.to_string(),
+                self.ttl,
+            )
+            .await?;
+
+        Ok(IssuedRefreshToken {
+            user_id,
+            session_id,
+            refresh_token,
+        })
+    }
+
+    pub async fn rotate(&self, event: RotateRefreshToken) -> AppResult<Rotation> {
+        let RotateRefreshToken {
+            refresh_token,
+            ip_address,
+            user_agent,
+        } = event;
+        let key = RefreshTokenKey::from(&refresh_token);
+        let Some(entry) = self.kv.get(&key).await? else {
+            return Err(AppError::UnauthorizedError);
+        };
+        let family_key = RefreshTokenFamilyKey::from(entry.session_id);
+
+        // 新しいトークンを先に保存しておき、ファミリーの現在のトークンを置き換える
+        // 置き換えは比較と同時にアトミックに行い、同じトークンでの同時リクエストは一方のみ成功させる
+        let new_refresh_token = RefreshToken::new();
+        let new_key = RefreshTokenKey::from(&new_refresh_token);
+        self.kv.set_ex(&new_key, &entry, self.ttl).await?;
+
+        match self
+            .kv
+            .compare_and_set(
+                &family_key,
+                &CurrentRefreshToken::from(&key),
+                &CurrentRefreshToken::from(&new_key),
+                self.ttl,
+            )
+            .await?
+        {
+            CompareAndSet::Swapped => {
+                let session_key = SessionKey::from(entry.session_id);
+                if let Some(mut session) = self.kv.get(&session_key).await? {
+                    session.last_used_at = Utc::now();
+                    session.ip_address = ip_address;
+                    session.user_agent = user_agent;
+                    self.kv.set_ex(&session_key, &session, self.ttl).await?;
+                }
+                self.kv
+                    .add_to_set(
+                        &UserSessionsKey::from(entry.user_id),
+                        &entry.session_id.to_string(),
+                        self.ttl,
+                    )
+                    .await?;
+                Ok(Rotation::Rotated(IssuedRefreshToken {
+                    user_id: entry.user_id,
+                    session_id: entry.session_id,
+                    refresh_token: new_refresh_token,
+                }))
+            }
+            CompareAndSet::Mismatched => {
+                tracing::warn!(
+                    user_id = %entry.user_id,
+                    session_id = %entry.session_id,
+                    "Refresh token reuse detected; revoking the session"
+                );
+                self.kv.delete(&new_key).await?;
+                Ok(Rotation::Reused {
+                    user_id: entry.user_id,
+                    session_id: entry.session_id,
+                })
+            }
+            CompareAndSet::Missing => {
+                self.kv.delete(&new_key).await?;
+                Err(AppError::UnauthorizedError)
+            }
+        }
+    }
+
+    // ユーザーのセッションを返す。他のユーザーのセッションは見つからないものとして扱う
+    pub async fn find(&self, user_id: UserId, session_id: SessionId) -> AppResult<Session> {
+        match self.kv.get(&SessionKey::from(session_id)).await? {
+            Some(session) if session.user_id == user_id => Ok(session.into_session(session_id)),
+            _ => Err(AppError::EntityNotFound(format!(
+                "セッション ({}) が見つかりませんでした",
+                session_id
+            ))),
+        }
+    }
+
+    pub async fn find_by_user(&self, user_id: UserId) -> AppResult<Vec<Session>> {
+        let sessions_key = UserSessionsKey::from(user_id);
+        let mut sessions = Vec::new();
+        for member in self.kv.set_members(&sessions_key).await? {
+            let session_id = SessionId::from_str(&member)?;
+            match self.kv.get(&SessionKey::from(session_id)).await? {
+                Some(session) => sessions.push(session.into_session(session_id)),
+                // 有効期限が切れたセッションは集合からも取り除く
+                None => self.kv.remove_from_set(&sessions_key, &member).await?,
+            }
+        }
+        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(sessions)
+    }
+
+    // セッションを終了し、以降はリフレッシュトークンを使えないようにする
+    pub async fn delete(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        self.kv.delete(&SessionKey::from(session_id)).await?;
+        self.kv
+            .delete(&RefreshTokenFamilyKey::from(session_id))
+            .await?;
+        self.kv
+            .remove_from_set(&UserSessionsKey::from(user_id), &session_id.to_string())
+            .await
+    }
+
+    // ユーザーのすべてのセッションを終了し、終了したセッションの一覧を返す
+    pub async fn delete_all(&self, user_id: UserId) -> AppResult<Vec<SessionId>> {
+        let sessions_key = UserSessionsKey::from(user_id);
+        let mut session_ids = Vec::new();
+        for member in self.kv.set_members(&sessions_key).await? {
+            let session_id = SessionId::from_str(&member)?;
+            self.kv.delete(&SessionKey::from(session_id)).await?;
+            self.kv
+                .delete(&RefreshTokenFamilyKey::from(session_id))
+                .await?;
+            session_ids.push(session_id);
+        }
+        self.kv.delete_set(&sessions_key).await?;
+        Ok(session_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::{model::auth::event::CreateToken, repository::auth::AuthRepository};
+    use shared::config::RedisConfig;
+
+    use super::*;
+    use crate::{database::ConnectionPool, repository::auth::AuthRepositoryImpl};
+
+    fn redis_client() -> anyhow::Result<Arc<RedisClient>> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(Arc::new(RedisClient::new(&config)?))
+    }
+
+    fn create_session(user_id: UserId, user_agent: &str) -> CreateSession {
+        CreateSession {
+            user_id,
+            ip_address: Some("192.0.2.1".into()),
+            user_agent: Some(user_agent.into()),
+        }
+    }
+
+    // セッションは Redis でテスト間で共有されるため、テストごとに別のユーザー ID を使う
+    #[sqlx::test]
+    async fn test_create_and_find_sessions() -> anyhow::Result<()> {
+        let store = SessionStore::new(redis_client()?, 1000);
+        let user_id = UserId::new();
+
+        let first = store.create(create_session(user_id, "first")).await?;
+        let second = store.create(create_session(user_id, "second")).await?;
+        assert_eq!(first.user_id, user_id);
+        assert_ne!(first.session_id, second.session_id);
+
+        // 作成日時の新しい順に返す
+        let sessions = store.find_by_user(user_id).await?;
+        let ids = sessions.iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![second.session_id, first.session_id]);
+        assert_eq!(sessions[1].ip_address.as_deref(), Some("192.0.2.1"));
+        assert_eq!(sessions[1].user_agent.as_deref(), Some("first"));
+        assert_eq!(sessions[1].created_at, sessions[1].last_used_at);
+
+        let session = store.find(user_id, first.session_id).await?;
+        assert_eq!(session.id, first.session_id);
+
+        // 他のユーザーのセッションは見つからないものとして扱う
+        let res = store.find(UserId::new(), first.session_id).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(store.find_by_user(UserId::new()).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_rotate_updates_last_used_at() -> anyhow::Result<()> {
+        let store = SessionStore::new(redis_client()?, 1000);
+        let user_id = UserId::new();
+
+        let issued = store.create(create_session(user_id, "before")).await?;
+        let before = store.find(user_id, issued.session_id).await?;
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let Rotation::Rotated(rotated) = store
+            .rotate(RotateRefreshToken {
+                refresh_token: issued.refresh_token,
+                ip_address: Some("198.51.100.1".into()),
+                user_agent: Some("after".into()),
+            })
+            .await?
+        else {
+            panic!("refresh token should be rotated");
+        };
+        assert_eq!(rotated.session_id, issued.session_id);
+
+        let after = store.find(user_id, issued.session_id).await?;
+        assert_eq!(after.created_at, before.created_at);
+        assert!(after.last_used_at > before.last_used_at);
+        assert_eq!(after.ip_address.as_deref(), Some("198.51.100.1"));
+        assert_eq!(after.user_agent.as_deref(), Some("after"));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_delete_session() -> anyhow::Result<()> {
+        let store = SessionStore::new(redis_client()?, 1000);
+        let user_id = UserId::new();
+
+        let deleted = store.create(create_session(user_id, "deleted")).await?;
+        let kept = store.create(create_session(user_id, "kept")).await?;
+
+        store.delete(user_id, deleted.session_id).await?;
+
+        let ids = store
+            .find_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![kept.session_id]);
+        // 終了したセッションのリフレッシュトークンは使えない
+        let res = store
+            .rotate(RotateRefreshToken {
+                refresh_token: deleted.refresh_token,
+                ip_address: None,
+                user_agent: None,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_delete_other_sessions(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let kv = redis_client()?;
+        let repo = AuthRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            kv.clone(),
+            1000,
+            SessionStore::new(kv, 1000),
+        );
+        let user_id = UserId::new();
+
+        let current = repo
+            .create_session(create_session(user_id, "current"))
+            .await?;
+        let other = repo
+            .create_session(create_session(user_id, "other"))
+            .await?;
+        let current_token = repo
+            .create_token(CreateToken::new(user_id, current.session_id))
+            .await?;
+        let other_token = repo
+            .create_token(CreateToken::new(user_id, other.session_id))
+            .await?;
+
+        repo.delete_other_sessions(user_id, current.session_id)
+            .await?;
+
+        let ids = repo
+            .find_sessions(user_id)
+            .await?
+            .into_iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![current.session_id]);
+        // 終了したセッションで発行したアクセストークンのみ無効になる
+        assert_eq!(
+            repo.fetch_user_id_from_token(&current_token).await?,
+            Some(user_id)
+        );
+        assert_eq!(repo.fetch_user_id_from_token(&other_token).await?, None);
+
+        // 他のユーザーのセッションは終了できない
+        let res = repo.delete_session(UserId::new(), current.session_id).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        repo.delete_session(user_id, current.session_id).await?;
+        assert!(repo.find_sessions(user_id).await?.is_empty());
+        assert_eq!(repo.fetch_user_id_from_token(&current_token).await?, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_reused_refresh_token_revokes_access_tokens(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let kv = redis_client()?;
+        let repo = AuthRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            kv.clone(),
+            1000,
+            SessionStore::new(kv, 1000),
+        );
+        let user_id = UserId::new();
+
+        let issued = repo
+            .create_session(create_session(user_id, "stolen"))
+            .await?;
+        let access_token = repo
+            .create_token(CreateToken::new(user_id, issued.session_id))
+            .await?;
+        let stolen = RefreshToken(issued.refresh_token.0.clone());
+        let rotated = repo
+            .rotate_refresh_token(RotateRefreshToken {
+                refresh_token: issued.refresh_token,
+                ip_address: None,
+                user_agent: None,
+            })
+            .await?;
+
+        // 置き換え済みのトークンが再び使われた場合は、セッションで発行したアクセストークンも無効にする
+        let res = repo
+            .rotate_refresh_token(RotateRefreshToken {
+                refresh_token: stolen,
+                ip_address: None,
+                user_agent: None,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+        assert_eq!(repo.fetch_user_id_from_token(&access_token).await?, None);
+        assert!(repo.find_sessions(user_id).await?.is_empty());
+
+        let res = repo
+            .rotate_refresh_token(RotateRefreshToken {
+                refresh_token: rotated.refresh_token,
+                ip_address: None,
+                user_agent: None,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+
+        Ok(())
+    }
+}
@@ -150,14 +150,12 @@ impl BookRepository for BookRespositoryImpl {
                     isbn = $3,
                     description = $4
                 WHERE book_id = $5
-                AND   user_id = $6
             "#,
             event.title,
             event.author,
             event.isbn,
             event.description,
-            event.book_id as _,
-            event.requested_user as _
+            event.book_id as _
         )
         .execute(self.db.inner_ref())
         .await
@@ -174,10 +172,8 @@ impl BookRepository for BookRespositoryImpl {
             r#"
                 DELETE FROM books
                 WHERE book_id = $1
-                AND   user_id = $2
             "#,
-            event.book_id as _,
-            event.requested_user as _
+            event.book_id as _
         )
         .execute(self.db.inner_ref())
         .await
@@ -224,7 +220,10 @@ impl BookRespositoryImpl {
 mod tests {
     use std::str::FromStr;
 
-    use kernel::{model::user::event::CreateUser, repository::user::UserRepository};
+    use kernel::{
+        model::user::event::CreateUser,
+        repository::{auth::MockAuthRepository, user::UserRepository},
+    };
 
     use crate::repository::user::UserRepsitoryImpl;
 
@@ -245,7 +244,6 @@ mod tests {
             author: NEW_AUTHOR.into(),
             isbn: book.isbn,
             description: book.description,
-            requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
         };
         repo.update(update_book).await.unwrap();
 
@@ -257,10 +255,21 @@ mod tests {
 
     #[sqlx::test]
     async fn test_register_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
-        sqlx::query!(r#"INSERT INTO roles(name) VALUES('Admin'), ('User');"#)
-            .execute(&pool)
-            .await?;
-        let user_repo = UserRepsitoryImpl::new(ConnectionPool::new(pool.clone()));
+        sqlx::query!(
+            r#"INSERT INTO roles(name) VALUES('Admin'), ('User') ON CONFLICT DO NOTHING;"#
+        )
+        .execute(&pool)
+        .await?;
+        let redis_config = shared::config::RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let user_repo = UserRepsitoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            std::sync::Arc::new(MockAuthRepository::new()),
+            std::sync::Arc::new(crate::redis::RedisClient::new(&redis_config)?),
+            1000,
+        );
         let repo = BookRespositoryImpl::new(ConnectionPool::new(pool.clone()));
 
         let user = user_repo
@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{calendar::CalendarFeedToken, id::UserId},
+    repository::calendar::CalendarFeedRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{database::ConnectionPool, token::hash_token};
+
+#[derive(new)]
+pub struct CalendarFeedRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl CalendarFeedRepository for CalendarFeedRepositoryImpl {
+    async fn issue_token(&self, user_id: UserId) -> AppResult<CalendarFeedToken> {
+        let token = CalendarFeedToken::new();
+
+        // ユーザーごとにトークンは1つのみとし、再発行時は古いトークンを置き換える
+        sqlx::query!(
+            r#"
+                INSERT INTO calendar_feed_tokens (user_id, token_hash)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                SET token_hash = EXCLUDED.token_hash,
+                    created_at = CURRENT_TIMESTAMP(3)
+            "#,
+            user_id as _,
+            hash_token(&token.0)
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(token)
+    }
+
+    async fn revoke_token(&self, user_id: UserId) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM calendar_feed_tokens
+                WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(
+                "calendar feed token not found".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn find_user_id_by_token(&self, token: &CalendarFeedToken) -> AppResult<Option<UserId>> {
+        let row = sqlx::query!(
+            r#"
+                SELECT user_id AS "user_id: UserId"
+                FROM calendar_feed_tokens
+                WHERE token_hash = $1
+            "#,
+            hash_token(&token.0)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(row.map(|r| r.user_id))
+    }
+}
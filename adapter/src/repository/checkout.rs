@@ -1,18 +1,19 @@
 use crate::database::{
     model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
+    transaction::{is_retryable_error, IsolationLevel},
     ConnectionPool,
 };
 use async_trait::async_trait;
 use derive_new::new;
-use kernel::model::id::{BookId, CheckoutId, UserId};
-use kernel::model::{
-    checkout::{
-        event::{CreateCheckout, UpdateReturned},
-        Checkout,
-    },
+use kernel::model::checkout::{
+    event::{CreateCheckout, CreateCheckouts, UpdateReturned, UpdateReturnedBatch},
+    BatchFailureReason, BatchItem, BatchItemStatus, BatchMode, BatchResult, Checkout,
 };
+use kernel::model::id::{BookId, CheckoutId, UserId};
+use kernel::model::user::UserStatus;
 use kernel::repository::checkout::CheckouRepository;
 use shared::error::{AppError, AppResult};
+use sqlx::Acquire;
 
 #[derive(new)]
 pub struct CheckouRepositoryImpl {
@@ -23,186 +24,45 @@ pub struct CheckouRepositoryImpl {
 impl CheckouRepository for CheckouRepositoryImpl {
     // 貸出操作をおこなう
     async fn create(&self, event: CreateCheckout) -> AppResult<()> {
-        let mut tx = self.db.begin().await?;
-
-        // トランザクション分離レベルを SERIALIZABLE に設定
-        self.set_transaction_serializable(&mut tx).await?;
-
-        // 事前のチェックとして、以下をしらべる
-        // - 指定の蔵書IDを持つ蔵書が存在するか
-        // - 存在した場合、この蔵書は貸出中ではないか？
-        {
-            let res = sqlx::query_as!(
-                CheckoutStateRow,
-                r#"
-                    SELECT
-                        b.book_id,
-                        c.checkout_id AS "checkout_id?: CheckoutId",
-                        NULL AS "user_id?: UserId"
-                    FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE b.book_id = $1;
-                "#,
-                event.book_id as _
-            )
-            .fetch_optional(&mut *tx)
+        // トランザクション分離レベルを SERIALIZABLE にして実行する
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let event = event.clone();
+                Box::pin(async move { checkout_book(conn, &event).await.map(|_| ()) })
+            })
             .await
-            .map_err(AppError::SpecificOperationError)?;
-
-            match res {
-                // 指定した書籍が存在しない場合
-                None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        "書籍 ({}) が見つかりませんでした",
-                        event.book_id
-                    )))
-                }
-                // 指定した書籍が存在するが貸出中の場合
-                Some(CheckoutStateRow {
-                    checkout_id: Some(_),
-                    ..
-                }) => {
-                    return Err(AppError::UnprocessableEntity(format!(
-                        "書籍 ({}) に対する貸出がすでに存在してます",
-                        event.book_id
-                    )))
-                }
-                _ => {} // それ以外は処理続行
-            }
-        }
-
-        // 貸出処理を行う、つまり checkouts テーブルにレコードを追加する
-        let checkout_id = CheckoutId::new();
-        let res = sqlx::query!(
-            r#"
-                INSERT INTO checkouts
-                (checkout_id, book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3, $4)
-                ;
-            "#,
-            checkout_id as _,
-            event.book_id as _,
-            event.checked_out_by as _,
-            event.checked_out_at,
-        )
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::SpecificOperationError)?;
-
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No checkout record has been created".into(),
-            ));
-        }
-
-        tx.commit().await.map_err(AppError::TransactionError)?;
+    }
 
-        Ok(())
+    // 複数の蔵書の貸出操作を1つのトランザクション内でまとめておこなう
+    async fn create_batch(&self, event: CreateCheckouts) -> AppResult<BatchResult<BookId>> {
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                Box::pin(checkout_books(conn, event.clone()))
+            })
+            .await
     }
 
     // 返却操作をおこなう
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()> {
-        let mut tx = self.db.begin().await?;
-
-        // トランザクション分離レベルを SERIALIZABLE に設定
-        self.set_transaction_serializable(&mut tx).await?;
-
-        // 返却操作時は事前のチェックとして、以下をしらべる
-        // - 指定の蔵書IDをもつ蔵書が存在するか
-        // - 存在した場合
-        //  - この蔵書は貸出中であり
-        //  - かつ、借りたユーザーが指定のユーザーと同じか
-        //
-        // 上記の両方がYesだった場合、このブロック以降の処理にすすむ。
-        // なお、ブロックの仕様は意図的である。こうすることで、
-        // res 変数がシャドーイングで上書きされるのを防ぐなどの
-        // メリットがある
-        {
-            let res = sqlx::query_as!(
-                CheckoutStateRow,
-                r#"
-                    SELECT
-                        b.book_id,
-                        c.checkout_id AS "checkout_id?: CheckoutId",
-                        c.user_id AS "user_id?: UserId"
-                    FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1;
-                "#,
-                event.book_id as _,
-            )
-            .fetch_optional(&mut *tx)
+        // トランザクション分離レベルを SERIALIZABLE にして実行する
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let event = event.clone();
+                Box::pin(async move { return_book(conn, &event).await })
+            })
             .await
-            .map_err(AppError::SpecificOperationError)?;
-
-            match res {
-                // 指定した書籍がそもそも存在しない場合
-                None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        "書籍 ({}) が見つかりませんでした",
-                        event.book_id
-                    )))
-                }
-                // 指定した書籍が貸出中であり、貸出IDまたは借りたユーザーが異なる場合
-                Some(CheckoutStateRow {
-                    checkout_id: Some(c),
-                    user_id: Some(u),
-                    ..
-                }) if (c, u) != (event.checkout_id, event.returned_by) => {
-                    return Err(AppError::UnprocessableEntity(format!(
-                        "指定の書籍 (ID ({}), ユーザー ({}), 書籍 ({})) は返却できません",
-                        event.checkout_id, event.returned_by, event.book_id
-                    )))
-                }
-                _ => {} // それ以外は処理続行
-            }
-        }
-
-        // データベス上の返却操作として
-        // checkouts テーブルにある該当貸出IDのレコードを、
-        // returned_at を追加して returned_checkouts テーブルに INSERT する
-        let res = sqlx::query!(
-            r#"
-                INSERT INTO returned_checkouts
-                (checkout_id, book_id, user_id, checked_out_at, returned_at)
-                SELECT checkout_id, book_id, user_id, checked_out_at, $2
-                FROM checkouts
-                WHERE checkout_id = $1
-                ;
-            "#,
-            event.checkout_id as _,
-            event.returned_at,
-        )
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::SpecificOperationError)?;
-
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No returning record has been updated".into(),
-            ));
-        }
-
-        // 上記処理が成功したら　checkouts テーブルから該当貸出IDのレコードを削除する
-        let res = sqlx::query!(
-            r#"
-                DELETE FROM checkouts WHERE checkout_id = $1;
-            "#,
-            event.checkout_id as _,
-        )
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::SpecificOperationError)?;
-
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No checkout record has been deleted".into(),
-            ));
-        }
-
-        tx.commit().await.map_err(AppError::TransactionError)?;
+    }
 
-        Ok(())
+    // 複数の貸出の返却操作を1つのトランザクション内でまとめておこなう
+    async fn update_returned_batch(
+        &self,
+        event: UpdateReturnedBatch,
+    ) -> AppResult<BatchResult<CheckoutId>> {
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                Box::pin(return_books(conn, event.clone()))
+            })
+            .await
     }
 
     // すべての未返却の貸出情報を取得する
@@ -308,20 +168,6 @@ impl CheckouRepository for CheckouRepositoryImpl {
 }
 
 impl CheckouRepositoryImpl {
-    // create, update_returned メソッドでのトランザクションを利用するにあたり
-    // トランザクション分離レベルを SERIALIZABLE にするために
-    // 内部的に使うメソッド
-    async fn set_transaction_serializable(
-        &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    ) -> AppResult<()> {
-        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
-            .execute(&mut **tx)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
-        Ok(())
-    }
-
     // find_history_by_book_id で未返却の貸出情報を取得するために
     // 内部的に使うメソッド
     async fn find_unreturned_by_book_id(&self, book_id: BookId) -> AppResult<Option<Checkout>> {
@@ -350,3 +196,410 @@ impl CheckouRepositoryImpl {
         Ok(res)
     }
 }
+
+// 貸出処理の本体。create, create_batch から呼び出す
+pub(crate) async fn checkout_book(
+    conn: &mut sqlx::PgConnection,
+    event: &CreateCheckout,
+) -> AppResult<CheckoutId> {
+    // 事前のチェックとして、以下をしらべる
+    // - 指定の蔵書IDを持つ蔵書が存在するか
+    // - 存在した場合、この蔵書は貸出中ではないか？
+    {
+        let res = sqlx::query_as!(
+            CheckoutStateRow,
+            r#"
+                SELECT
+                    b.book_id,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    NULL AS "user_id?: UserId"
+                FROM books AS b
+                LEFT OUTER JOIN checkouts AS c USING(book_id)
+                WHERE b.book_id = $1;
+            "#,
+            event.book_id as _
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        match res {
+            // 指定した書籍が存在しない場合
+            None => {
+                return Err(AppError::EntityNotFound(format!(
+                    "書籍 ({}) が見つかりませんでした",
+                    event.book_id
+                )))
+            }
+            // 指定した書籍が存在するが貸出中の場合
+            Some(CheckoutStateRow {
+                checkout_id: Some(_),
+                ..
+            }) => {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "書籍 ({}) に対する貸出がすでに存在してます",
+                    event.book_id
+                )))
+            }
+            _ => {} // それ以外は処理続行
+        }
+    }
+
+    // 利用停止中や無効化されたユーザーには貸し出さない（返却はできる）
+    {
+        let status = sqlx::query_scalar!(
+            r#"SELECT status FROM users WHERE user_id = $1"#,
+            event.checked_out_by as _
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if status.as_deref() != Some(UserStatus::Active.as_ref()) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "ユーザー ({}) は現在、貸出を利用できません",
+                event.checked_out_by
+            )));
+        }
+    }
+
+    // 貸出処理を行う、つまり checkouts テーブルにレコードを追加する
+    let checkout_id = CheckoutId::new();
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO checkouts
+            (checkout_id, book_id, user_id, checked_out_at)
+            VALUES ($1, $2, $3, $4)
+            ;
+        "#,
+        checkout_id as _,
+        event.book_id as _,
+        event.checked_out_by as _,
+        event.checked_out_at,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "No checkout record has been created".into(),
+        ));
+    }
+
+    Ok(checkout_id)
+}
+
+// 返却処理の本体。update_returned, update_returned_batch から呼び出す
+pub(crate) async fn return_book(
+    conn: &mut sqlx::PgConnection,
+    event: &UpdateReturned,
+) -> AppResult<()> {
+    // 返却操作時は事前のチェックとして、以下をしらべる
+    // - 指定の蔵書IDをもつ蔵書が存在するか
+    // - 存在した場合
+    //  - この蔵書は貸出中であり
+    //  - かつ、借りたユーザーが指定のユーザーと同じか（他のユーザーの貸出も返却できる場合は問わない）
+    //
+    // 上記の両方がYesだった場合、このブロック以降の処理にすすむ。
+    // なお、ブロックの仕様は意図的である。こうすることで、
+    // res 変数がシャドーイングで上書きされるのを防ぐなどの
+    // メリットがある
+    {
+        let res = sqlx::query_as!(
+            CheckoutStateRow,
+            r#"
+                SELECT
+                    b.book_id,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    c.user_id AS "user_id?: UserId"
+                FROM books AS b
+                LEFT OUTER JOIN checkouts AS c USING(book_id)
+                WHERE book_id = $1;
+            "#,
+            event.book_id as _,
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        match res {
+            // 指定した書籍がそもそも存在しない場合
+            None => {
+                return Err(AppError::EntityNotFound(format!(
+                    "書籍 ({}) が見つかりませんでした",
+                    event.book_id
+                )))
+            }
+            // 指定した書籍が貸出中であり、貸出IDまたは借りたユーザーが異なる場合
+            Some(CheckoutStateRow {
+                checkout_id: Some(c),
+                user_id: Some(u),
+                ..
+            }) if c != event.checkout_id || (u != event.returned_by && !event.return_any) => {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "指定の書籍 (ID ({}), ユーザー ({}), 書籍 ({})) は返却できません",
+                    event.checkout_id, event.returned_by, event.book_id
+                )))
+            }
+            _ => {} // それ以外は処理続行
+        }
+    }
+
+    // データベス上の返却操作として
+    // checkouts テーブルにある該当貸出IDのレコードを、
+    // returned_at を追加して returned_checkouts テーブルに INSERT する
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO returned_checkouts
+            (checkout_id, book_id, user_id, checked_out_at, returned_at)
+            SELECT checkout_id, book_id, user_id, checked_out_at, $2
+            FROM checkouts
+            WHERE checkout_id = $1
+            ;
+        "#,
+        event.checkout_id as _,
+        event.returned_at,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "No returning record has been updated".into(),
+        ));
+    }
+
+    // 上記処理が成功したら　checkouts テーブルから該当貸出IDのレコードを削除する
+    let res = sqlx::query!(
+        r#"
+            DELETE FROM checkouts WHERE checkout_id = $1;
+        "#,
+        event.checkout_id as _,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "No checkout record has been deleted".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+// 一括貸出の本体。蔵書ごとにセーブポイントを作成し、失敗した貸出のみを取り消せるようにする
+async fn checkout_books(
+    conn: &mut sqlx::PgConnection,
+    event: CreateCheckouts,
+) -> AppResult<BatchResult<BookId>> {
+    // AllOrNothing で失敗した場合にまとめて取り消せるよう、一括操作全体もセーブポイントで囲む
+    let mut batch = conn.begin().await.map_err(AppError::TransactionError)?;
+
+    let mut items = Vec::with_capacity(event.book_ids.len());
+    for book_id in event.book_ids {
+        let checkout = CreateCheckout::new(book_id, event.checked_out_by, event.checked_out_at);
+
+        let mut savepoint = batch.begin().await.map_err(AppError::TransactionError)?;
+        let res = checkout_book(&mut savepoint, &checkout).await;
+        let status = finish_item(savepoint, res).await?;
+        items.push(BatchItem {
+            key: book_id,
+            status,
+        });
+    }
+
+    finish_batch(batch, event.mode, items).await
+}
+
+// 一括返却の本体。貸出ごとにセーブポイントを作成し、失敗した返却のみを取り消せるようにする
+async fn return_books(
+    conn: &mut sqlx::PgConnection,
+    event: UpdateReturnedBatch,
+) -> AppResult<BatchResult<CheckoutId>> {
+    // AllOrNothing で失敗した場合にまとめて取り消せるよう、一括操作全体もセーブポイントで囲む
+    let mut batch = conn.begin().await.map_err(AppError::TransactionError)?;
+
+    let mut items = Vec::with_capacity(event.checkout_ids.len());
+    for checkout_id in event.checkout_ids {
+        let mut savepoint = batch.begin().await.map_err(AppError::TransactionError)?;
+        let res = match find_book_id_by_checkout_id(&mut savepoint, checkout_id).await {
+            Ok(book_id) => {
+                let returned = UpdateReturned::new(
+                    checkout_id,
+                    book_id,
+                    event.returned_by,
+                    event.returned_at,
+                    event.return_any,
+                );
+                return_book(&mut savepoint, &returned)
+                    .await
+                    .map(|_| checkout_id)
+            }
+            Err(e) => Err(e),
+        };
+        let status = finish_item(savepoint, res).await?;
+        items.push(BatchItem {
+            key: checkout_id,
+            status,
+        });
+    }
+
+    finish_batch(batch, event.mode, items).await
+}
+
+// 1件分の操作の結果に応じてセーブポイントを確定または取り消す
+async fn finish_item(
+    savepoint: sqlx::Transaction<'_, sqlx::Postgres>,
+    res: AppResult<CheckoutId>,
+) -> AppResult<BatchItemStatus> {
+    match res {
+        Ok(checkout_id) => {
+            savepoint
+                .commit()
+                .await
+                .map_err(AppError::TransactionError)?;
+            Ok(BatchItemStatus::Succeeded { checkout_id })
+        }
+        // シリアライズ失敗などはトランザクション全体をやり直す必要があるため、
+        // 項目単位の失敗とせずにそのまま返す
+        Err(e) if is_retryable_error(&e) => Err(e),
+        Err(e) => {
+            savepoint
+                .rollback()
+                .await
+                .map_err(AppError::TransactionError)?;
+            // エラーの詳細は内部の情報を含みうるため、レスポンスには理由のコードのみを返し、詳細はログに残す
+            let reason = match e {
+                AppError::EntityNotFound(_) => BatchFailureReason::NotFound,
+                AppError::UnprocessableEntity(_) => BatchFailureReason::Unavailable,
+                _ => BatchFailureReason::InternalError,
+            };
+            tracing::warn!(reason = reason.as_ref(), error = %e, "Batch item has failed");
+            Ok(BatchItemStatus::Failed { reason })
+        }
+    }
+}
+
+// 一括返却では貸出IDのみが指定されるため、返却処理の前に対象の蔵書IDを引く
+async fn find_book_id_by_checkout_id(
+    conn: &mut sqlx::PgConnection,
+    checkout_id: CheckoutId,
+) -> AppResult<BookId> {
+    sqlx::query!(
+        r#"
+            SELECT book_id AS "book_id: BookId"
+            FROM checkouts
+            WHERE checkout_id = $1;
+        "#,
+        checkout_id as _,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?
+    .map(|row| row.book_id)
+    .ok_or_else(|| {
+        AppError::EntityNotFound(format!("貸出 ({}) が見つかりませんでした", checkout_id))
+    })
+}
+
+// 一括操作の結果に応じてセーブポイントを確定する
+// AllOrNothing で1件でも失敗していた場合は、成功していた操作も含めてすべて取り消す
+async fn finish_batch<K>(
+    tx: sqlx::Transaction<'_, sqlx::Postgres>,
+    mode: BatchMode,
+    items: Vec<BatchItem<K>>,
+) -> AppResult<BatchResult<K>> {
+    let mut result = BatchResult {
+        committed: true,
+        items,
+    };
+
+    if mode == BatchMode::AllOrNothing && result.has_failure() {
+        tx.rollback().await.map_err(AppError::TransactionError)?;
+        for item in result.items.iter_mut() {
+            if let BatchItemStatus::Succeeded { checkout_id } = item.status {
+                item.status = BatchItemStatus::RolledBack { checkout_id };
+            }
+        }
+        result.committed = false;
+    } else {
+        tx.commit().await.map_err(AppError::TransactionError)?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn batch(book_ids: Vec<BookId>, mode: BatchMode) -> CreateCheckouts {
+        CreateCheckouts::new(
+            book_ids,
+            UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
+            chrono::Utc::now(),
+            mode,
+        )
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_create_batch_all_or_nothing(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckouRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let missing_book_id = BookId::new();
+
+        let result = repo
+            .create_batch(batch(
+                vec![book_id, missing_book_id],
+                BatchMode::AllOrNothing,
+            ))
+            .await?;
+
+        assert!(!result.committed);
+        assert!(matches!(
+            result.items[0].status,
+            BatchItemStatus::RolledBack { .. }
+        ));
+        assert_eq!(
+            result.items[1].status,
+            BatchItemStatus::Failed {
+                reason: BatchFailureReason::NotFound
+            }
+        );
+        assert!(repo.find_unreturned_all().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_create_batch_best_effort(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckouRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        // 同じ蔵書を2回指定すると、2件目は貸出中のため失敗する
+        let result = repo
+            .create_batch(batch(vec![book_id, book_id], BatchMode::BestEffort))
+            .await?;
+
+        assert!(result.committed);
+        assert!(matches!(
+            result.items[0].status,
+            BatchItemStatus::Succeeded { .. }
+        ));
+        assert_eq!(
+            result.items[1].status,
+            BatchItemStatus::Failed {
+                reason: BatchFailureReason::Unavailable
+            }
+        );
+        assert_eq!(repo.find_unreturned_all().await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+-- 利用統計の集計に使う貸出の記録
+-- 集計に含めることに同意した利用者と、同意していない利用者を1人ずつ用意する
+INSERT INTO users(user_id, name, email, password_hash, role_id, share_loan_statistics)
+SELECT
+    'a1b2c3d4-0000-4000-8000-000000000001'
+    , 'Sharing Borrower'
+    , 'sharing.borrower@example.com'
+    , '!'
+    , role_id
+    , true
+FROM roles WHERE name = 'User';
+
+INSERT INTO users(user_id, name, email, password_hash, role_id, share_loan_statistics)
+SELECT
+    'a1b2c3d4-0000-4000-8000-000000000002'
+    , 'Private Borrower'
+    , 'private.borrower@example.com'
+    , '!'
+    , role_id
+    , false
+FROM roles WHERE name = 'User';
+
+-- 返却済みの貸出。貸出期間はそれぞれ 2 日、4 日、1 日、3 日
+INSERT INTO returned_checkouts(checkout_id, book_id, user_id, checked_out_at, returned_at)
+VALUES
+  (
+    'c0000000-0000-4000-8000-000000000001',
+    '9890736e-a4e4-461a-a77d-eac3517ef11b',
+    'a1b2c3d4-0000-4000-8000-000000000001',
+    '2024-01-10T00:00:00Z',
+    '2024-01-12T00:00:00Z'
+  ),
+  (
+    'c0000000-0000-4000-8000-000000000002',
+    '9890736e-a4e4-461a-a77d-eac3517ef11b',
+    'a1b2c3d4-0000-4000-8000-000000000002',
+    '2024-01-20T00:00:00Z',
+    '2024-01-24T00:00:00Z'
+  ),
+  (
+    'c0000000-0000-4000-8000-000000000003',
+    '9890736e-a4e4-461a-a77d-eac3517ef11b',
+    'a1b2c3d4-0000-4000-8000-000000000001',
+    '2024-02-05T00:00:00Z',
+    '2024-02-06T00:00:00Z'
+  ),
+  (
+    'c0000000-0000-4000-8000-000000000004',
+    'f397b83a-dd2a-4a01-9e77-db1eea7de5b6',
+    'a1b2c3d4-0000-4000-8000-000000000002',
+    '2024-02-10T00:00:00Z',
+    '2024-02-13T00:00:00Z'
+  );
+
+-- 貸出中の蔵書
+INSERT INTO checkouts(checkout_id, book_id, user_id, checked_out_at)
+VALUES
+  (
+    'c0000000-0000-4000-8000-000000000005',
+    'f397b83a-dd2a-4a01-9e77-db1eea7de5b6',
+    'a1b2c3d4-0000-4000-8000-000000000001',
+    '2024-03-01T00:00:00Z'
+  );
@@ -0,0 +1,493 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        invitation::{
+            event::{AcceptInvitation, CreateInvitation},
+            Invitation, InvitationToken, IssuedInvitation,
+        },
+        user::UserStatus,
+    },
+    repository::invitation::InvitationRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        conflict_on_unique_violation, model::invitation::InvitationRow,
+        transaction::IsolationLevel, ConnectionPool,
+    },
+    password::{hash_password, UNUSABLE_PASSWORD_HASH},
+    token::hash_token,
+};
+
+#[derive(new)]
+pub struct InvitationRepositoryImpl {
+    db: ConnectionPool,
+    // 招待の有効期間（秒）
+    ttl: u64,
+}
+
+#[async_trait]
+impl InvitationRepository for InvitationRepositoryImpl {
+    async fn create(&self, event: CreateInvitation) -> AppResult<IssuedInvitation> {
+        let mut issued = self.create_batch(vec![event]).await?;
+        Ok(issued.remove(0))
+    }
+
+    async fn create_batch(
+        &self,
+        events: Vec<CreateInvitation>,
+    ) -> AppResult<Vec<IssuedInvitation>> {
+        let pending = events
+            .into_iter()
+            .map(|event| (UserId::new(), event, InvitationToken::new()))
+            .collect::<Vec<_>>();
+        let expires_at = self.expires_at();
+
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let rows = pending
+                    .iter()
+                    .map(|(user_id, event, token)| {
+                        (
+                            *user_id,
+                            event.email.clone(),
+                            event.name.clone(),
+                            event.role.clone(),
+                            event.invited_by,
+                            hash_token(&token.0),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Box::pin(async move {
+                    for (user_id, email, name, role, invited_by, token_hash) in rows {
+                        // 名前とパスワードは受け入れ時に設定するため、名前は指定がなければメールアドレスを入れておき、
+                        // パスワードはどのような値でもログインできないハッシュにしておく
+                        let res = sqlx::query!(
+                            r#"
+                                INSERT INTO users(user_id, name, email, password_hash, role_id, status)
+                                SELECT $1, COALESCE($3, $2), $2, $4, role_id, $5 FROM roles WHERE name = $6
+                            "#,
+                            user_id as _,
+                            email,
+                            name,
+                            UNUSABLE_PASSWORD_HASH,
+                            UserStatus::Invited.as_ref(),
+                            role.as_ref()
+                        )
+                        .execute(&mut *conn)
+                        .await
+                        .map_err(conflict_on_unique_violation(
+                            "このメールアドレスはすでに登録されています",
+                        ))?;
+                        if res.rows_affected() < 1 {
+                            return Err(AppError::EntityNotFound(
+                                "Specified role not found".into(),
+                            ));
+                        }
+
+                        sqlx::query!(
+                            r#"
+                                INSERT INTO user_invitations(user_id, token_hash, invited_by, expires_at)
+                                VALUES ($1, $2, $3, $4)
+                            "#,
+                            user_id as _,
+                            token_hash,
+                            invited_by as _,
+                            expires_at
+                        )
+                        .execute(&mut *conn)
+                        .await
+                        .map_err(AppError::SpecificOperationError)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        let mut issued = Vec::with_capacity(pending.len());
+        for (user_id, _, token) in pending {
+            let invitation = self.find_by_user_id(user_id).await?;
+            issued.push(IssuedInvitation { invitation, token });
+        }
+        Ok(issued)
+    }
+
+    async fn find_pending(&self) -> AppResult<Vec<Invitation>> {
+        sqlx::query_as!(
+            InvitationRow,
+            r#"
+                SELECT
+                    i.user_id,
+                    u.email,
+                    r.name AS role_name,
+                    i.invited_by,
+                    i.expires_at,
+                    i.created_at
+                FROM user_invitations AS i
+                INNER JOIN users AS u USING(user_id)
+                INNER JOIN roles AS r ON r.role_id = u.role_id
+                WHERE u.status = $1
+                ORDER BY i.created_at DESC
+            "#,
+            UserStatus::Invited.as_ref()
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Invitation::try_from)
+        .collect()
+    }
+
+    async fn resend(&self, user_id: UserId) -> AppResult<IssuedInvitation> {
+        let token = InvitationToken::new();
+        let res = sqlx::query!(
+            r#"
+                UPDATE user_invitations AS i
+                SET token_hash = $2,
+                    expires_at = $3
+                FROM users AS u
+                WHERE i.user_id = $1
+                AND u.user_id = i.user_id
+                AND u.status = $4
+            "#,
+            user_id as _,
+            hash_token(&token.0),
+            self.expires_at(),
+            UserStatus::Invited.as_ref()
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(not_found(user_id));
+        }
+
+        let invitation = self.find_by_user_id(user_id).await?;
+        Ok(IssuedInvitation { invitation, token })
+    }
+
+    async fn revoke(&self, user_id: UserId) -> AppResult<()> {
+        // 招待中のユーザーは蔵書や貸出の記録を持たないため、ユーザーごと削除する
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM users AS u
+                USING user_invitations AS i
+                WHERE u.user_id = $1
+                AND i.user_id = u.user_id
+                AND u.status = $2
+            "#,
+            user_id as _,
+            UserStatus::Invited.as_ref()
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(not_found(user_id));
+        }
+        Ok(())
+    }
+
+    async fn find_email(&self, token: &InvitationToken) -> AppResult<Option<String>> {
+        sqlx::query_scalar!(
+            r#"
+                SELECT u.email FROM user_invitations AS i
+                INNER JOIN users AS u USING(user_id)
+                WHERE i.token_hash = $1
+                AND i.expires_at > CURRENT_TIMESTAMP(3)
+            "#,
+            hash_token(&token.0)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn accept(&self, event: AcceptInvitation) -> AppResult<UserId> {
+        let password_hash = hash_password(&event.password).await?;
+        let token_hash = hash_token(&event.token.0);
+
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let name = event.name.clone();
+                let password_hash = password_hash.clone();
+                let token_hash = token_hash.clone();
+                Box::pin(async move {
+                    // 招待は一度しか使えないよう、受け入れと同時に削除する
+                    // 有効期限が切れた招待は、再送できるよう残しておく
+                    let user_id = sqlx::query_scalar!(
+                        r#"
+                            DELETE FROM user_invitations
+                            WHERE token_hash = $1
+                            AND expires_at > CURRENT_TIMESTAMP(3)
+                            RETURNING user_id AS "user_id: UserId"
+                        "#,
+                        token_hash
+                    )
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?
+                    .ok_or_else(|| {
+                        AppError::EntityNotFound(
+                            "招待が無効か、有効期限が切れています".into(),
+                        )
+                    })?;
+
+                    // 招待のメールを受け取れたことで、メールアドレスの確認も済んだものとする
+                    let res = sqlx::query!(
+                        r#"
+                            UPDATE users
+                            SET name = $2,
+                                password_hash = $3,
+                                status = $4,
+                                status_changed_at = CURRENT_TIMESTAMP(3),
+                                email_verified_at = CURRENT_TIMESTAMP(3)
+                            WHERE user_id = $1
+                            AND status = $5
+                        "#,
+                        user_id as _,
+                        name,
+                        password_hash,
+                        UserStatus::Active.as_ref(),
+                        UserStatus::Invited.as_ref()
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    if res.rows_affected() < 1 {
+                        return Err(not_found(user_id));
+                    }
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO user_status_changes(user_id, from_status, to_status, changed_by)
+                            VALUES ($1, $2, $3, $1)
+                        "#,
+                        user_id as _,
+                        UserStatus::Invited.as_ref(),
+                        UserStatus::Active.as_ref()
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    Ok(user_id)
+                })
+            })
+            .await
+    }
+}
+
+impl InvitationRepositoryImpl {
+    fn expires_at(&self) -> DateTime<Utc> {
+        Utc::now() + Duration::seconds(self.ttl as i64)
+    }
+
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Invitation> {
+        sqlx::query_as!(
+            InvitationRow,
+            r#"
+                SELECT
+                    i.user_id,
+                    u.email,
+                    r.name AS role_name,
+                    i.invited_by,
+                    i.expires_at,
+                    i.created_at
+                FROM user_invitations AS i
+                INNER JOIN users AS u USING(user_id)
+                INNER JOIN roles AS r ON r.role_id = u.role_id
+                WHERE i.user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| not_found(user_id))?
+        .try_into()
+    }
+}
+
+fn not_found(user_id: UserId) -> AppError {
+    AppError::EntityNotFound(format!(
+        "ユーザー ({}) への招待が見つかりませんでした",
+        user_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use kernel::model::role::Role;
+
+    use super::*;
+    use crate::password::verify_password;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_invite_and_accept(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = InvitationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3600);
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        let issued = repo
+            .create(CreateInvitation {
+                email: "invitee@example.com".into(),
+                name: None,
+                role: Role::User,
+                invited_by: admin_id,
+            })
+            .await?;
+        assert_eq!(issued.invitation.invited_by, Some(admin_id));
+
+        // 同じメールアドレスには招待できない
+        let res = repo
+            .create(CreateInvitation {
+                email: "invitee@example.com".into(),
+                name: None,
+                role: Role::User,
+                invited_by: admin_id,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityConflict(_))));
+
+        // 受け入れるまでは、どのようなパスワードでも検証に成功しない
+        let password_hash = sqlx::query_scalar!(
+            r#"SELECT password_hash FROM users WHERE user_id = $1"#,
+            issued.invitation.user_id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(password_hash, UNUSABLE_PASSWORD_HASH);
+
+        // 再送すると、以前のトークンは使えなくなる
+        let resent = repo.resend(issued.invitation.user_id).await?;
+        let res = repo
+            .accept(AcceptInvitation {
+                token: issued.token,
+                name: "Invitee".into(),
+                password: "invitee_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert_eq!(repo.find_pending().await?.len(), 1);
+
+        let user_id = repo
+            .accept(AcceptInvitation {
+                token: InvitationToken(resent.token.0.clone()),
+                name: "Invitee".into(),
+                password: "invitee_password".into(),
+            })
+            .await?;
+        assert_eq!(user_id, issued.invitation.user_id);
+        assert!(repo.find_pending().await?.is_empty());
+
+        let row = sqlx::query!(
+            r#"SELECT name, status, password_hash FROM users WHERE user_id = $1"#,
+            user_id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(row.name, "Invitee");
+        assert_eq!(row.status, "active");
+        assert!(verify_password("invitee_password", &row.password_hash)
+            .await?
+            .is_valid());
+
+        // 受け入れた招待は、もう一度使うことも取り消すこともできない
+        let res = repo
+            .accept(AcceptInvitation {
+                token: resent.token,
+                name: "Invitee".into(),
+                password: "invitee_password".into(),
+            })
+            .await;
+        assert!(res.is_err());
+        assert!(repo.revoke(user_id).await.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_create_batch_is_all_or_nothing(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = InvitationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3600);
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let event = |email: &str, role: Role| CreateInvitation {
+            email: email.into(),
+            name: Some(format!("{email} の名前")),
+            role,
+            invited_by: admin_id,
+        };
+
+        // 1件でも失敗すると、ほかのユーザーも作成されない
+        let res = repo
+            .create_batch(vec![
+                event("first@example.com", Role::User),
+                event("second@example.com", Role::Custom("Missing".into())),
+            ])
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(repo.find_pending().await?.is_empty());
+
+        let issued = repo
+            .create_batch(vec![
+                event("first@example.com", Role::User),
+                event("second@example.com", Role::Admin),
+            ])
+            .await?;
+        assert_eq!(issued.len(), 2);
+        assert_eq!(issued[1].invitation.role, Role::Admin);
+
+        let name = sqlx::query_scalar!(
+            r#"SELECT name FROM users WHERE user_id = $1"#,
+            issued[0].invitation.user_id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(name, "first@example.com の名前");
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_expired_invitation(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let expired = InvitationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 0);
+        let issued = expired
+            .create(CreateInvitation {
+                email: "invitee@example.com".into(),
+                name: None,
+                role: Role::User,
+                invited_by: admin_id,
+            })
+            .await?;
+
+        let res = expired
+            .accept(AcceptInvitation {
+                token: issued.token,
+                name: "Invitee".into(),
+                password: "invitee_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 期限が切れた招待も一覧に残り、取り消すとユーザーも削除される
+        assert_eq!(expired.find_pending().await?.len(), 1);
+        expired.revoke(issued.invitation.user_id).await?;
+        assert!(expired.find_pending().await?.is_empty());
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM users"#)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(count, 1);
+
+        Ok(())
+    }
+}
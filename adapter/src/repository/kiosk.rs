@@ -0,0 +1,435 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        checkout::event::{CreateCheckout, UpdateReturned},
+        id::{CheckoutId, KioskId, UserId},
+        kiosk::{
+            event::{CreateKioskDevice, KioskScan, UpdateBookBarcode, UpdateUserBadgeCode},
+            isbn_candidates, KioskAction, KioskDevice, KioskScanResult, KioskSecret,
+            RegisteredKioskDevice,
+        },
+    },
+    repository::kiosk::KioskRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::kiosk::{KioskBookRow, KioskDeviceRow},
+        transaction::IsolationLevel,
+        ConnectionPool,
+    },
+    repository::checkout::{checkout_book, return_book},
+    token::hash_token,
+};
+
+#[derive(new)]
+pub struct KioskRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl KioskRepository for KioskRepositoryImpl {
+    async fn register_device(&self, event: CreateKioskDevice) -> AppResult<RegisteredKioskDevice> {
+        let secret = KioskSecret::new();
+
+        let device = sqlx::query_as!(
+            KioskDeviceRow,
+            r#"
+                INSERT INTO kiosk_devices (name, secret_hash)
+                VALUES ($1, $2)
+                RETURNING kiosk_id, name, created_at, last_used_at
+            "#,
+            event.name,
+            hash_token(&secret.0)
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(RegisteredKioskDevice {
+            device: device.into(),
+            secret,
+        })
+    }
+
+    async fn find_all_devices(&self) -> AppResult<Vec<KioskDevice>> {
+        sqlx::query_as!(
+            KioskDeviceRow,
+            r#"
+                SELECT kiosk_id, name, created_at, last_used_at
+                FROM kiosk_devices
+                ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn delete_device(&self, kiosk_id: KioskId) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM kiosk_devices
+                WHERE kiosk_id = $1
+            "#,
+            kiosk_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "端末 ({}) が見つかりませんでした",
+                kiosk_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn authenticate(&self, secret: &KioskSecret) -> AppResult<Option<KioskDevice>> {
+        sqlx::query_as!(
+            KioskDeviceRow,
+            r#"
+                UPDATE kiosk_devices
+                SET last_used_at = CURRENT_TIMESTAMP(3)
+                WHERE secret_hash = $1
+                RETURNING kiosk_id, name, created_at, last_used_at
+            "#,
+            hash_token(&secret.0)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map(|row| row.map(Into::into))
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn update_book_barcode(&self, event: UpdateBookBarcode) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE books
+                SET barcode = $2
+                WHERE book_id = $1
+            "#,
+            event.book_id as _,
+            event.barcode
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| map_unique_violation(e, "バーコード"))?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "書籍 ({}) が見つかりませんでした",
+                event.book_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn update_user_badge_code(&self, event: UpdateUserBadgeCode) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET badge_code = $2
+                WHERE user_id = $1
+            "#,
+            event.user_id as _,
+            event.badge_code
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| map_unique_violation(e, "利用者カードのコード"))?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "ユーザー ({}) が見つかりませんでした",
+                event.user_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn scan(&self, event: KioskScan) -> AppResult<KioskScanResult> {
+        // 貸出・返却と同様に、トランザクション分離レベルを SERIALIZABLE にして実行する
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                Box::pin(toggle_checkout(conn, event.clone()))
+            })
+            .await
+    }
+}
+
+// 読み取った蔵書の貸出状況に応じて、貸出または返却をおこなう
+async fn toggle_checkout(
+    conn: &mut sqlx::PgConnection,
+    event: KioskScan,
+) -> AppResult<KioskScanResult> {
+    let user = sqlx::query!(
+        r#"
+            SELECT user_id AS "user_id: UserId", name
+            FROM users
+            WHERE badge_code = $1
+        "#,
+        event.badge_code
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?
+    .ok_or_else(|| {
+        AppError::EntityNotFound(format!(
+            "利用者カード ({}) が見つかりませんでした",
+            event.badge_code
+        ))
+    })?;
+
+    // 蔵書ごとのバーコードのほか、ISBN でも蔵書を特定できるようにする
+    // 同じ ISBN の蔵書が複数ある場合は、そのすべてが候補となる
+    let isbns = isbn_candidates(&event.book_code);
+    let books = sqlx::query_as!(
+        KioskBookRow,
+        r#"
+            SELECT
+                b.book_id,
+                b.title,
+                c.checkout_id AS "checkout_id?: CheckoutId",
+                c.user_id AS "user_id?: UserId"
+            FROM books AS b
+            LEFT OUTER JOIN checkouts AS c USING(book_id)
+            WHERE b.barcode = $1
+            OR regexp_replace(upper(b.isbn), '[^0-9X]', '', 'g') = ANY($2)
+            ORDER BY b.created_at ASC
+        "#,
+        event.book_code,
+        &isbns[..]
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if books.is_empty() {
+        return Err(AppError::EntityNotFound(format!(
+            "書籍 ({}) が見つかりませんでした",
+            event.book_code
+        )));
+    }
+
+    // 読み取った利用者が借りている蔵書があれば返却する
+    let returning = books.iter().find_map(|book| match book {
+        KioskBookRow {
+            checkout_id: Some(checkout_id),
+            user_id: Some(user_id),
+            ..
+        } if *user_id == user.user_id => Some((book, *checkout_id)),
+        _ => None,
+    });
+    if let Some((book, checkout_id)) = returning {
+        let update_returned = UpdateReturned::new(
+            checkout_id,
+            book.book_id,
+            user.user_id,
+            event.scanned_at,
+            false,
+        );
+        return_book(&mut *conn, &update_returned).await?;
+
+        return Ok(KioskScanResult {
+            action: KioskAction::Returned,
+            checkout_id,
+            book_id: book.book_id,
+            book_title: book.title.clone(),
+            user_id: user.user_id,
+            user_name: user.name,
+        });
+    }
+
+    // そうでなければ、貸出中でない蔵書を貸し出す
+    let Some(book) = books.iter().find(|book| book.checkout_id.is_none()) else {
+        return Err(AppError::UnprocessableEntity(format!(
+            "書籍 ({}) はすべて貸出中です",
+            event.book_code
+        )));
+    };
+    let create_checkout = CreateCheckout::new(book.book_id, user.user_id, event.scanned_at);
+    let checkout_id = checkout_book(&mut *conn, &create_checkout).await?;
+
+    Ok(KioskScanResult {
+        action: KioskAction::CheckedOut,
+        checkout_id,
+        book_id: book.book_id,
+        book_title: book.title.clone(),
+        user_id: user.user_id,
+        user_name: user.name,
+    })
+}
+
+// 一意制約に違反した場合は、すでに他の蔵書・ユーザーで使われているものとして扱う
+fn map_unique_violation(e: sqlx::Error, label: &str) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::UnprocessableEntity(format!("指定の{}はすでに使われています", label))
+        }
+        _ => AppError::SpecificOperationError(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::Utc;
+    use kernel::model::{id::BookId, user::UserStatus};
+
+    use super::*;
+
+    const BADGE_CODE: &str = "BADGE-0001";
+    const BOOK_CODE: &str = "BOOK-0001";
+
+    // 管理者に利用者カードを、1冊目の蔵書にバーコードを割り当てる
+    async fn setup(pool: &sqlx::PgPool) -> anyhow::Result<(KioskRepositoryImpl, KioskId, UserId)> {
+        let repo = KioskRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        let registered = repo
+            .register_device(CreateKioskDevice {
+                name: "Front desk".into(),
+            })
+            .await?;
+        repo.update_user_badge_code(UpdateUserBadgeCode::new(user_id, Some(BADGE_CODE.into())))
+            .await?;
+        repo.update_book_barcode(UpdateBookBarcode::new(book_id, Some(BOOK_CODE.into())))
+            .await?;
+
+        Ok((repo, registered.device.id, user_id))
+    }
+
+    async fn checkout_count(pool: &sqlx::PgPool) -> anyhow::Result<i64> {
+        Ok(
+            sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM checkouts"#)
+                .fetch_one(pool)
+                .await?,
+        )
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_scan_toggles_checkout(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, kiosk_id, user_id) = setup(&pool).await?;
+
+        // 貸出中でなければ貸し出し、同じ利用者がもう一度読み取ると返却する
+        let checked_out = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                BOOK_CODE.into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await?;
+        assert_eq!(checked_out.action, KioskAction::CheckedOut);
+        assert_eq!(checked_out.user_id, user_id);
+        assert_eq!(checkout_count(&pool).await?, 1);
+
+        let returned = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                BOOK_CODE.into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await?;
+        assert_eq!(returned.action, KioskAction::Returned);
+        assert_eq!(returned.checkout_id, checked_out.checkout_id);
+        assert_eq!(returned.book_id, checked_out.book_id);
+        assert_eq!(checkout_count(&pool).await?, 0);
+
+        // ISBN を読み取った場合も、同じ蔵書として扱う
+        let checked_out = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                "9784798061702".into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await?;
+        assert_eq!(checked_out.action, KioskAction::CheckedOut);
+        assert_eq!(checked_out.book_id, returned.book_id);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_scan_with_inactive_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, kiosk_id, user_id) = setup(&pool).await?;
+        repo.scan(KioskScan::new(
+            kiosk_id,
+            BOOK_CODE.into(),
+            BADGE_CODE.into(),
+            Utc::now(),
+        ))
+        .await?;
+        sqlx::query!(
+            "UPDATE users SET status = $2 WHERE user_id = $1",
+            user_id as _,
+            UserStatus::Suspended.as_ref()
+        )
+        .execute(&pool)
+        .await?;
+
+        // 利用停止中の利用者でも、借りている蔵書は返却できる
+        let returned = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                BOOK_CODE.into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await?;
+        assert_eq!(returned.action, KioskAction::Returned);
+
+        // 新たに貸し出すことはできない
+        let res = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                BOOK_CODE.into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        assert_eq!(checkout_count(&pool).await?, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_scan_with_unknown_codes(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, kiosk_id, _) = setup(&pool).await?;
+
+        // 登録されていない利用者カードでは、貸出も返却もしない
+        let res = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                BOOK_CODE.into(),
+                "BADGE-9999".into(),
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        let res = repo
+            .scan(KioskScan::new(
+                kiosk_id,
+                "BOOK-9999".into(),
+                BADGE_CODE.into(),
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert_eq!(checkout_count(&pool).await?, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{id::UserId, login_throttle::LoginAttempt},
+    repository::login_throttle::LoginThrottleRepository,
+};
+use shared::{
+    config::LoginThrottleConfig,
+    error::{AppError, AppResult},
+};
+
+use crate::{
+    database::{
+        model::login_throttle::{
+            BlockedUntil, LoginBlockKey, LoginFailuresKey, LoginThrottleTarget,
+        },
+        ConnectionPool,
+    },
+    redis::RedisClient,
+};
+
+// ログインに失敗した回数を Redis で数え、総当たりでの推測を防ぐ
+// メールアドレスごとに失敗するたびに待ち時間を倍にし、上限に達したらロックする
+// IP アドレスごとには、複数のメールアドレスを試す攻撃に備えて上限のみを設ける
+#[derive(new)]
+pub struct LoginThrottleRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    config: LoginThrottleConfig,
+}
+
+impl LoginThrottleRepositoryImpl {
+    fn targets(attempt: &LoginAttempt) -> Vec<LoginThrottleTarget> {
+        let mut targets = vec![LoginThrottleTarget::email(&attempt.email)];
+        if let Some(ip_address) = &attempt.ip_address {
+            targets.push(LoginThrottleTarget::ip(ip_address));
+        }
+        targets
+    }
+
+    async fn block(&self, target: &LoginThrottleTarget, seconds: u64) -> AppResult<()> {
+        if seconds == 0 {
+            return Ok(());
+        }
+        self.kv
+            .set_ex(
+                &LoginBlockKey(target),
+                &BlockedUntil(Utc::now().timestamp() + seconds as i64),
+                seconds,
+            )
+            .await
+    }
+
+    // 失敗した回数に応じて、次に試行できるまでの待ち時間を設定し、上限に達したらロックする
+    async fn block_email(&self, email: &LoginThrottleTarget, failures: u64) -> AppResult<()> {
+        if failures >= self.config.max_failures_per_email {
+            tracing::warn!(
+                failures,
+                "Too many failed login attempts; locking the account"
+            );
+            self.block(email, self.config.lockout).await
+        } else {
+            self.block(email, self.backoff(failures)).await
+        }
+    }
+
+    async fn email_target(&self, user_id: UserId) -> AppResult<LoginThrottleTarget> {
+        let email = sqlx::query!(
+            r#"
+                SELECT email FROM users WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))?
+        .email;
+        Ok(LoginThrottleTarget::email(&email))
+    }
+
+    fn max_failures(&self, target: &LoginThrottleTarget) -> u64 {
+        match target {
+            LoginThrottleTarget::Email(_) => self.config.max_failures_per_email,
+            LoginThrottleTarget::Ip(_) => self.config.max_failures_per_ip,
+        }
+    }
+
+    // n 回目の失敗の後の待ち時間。ロックする期間を超えない
+    fn backoff(&self, failures: u64) -> u64 {
+        let exponent = failures.saturating_sub(1).min(32) as u32;
+        self.config
+            .backoff_base
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.config.lockout)
+    }
+}
+
+#[async_trait]
+impl LoginThrottleRepository for LoginThrottleRepositoryImpl {
+    async fn check(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let targets = Self::targets(attempt);
+        let keys: Vec<_> = targets.iter().map(LoginBlockKey).collect();
+        let now = Utc::now().timestamp();
+        let retry_after = self
+            .kv
+            .get_many(&keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|BlockedUntil(until)| until - now)
+            .max();
+        if let Some(seconds) = retry_after.filter(|&seconds| seconds > 0) {
+            return Err(AppError::TooManyRequests(seconds as u64));
+        }
+
+        // 同時に試行されても上限を超えて検証しないよう、検証する前に失敗として数えておく
+        // IP アドレスの上限に達している場合は、メールアドレスの失敗回数を増やさない
+        for target in targets.iter().rev() {
+            let failures = self
+                .kv
+                .increment(&LoginFailuresKey(target), self.config.lockout)
+                .await?;
+            if failures > self.max_failures(target) {
+                return Err(AppError::TooManyRequests(self.config.lockout));
+            }
+        }
+        Ok(())
+    }
+
+    async fn record_failure(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        // 失敗の回数は check で数えているため、ここでは待ち時間とロックのみを設定する
+        let email = LoginThrottleTarget::email(&attempt.email);
+        let failures = self.kv.counter(&LoginFailuresKey(&email)).await?;
+        self.block_email(&email, failures).await?;
+
+        if let Some(ip_address) = &attempt.ip_address {
+            let ip = LoginThrottleTarget::ip(ip_address);
+            let failures = self.kv.counter(&LoginFailuresKey(&ip)).await?;
+            if failures >= self.config.max_failures_per_ip {
+                tracing::warn!(
+                    failures,
+                    ip_address = %ip_address,
+                    "Too many failed login attempts; blocking the client"
+                );
+                self.block(&ip, self.config.lockout).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn record_success(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        self.kv
+            .delete_counter(&LoginFailuresKey(&LoginThrottleTarget::email(
+                &attempt.email,
+            )))
+            .await?;
+        // IP アドレスの失敗回数は、check で数えた分だけを取り消す
+        if let Some(ip_address) = &attempt.ip_address {
+            self.kv
+                .decrement(&LoginFailuresKey(&LoginThrottleTarget::ip(ip_address)))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn record_two_factor_failure(&self, user_id: UserId) -> AppResult<()> {
+        let email = self.email_target(user_id).await?;
+        let failures = self
+            .kv
+            .increment(&LoginFailuresKey(&email), self.config.lockout)
+            .await?;
+        self.block_email(&email, failures).await
+    }
+
+    async fn unlock(&self, user_id: UserId) -> AppResult<()> {
+        let target = self.email_target(user_id).await?;
+        self.kv.delete_counter(&LoginFailuresKey(&target)).await?;
+        self.kv.delete(&LoginBlockKey(&target)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use shared::config::RedisConfig;
+
+    use super::*;
+
+    fn repository(pool: sqlx::PgPool, config: LoginThrottleConfig) -> LoginThrottleRepositoryImpl {
+        let redis_config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        LoginThrottleRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(RedisClient::new(&redis_config).unwrap()),
+            config,
+        )
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_lockout_and_unlock(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 待ち時間なしで、3 回失敗したらロックする
+        let repo = repository(
+            pool,
+            LoginThrottleConfig {
+                max_failures_per_email: 3,
+                max_failures_per_ip: 100,
+                backoff_base: 0,
+                lockout: 60,
+            },
+        );
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let attempt = LoginAttempt::new("Eleazar.Fig@example.com".into(), None);
+        repo.unlock(user_id).await?;
+
+        for _ in 0..2 {
+            repo.check(&attempt).await?;
+            repo.record_failure(&attempt).await?;
+        }
+        repo.check(&attempt).await?;
+        repo.record_failure(&attempt).await?;
+        assert!(matches!(
+            repo.check(&attempt).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+
+        // 管理者がロックを解除すると、再び試行できる
+        repo.unlock(user_id).await?;
+        repo.check(&attempt).await?;
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_backoff(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(
+            pool,
+            LoginThrottleConfig {
+                max_failures_per_email: 5,
+                max_failures_per_ip: 100,
+                backoff_base: 10,
+                lockout: 60,
+            },
+        );
+        assert_eq!(repo.backoff(1), 10);
+        assert_eq!(repo.backoff(2), 20);
+        assert_eq!(repo.backoff(3), 40);
+        // ロックする期間を超えない
+        assert_eq!(repo.backoff(4), 60);
+
+        // 一度失敗すると、待ち時間が過ぎるまで試行できない
+        // 他のテストと Redis のキーを共有しないよう、実行ごとに異なるメールアドレスを使う
+        let attempt = LoginAttempt::new(unique_email(), None);
+        repo.check(&attempt).await?;
+        repo.record_failure(&attempt).await?;
+        match repo.check(&attempt).await {
+            Err(AppError::TooManyRequests(retry_after)) => assert!(retry_after <= 10),
+            _ => panic!("login attempt should be throttled"),
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_reserve_attempts_before_verification(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(
+            pool,
+            LoginThrottleConfig {
+                max_failures_per_email: 3,
+                max_failures_per_ip: 100,
+                backoff_base: 0,
+                lockout: 60,
+            },
+        );
+        let attempt = LoginAttempt::new(unique_email(), Some(unique_ip()));
+
+        // 結果を記録する前の試行も数えるため、同時に試行しても上限を超えて検証できない
+        for _ in 0..3 {
+            repo.check(&attempt).await?;
+        }
+        assert!(matches!(
+            repo.check(&attempt).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+
+        // 成功すると、数えておいた試行は取り消される
+        repo.record_success(&attempt).await?;
+        repo.check(&attempt).await?;
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_ip_limit(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(
+            pool,
+            LoginThrottleConfig {
+                max_failures_per_email: 100,
+                max_failures_per_ip: 2,
+                backoff_base: 0,
+                lockout: 60,
+            },
+        );
+        let ip_address = unique_ip();
+
+        // 同じ IP アドレスからは、メールアドレスを変えても上限を超えて試行できない
+        for _ in 0..2 {
+            let attempt = LoginAttempt::new(unique_email(), Some(ip_address.clone()));
+            repo.check(&attempt).await?;
+            repo.record_failure(&attempt).await?;
+        }
+        let attempt = LoginAttempt::new(unique_email(), Some(ip_address));
+        assert!(matches!(
+            repo.check(&attempt).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+
+        Ok(())
+    }
+
+    fn unique_email() -> String {
+        format!("{}@example.com", uuid::Uuid::new_v4().simple())
+    }
+
+    fn unique_ip() -> String {
+        format!("test-{}", uuid::Uuid::new_v4().simple())
+    }
+}
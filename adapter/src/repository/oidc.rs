@@ -0,0 +1,656 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use kernel::{
+    model::{
+        id::UserId,
+        oidc::{OidcAuthorizationRequest, OidcCallback, OidcIdentity},
+        role::Role,
+        user::{User, UserStatus},
+    },
+    repository::oidc::OidcRepository,
+};
+use serde::de::DeserializeOwned;
+use shared::{
+    config::OidcConfig,
+    error::{AppError, AppResult},
+};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::password::UNUSABLE_PASSWORD_HASH;
+use crate::token::{hash_token, random_token};
+use crate::{
+    database::{
+        conflict_on_unique_violation,
+        model::{
+            oidc::{OidcStateKey, PendingOidcLogin},
+            user::UserRow,
+        },
+        transaction::IsolationLevel,
+        ConnectionPool,
+    },
+    oidc::{authorization_url, role_for_groups, IdTokenClaims, ProviderMetadata, TokenResponse},
+    redis::RedisClient,
+};
+
+// 知らない kid の ID トークンを受け取っても、IdP の公開鍵はこの間隔より短い間隔では取得し直さない
+// 任意の kid を指定したリクエストで、IdP へのリクエストを繰り返させないようにする
+const JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+// OpenID Connect の認可コードフロー（PKCE）でユーザーを認証する
+pub struct OidcRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    config: OidcConfig,
+    http: reqwest::Client,
+    // ディスカバリーの結果は最初に使うときに取得し、以降は使い回す
+    metadata: OnceCell<ProviderMetadata>,
+    // IdP の公開鍵と、最後に取得した時刻。鍵の入れ替えに備え、知らない kid の ID トークンを受け取ったら取得し直す
+    jwks: RwLock<(JwkSet, Option<Instant>)>,
+}
+
+impl OidcRepositoryImpl {
+    pub fn new(db: ConnectionPool, kv: Arc<RedisClient>, config: OidcConfig) -> Self {
+        Self {
+            db,
+            kv,
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new((JwkSet { keys: Vec::new() }, None)),
+        }
+    }
+
+    async fn metadata(&self) -> AppResult<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let issuer = self.config.issuer_url.trim_end_matches('/');
+                let metadata: ProviderMetadata = fetch_json(
+                    self.http
+                        .get(format!("{}/.well-known/openid-configuration", issuer)),
+                )
+                .await?;
+                if metadata.issuer.trim_end_matches('/') != issuer {
+                    return Err(AppError::OidcError(format!(
+                        "issuer mismatch: {}",
+                        metadata.issuer
+                    )));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    async fn decoding_key(&self, kid: &str) -> AppResult<Option<DecodingKey>> {
+        if let Some(jwk) = self.jwks.read().await.0.find(kid) {
+            return DecodingKey::from_jwk(jwk).map(Some).map_err(oidc_error);
+        }
+
+        // 同時に取得し直さないよう、書き込みのロックを取ってから確認し直す
+        let mut jwks = self.jwks.write().await;
+        if let Some(jwk) = jwks.0.find(kid) {
+            return DecodingKey::from_jwk(jwk).map(Some).map_err(oidc_error);
+        }
+        if jwks
+            .1
+            .is_some_and(|fetched_at| fetched_at.elapsed() < JWKS_REFETCH_INTERVAL)
+        {
+            return Ok(None);
+        }
+
+        let metadata = self.metadata().await?;
+        let fetched: JwkSet = fetch_json(self.http.get(&metadata.jwks_uri)).await?;
+        let key = fetched
+            .find(kid)
+            .map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(oidc_error)?;
+        *jwks = (fetched, Some(Instant::now()));
+        Ok(key)
+    }
+
+    async fn exchange_code(&self, code: &str, code_verifier: &str) -> AppResult<TokenResponse> {
+        let metadata = self.metadata().await?;
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(client_secret) = &self.config.client_secret {
+            params.push(("client_secret", client_secret.as_str()));
+        }
+
+        let res = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&params)
+            .send()
+            .await
+            .map_err(oidc_error)?;
+        // 認可コードが無効な場合や、code_verifier が一致しない場合
+        if res.status().is_client_error() {
+            return Err(AppError::UnauthenticatedError);
+        }
+        res.error_for_status()
+            .map_err(oidc_error)?
+            .json()
+            .await
+            .map_err(oidc_error)
+    }
+
+    async fn verify_id_token(&self, id_token: &str, nonce: &str) -> AppResult<IdTokenClaims> {
+        let header =
+            jsonwebtoken::decode_header(id_token).map_err(|_| AppError::UnauthenticatedError)?;
+        // 共通鍵での署名は、クライアントシークレットを知る者なら誰でも作れるため受け付けない
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(AppError::UnauthenticatedError);
+        }
+        let kid = header.kid.ok_or(AppError::UnauthenticatedError)?;
+        let key = self
+            .decoding_key(&kid)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        let metadata = self.metadata().await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|_| AppError::UnauthenticatedError)?
+            .claims;
+
+        // 認可リクエストで送った nonce と一致しない場合は、別のリクエストの ID トークンとみなす
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(AppError::UnauthenticatedError);
+        }
+        Ok(claims)
+    }
+
+    // issuer と subject の組で紐付け済みのユーザー
+    async fn find_linked_user(&self, identity: &OidcIdentity) -> AppResult<Option<User>> {
+        sqlx::query_as!(
+            UserRow,
+            r#"
+                SELECT
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name as role_name,
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+                FROM user_identities AS i
+                INNER JOIN users AS u USING(user_id)
+                INNER JOIN roles AS r USING(role_id)
+                WHERE i.issuer = $1 AND i.subject = $2
+            "#,
+            identity.issuer,
+            identity.subject
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(User::try_from)
+        .transpose()
+    }
+
+    // 同じメールアドレスのユーザーに紐付ける。いなければ、自動作成が有効な場合に限り作成してから紐付ける
+    // 同時に同じユーザーの作成や紐付けが行われた場合は、一意制約の違反を EntityConflict として返す
+    async fn link_or_provision_user(
+        &self,
+        identity: &OidcIdentity,
+        email: &str,
+    ) -> AppResult<User> {
+        let auto_provision = self.config.auto_provision;
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let issuer = identity.issuer.clone();
+                let subject = identity.subject.clone();
+                let email = email.to_string();
+                let name = identity.name.clone().unwrap_or_else(|| email.clone());
+                Box::pin(async move {
+                    let existing = sqlx::query_as!(
+                        UserRow,
+                        r#"
+                            SELECT
+                                u.user_id,
+                                u.name,
+                                u.email,
+                                r.name as role_name,
+                                u.status,
+                                u.created_at,
+                                u.updated_at
+                            FROM users AS u
+                            INNER JOIN roles AS r USING(role_id)
+                            WHERE u.email = $1
+                        "#,
+                        email
+                    )
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    let user: User = match existing {
+                        Some(user) => user.try_into()?,
+                        None if auto_provision => {
+                            let user_id = UserId::new();
+                            let role = Role::User;
+
+                            // パスワードでのログインには使わないため、どのようなパスワードでも検証に成功しないハッシュを設定する
+                            let res = sqlx::query!(
+                                r#"
+                                    INSERT INTO users(user_id, name, email, password_hash, role_id)
+                                    SELECT $1, $2, $3, $4, role_id FROM roles WHERE name = $5;
+                                "#,
+                                user_id as _,
+                                name,
+                                email,
+                                UNUSABLE_PASSWORD_HASH,
+                                role.as_ref()
+                            )
+                            .execute(&mut *conn)
+                            .await
+                            .map_err(conflict_on_unique_violation(
+                                "このメールアドレスのユーザーは同時に作成されました",
+                            ))?;
+                            if res.rows_affected() < 1 {
+                                return Err(AppError::NoRowsAffectedError(
+                                    "No user has been created".into(),
+                                ));
+                            }
+
+                            User {
+                                id: user_id,
+                                name,
+                                email,
+                                role,
+                                status: UserStatus::Active,
+                            }
+                        }
+                        None => return Err(AppError::UnauthenticatedError),
+                    };
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO user_identities(issuer, subject, user_id)
+                            VALUES ($1, $2, $3)
+                        "#,
+                        issuer,
+                        subject,
+                        user.id as _
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(conflict_on_unique_violation(
+                        "この IdP のユーザーは同時に紐付けられました",
+                    ))?;
+
+                    Ok(user)
+                })
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl OidcRepository for OidcRepositoryImpl {
+    async fn begin_login(&self) -> AppResult<OidcAuthorizationRequest> {
+        let metadata = self.metadata().await?;
+        let state = random_token();
+        let browser_binding = random_token();
+        let pending = PendingOidcLogin {
+            code_verifier: random_token(),
+            nonce: random_token(),
+            browser_binding: hash_token(&browser_binding),
+        };
+        let authorization_url = authorization_url(
+            &metadata.authorization_endpoint,
+            &self.config,
+            &state,
+            &pending.nonce,
+            &pending.code_verifier,
+        )?;
+
+        self.kv
+            .set_ex(
+                &OidcStateKey::from(state.as_str()),
+                &pending,
+                self.config.state_ttl,
+            )
+            .await?;
+
+        Ok(OidcAuthorizationRequest {
+            authorization_url,
+            browser_binding,
+            expires_in: self.config.state_ttl,
+        })
+    }
+
+    async fn complete_login(&self, callback: OidcCallback) -> AppResult<OidcIdentity> {
+        // state は一度しか使えないよう、取り出すと同時に削除する
+        let pending = self
+            .kv
+            .get_del(&OidcStateKey::from(callback.state.as_str()))
+            .await?
+            .ok_or(AppError::UnauthorizedError)?;
+        // 別のブラウザで開始したログインの state では、認可コードを受け付けない
+        // 攻撃者の認可コードを被害者のブラウザに送らせ、攻撃者のアカウントでログインさせる攻撃を防ぐ
+        if pending.browser_binding.is_empty()
+            || hash_token(&callback.browser_binding) != pending.browser_binding
+        {
+            return Err(AppError::UnauthorizedError);
+        }
+
+        let token = self
+            .exchange_code(&callback.code, &pending.code_verifier)
+            .await?;
+        let claims = self
+            .verify_id_token(&token.id_token, &pending.nonce)
+            .await?;
+
+        let groups = claims.groups(&self.config.groups_claim);
+        Ok(OidcIdentity {
+            issuer: self.metadata().await?.issuer.clone(),
+            subject: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            name: claims.name,
+            groups,
+        })
+    }
+
+    async fn find_or_provision_user(&self, identity: &OidcIdentity) -> AppResult<User> {
+        if let Some(user) = self.find_linked_user(identity).await? {
+            return Ok(user);
+        }
+
+        // IdP で確認済みのメールアドレスでなければ、既存のユーザーとの紐付けもユーザーの作成もしない
+        let email = match &identity.email {
+            Some(email) if identity.email_verified => email,
+            _ => return Err(AppError::UnauthenticatedError),
+        };
+
+        match self.link_or_provision_user(identity, email).await {
+            // 同じ IdP のユーザーが同時にログインし、他方が先に紐付けやユーザーの作成を済ませた場合は、
+            // その結果を読み直して使う
+            Err(AppError::EntityConflict(_)) => match self.find_linked_user(identity).await? {
+                Some(user) => Ok(user),
+                None => self.link_or_provision_user(identity, email).await,
+            },
+            res => res,
+        }
+    }
+
+    fn role_for_groups(&self, groups: &[String]) -> Option<Role> {
+        role_for_groups(&self.config.group_roles, groups)
+    }
+}
+
+async fn fetch_json<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> AppResult<T> {
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(oidc_error)?
+        .json()
+        .await
+        .map_err(oidc_error)
+}
+
+fn oidc_error(e: impl std::fmt::Display) -> AppError {
+    AppError::OidcError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use shared::config::RedisConfig;
+
+    use super::*;
+
+    fn repository(pool: &sqlx::PgPool, auto_provision: bool) -> anyhow::Result<OidcRepositoryImpl> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(OidcRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(RedisClient::new(&config)?),
+            OidcConfig {
+                issuer_url: std::env::var("OIDC_ISSUER_URL").unwrap(),
+                client_id: "book-manager".into(),
+                client_secret: None,
+                redirect_uri: "http://localhost:3000/auth/oidc/callback".into(),
+                scopes: "openid email profile".into(),
+                groups_claim: "groups".into(),
+                group_roles: vec![],
+                auto_provision,
+                state_ttl: 600,
+            },
+        ))
+    }
+
+    fn identity(subject: &str, email: &str, email_verified: bool) -> OidcIdentity {
+        OidcIdentity {
+            issuer: "http://localhost:8090/default".into(),
+            subject: subject.into(),
+            email: Some(email.into()),
+            email_verified,
+            name: Some("SSO User".into()),
+            groups: vec![],
+        }
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_link_existing_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, false)?;
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        // 確認済みでないメールアドレスでは紐付けない
+        let res = repo
+            .find_or_provision_user(&identity("eleazar", "eleazar.fig@example.com", false))
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+
+        let user = repo
+            .find_or_provision_user(&identity("eleazar", "eleazar.fig@example.com", true))
+            .await?;
+        assert_eq!(user.id, admin_id);
+
+        // 一度紐付けると、IdP のメールアドレスが変わっても subject で見つかる
+        let user = repo
+            .find_or_provision_user(&identity("eleazar", "renamed@example.com", false))
+            .await?;
+        assert_eq!(user.id, admin_id);
+
+        // 自動作成が無効の場合、存在しないユーザーはログインできない
+        let res = repo
+            .find_or_provision_user(&identity("newcomer", "newcomer@example.com", true))
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_auto_provision(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, true)?;
+
+        let user = repo
+            .find_or_provision_user(&identity("newcomer", "newcomer@example.com", true))
+            .await?;
+        assert_eq!(user.name, "SSO User");
+        assert_eq!(user.email, "newcomer@example.com");
+        assert_eq!(user.role, Role::User);
+
+        let again = repo
+            .find_or_provision_user(&identity("newcomer", "newcomer@example.com", true))
+            .await?;
+        assert_eq!(again.id, user.id);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_auto_provision_concurrently(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, true)?;
+
+        // 同じ IdP のユーザーの初回ログインが同時に行われても、ユーザーは1人だけ作成され、
+        // どちらのログインも同じユーザーとして成功する
+        for i in 0..5 {
+            let subject = format!("newcomer-{i}");
+            let email = format!("newcomer-{i}@example.com");
+            let identity = identity(&subject, &email, true);
+            let (first, second) = tokio::join!(
+                repo.find_or_provision_user(&identity),
+                repo.find_or_provision_user(&identity),
+            );
+            let (first, second) = (first?, second?);
+            assert_eq!(first.id, second.id);
+
+            let count = sqlx::query_scalar!(
+                r#"SELECT COUNT(*) AS "count!" FROM users WHERE email = $1"#,
+                email
+            )
+            .fetch_one(&pool)
+            .await?;
+            assert_eq!(count, 1);
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_link_existing_user_concurrently(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, false)?;
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        // 既存のユーザーへの紐付けが同時に行われても、どちらも同じユーザーとして成功する
+        let identity = identity("eleazar", "eleazar.fig@example.com", true);
+        let (first, second) = tokio::join!(
+            repo.find_or_provision_user(&identity),
+            repo.find_or_provision_user(&identity),
+        );
+        assert_eq!(first?.id, admin_id);
+        assert_eq!(second?.id, admin_id);
+
+        Ok(())
+    }
+
+    // compose の mock-oidc で認証し、リダイレクト URI に付与された code と state を返す
+    // ログイン画面（interactiveLogin）のフォームの送信と同じリクエストを送る
+    async fn authorize_with_mock_provider(
+        authorization_url: &str,
+        email: &str,
+    ) -> anyhow::Result<(String, String)> {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let claims = serde_json::json!({ "email": email, "email_verified": true }).to_string();
+        let res = client
+            .post(authorization_url)
+            .form(&[("username", "sso-user"), ("claims", claims.as_str())])
+            .send()
+            .await?;
+        let location = res
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .ok_or_else(|| anyhow::anyhow!("mock-oidc did not redirect: {}", res.status()))?
+            .to_str()?;
+        let url = reqwest::Url::parse(location)?;
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .ok_or_else(|| anyhow::anyhow!("{} not found in {}", name, location))
+        };
+        Ok((param("code")?, param("state")?))
+    }
+
+    #[sqlx::test]
+    async fn test_login_with_mock_provider(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, true)?;
+
+        let request = repo.begin_login().await?;
+        let (code, state) =
+            authorize_with_mock_provider(&request.authorization_url, "sso@example.com").await?;
+        let identity = repo
+            .complete_login(OidcCallback {
+                code: code.clone(),
+                state: state.clone(),
+                browser_binding: request.browser_binding.clone(),
+            })
+            .await?;
+        assert_eq!(identity.issuer, repo.metadata().await?.issuer);
+        assert_eq!(identity.email.as_deref(), Some("sso@example.com"));
+        assert!(identity.email_verified);
+
+        // state は一度しか使えない
+        let res = repo
+            .complete_login(OidcCallback {
+                code,
+                state,
+                browser_binding: request.browser_binding,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_reject_callback_from_another_browser(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, true)?;
+
+        // 攻撃者が開始したログインの code と state を、別のブラウザから送った場合
+        let request = repo.begin_login().await?;
+        let (code, state) =
+            authorize_with_mock_provider(&request.authorization_url, "attacker@example.com")
+                .await?;
+        let res = repo
+            .complete_login(OidcCallback {
+                code: code.clone(),
+                state: state.clone(),
+                browser_binding: random_token(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+
+        // 照合に失敗した state も使えなくなる
+        let res = repo
+            .complete_login(OidcCallback {
+                code,
+                state,
+                browser_binding: request.browser_binding,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthorizedError)));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_jwks_refetch_is_rate_limited(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, true)?;
+
+        // 知らない kid では取得し直すが、見つからなくても一定の間は再び取得しない
+        assert!(repo.decoding_key("unknown-kid").await?.is_none());
+        let fetched_at = repo.jwks.read().await.1;
+        assert!(fetched_at.is_some());
+
+        assert!(repo.decoding_key("another-unknown-kid").await?.is_none());
+        assert_eq!(repo.jwks.read().await.1, fetched_at);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        password_reset::{
+            event::{ForcePasswordReset, ResetPassword},
+            PasswordResetRequest, PasswordResetToken,
+        },
+        user::User,
+    },
+    repository::password_reset::PasswordResetRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::{
+            password_reset::{PasswordResetKey, ResettingUserId, UserPasswordResetsKey},
+            user::UserRow,
+        },
+        ConnectionPool,
+    },
+    password::hash_password,
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct PasswordResetRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    ttl: u64,
+}
+
+impl PasswordResetRepositoryImpl {
+    async fn delete_outstanding_tokens(&self, user_id: UserId) -> AppResult<()> {
+        let tokens_key = UserPasswordResetsKey::from(user_id);
+        for hash in self.kv.set_members(&tokens_key).await? {
+            self.kv.delete(&PasswordResetKey::from_hash(hash)).await?;
+        }
+        self.kv.delete_set(&tokens_key).await
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for PasswordResetRepositoryImpl {
+    async fn issue_token(&self, email: &str) -> AppResult<Option<PasswordResetRequest>> {
+        // メールアドレスの確認が済んでいないユーザーには発行しない
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+                SELECT
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name as role_name,
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+                FROM users AS u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE u.email = $1
+                AND u.email_verified_at IS NOT NULL
+            "#,
+            email
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let user = User::try_from(row)?;
+        let token = self.issue_token_for_user(user.id).await?;
+
+        Ok(Some(PasswordResetRequest { user, token }))
+    }
+
+    async fn issue_token_for_user(&self, user_id: UserId) -> AppResult<PasswordResetToken> {
+        let token = PasswordResetToken::new();
+        let key = PasswordResetKey::from(&token);
+        self.kv
+            .set_ex(&key, &ResettingUserId(user_id), self.ttl)
+            .await?;
+        self.kv
+            .add_to_set(&UserPasswordResetsKey::from(user_id), key.hash(), self.ttl)
+            .await?;
+        Ok(token)
+    }
+
+    async fn find_email(&self, token: &PasswordResetToken) -> AppResult<Option<String>> {
+        let key = PasswordResetKey::from(token);
+        let Some(ResettingUserId(user_id)) = self.kv.get(&key).await? else {
+            return Ok(None);
+        };
+        sqlx::query_scalar!(
+            r#"
+                SELECT email FROM users WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn reset_password(&self, event: ResetPassword) -> AppResult<UserId> {
+        // トークンは一度しか使えないよう、取得と同時に削除する
+        let key = PasswordResetKey::from(&event.token);
+        let Some(ResettingUserId(user_id)) = self.kv.get_del(&key).await? else {
+            return Err(AppError::EntityNotFound(
+                "再設定用のトークンが無効か、有効期限が切れています".into(),
+            ));
+        };
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET password_hash = $2,
+                    password_change_required = false
+                WHERE user_id = $1
+            "#,
+            user_id as _,
+            hash_password(&event.new_password).await?
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "ユーザー ({}) が見つかりませんでした",
+                user_id
+            )));
+        }
+
+        // 同じユーザーに発行した他のトークンでは、再設定後のパスワードを変更できないようにする
+        self.delete_outstanding_tokens(user_id).await?;
+
+        Ok(user_id)
+    }
+
+    async fn force_reset(&self, event: ForcePasswordReset) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET password_hash = $2,
+                    password_change_required = true
+                WHERE user_id = $1
+            "#,
+            event.user_id as _,
+            hash_password(&event.temporary_password).await?
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound("Specified user not found".into()));
+        }
+
+        Ok(())
+    }
+
+    async fn is_password_change_required(&self, user_id: UserId) -> AppResult<bool> {
+        let row = sqlx::query!(
+            r#"
+                SELECT password_change_required
+                FROM users
+                WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        row.map(|r| r.password_change_required)
+            .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::config::RedisConfig;
+
+    use super::*;
+    use crate::password::verify_password;
+
+    fn repository(pool: &sqlx::PgPool) -> anyhow::Result<PasswordResetRepositoryImpl> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(PasswordResetRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(RedisClient::new(&config)?),
+            1000,
+        ))
+    }
+
+    // 発行済みのトークンは Redis でテスト間で共有されるため、テストごとに別のユーザーを作成する
+    async fn create_user(pool: &sqlx::PgPool, verified: bool) -> anyhow::Result<(UserId, String)> {
+        let user_id = UserId::new();
+        let email = format!("{}@example.com", user_id);
+        sqlx::query!(
+            r#"
+                INSERT INTO users(user_id, name, email, password_hash, role_id, email_verified_at)
+                SELECT $1, 'Test User', $2, $3, role_id,
+                    CASE WHEN $4 THEN CURRENT_TIMESTAMP(3) END
+                FROM roles WHERE name = 'User'
+            "#,
+            user_id as _,
+            email,
+            hash_password("old_password").await?,
+            verified
+        )
+        .execute(pool)
+        .await?;
+        Ok((user_id, email))
+    }
+
+    async fn password_hash(pool: &sqlx::PgPool, user_id: UserId) -> anyhow::Result<String> {
+        Ok(sqlx::query_scalar!(
+            "SELECT password_hash FROM users WHERE user_id = $1",
+            user_id as _
+        )
+        .fetch_one(pool)
+        .await?)
+    }
+
+    #[sqlx::test]
+    async fn test_issue_and_reset(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        let (user_id, email) = create_user(&pool, true).await?;
+
+        let request = repo.issue_token(&email).await?.unwrap();
+        assert_eq!(request.user.id, user_id);
+        assert_eq!(repo.find_email(&request.token).await?, Some(email));
+
+        let reset_user_id = repo
+            .reset_password(ResetPassword {
+                token: PasswordResetToken(request.token.0.clone()),
+                new_password: "new_password".into(),
+            })
+            .await?;
+        assert_eq!(reset_user_id, user_id);
+        let hash = password_hash(&pool, user_id).await?;
+        assert!(verify_password("new_password", &hash).await?.is_valid());
+        assert!(!repo.is_password_change_required(user_id).await?);
+
+        // 使用済みのトークンは再び使えない
+        assert_eq!(repo.find_email(&request.token).await?, None);
+        let res = repo
+            .reset_password(ResetPassword {
+                token: request.token,
+                new_password: "another_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_reset_invalidates_other_tokens(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        let (user_id, email) = create_user(&pool, true).await?;
+
+        let first = repo.issue_token(&email).await?.unwrap().token;
+        let second = repo.issue_token_for_user(user_id).await?;
+        repo.reset_password(ResetPassword {
+            token: second,
+            new_password: "new_password".into(),
+        })
+        .await?;
+
+        // 再設定に使わなかったトークンも無効になる
+        assert_eq!(repo.find_email(&first).await?, None);
+        let res = repo
+            .reset_password(ResetPassword {
+                token: first,
+                new_password: "attacker_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let hash = password_hash(&pool, user_id).await?;
+        assert!(verify_password("new_password", &hash).await?.is_valid());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_issue_token_for_unknown_or_unverified_email(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        let (_, email) = create_user(&pool, false).await?;
+
+        // メールアドレスが存在しない場合や、確認が済んでいない場合は発行しない
+        assert!(repo.issue_token("unknown@example.com").await?.is_none());
+        assert!(repo.issue_token(&email).await?.is_none());
+
+        Ok(())
+    }
+}
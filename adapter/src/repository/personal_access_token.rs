@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        personal_access_token::{
+            event::{CreatePersonalAccessToken, DeletePersonalAccessToken},
+            CreatedPersonalAccessToken, PersonalAccessToken, PersonalAccessTokenSecret,
+        },
+    },
+    repository::personal_access_token::PersonalAccessTokenRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{model::personal_access_token::PersonalAccessTokenRow, ConnectionPool},
+    token::hash_token,
+};
+
+#[derive(new)]
+pub struct PersonalAccessTokenRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl PersonalAccessTokenRepository for PersonalAccessTokenRepositoryImpl {
+    async fn create(
+        &self,
+        event: CreatePersonalAccessToken,
+    ) -> AppResult<CreatedPersonalAccessToken> {
+        let secret = PersonalAccessTokenSecret::new();
+        let scopes: Vec<String> = event
+            .scopes
+            .iter()
+            .map(|scope| scope.as_ref().to_string())
+            .collect();
+
+        let token = sqlx::query_as!(
+            PersonalAccessTokenRow,
+            r#"
+                INSERT INTO personal_access_tokens (user_id, name, token_hash, scopes, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING token_id, user_id, name, scopes, expires_at, last_used_at, created_at
+            "#,
+            event.user_id as _,
+            event.name,
+            hash_token(&secret.0),
+            &scopes,
+            event.expires_at
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(CreatedPersonalAccessToken {
+            token: token.try_into()?,
+            secret,
+        })
+    }
+
+    async fn find_by_user(&self, user_id: UserId) -> AppResult<Vec<PersonalAccessToken>> {
+        sqlx::query_as!(
+            PersonalAccessTokenRow,
+            r#"
+                SELECT token_id, user_id, name, scopes, expires_at, last_used_at, created_at
+                FROM personal_access_tokens
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(PersonalAccessToken::try_from)
+        .collect()
+    }
+
+    async fn delete(&self, event: DeletePersonalAccessToken) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM personal_access_tokens
+                WHERE token_id = $1 AND user_id = $2
+            "#,
+            event.token_id as _,
+            event.user_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "トークン ({}) が見つかりませんでした",
+                event.token_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn authenticate(
+        &self,
+        secret: &PersonalAccessTokenSecret,
+    ) -> AppResult<Option<PersonalAccessToken>> {
+        sqlx::query_as!(
+            PersonalAccessTokenRow,
+            r#"
+                UPDATE personal_access_tokens
+                SET last_used_at = CURRENT_TIMESTAMP(3)
+                WHERE token_hash = $1
+                    AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP(3))
+                RETURNING token_id, user_id, name, scopes, expires_at, last_used_at, created_at
+            "#,
+            hash_token(&secret.0)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(PersonalAccessToken::try_from)
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{Duration, Utc};
+    use kernel::model::personal_access_token::Scope;
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_authenticate(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = PersonalAccessTokenRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        let created = repo
+            .create(CreatePersonalAccessToken {
+                user_id,
+                name: "script".into(),
+                scopes: vec![Scope::BooksRead, Scope::CheckoutsWrite],
+                expires_at: None,
+            })
+            .await?;
+        assert!(PersonalAccessTokenSecret::is_personal_access_token(
+            &created.secret.0
+        ));
+        assert!(created.token.last_used_at.is_none());
+
+        // 認証に成功すると、スコープとともに最終利用日時が返る
+        let token = repo.authenticate(&created.secret).await?.unwrap();
+        assert_eq!(token.id, created.token.id);
+        assert_eq!(token.scopes, vec![Scope::BooksRead, Scope::CheckoutsWrite]);
+        assert!(token.last_used_at.is_some());
+
+        // 有効期限が切れたトークンでは認証できない
+        let expired = repo
+            .create(CreatePersonalAccessToken {
+                user_id,
+                name: "expired".into(),
+                scopes: vec![Scope::BooksRead],
+                expires_at: Some(Utc::now() - Duration::minutes(1)),
+            })
+            .await?;
+        assert!(repo.authenticate(&expired.secret).await?.is_none());
+
+        // 削除したトークンでは認証できない
+        repo.delete(DeletePersonalAccessToken {
+            user_id,
+            token_id: created.token.id,
+        })
+        .await?;
+        assert!(repo.authenticate(&created.secret).await?.is_none());
+        assert_eq!(repo.find_by_user(user_id).await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::role::{
+        event::{CreateRole, DeleteRole, UpdateRolePermissions},
+        Permission, Role, RolePermissions,
+    },
+    repository::role::RoleRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::role::{parse_permissions, CachedPermissions, RolePermissionsKey, RoleRow},
+        transaction::IsolationLevel,
+        ConnectionPool,
+    },
+    redis::RedisClient,
+};
+
+// 権限を変更した場合はキャッシュを削除するため、有効期限は念のためのもの
+const PERMISSIONS_CACHE_TTL: u64 = 300;
+
+#[derive(new)]
+pub struct RoleRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+}
+
+fn permission_names(permissions: &[Permission]) -> Vec<String> {
+    permissions
+        .iter()
+        .map(|permission| permission.as_ref().to_string())
+        .collect()
+}
+
+#[async_trait]
+impl RoleRepository for RoleRepositoryImpl {
+    async fn find_all(&self) -> AppResult<Vec<RolePermissions>> {
+        sqlx::query_as!(
+            RoleRow,
+            r#"
+                SELECT
+                    r.name,
+                    r.built_in,
+                    COALESCE(
+                        ARRAY_AGG(p.permission ORDER BY p.permission)
+                            FILTER (WHERE p.permission IS NOT NULL),
+                        '{}'
+                    ) AS "permissions!"
+                FROM roles AS r
+                LEFT JOIN role_permissions AS p USING(role_id)
+                GROUP BY r.role_id
+                ORDER BY r.built_in DESC, r.name
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(RolePermissions::try_from)
+        .collect()
+    }
+
+    async fn find_permissions(&self, role: &Role) -> AppResult<Vec<Permission>> {
+        let key = RolePermissionsKey::from(role);
+        if let Some(CachedPermissions(permissions)) = self.kv.get(&key).await? {
+            return parse_permissions(&permissions);
+        }
+
+        // 存在しないロールは権限を持たないものとして扱う
+        let permissions: Vec<String> = sqlx::query_scalar!(
+            r#"
+                SELECT p.permission
+                FROM role_permissions AS p
+                INNER JOIN roles AS r USING(role_id)
+                WHERE r.name = $1
+            "#,
+            role.as_ref()
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        self.kv
+            .set_ex(
+                &key,
+                &CachedPermissions(permissions.clone()),
+                PERMISSIONS_CACHE_TTL,
+            )
+            .await?;
+        parse_permissions(&permissions)
+    }
+
+    async fn create(&self, event: CreateRole) -> AppResult<RolePermissions> {
+        let permissions = permission_names(&event.permissions);
+
+        // 同じ名前のロールの作成とは一意制約で競合するため、READ COMMITTED で足りる
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let name = event.name.clone();
+                let permissions = permissions.clone();
+                Box::pin(async move {
+                    let role_id = sqlx::query_scalar!(
+                        r#"
+                            INSERT INTO roles(name)
+                            VALUES ($1)
+                            ON CONFLICT DO NOTHING
+                            RETURNING role_id
+                        "#,
+                        name
+                    )
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?
+                    .ok_or_else(|| AppError::UnprocessableEntity("role already exists".into()))?;
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO role_permissions(role_id, permission)
+                            SELECT $1, UNNEST($2::VARCHAR[])
+                        "#,
+                        role_id,
+                        &permissions
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    Ok(())
+                })
+            })
+            .await?;
+
+        Ok(RolePermissions {
+            role: Role::Custom(event.name),
+            built_in: false,
+            permissions: event.permissions,
+        })
+    }
+
+    async fn update_permissions(&self, event: UpdateRolePermissions) -> AppResult<()> {
+        // 管理者が操作できなくならないよう、Admin の権限は変更できない
+        if event.role == Role::Admin {
+            return Err(AppError::UnprocessableEntity(
+                "the Admin role cannot be changed".into(),
+            ));
+        }
+        let permissions = permission_names(&event.permissions);
+
+        // 同じロールの権限が同時に変更されても、どちらか一方の権限だけが残るよう直列化可能な分離レベルで置き換える
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let role = event.role.clone();
+                let permissions = permissions.clone();
+                Box::pin(async move {
+                    let role_id = sqlx::query_scalar!(
+                        r#"
+                            SELECT role_id FROM roles WHERE name = $1
+                        "#,
+                        role.as_ref()
+                    )
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?
+                    .ok_or_else(|| AppError::EntityNotFound("Specified role not found".into()))?;
+
+                    sqlx::query!(
+                        r#"
+                            DELETE FROM role_permissions WHERE role_id = $1
+                        "#,
+                        role_id
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO role_permissions(role_id, permission)
+                            SELECT $1, UNNEST($2::VARCHAR[])
+                        "#,
+                        role_id,
+                        &permissions
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    Ok(())
+                })
+            })
+            .await?;
+
+        self.kv.delete(&RolePermissionsKey::from(&event.role)).await
+    }
+
+    async fn delete(&self, event: DeleteRole) -> AppResult<()> {
+        let role = sqlx::query!(
+            r#"
+                SELECT
+                    r.built_in,
+                    EXISTS(SELECT 1 FROM users AS u WHERE u.role_id = r.role_id) AS "in_use!"
+                FROM roles AS r
+                WHERE r.name = $1
+            "#,
+            event.role.as_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified role not found".into()))?;
+
+        if role.built_in {
+            return Err(AppError::UnprocessableEntity(
+                "built-in roles cannot be deleted".into(),
+            ));
+        }
+        if role.in_use {
+            return Err(AppError::UnprocessableEntity(
+                "the role is assigned to users".into(),
+            ));
+        }
+
+        // users は roles の削除に連動して削除されるため、割り当てられていないことを削除時にも確認する
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM roles AS r
+                WHERE r.name = $1
+                AND NOT r.built_in
+                AND NOT EXISTS(SELECT 1 FROM users AS u WHERE u.role_id = r.role_id)
+            "#,
+            event.role.as_ref()
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(
+                "the role is assigned to users".into(),
+            ));
+        }
+
+        self.kv.delete(&RolePermissionsKey::from(&event.role)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::config::RedisConfig;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_role_permissions(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let kv = Arc::new(RedisClient::new(&config)?);
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool), kv);
+
+        let librarian = repo.find_permissions(&Role::Librarian).await?;
+        assert!(librarian.contains(&Permission::BookUpdateAny));
+        assert!(!librarian.contains(&Permission::UserManage));
+        assert!(repo.find_permissions(&Role::User).await?.is_empty());
+
+        let name = format!("Curator-{}", uuid::Uuid::new_v4());
+        let role = Role::Custom(name.clone());
+        repo.create(CreateRole {
+            name: name.clone(),
+            permissions: vec![Permission::StatisticsRead],
+        })
+        .await?;
+        assert_eq!(
+            repo.find_permissions(&role).await?,
+            vec![Permission::StatisticsRead]
+        );
+        assert!(repo
+            .create(CreateRole {
+                name: name.clone(),
+                permissions: vec![],
+            })
+            .await
+            .is_err());
+
+        // 変更するとキャッシュも更新される
+        repo.update_permissions(UpdateRolePermissions {
+            role: role.clone(),
+            permissions: vec![Permission::KioskManage],
+        })
+        .await?;
+        assert_eq!(
+            repo.find_permissions(&role).await?,
+            vec![Permission::KioskManage]
+        );
+        assert!(repo
+            .update_permissions(UpdateRolePermissions {
+                role: Role::Admin,
+                permissions: vec![],
+            })
+            .await
+            .is_err());
+
+        assert!(repo.delete(DeleteRole { role: Role::User }).await.is_err());
+        repo.delete(DeleteRole { role: role.clone() }).await?;
+        assert!(repo.find_permissions(&role).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_update_permissions_concurrently(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        let kv = Arc::new(RedisClient::new(&config)?);
+        let repo = RoleRepositoryImpl::new(
+            ConnectionPool::new(pool).with_max_transaction_retries(5),
+            kv,
+        );
+
+        let name = format!("Curator-{}", uuid::Uuid::new_v4());
+        let role = Role::Custom(name.clone());
+        repo.create(CreateRole {
+            name,
+            permissions: vec![Permission::StatisticsRead],
+        })
+        .await?;
+
+        // 同時に置き換えても、権限が混ざらずにどちらか一方の権限だけが残る
+        let first = vec![Permission::KioskManage, Permission::BookUpdateAny];
+        let second = vec![Permission::UserManage];
+        let (res1, res2) = tokio::join!(
+            repo.update_permissions(UpdateRolePermissions {
+                role: role.clone(),
+                permissions: first.clone(),
+            }),
+            repo.update_permissions(UpdateRolePermissions {
+                role: role.clone(),
+                permissions: second.clone(),
+            }),
+        );
+        res1?;
+        res2?;
+
+        let mut permissions = repo
+            .find_all()
+            .await?
+            .into_iter()
+            .find(|r| r.role == role)
+            .map(|r| r.permissions)
+            .unwrap_or_default();
+        permissions.sort_by_key(|p| p.as_ref().to_string());
+        let mut first = first;
+        first.sort_by_key(|p| p.as_ref().to_string());
+        assert!(permissions == first || permissions == second);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        role::Role,
+        user::{event::CreateUser, User, UserStatus},
+    },
+    repository::setup::SetupRepository,
+};
+use shared::error::{AppError, AppResult};
+use sqlx::PgExecutor;
+
+use crate::{
+    database::{conflict_on_unique_violation, transaction::IsolationLevel, ConnectionPool},
+    password::hash_password,
+};
+
+#[derive(new)]
+pub struct SetupRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl SetupRepository for SetupRepositoryImpl {
+    async fn is_required(&self) -> AppResult<bool> {
+        Ok(!admin_exists(self.db.inner_ref()).await?)
+    }
+
+    async fn create_initial_admin(&self, event: CreateUser) -> AppResult<User> {
+        // トランザクションがやり直しになった場合にハッシュ化を繰り返さないよう、事前に作成しておく
+        let hashed_password = hash_password(&event.password).await?;
+        let user_id = UserId::new();
+        let role = Role::Admin;
+
+        // 同時に初期設定が行われても管理者が1人だけ作成されるよう、直列化可能な分離レベルで確認と作成を行う
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let name = event.name.clone();
+                let email = event.email.clone();
+                let hashed_password = hashed_password.clone();
+                let role = role.clone();
+                Box::pin(async move {
+                    if admin_exists(&mut *conn).await? {
+                        return Err(AppError::EntityConflict(
+                            "初期設定はすでに完了しています".into(),
+                        ));
+                    }
+
+                    let res = sqlx::query!(
+                        r#"
+                            INSERT INTO users(user_id, name, email, password_hash, role_id)
+                            SELECT $1, $2, $3, $4, role_id FROM roles WHERE name = $5;
+                        "#,
+                        user_id as _,
+                        name,
+                        email,
+                        hashed_password,
+                        role.as_ref()
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(conflict_on_unique_violation(
+                        "このメールアドレスはすでに使われています",
+                    ))?;
+                    if res.rows_affected() < 1 {
+                        return Err(AppError::NoRowsAffectedError(
+                            "No user has been created".into(),
+                        ));
+                    }
+                    Ok(())
+                })
+            })
+            .await?;
+
+        Ok(User {
+            id: user_id,
+            name: event.name,
+            email: event.email,
+            role,
+            status: UserStatus::Active,
+        })
+    }
+}
+
+// 状態によらず、管理者のロールを持つユーザーがいるかどうか
+async fn admin_exists<'e>(executor: impl PgExecutor<'e>) -> AppResult<bool> {
+    sqlx::query_scalar!(
+        r#"
+            SELECT EXISTS (
+                SELECT 1 FROM users
+                INNER JOIN roles USING(role_id)
+                WHERE roles.name = $1
+            ) AS "exists!"
+        "#,
+        Role::Admin.as_ref()
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::SpecificOperationError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_create_initial_admin_only_once(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = SetupRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        assert!(repo.is_required().await?);
+
+        let admin = repo
+            .create_initial_admin(CreateUser {
+                name: "Admin".into(),
+                email: "admin@example.com".into(),
+                password: "admin_password".into(),
+            })
+            .await?;
+        assert_eq!(admin.role, Role::Admin);
+        assert!(!repo.is_required().await?);
+
+        // 管理者がいる場合は、2人目を作成できない
+        let res = repo
+            .create_initial_admin(CreateUser {
+                name: "Another Admin".into(),
+                email: "another@example.com".into(),
+                password: "admin_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityConflict(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_create_initial_admin_concurrently(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 複数のインスタンスが同時に起動した場合を想定し、同時に作成する
+        // シリアライズ失敗はやり直され、後から確認した方は完了済みとして扱われる
+        let repo = SetupRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()).with_max_transaction_retries(5),
+        );
+        let create = |i: usize| {
+            repo.create_initial_admin(CreateUser {
+                name: format!("Admin {i}"),
+                email: format!("admin{i}@example.com"),
+                password: "admin_password".into(),
+            })
+        };
+        let (a, b, c, d) = tokio::join!(create(0), create(1), create(2), create(3));
+        let results = [a, b, c, d];
+
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .filter_map(|res| res.as_ref().err())
+            .all(|e| matches!(e, AppError::EntityConflict(_))));
+
+        let admins = sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "count!" FROM users
+                INNER JOIN roles USING(role_id)
+                WHERE roles.name = 'Admin'
+            "#
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(admins, 1);
+
+        Ok(())
+    }
+}
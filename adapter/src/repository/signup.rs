@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        role::Role,
+        signup::{EmailVerificationToken, PendingUser},
+        user::{event::CreateUser, User, UserStatus},
+    },
+    repository::signup::SignupRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::signup::{
+            EmailVerificationAttempt, EmailVerificationAttemptKey, EmailVerificationKey,
+            PendingRegistration,
+        },
+        ConnectionPool,
+    },
+    password::hash_password,
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct SignupRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    ttl: u64,
+}
+
+#[async_trait]
+impl SignupRepository for SignupRepositoryImpl {
+    async fn register(&self, event: CreateUser) -> AppResult<Option<PendingUser>> {
+        let hashed_password = hash_password(&event.password).await?;
+        // セルフサインアップで作成するユーザーは一般のユーザー権限とする
+        let role = Role::User;
+
+        // 同じメールアドレスのユーザーがいる場合、その名前とパスワードはここでは書き換えない
+        let inserted = sqlx::query_scalar!(
+            r#"
+                INSERT INTO users(user_id, name, email, password_hash, role_id, email_verified_at)
+                SELECT $1, $2, $3, $4, role_id, NULL FROM roles WHERE name = $5
+                ON CONFLICT (email) DO NOTHING
+                RETURNING user_id AS "user_id: UserId"
+            "#,
+            UserId::new() as _,
+            event.name,
+            event.email,
+            hashed_password,
+            role.as_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let user_id = match inserted {
+            Some(user_id) => user_id,
+            None => {
+                let existing = sqlx::query!(
+                    r#"
+                        SELECT
+                            user_id AS "user_id: UserId",
+                            email_verified_at IS NOT NULL AS "verified!"
+                        FROM users
+                        WHERE email = $1
+                    "#,
+                    event.email
+                )
+                .fetch_one(self.db.inner_ref())
+                .await
+                .map_err(AppError::SpecificOperationError)?;
+                // 登録済みかどうかを知られないよう、確認済みのユーザーがいる場合は何もせずに終える
+                if existing.verified {
+                    return Ok(None);
+                }
+                existing.user_id
+            }
+        };
+
+        // 名前とパスワードはトークンとともに保存し、メールアドレスの確認が済んだ時点で反映する
+        let verification_token = EmailVerificationToken::new();
+        self.kv
+            .set_ex(
+                &EmailVerificationKey::from(&verification_token),
+                &PendingRegistration {
+                    user_id,
+                    name: event.name.clone(),
+                    password_hash: hashed_password,
+                },
+                self.ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(
+                &EmailVerificationAttemptKey(user_id),
+                &EmailVerificationAttempt::from(&verification_token),
+                self.ttl,
+            )
+            .await?;
+
+        Ok(Some(PendingUser {
+            user: User {
+                id: user_id,
+                name: event.name,
+                email: event.email,
+                role,
+                status: UserStatus::Active,
+            },
+            verification_token,
+        }))
+    }
+
+    async fn verify_email(&self, token: &EmailVerificationToken) -> AppResult<UserId> {
+        // トークンは一度しか使えないよう、取得と同時に削除する
+        let key = EmailVerificationKey::from(token);
+        let Some(registration) = self.kv.get_del(&key).await? else {
+            return Err(AppError::EntityNotFound(
+                "確認用のトークンが無効か、有効期限が切れています".into(),
+            ));
+        };
+
+        // 登録し直した場合は、最後に発行したトークンしか使えない
+        let attempt_key = EmailVerificationAttemptKey(registration.user_id);
+        if self.kv.get(&attempt_key).await? != Some(EmailVerificationAttempt::from(token)) {
+            return Err(AppError::EntityNotFound(
+                "確認用のトークンが無効か、有効期限が切れています".into(),
+            ));
+        }
+
+        let PendingRegistration {
+            user_id,
+            name,
+            password_hash,
+        } = registration;
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET name = $2,
+                    password_hash = $3,
+                    email_verified_at = CURRENT_TIMESTAMP(3)
+                WHERE user_id = $1
+                AND email_verified_at IS NULL
+            "#,
+            user_id as _,
+            name,
+            password_hash
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(format!(
+                "確認が必要なユーザー ({}) が見つかりませんでした",
+                user_id
+            )));
+        }
+        self.kv.delete(&attempt_key).await?;
+
+        Ok(user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::config::RedisConfig;
+
+    use super::*;
+    use crate::password::verify_password;
+
+    fn signup_repository(pool: &sqlx::PgPool) -> anyhow::Result<SignupRepositoryImpl> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(SignupRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(RedisClient::new(&config)?),
+            1000,
+        ))
+    }
+
+    fn create_user(name: &str, password: &str) -> CreateUser {
+        CreateUser {
+            name: name.into(),
+            email: "signup@example.com".into(),
+            password: password.into(),
+        }
+    }
+
+    async fn fetch_user(pool: &sqlx::PgPool, user_id: UserId) -> anyhow::Result<(String, String)> {
+        let row = sqlx::query!(
+            "SELECT name, password_hash FROM users WHERE user_id = $1",
+            user_id as _
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok((row.name, row.password_hash))
+    }
+
+    #[sqlx::test]
+    async fn test_register_and_verify(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = signup_repository(&pool)?;
+
+        let pending = repo
+            .register(create_user("Signup User", "first_password"))
+            .await?
+            .expect("a new user should be registered");
+        let user_id = pending.user.id;
+        assert_eq!(
+            repo.verify_email(&pending.verification_token).await?,
+            user_id
+        );
+
+        let (name, password_hash) = fetch_user(&pool, user_id).await?;
+        assert_eq!(name, "Signup User");
+        assert!(verify_password("first_password", &password_hash)
+            .await?
+            .is_valid());
+
+        // トークンは一度しか使えない
+        let res = repo.verify_email(&pending.verification_token).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_register_again_before_verification(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = signup_repository(&pool)?;
+
+        let first = repo
+            .register(create_user("First User", "first_password"))
+            .await?
+            .expect("a new user should be registered");
+        let second = repo
+            .register(create_user("Second User", "second_password"))
+            .await?
+            .expect("an unverified user can register again");
+        assert_eq!(first.user.id, second.user.id);
+
+        // 確認が済むまでは、登録し直した内容でユーザーを書き換えない
+        let (name, password_hash) = fetch_user(&pool, first.user.id).await?;
+        assert_eq!(name, "First User");
+        assert!(!verify_password("second_password", &password_hash)
+            .await?
+            .is_valid());
+
+        // 登録し直す前に発行したトークンは使えない
+        let res = repo.verify_email(&first.verification_token).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        repo.verify_email(&second.verification_token).await?;
+        let (name, password_hash) = fetch_user(&pool, first.user.id).await?;
+        assert_eq!(name, "Second User");
+        assert!(verify_password("second_password", &password_hash)
+            .await?
+            .is_valid());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_register_verified_email(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = signup_repository(&pool)?;
+
+        let pending = repo
+            .register(create_user("Signup User", "first_password"))
+            .await?
+            .expect("a new user should be registered");
+        repo.verify_email(&pending.verification_token).await?;
+
+        // 確認済みのユーザーがいる場合は、トークンを発行せず、ユーザーも書き換えない
+        let res = repo
+            .register(create_user("Attacker", "attacker_password"))
+            .await?;
+        assert!(res.is_none());
+        let (name, password_hash) = fetch_user(&pool, pending.user.id).await?;
+        assert_eq!(name, "Signup User");
+        assert!(verify_password("first_password", &password_hash)
+            .await?
+            .is_valid());
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::statistics::{
+        BookLoanCount, BorrowerLoanCount, LibraryStatistics, MonthlyLoanCount, StatisticsBook,
+        StatisticsOptions, Utilization,
+    },
+    repository::statistics::StatisticsRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::statistics::{
+            BookLoanCountRow, BorrowerLoanCountRow, CachedStatistics, MonthlyLoanCountRow,
+            StatisticsBookRow, StatisticsCacheKey,
+        },
+        ConnectionPool,
+    },
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct StatisticsRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    ttl: u64,
+}
+
+#[async_trait]
+impl StatisticsRepository for StatisticsRepositoryImpl {
+    async fn summarize(&self, options: StatisticsOptions) -> AppResult<LibraryStatistics> {
+        // 集計クエリは重いため、同じ条件での集計結果は一定時間 Redis にキャッシュする
+        let key = StatisticsCacheKey::from(&options);
+        if let Some(cached) = self.kv.get(&key).await? {
+            return cached.into_statistics();
+        }
+
+        let most_active_borrowers = if options.include_borrowers {
+            Some(self.most_active_borrowers(&options).await?)
+        } else {
+            None
+        };
+        let statistics = LibraryStatistics {
+            most_borrowed_books: self.most_borrowed_books(&options).await?,
+            most_active_borrowers,
+            average_loan_duration_seconds: self.average_loan_duration(&options).await?,
+            loans_per_month: self.loans_per_month(&options).await?,
+            never_borrowed_books: self.never_borrowed_books(&options).await?,
+            utilization: self.utilization().await?,
+            generated_at: Utc::now(),
+        };
+
+        self.kv
+            .set_ex(&key, &CachedStatistics::new(&statistics)?, self.ttl)
+            .await?;
+
+        Ok(statistics)
+    }
+}
+
+// 以降の集計クエリでは、貸出中（checkouts）と返却済み（returned_checkouts）の
+// 両方を「貸出」として扱い、貸出日時が指定期間に含まれるものを集計対象とする
+impl StatisticsRepositoryImpl {
+    async fn most_borrowed_books(
+        &self,
+        options: &StatisticsOptions,
+    ) -> AppResult<Vec<BookLoanCount>> {
+        sqlx::query_as!(
+            BookLoanCountRow,
+            r#"
+                WITH loans AS (
+                    SELECT book_id, checked_out_at FROM checkouts
+                    UNION ALL
+                    SELECT book_id, checked_out_at FROM returned_checkouts
+                )
+                SELECT
+                    b.book_id,
+                    b.title,
+                    b.author,
+                    COUNT(*) AS "loan_count!"
+                FROM loans AS l
+                INNER JOIN books AS b USING(book_id)
+                WHERE ($1::timestamptz IS NULL OR l.checked_out_at >= $1)
+                AND   ($2::timestamptz IS NULL OR l.checked_out_at < $2)
+                GROUP BY b.book_id, b.title, b.author
+                ORDER BY COUNT(*) DESC, b.title ASC
+                LIMIT $3
+            "#,
+            options.from,
+            options.to,
+            options.limit
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 集計に含めることに同意したユーザーのみを対象にする
+    async fn most_active_borrowers(
+        &self,
+        options: &StatisticsOptions,
+    ) -> AppResult<Vec<BorrowerLoanCount>> {
+        sqlx::query_as!(
+            BorrowerLoanCountRow,
+            r#"
+                WITH loans AS (
+                    SELECT user_id, checked_out_at FROM checkouts
+                    UNION ALL
+                    SELECT user_id, checked_out_at FROM returned_checkouts
+                )
+                SELECT
+                    u.user_id,
+                    u.name AS user_name,
+                    COUNT(*) AS "loan_count!"
+                FROM loans AS l
+                INNER JOIN users AS u USING(user_id)
+                WHERE u.share_loan_statistics
+                AND   ($1::timestamptz IS NULL OR l.checked_out_at >= $1)
+                AND   ($2::timestamptz IS NULL OR l.checked_out_at < $2)
+                GROUP BY u.user_id, u.name
+                ORDER BY COUNT(*) DESC, u.name ASC
+                LIMIT $3
+            "#,
+            options.from,
+            options.to,
+            options.limit
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 貸出期間は返却済みの貸出のみを対象に算出する
+    async fn average_loan_duration(&self, options: &StatisticsOptions) -> AppResult<Option<f64>> {
+        let row = sqlx::query!(
+            r#"
+                SELECT
+                    EXTRACT(EPOCH FROM AVG(returned_at - checked_out_at))::float8 AS average_seconds
+                FROM returned_checkouts
+                WHERE ($1::timestamptz IS NULL OR checked_out_at >= $1)
+                AND   ($2::timestamptz IS NULL OR checked_out_at < $2)
+            "#,
+            options.from,
+            options.to,
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(row.average_seconds)
+    }
+
+    async fn loans_per_month(
+        &self,
+        options: &StatisticsOptions,
+    ) -> AppResult<Vec<MonthlyLoanCount>> {
+        sqlx::query_as!(
+            MonthlyLoanCountRow,
+            r#"
+                WITH loans AS (
+                    SELECT checked_out_at FROM checkouts
+                    UNION ALL
+                    SELECT checked_out_at FROM returned_checkouts
+                )
+                SELECT
+                    date_trunc('month', checked_out_at) AS "month!",
+                    COUNT(*) AS "loan_count!"
+                FROM loans
+                WHERE ($1::timestamptz IS NULL OR checked_out_at >= $1)
+                AND   ($2::timestamptz IS NULL OR checked_out_at < $2)
+                GROUP BY 1
+                ORDER BY 1 ASC
+            "#,
+            options.from,
+            options.to,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn never_borrowed_books(
+        &self,
+        options: &StatisticsOptions,
+    ) -> AppResult<Vec<StatisticsBook>> {
+        sqlx::query_as!(
+            StatisticsBookRow,
+            r#"
+                WITH loans AS (
+                    SELECT book_id, checked_out_at FROM checkouts
+                    UNION ALL
+                    SELECT book_id, checked_out_at FROM returned_checkouts
+                )
+                SELECT
+                    b.book_id,
+                    b.title,
+                    b.author
+                FROM books AS b
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM loans AS l
+                    WHERE l.book_id = b.book_id
+                    AND   ($1::timestamptz IS NULL OR l.checked_out_at >= $1)
+                    AND   ($2::timestamptz IS NULL OR l.checked_out_at < $2)
+                )
+                ORDER BY b.created_at ASC
+            "#,
+            options.from,
+            options.to,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 利用率は期間指定によらず、現時点での貸出状況から算出する
+    async fn utilization(&self) -> AppResult<Utilization> {
+        let row = sqlx::query!(
+            r#"
+                SELECT
+                    (SELECT COUNT(*) FROM books) AS "total_books!",
+                    (SELECT COUNT(*) FROM checkouts) AS "checked_out_books!"
+            "#
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(Utilization {
+            total_books: row.total_books,
+            checked_out_books: row.checked_out_books,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{DateTime, TimeZone};
+    use kernel::model::id::{BookId, UserId};
+    use shared::config::RedisConfig;
+
+    use super::*;
+
+    const DAY_SECONDS: f64 = 24.0 * 60.0 * 60.0;
+
+    fn repository(pool: &sqlx::PgPool) -> anyhow::Result<StatisticsRepositoryImpl> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(StatisticsRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(RedisClient::new(&config)?),
+            60,
+        ))
+    }
+
+    fn utc(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn options(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> StatisticsOptions {
+        StatisticsOptions {
+            from,
+            to,
+            include_borrowers: true,
+            limit: 10,
+        }
+    }
+
+    // キャッシュは他のテストと共有されるため、集計クエリを直接呼び出して確かめる
+    #[sqlx::test(fixtures("common", "book", "statistics"))]
+    async fn test_loan_aggregates(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        let first_book = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let second_book = BookId::from_str("f397b83a-dd2a-4a01-9e77-db1eea7de5b6")?;
+        let never_borrowed = BookId::from_str("17afb850-c786-49c5-a303-a3a443a2212c")?;
+        let all = options(None, None);
+
+        // 貸出中と返却済みの両方を貸出として数える
+        let books = repo.most_borrowed_books(&all).await?;
+        assert_eq!(
+            books
+                .iter()
+                .map(|b| (b.book.book_id, b.loan_count))
+                .collect::<Vec<_>>(),
+            vec![(first_book, 3), (second_book, 2)]
+        );
+
+        // 貸出期間は返却済みの貸出のみから算出する
+        assert_eq!(
+            repo.average_loan_duration(&all).await?,
+            Some(2.5 * DAY_SECONDS)
+        );
+
+        assert_eq!(
+            repo.loans_per_month(&all)
+                .await?
+                .iter()
+                .map(|m| (m.month, m.loan_count))
+                .collect::<Vec<_>>(),
+            vec![
+                (utc(2024, 1, 1), 2),
+                (utc(2024, 2, 1), 2),
+                (utc(2024, 3, 1), 1)
+            ]
+        );
+
+        assert_eq!(
+            repo.never_borrowed_books(&all)
+                .await?
+                .iter()
+                .map(|b| b.book_id)
+                .collect::<Vec<_>>(),
+            vec![never_borrowed]
+        );
+
+        // 利用率は期間によらず、現在貸出中の蔵書から算出する
+        let utilization = repo.utilization().await?;
+        assert_eq!(utilization.total_books, 3);
+        assert_eq!(utilization.checked_out_books, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "statistics"))]
+    async fn test_loan_aggregates_in_range(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        // 2月の貸出のみを対象にする（to は含まない）
+        let february = options(Some(utc(2024, 2, 1)), Some(utc(2024, 3, 1)));
+
+        let mut counts = repo
+            .most_borrowed_books(&february)
+            .await?
+            .iter()
+            .map(|b| b.loan_count)
+            .collect::<Vec<_>>();
+        counts.sort();
+        assert_eq!(counts, vec![1, 1]);
+
+        assert_eq!(
+            repo.average_loan_duration(&february).await?,
+            Some(2.0 * DAY_SECONDS)
+        );
+        assert_eq!(
+            repo.loans_per_month(&february)
+                .await?
+                .iter()
+                .map(|m| (m.month, m.loan_count))
+                .collect::<Vec<_>>(),
+            vec![(utc(2024, 2, 1), 2)]
+        );
+
+        // 期間内に貸出のない蔵書は、過去に貸し出されていても含める
+        let january = options(Some(utc(2024, 1, 1)), Some(utc(2024, 2, 1)));
+        assert_eq!(repo.never_borrowed_books(&january).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "statistics"))]
+    async fn test_most_active_borrowers_requires_opt_in(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool)?;
+        let sharing = UserId::from_str("a1b2c3d4-0000-4000-8000-000000000001")?;
+        let private = UserId::from_str("a1b2c3d4-0000-4000-8000-000000000002")?;
+
+        // 集計に含めることに同意していない利用者は、貸出があっても含めない
+        let borrowers = repo.most_active_borrowers(&options(None, None)).await?;
+        assert_eq!(
+            borrowers
+                .iter()
+                .map(|b| (b.user_id, b.loan_count))
+                .collect::<Vec<_>>(),
+            vec![(sharing, 3)]
+        );
+
+        sqlx::query!(
+            "UPDATE users SET share_loan_statistics = true WHERE user_id = $1",
+            private as _
+        )
+        .execute(&pool)
+        .await?;
+        let borrowers = repo.most_active_borrowers(&options(None, None)).await?;
+        assert_eq!(
+            borrowers
+                .iter()
+                .map(|b| (b.user_id, b.loan_count))
+                .collect::<Vec<_>>(),
+            vec![(sharing, 3), (private, 2)]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,488 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        two_factor::{
+            RecoveryCode, TotpEnrollment, TwoFactorChallenge, TwoFactorChallengePurpose,
+            TwoFactorVerified,
+        },
+    },
+    repository::{login_throttle::LoginThrottleRepository, two_factor::TwoFactorRepository},
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        model::two_factor::{
+            ChallengedUser, TwoFactorAttemptsKey, TwoFactorChallengeKey, TwoFactorFailuresKey,
+        },
+        transaction::IsolationLevel,
+        ConnectionPool,
+    },
+    redis::RedisClient,
+    token::hash_token,
+    totp,
+};
+
+// 1 つのチャレンジでコードの検証に失敗できる回数
+const MAX_CHALLENGE_ATTEMPTS: u64 = 5;
+
+#[derive(new)]
+pub struct TwoFactorRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    // コードの誤りを、パスワードでのログインの失敗とあわせて数える
+    login_throttle: Arc<dyn LoginThrottleRepository>,
+    // 認証アプリに表示されるサービス名
+    issuer: String,
+    challenge_ttl: u64,
+    // チャレンジを発行し直しても試行を続けられないよう、ユーザーごとにも失敗の回数を制限する
+    max_failures: u64,
+    lockout: u64,
+}
+
+impl TwoFactorRepositoryImpl {
+    // 有効な TOTP のコード、または未使用のリカバリーコードであれば成功とする
+    async fn verify_code(&self, user_id: UserId, code: &str) -> AppResult<()> {
+        let totp = sqlx::query!(
+            r#"
+                SELECT secret, last_used_step FROM user_totp
+                WHERE user_id = $1 AND enabled_at IS NOT NULL
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or(AppError::UnauthenticatedError)?;
+
+        if let Some(step) = totp::verify(&totp.secret, code, Utc::now().timestamp()) {
+            // 同じ時間ステップのコードを二度使えないよう、使ったステップを記録する
+            let res = sqlx::query!(
+                r#"
+                    UPDATE user_totp SET last_used_step = $2
+                    WHERE user_id = $1 AND last_used_step < $2
+                "#,
+                user_id as _,
+                step
+            )
+            .execute(self.db.inner_ref())
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+            if res.rows_affected() < 1 {
+                return Err(AppError::UnauthenticatedError);
+            }
+            return Ok(());
+        }
+
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM totp_recovery_codes
+                WHERE user_id = $1 AND code_hash = $2
+            "#,
+            user_id as _,
+            hash_token(&totp::normalize_recovery_code(code))
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnauthenticatedError);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactorRepository for TwoFactorRepositoryImpl {
+    async fn is_enabled(&self, user_id: UserId) -> AppResult<bool> {
+        sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM user_totp
+                    WHERE user_id = $1 AND enabled_at IS NOT NULL
+                ) AS "enabled!"
+            "#,
+            user_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    async fn begin_enrollment(&self, user_id: UserId) -> AppResult<TotpEnrollment> {
+        let email = sqlx::query_scalar!(
+            r#"
+                SELECT email FROM users WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))?;
+
+        let secret = totp::generate_secret();
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO user_totp(user_id, secret) VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret
+                WHERE user_totp.enabled_at IS NULL
+            "#,
+            user_id as _,
+            secret
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(
+                "2 段階認証はすでに有効になっています".into(),
+            ));
+        }
+
+        Ok(TotpEnrollment {
+            secret: totp::base32_encode(&secret),
+            provisioning_uri: totp::provisioning_uri(&self.issuer, &email, &secret),
+        })
+    }
+
+    async fn activate(&self, user_id: UserId, code: &str) -> AppResult<Vec<RecoveryCode>> {
+        let secret = sqlx::query_scalar!(
+            r#"
+                SELECT secret FROM user_totp
+                WHERE user_id = $1 AND enabled_at IS NULL
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::UnprocessableEntity("2 段階認証の登録が開始されていません".into())
+        })?;
+        let step = totp::verify(&secret, code, Utc::now().timestamp())
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        // リカバリーコードはハッシュ値のみを保存し、平文はこのときに一度だけ返す
+        let recovery_codes = totp::generate_recovery_codes();
+        let code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| hash_token(&totp::normalize_recovery_code(code)))
+            .collect();
+
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let code_hashes = code_hashes.clone();
+                Box::pin(async move {
+                    let res = sqlx::query!(
+                        r#"
+                            UPDATE user_totp
+                            SET enabled_at = CURRENT_TIMESTAMP(3), last_used_step = $2
+                            WHERE user_id = $1 AND enabled_at IS NULL
+                        "#,
+                        user_id as _,
+                        step
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    if res.rows_affected() < 1 {
+                        return Err(AppError::UnprocessableEntity(
+                            "2 段階認証の登録が開始されていません".into(),
+                        ));
+                    }
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO totp_recovery_codes(user_id, code_hash)
+                            SELECT $1, UNNEST($2::VARCHAR[])
+                        "#,
+                        user_id as _,
+                        &code_hashes
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        Ok(recovery_codes.into_iter().map(RecoveryCode).collect())
+    }
+
+    async fn disable(&self, user_id: UserId, code: &str) -> AppResult<()> {
+        self.verify_code(user_id, code).await?;
+
+        // リカバリーコードも外部キーの制約によってあわせて削除される
+        sqlx::query!(
+            r#"
+                DELETE FROM user_totp WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(())
+    }
+
+    async fn issue_challenge(
+        &self,
+        user_id: UserId,
+        purpose: TwoFactorChallengePurpose,
+    ) -> AppResult<TwoFactorChallenge> {
+        let challenge = TwoFactorChallenge::new();
+        self.kv
+            .set_ex(
+                &TwoFactorChallengeKey::from(&challenge),
+                &ChallengedUser { purpose, user_id },
+                self.challenge_ttl,
+            )
+            .await?;
+        Ok(challenge)
+    }
+
+    async fn verify_challenge(
+        &self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> AppResult<TwoFactorVerified> {
+        let key = TwoFactorChallengeKey::from(challenge);
+        let Some(ChallengedUser { purpose, user_id }) = self.kv.get(&key).await? else {
+            return Err(AppError::UnauthorizedError);
+        };
+
+        // 同時に試行されても上限を超えて検証しないよう、検証する前に失敗として数えておく
+        // パスワードでのログインに成功しても、この回数はリセットされない
+        let failures_key = TwoFactorFailuresKey(user_id);
+        let failures = self.kv.increment(&failures_key, self.lockout).await?;
+        if failures > self.max_failures {
+            self.kv.delete(&key).await?;
+            return Err(AppError::TooManyRequests(self.lockout));
+        }
+
+        let result = match purpose {
+            TwoFactorChallengePurpose::Verify => {
+                self.verify_code(user_id, code).await.map(|_| Vec::new())
+            }
+            TwoFactorChallengePurpose::Enroll => self.activate(user_id, code).await,
+        };
+
+        let attempts_key = TwoFactorAttemptsKey(&key);
+        match result {
+            Ok(recovery_codes) => {
+                self.kv.delete(&key).await?;
+                self.kv.delete_counter(&attempts_key).await?;
+                self.kv.delete_counter(&failures_key).await?;
+                Ok(TwoFactorVerified {
+                    user_id,
+                    recovery_codes,
+                })
+            }
+            Err(AppError::UnauthenticatedError) => {
+                self.login_throttle
+                    .record_two_factor_failure(user_id)
+                    .await?;
+                let attempts = self.kv.increment(&attempts_key, self.challenge_ttl).await?;
+                if attempts >= MAX_CHALLENGE_ATTEMPTS || failures >= self.max_failures {
+                    tracing::warn!(
+                        user_id = %user_id,
+                        failures,
+                        "Too many failed two-factor attempts; discarding the challenge"
+                    );
+                    self.kv.delete(&key).await?;
+                    self.kv.delete_counter(&attempts_key).await?;
+                }
+                Err(AppError::UnauthenticatedError)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::config::{LoginThrottleConfig, RedisConfig};
+
+    use super::*;
+    use crate::{
+        database::model::login_throttle::{LoginFailuresKey, LoginThrottleTarget},
+        repository::login_throttle::LoginThrottleRepositoryImpl,
+    };
+
+    fn redis_client() -> anyhow::Result<Arc<RedisClient>> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(Arc::new(RedisClient::new(&config)?))
+    }
+
+    fn repository(
+        pool: &sqlx::PgPool,
+        max_failures: u64,
+    ) -> anyhow::Result<TwoFactorRepositoryImpl> {
+        let kv = redis_client()?;
+        let login_throttle = LoginThrottleRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            kv.clone(),
+            LoginThrottleConfig {
+                max_failures_per_email: 100,
+                max_failures_per_ip: 100,
+                backoff_base: 0,
+                lockout: 60,
+            },
+        );
+        Ok(TwoFactorRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            kv,
+            Arc::new(login_throttle),
+            "book-manager".into(),
+            60,
+            max_failures,
+            60,
+        ))
+    }
+
+    // 他のテストと Redis のキーを共有しないよう、実行ごとに異なるユーザーを作成する
+    async fn create_user(pool: &sqlx::PgPool) -> anyhow::Result<(UserId, String)> {
+        let user_id = UserId::new();
+        let email = format!("{}@example.com", user_id.raw().simple());
+        sqlx::query!(
+            r#"
+                INSERT INTO users(user_id, name, email, password_hash, role_id)
+                SELECT $1, 'Two Factor User', $2, '', role_id FROM roles WHERE name = 'User'
+            "#,
+            user_id as _,
+            email
+        )
+        .execute(pool)
+        .await?;
+        Ok((user_id, email))
+    }
+
+    async fn current_code(pool: &sqlx::PgPool, user_id: UserId) -> anyhow::Result<String> {
+        let secret = sqlx::query_scalar!(
+            "SELECT secret FROM user_totp WHERE user_id = $1",
+            user_id as _
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(totp::generate(&secret, Utc::now().timestamp()))
+    }
+
+    // TOTP を登録して有効にし、発行されたリカバリーコードを返す
+    async fn enable(
+        repo: &TwoFactorRepositoryImpl,
+        pool: &sqlx::PgPool,
+        user_id: UserId,
+    ) -> anyhow::Result<(String, Vec<RecoveryCode>)> {
+        repo.begin_enrollment(user_id).await?;
+        let code = current_code(pool, user_id).await?;
+        let recovery_codes = repo.activate(user_id, &code).await?;
+        Ok((code, recovery_codes))
+    }
+
+    async fn verify(
+        repo: &TwoFactorRepositoryImpl,
+        user_id: UserId,
+        code: &str,
+    ) -> AppResult<TwoFactorVerified> {
+        let challenge = repo
+            .issue_challenge(user_id, TwoFactorChallengePurpose::Verify)
+            .await?;
+        repo.verify_challenge(&challenge, code).await
+    }
+
+    #[sqlx::test]
+    async fn test_enroll_and_activate(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, 10)?;
+        let (user_id, _) = create_user(&pool).await?;
+        assert!(!repo.is_enabled(user_id).await?);
+
+        let enrollment = repo.begin_enrollment(user_id).await?;
+        assert!(enrollment.provisioning_uri.starts_with("otpauth://totp/"));
+        assert!(enrollment.provisioning_uri.contains(&enrollment.secret));
+
+        // 誤ったコードでは有効にならない
+        let res = repo.activate(user_id, "abcdef").await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        assert!(!repo.is_enabled(user_id).await?);
+
+        let code = current_code(&pool, user_id).await?;
+        let recovery_codes = repo.activate(user_id, &code).await?;
+        assert_eq!(recovery_codes.len(), 10);
+        assert!(repo.is_enabled(user_id).await?);
+
+        // 有効になった後は、共有鍵を作り直せない
+        let res = repo.begin_enrollment(user_id).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_code_cannot_be_replayed(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, 10)?;
+        let (user_id, _) = create_user(&pool).await?;
+        let (code, _) = enable(&repo, &pool, user_id).await?;
+
+        // 登録の確認に使ったコードは、ログインには使えない
+        let res = verify(&repo, user_id, &code).await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_recovery_code_can_be_used_once(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, 10)?;
+        let (user_id, _) = create_user(&pool).await?;
+        let (_, recovery_codes) = enable(&repo, &pool, user_id).await?;
+
+        // 区切り文字や大文字・小文字の違いは無視する
+        let recovery_code = recovery_codes[0].0.to_uppercase().replace('-', "");
+        let verified = verify(&repo, user_id, &recovery_code).await?;
+        assert_eq!(verified.user_id, user_id);
+
+        let res = verify(&repo, user_id, &recovery_codes[0].0).await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        verify(&repo, user_id, &recovery_codes[1].0).await?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_lockout_across_challenges(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = repository(&pool, 3)?;
+        let (user_id, email) = create_user(&pool).await?;
+        let (_, recovery_codes) = enable(&repo, &pool, user_id).await?;
+
+        // チャレンジを発行し直しても、失敗の回数は引き継がれる
+        for _ in 0..3 {
+            let res = verify(&repo, user_id, "abcdef").await;
+            assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        }
+        let res = verify(&repo, user_id, &recovery_codes[0].0).await;
+        assert!(matches!(res, Err(AppError::TooManyRequests(_))));
+
+        // コードの誤りは、パスワードでのログインの失敗としても数えられる
+        let failures = repo
+            .kv
+            .counter(&LoginFailuresKey(&LoginThrottleTarget::email(&email)))
+            .await?;
+        assert_eq!(failures, 3);
+
+        // ロックされている間に使おうとしたリカバリーコードは消費されない
+        repo.kv
+            .delete_counter(&TwoFactorFailuresKey(user_id))
+            .await?;
+        verify(&repo, user_id, &recovery_codes[0].0).await?;
+        Ok(())
+    }
+}
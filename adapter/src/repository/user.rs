@@ -1,19 +1,43 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::model::id::UserId;
+use kernel::model::list::PaginatedList;
 use kernel::model::role::Role;
 use kernel::model::user::{
-    event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
-    User,
+    event::{
+        CreateUser, ProvisionUser, SyncRoleMembers, SyncUserProfile, UpdateLoanStatisticsSharing,
+        UpdateUserPassword, UpdateUserProfile, UpdateUserRole, UpdateUserStatus,
+    },
+    EmailChangeToken, PendingEmailChange, User, UserListOptions, UserSort, UserStatus,
 };
-use kernel::repository::user::UserRepository;
+use kernel::repository::{auth::AuthRepository, user::UserRepository};
 use shared::error::{AppError, AppResult};
+use sqlx::PgConnection;
+use std::str::FromStr;
+
+use crate::{
+    database::{
+        conflict_on_unique_violation,
+        model::user::{EmailChangeKey, EmailChangeRequest, PaginatedUserRow, UserRow},
+        transaction::IsolationLevel,
+        ConnectionPool,
+    },
+    password::{hash_password, verify_password, UNUSABLE_PASSWORD_HASH},
+    redis::RedisClient,
+};
 
-use crate::database::{model::user::UserRow, ConnectionPool};
+const EMAIL_ALREADY_USED: &str = "このメールアドレスはすでに使われています";
 
 #[derive(new)]
 pub struct UserRepsitoryImpl {
     db: ConnectionPool,
+    // パスワードやロール、状態の変更の際に、発行済みのトークンを無効にするために使う
+    auth: Arc<dyn AuthRepository>,
+    kv: Arc<RedisClient>,
+    // メールアドレス変更の確認用トークンの有効期間（秒）
+    email_change_ttl: u64,
 }
 
 #[async_trait]
@@ -27,6 +51,7 @@ impl UserRepository for UserRepsitoryImpl {
                     u.name,
                     u.email,
                     r.name as role_name,
+                    u.status,
                     u.created_at,
                     u.updated_at
                 FROM users AS u
@@ -44,8 +69,9 @@ impl UserRepository for UserRepsitoryImpl {
         }
     }
 
-    async fn find_all(&self) -> AppResult<Vec<User>> {
-        let users = sqlx::query_as!(
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        // 大文字・小文字の違いだけのアドレスがある場合は、完全に一致するものを優先する
+        let row = sqlx::query_as!(
             UserRow,
             r#"
                 SELECT
@@ -53,123 +79,544 @@ impl UserRepository for UserRepsitoryImpl {
                     u.name,
                     u.email,
                     r.name as role_name,
+                    u.status,
                     u.created_at,
                     u.updated_at
-                FROM users As u
+                FROM users AS u
                 INNER JOIN roles AS r USING(role_id)
-                ORDER BY u.created_at DESC;
-            "#
+                WHERE LOWER(u.email) = LOWER($1)
+                ORDER BY u.email = $1 DESC
+                LIMIT 1
+            "#,
+            email
         )
-        .fetch_all(self.db.inner_ref())
+        .fetch_optional(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .into_iter()
-        .filter_map(|row| User::try_from(row).ok())
-        .collect();
-
-        Ok(users)
+        .map_err(AppError::SpecificOperationError)?;
+        row.map(User::try_from).transpose()
     }
 
-    async fn create(&self, event: CreateUser) -> AppResult<User> {
-        let user_id = UserId::new();
-        let hashed_password = hash_password(&event.password)?;
-        // ユーザーを追加するときは管理者でなく一般のユーザー権限とする。
-        let role = Role::User;
+    async fn find_all(&self, options: UserListOptions) -> AppResult<PaginatedList<User>> {
+        let UserListOptions {
+            limit,
+            offset,
+            keyword,
+            search_email,
+            role,
+            status,
+            sort,
+        } = options;
+        let (sort_key, descending) = sort_columns(sort);
 
-        let res = sqlx::query!(
+        // 並び順はクエリを組み立てずに済むよう、CASE 式で切り替える
+        // 無効化したユーザーは匿名化して表示するため、名前やメールアドレスでは検索できない
+        // 招待中のユーザーは招待の一覧で扱うため、状態を指定した場合のみ含める
+        let rows = sqlx::query_as!(
+            PaginatedUserRow,
             r#"
-                INSERT INTO users(user_id, name, email, password_hash, role_id)
-                SELECT $1, $2, $3, $4, role_id FROM roles WHERE name = $5;
+                SELECT
+                    COUNT(*) OVER() AS "total!",
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name as role_name,
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+                FROM users As u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE (
+                    $3::VARCHAR IS NULL
+                    OR (
+                        u.status <> 'deactivated'
+                        AND (u.name ILIKE $3 OR ($4 AND u.email ILIKE $3))
+                    )
+                )
+                AND ($5::VARCHAR IS NULL OR r.name = $5)
+                AND (($8::VARCHAR IS NULL AND u.status <> 'invited') OR u.status = $8)
+                ORDER BY
+                    CASE WHEN $6 = 'name' AND NOT $7 THEN u.name END ASC,
+                    CASE WHEN $6 = 'name' AND $7 THEN u.name END DESC,
+                    CASE WHEN $6 = 'email' AND NOT $7 THEN u.email END ASC,
+                    CASE WHEN $6 = 'email' AND $7 THEN u.email END DESC,
+                    CASE WHEN $6 = 'created_at' AND NOT $7 THEN u.created_at END ASC,
+                    u.created_at DESC,
+                    u.user_id
+                LIMIT $1
+                OFFSET $2
             "#,
-            user_id as _,
-            event.name,
-            event.email,
-            hashed_password,
-            role.as_ref()
+            limit,
+            offset,
+            keyword.as_deref().map(like_pattern),
+            search_email,
+            role.as_ref().map(|role| role.as_ref()),
+            sort_key,
+            descending,
+            status.as_ref().map(|status| status.as_ref())
         )
-        .execute(self.db.inner_ref())
+        .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No user has been created".into(),
-            ));
-        }
+        let total = rows.first().map(|r| r.total).unwrap_or_default(); // レコードが一件も無い時は 0 にする
 
-        Ok(User {
-            id: user_id,
-            name: event.name,
-            email: event.email,
-            role,
+        // 変換できない行があった場合は、黙って除外せずにエラーとして返す
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                let user_id = row.user_id;
+                User::try_from(UserRow::from(row)).map_err(|e| {
+                    tracing::error!(
+                        user_id = %user_id,
+                        error.message = %e,
+                        "Failed to convert user row"
+                    );
+                    e
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
         })
     }
 
-    async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()> {
-        let mut tx = self.db.begin().await?;
-
-        let original_password_hash = sqlx::query!(
+    async fn find_existing_emails(&self, emails: Vec<String>) -> AppResult<Vec<String>> {
+        let emails = emails
+            .into_iter()
+            .map(|email| email.to_lowercase())
+            .collect::<Vec<_>>();
+        // 招待中のユーザーも、同じメールアドレスでは登録できないため含める
+        sqlx::query_scalar!(
             r#"
-                SELECT password_hash FROM users WHERE user_id = $1;
+                SELECT email FROM users
+                WHERE LOWER(email) = ANY($1)
             "#,
-            event.user_id as _
+            &emails
         )
-        .fetch_one(&mut *tx)
+        .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .password_hash;
+        .map_err(AppError::SpecificOperationError)
+    }
 
-        // 現在のパスワードが正しいか検証
-        verify_password(&event.current_password, &original_password_hash)?;
+    async fn create(&self, event: CreateUser) -> AppResult<User> {
+        let hashed_password = hash_password(&event.password).await?;
+        insert_user(&self.db, event.name, event.email, &hashed_password).await
+    }
 
-        // 新しいパスワードハッシュに置き換える
-        let new_password_hash = hash_password(&event.new_password)?;
-        sqlx::query!(
+    async fn provision(&self, event: ProvisionUser) -> AppResult<User> {
+        insert_user(&self.db, event.name, event.email, UNUSABLE_PASSWORD_HASH).await
+    }
+
+    async fn update_profile(
+        &self,
+        event: UpdateUserProfile,
+    ) -> AppResult<Option<PendingEmailChange>> {
+        let UpdateUserProfile {
+            user_id,
+            name,
+            email,
+        } = event;
+
+        // 名前だけが変更されることのないよう、メールアドレスが使えるかを先に確認する
+        let used = sqlx::query_scalar!(
             r#"
-                UPDATE users SET password_hash = $2 WHERE user_id = $1;
+                SELECT EXISTS(
+                    SELECT 1 FROM users WHERE email = $1 AND user_id <> $2
+                ) AS "used!"
             "#,
-            event.user_id as _,
-            new_password_hash,
+            email,
+            user_id as _
         )
-        .execute(&mut *tx)
+        .fetch_one(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
+        if used {
+            return Err(AppError::EntityConflict(EMAIL_ALREADY_USED.into()));
+        }
 
-        tx.commit().await.map_err(AppError::TransactionError)?;
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+                UPDATE users AS u
+                SET name = $2
+                FROM roles AS r
+                WHERE u.user_id = $1 AND r.role_id = u.role_id
+                RETURNING
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name AS role_name,
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+            "#,
+            user_id as _,
+            name
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))?;
+        let user = User::try_from(row)?;
 
-        Ok(())
+        if user.email == email {
+            return Ok(None);
+        }
+
+        // 新しいメールアドレスは、届いたリンクから確認が済むまで反映しない
+        let token = EmailChangeToken::new();
+        self.kv
+            .set_ex(
+                &EmailChangeKey::from(&token),
+                &EmailChangeRequest {
+                    user_id,
+                    new_email: email.clone(),
+                },
+                self.email_change_ttl,
+            )
+            .await?;
+
+        Ok(Some(PendingEmailChange {
+            user,
+            new_email: email,
+            token,
+        }))
     }
 
-    async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+    async fn confirm_email_change(&self, token: &EmailChangeToken) -> AppResult<UserId> {
+        // トークンは一度しか使えないよう、取得と同時に削除する
+        let key = EmailChangeKey::from(token);
+        let Some(EmailChangeRequest { user_id, new_email }) = self.kv.get_del(&key).await? else {
+            return Err(AppError::EntityNotFound(
+                "確認用のトークンが無効か、有効期限が切れています".into(),
+            ));
+        };
+
+        // 確認を待つ間に他のユーザーが同じアドレスを使い始めた場合は、一意制約違反として競合を返す
         let res = sqlx::query!(
             r#"
                 UPDATE users
-                SET role_id = (
-                    SELECT role_id FROM roles WHERE name = $2
-                )
+                SET email = $2,
+                    email_verified_at = CURRENT_TIMESTAMP(3)
                 WHERE user_id = $1
             "#,
-            event.user_id as _,
-            event.role.as_ref()
+            user_id as _,
+            new_email
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(conflict_on_unique_violation(EMAIL_ALREADY_USED))?;
 
         if res.rows_affected() < 1 {
             return Err(AppError::EntityNotFound("Specified user not found".into()));
         }
 
+        Ok(user_id)
+    }
+
+    async fn sync_profile(&self, event: SyncUserProfile) -> AppResult<User> {
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+                UPDATE users AS u
+                SET name = $2,
+                    email = $3
+                FROM roles AS r
+                WHERE u.user_id = $1 AND r.role_id = u.role_id
+                RETURNING
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name AS role_name,
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+            "#,
+            event.user_id as _,
+            event.name,
+            event.email
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(conflict_on_unique_violation(EMAIL_ALREADY_USED))?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))?;
+        User::try_from(row)
+    }
+
+    async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()> {
+        let user_id = event.user_id;
+        let original_password_hash = sqlx::query_scalar!(
+            r#"
+                SELECT password_hash FROM users WHERE user_id = $1;
+            "#,
+            user_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // 現在のパスワードが誤っている場合に新しいパスワードのハッシュ化を行わないよう、先に検証する
+        if !verify_password(&event.current_password, &original_password_hash)
+            .await?
+            .is_valid()
+        {
+            return Err(AppError::UnauthenticatedError);
+        }
+
+        // トランザクションがやり直しになった場合にハッシュ化を繰り返さないよう、
+        // 新しいパスワードハッシュは事前に作成しておく
+        let new_password_hash = hash_password(&event.new_password).await?;
+
+        self.db
+            .run_in_transaction(IsolationLevel::ReadCommitted, |conn| {
+                let original_password_hash = original_password_hash.clone();
+                let new_password_hash = new_password_hash.clone();
+                Box::pin(async move {
+                    // 検証した後に他の処理でパスワードが変更されていた場合は置き換えない
+                    let res = sqlx::query!(
+                        r#"
+                            UPDATE users SET password_hash = $3
+                            WHERE user_id = $1 AND password_hash = $2;
+                        "#,
+                        user_id as _,
+                        original_password_hash,
+                        new_password_hash,
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    if res.rows_affected() < 1 {
+                        return Err(AppError::UnauthenticatedError);
+                    }
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        // 古いパスワードでログインしたセッションは使えないようにする
+        self.auth.delete_all_tokens(user_id).await
+    }
+
+    async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+        // 有効な管理者の人数を確認してから変更するため、シリアライザブルで実行する
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let user_id = event.user_id;
+                let role = event.role.clone();
+                Box::pin(async move {
+                    let current = find_role_and_status(&mut *conn, user_id)
+                        .await?
+                        .ok_or_else(|| {
+                            AppError::EntityNotFound("Specified user or role not found".into())
+                        })?;
+                    if current.0 == Role::Admin
+                        && current.1 == UserStatus::Active
+                        && role != Role::Admin
+                    {
+                        ensure_other_active_admin(&mut *conn, user_id).await?;
+                    }
+
+                    let res = sqlx::query!(
+                        r#"
+                            UPDATE users AS u
+                            SET role_id = r.role_id
+                            FROM roles AS r
+                            WHERE u.user_id = $1 AND r.name = $2
+                        "#,
+                        user_id as _,
+                        role.as_ref()
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    if res.rows_affected() < 1 {
+                        return Err(AppError::EntityNotFound(
+                            "Specified user or role not found".into(),
+                        ));
+                    }
+                    Ok(())
+                })
+            })
+            .await?;
+
+        // 変更前のロールを前提とした（JWT の場合はクレームに含まれる）セッションを残さない
+        self.auth.delete_all_tokens(event.user_id).await
+    }
+
+    async fn sync_role_members(&self, event: SyncRoleMembers) -> AppResult<()> {
+        let mut member_ids = event.member_ids;
+        member_ids.sort_by_key(|user_id| user_id.raw());
+        member_ids.dedup();
+
+        // すべての変更を反映してから有効な管理者の人数を確認するため、シリアライザブルで実行する
+        let changed = self
+            .db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                let role = event.role.clone();
+                let member_ids = member_ids.clone();
+                Box::pin(async move {
+                    let role_exists = sqlx::query_scalar!(
+                        r#"SELECT EXISTS(SELECT 1 FROM roles WHERE name = $1) AS "exists!""#,
+                        role.as_ref()
+                    )
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    if !role_exists {
+                        return Err(AppError::EntityNotFound("Specified role not found".into()));
+                    }
+
+                    // 変更を始める前に、指定したユーザーがすべて存在することを確認する
+                    let found = sqlx::query_scalar!(
+                        r#"SELECT COUNT(*) AS "count!" FROM users WHERE user_id = ANY($1)"#,
+                        &member_ids as _
+                    )
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                    if found != member_ids.len() as i64 {
+                        return Err(AppError::EntityNotFound("Specified user not found".into()));
+                    }
+
+                    let mut changed = sqlx::query_scalar!(
+                        r#"
+                            UPDATE users AS u
+                            SET role_id = r.role_id
+                            FROM roles AS r
+                            WHERE r.name = $1
+                            AND u.user_id = ANY($2)
+                            AND u.role_id <> r.role_id
+                            RETURNING u.user_id AS "user_id: UserId"
+                        "#,
+                        role.as_ref(),
+                        &member_ids as _
+                    )
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    // ユーザーは必ず1つのロールを持つため、外したユーザーは既定の User ロールに戻す
+                    if role != Role::User {
+                        let removed = sqlx::query_scalar!(
+                            r#"
+                                UPDATE users AS u
+                                SET role_id = d.role_id
+                                FROM roles AS r, roles AS d
+                                WHERE r.name = $1
+                                AND d.name = $2
+                                AND u.role_id = r.role_id
+                                AND NOT (u.user_id = ANY($3))
+                                RETURNING u.user_id AS "user_id: UserId"
+                            "#,
+                            role.as_ref(),
+                            Role::User.as_ref(),
+                            &member_ids as _
+                        )
+                        .fetch_all(&mut *conn)
+                        .await
+                        .map_err(AppError::SpecificOperationError)?;
+                        changed.extend(removed);
+                    }
+
+                    if !changed.is_empty() {
+                        ensure_active_admin(&mut *conn).await?;
+                    }
+                    Ok(changed)
+                })
+            })
+            .await?;
+
+        // 変更前のロールを前提とした（JWT の場合はクレームに含まれる）セッションを残さない
+        for user_id in changed {
+            self.auth.delete_all_tokens(user_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn update_status(&self, event: UpdateUserStatus) -> AppResult<()> {
+        let UpdateUserStatus {
+            user_id,
+            status,
+            changed_by,
+        } = event;
+
+        self.db
+            .run_in_transaction(IsolationLevel::Serializable, |conn| {
+                Box::pin(async move {
+                    let (role, current) = find_role_and_status(&mut *conn, user_id)
+                        .await?
+                        .ok_or_else(|| {
+                            AppError::EntityNotFound("Specified user not found".into())
+                        })?;
+                    if !current.can_transition_to(status) {
+                        return Err(AppError::UnprocessableEntity(format!(
+                            "ユーザーの状態を {} から {} には変更できません",
+                            current.as_ref(),
+                            status.as_ref()
+                        )));
+                    }
+                    if role == Role::Admin && current == UserStatus::Active {
+                        ensure_other_active_admin(&mut *conn, user_id).await?;
+                    }
+
+                    sqlx::query!(
+                        r#"
+                            UPDATE users
+                            SET status = $2,
+                                status_changed_at = CURRENT_TIMESTAMP(3)
+                            WHERE user_id = $1
+                        "#,
+                        user_id as _,
+                        status.as_ref()
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    sqlx::query!(
+                        r#"
+                            INSERT INTO user_status_changes(user_id, from_status, to_status, changed_by)
+                            VALUES ($1, $2, $3, $4)
+                        "#,
+                        user_id as _,
+                        current.as_ref(),
+                        status.as_ref(),
+                        changed_by as _
+                    )
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        // 利用停止や無効化の前にログインしたセッションは使えないようにする
+        if status != UserStatus::Active {
+            self.auth.delete_all_tokens(user_id).await?;
+        }
         Ok(())
     }
 
-    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+    async fn update_loan_statistics_sharing(
+        &self,
+        event: UpdateLoanStatisticsSharing,
+    ) -> AppResult<()> {
         let res = sqlx::query!(
             r#"
-                DELETE FROM users
+                UPDATE users
+                SET share_loan_statistics = $2
                 WHERE user_id = $1
             "#,
-            event.user_id as _
+            event.user_id as _,
+            event.share
         )
         .execute(self.db.inner_ref())
         .await
@@ -178,19 +625,779 @@ impl UserRepository for UserRepsitoryImpl {
         if res.rows_affected() < 1 {
             return Err(AppError::EntityNotFound("Specified user not found".into()));
         }
-
         Ok(())
     }
 }
 
-fn hash_password(password: &str) -> AppResult<String> {
-    bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(AppError::from)
+// パスワードのハッシュを指定してユーザーを追加する
+async fn insert_user(
+    db: &ConnectionPool,
+    name: String,
+    email: String,
+    password_hash: &str,
+) -> AppResult<User> {
+    let user_id = UserId::new();
+    // ユーザーを追加するときは管理者でなく一般のユーザー権限とする。
+    let role = Role::User;
+
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO users(user_id, name, email, password_hash, role_id)
+            SELECT $1, $2, $3, $4, role_id FROM roles WHERE name = $5;
+        "#,
+        user_id as _,
+        name,
+        email,
+        password_hash,
+        role.as_ref()
+    )
+    .execute(db.inner_ref())
+    .await
+    .map_err(conflict_on_unique_violation(EMAIL_ALREADY_USED))?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "No user has been created".into(),
+        ));
+    }
+
+    Ok(User {
+        id: user_id,
+        name,
+        email,
+        role,
+        status: UserStatus::Active,
+    })
 }
 
-fn verify_password(password: &str, hash: &str) -> AppResult<()> {
-    let valid = bcrypt::verify(password, hash)?;
-    if !valid {
-        return Err(AppError::UnauthenticatedError);
+// ユーザーの現在のロールと状態
+async fn find_role_and_status(
+    conn: &mut PgConnection,
+    user_id: UserId,
+) -> AppResult<Option<(Role, UserStatus)>> {
+    let Some(row) = sqlx::query!(
+        r#"
+            SELECT r.name AS role_name, u.status
+            FROM users AS u
+            INNER JOIN roles AS r USING(role_id)
+            WHERE u.user_id = $1
+        "#,
+        user_id as _
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?
+    else {
+        return Ok(None);
+    };
+
+    let role = Role::from_str(&row.role_name)
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    let status = UserStatus::from_str(&row.status)
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    Ok(Some((role, status)))
+}
+
+// 指定したユーザーのほかに有効な管理者がいなければ、変更を受け付けない
+// 管理者が誰もログインできなくなることを防ぐ
+async fn ensure_other_active_admin(conn: &mut PgConnection, user_id: UserId) -> AppResult<()> {
+    let exists = sqlx::query_scalar!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM users AS u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE r.name = $1 AND u.status = $2 AND u.user_id <> $3
+            ) AS "exists!"
+        "#,
+        Role::Admin.as_ref(),
+        UserStatus::Active.as_ref(),
+        user_id as _
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if !exists {
+        return Err(AppError::UnprocessableEntity(
+            "有効な管理者がいなくなるため、変更できません".into(),
+        ));
     }
     Ok(())
 }
+
+// 有効な管理者が1人もいなくなる変更は受け付けない
+async fn ensure_active_admin(conn: &mut PgConnection) -> AppResult<()> {
+    let exists = sqlx::query_scalar!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM users AS u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE r.name = $1 AND u.status = $2
+            ) AS "exists!"
+        "#,
+        Role::Admin.as_ref(),
+        UserStatus::Active.as_ref()
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if !exists {
+        return Err(AppError::UnprocessableEntity(
+            "有効な管理者がいなくなるため、変更できません".into(),
+        ));
+    }
+    Ok(())
+}
+
+// 並べ替えに使う列と、降順かどうか
+fn sort_columns(sort: UserSort) -> (&'static str, bool) {
+    match sort {
+        UserSort::CreatedAtDesc => ("created_at", true),
+        UserSort::CreatedAtAsc => ("created_at", false),
+        UserSort::NameAsc => ("name", false),
+        UserSort::NameDesc => ("name", true),
+        UserSort::EmailAsc => ("email", false),
+        UserSort::EmailDesc => ("email", true),
+    }
+}
+
+// 入力された文字列をそのまま部分一致で検索できるよう、LIKE の特殊文字をエスケープする
+fn like_pattern(keyword: &str) -> String {
+    let escaped = keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::{
+        model::{
+            auth::{
+                event::{CreateSession, CreateToken},
+                AccessToken,
+            },
+            personal_access_token::{event::CreatePersonalAccessToken, Scope},
+        },
+        repository::personal_access_token::PersonalAccessTokenRepository,
+    };
+    use shared::config::RedisConfig;
+
+    use super::*;
+    use crate::{
+        redis::RedisClient,
+        repository::{
+            auth::{AuthRepositoryImpl, SessionStore},
+            personal_access_token::PersonalAccessTokenRepositoryImpl,
+        },
+    };
+
+    fn redis_client() -> anyhow::Result<Arc<RedisClient>> {
+        let config = RedisConfig {
+            host: std::env::var("REDIS_HOST").unwrap(),
+            port: std::env::var("REDIS_PORT").unwrap().parse().unwrap(),
+        };
+        Ok(Arc::new(RedisClient::new(&config)?))
+    }
+
+    fn auth_repository(pool: &sqlx::PgPool) -> anyhow::Result<Arc<dyn AuthRepository>> {
+        let kv = redis_client()?;
+        Ok(Arc::new(AuthRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            kv.clone(),
+            1000,
+            SessionStore::new(kv, 1000),
+        )))
+    }
+
+    // ユーザーを作成し、ログインした状態（セッションとアクセストークン）と
+    // パーソナルアクセストークンを用意する
+    async fn setup(
+        pool: &sqlx::PgPool,
+    ) -> anyhow::Result<(
+        UserRepsitoryImpl,
+        Arc<dyn AuthRepository>,
+        User,
+        AccessToken,
+    )> {
+        sqlx::query!(
+            r#"INSERT INTO roles(name) VALUES('Admin'), ('User') ON CONFLICT DO NOTHING;"#
+        )
+        .execute(pool)
+        .await?;
+        let auth = auth_repository(pool)?;
+        let repo = UserRepsitoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            auth.clone(),
+            redis_client()?,
+            1000,
+        );
+
+        let user = repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        let issued = auth
+            .create_session(CreateSession {
+                user_id: user.id,
+                ip_address: None,
+                user_agent: None,
+            })
+            .await?;
+        let access_token = auth
+            .create_token(CreateToken::new(user.id, issued.session_id))
+            .await?;
+        assert_eq!(
+            auth.fetch_user_id_from_token(&access_token).await?,
+            Some(user.id)
+        );
+        PersonalAccessTokenRepositoryImpl::new(ConnectionPool::new(pool.clone()))
+            .create(CreatePersonalAccessToken {
+                user_id: user.id,
+                name: "script".into(),
+                scopes: vec![Scope::BooksRead],
+                expires_at: None,
+            })
+            .await?;
+
+        Ok((repo, auth, user, access_token))
+    }
+
+    async fn personal_access_token_count(
+        pool: &sqlx::PgPool,
+        user_id: UserId,
+    ) -> anyhow::Result<usize> {
+        Ok(
+            PersonalAccessTokenRepositoryImpl::new(ConnectionPool::new(pool.clone()))
+                .find_by_user(user_id)
+                .await?
+                .len(),
+        )
+    }
+
+    async fn assert_revoked(
+        pool: &sqlx::PgPool,
+        auth: &Arc<dyn AuthRepository>,
+        user_id: UserId,
+        access_token: &AccessToken,
+    ) -> anyhow::Result<()> {
+        assert!(auth.fetch_user_id_from_token(access_token).await?.is_none());
+        assert!(auth.find_sessions(user_id).await?.is_empty());
+        assert_eq!(personal_access_token_count(pool, user_id).await?, 0);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_password_revokes_sessions(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, auth, user, access_token) = setup(&pool).await?;
+
+        // 現在のパスワードが誤っている場合は、セッションは残る
+        let res = repo
+            .update_password(UpdateUserPassword {
+                user_id: user.id,
+                current_password: "wrong_password".into(),
+                new_password: "new_password".into(),
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(
+            auth.fetch_user_id_from_token(&access_token).await?,
+            Some(user.id)
+        );
+        assert_eq!(personal_access_token_count(&pool, user.id).await?, 1);
+
+        repo.update_password(UpdateUserPassword {
+            user_id: user.id,
+            current_password: "test_password".into(),
+            new_password: "new_password".into(),
+        })
+        .await?;
+        assert_revoked(&pool, &auth, user.id, &access_token).await
+    }
+
+    #[sqlx::test]
+    async fn test_legacy_password_hash_is_rehashed_on_login(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let (_, auth, user, _) = setup(&pool).await?;
+        let legacy_hash = bcrypt::hash("test_password", 4)?;
+        sqlx::query!(
+            "UPDATE users SET password_hash = $2 WHERE user_id = $1",
+            user.id as _,
+            legacy_hash,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 誤ったパスワードでは置き換えない
+        assert!(auth
+            .verify_user("test@example.com", "wrong_password")
+            .await
+            .is_err());
+        let password_hash = sqlx::query_scalar!(
+            "SELECT password_hash FROM users WHERE user_id = $1",
+            user.id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(password_hash, legacy_hash);
+
+        assert_eq!(
+            auth.verify_user("test@example.com", "test_password")
+                .await?,
+            user.id
+        );
+        let password_hash = sqlx::query_scalar!(
+            "SELECT password_hash FROM users WHERE user_id = $1",
+            user.id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert!(password_hash.starts_with("$argon2id$"));
+
+        // 置き換えた後も同じパスワードでログインできる
+        assert_eq!(
+            auth.verify_user("test@example.com", "test_password")
+                .await?,
+            user.id
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_provisioned_user_cannot_log_in_with_password(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let (repo, auth, _, _) = setup(&pool).await?;
+
+        let user = repo
+            .provision(ProvisionUser {
+                name: "Provisioned User".into(),
+                email: "provisioned@example.com".into(),
+            })
+            .await?;
+        let password_hash = sqlx::query_scalar!(
+            "SELECT password_hash FROM users WHERE user_id = $1",
+            user.id as _
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(password_hash, UNUSABLE_PASSWORD_HASH);
+
+        // 存在しないユーザーと同じく、どのようなパスワードでもログインに失敗する
+        for password in ["", UNUSABLE_PASSWORD_HASH, "test_password"] {
+            assert!(matches!(
+                auth.verify_user("provisioned@example.com", password).await,
+                Err(AppError::UnauthenticatedError)
+            ));
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_role_revokes_sessions(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, auth, user, access_token) = setup(&pool).await?;
+
+        repo.update_role(UpdateUserRole {
+            user_id: user.id,
+            role: Role::Admin,
+        })
+        .await?;
+        assert_revoked(&pool, &auth, user.id, &access_token).await
+    }
+
+    #[sqlx::test]
+    async fn test_deactivate_revokes_sessions(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, auth, user, access_token) = setup(&pool).await?;
+
+        repo.update_status(UpdateUserStatus {
+            user_id: user.id,
+            status: UserStatus::Deactivated,
+            changed_by: Some(user.id),
+        })
+        .await?;
+        assert_revoked(&pool, &auth, user.id, &access_token).await?;
+
+        // 無効化してもユーザーのデータは残り、状態だけが変わる
+        let found = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(found.status, UserStatus::Deactivated);
+
+        // 無効化したユーザーは元に戻せない
+        let res = repo
+            .update_status(UpdateUserStatus {
+                user_id: user.id,
+                status: UserStatus::Active,
+                changed_by: Some(user.id),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_suspend_and_resume(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, auth, user, access_token) = setup(&pool).await?;
+
+        for status in [UserStatus::Suspended, UserStatus::Active] {
+            repo.update_status(UpdateUserStatus {
+                user_id: user.id,
+                status,
+                changed_by: Some(user.id),
+            })
+            .await?;
+        }
+        assert_revoked(&pool, &auth, user.id, &access_token).await?;
+        let found = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(found.status, UserStatus::Active);
+
+        let changes = sqlx::query_scalar!(
+            r#"
+                SELECT to_status FROM user_status_changes
+                WHERE user_id = $1
+                ORDER BY changed_at, user_status_change_id
+            "#,
+            user.id as _
+        )
+        .fetch_all(&pool)
+        .await?;
+        assert_eq!(changes, vec!["suspended", "active"]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_last_active_admin_cannot_be_locked_out(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, _auth, user, _access_token) = setup(&pool).await?;
+        repo.update_role(UpdateUserRole {
+            user_id: user.id,
+            role: Role::Admin,
+        })
+        .await?;
+
+        // 有効な管理者がほかにいない場合は、利用停止もロールの変更もできない
+        let res = repo
+            .update_status(UpdateUserStatus {
+                user_id: user.id,
+                status: UserStatus::Suspended,
+                changed_by: Some(user.id),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = repo
+            .update_role(UpdateUserRole {
+                user_id: user.id,
+                role: Role::User,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 利用停止中の管理者は数に含めない
+        let other = repo
+            .create(CreateUser {
+                name: "Other Admin".into(),
+                email: "other@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        repo.update_role(UpdateUserRole {
+            user_id: other.id,
+            role: Role::Admin,
+        })
+        .await?;
+        repo.update_status(UpdateUserStatus {
+            user_id: other.id,
+            status: UserStatus::Suspended,
+            changed_by: Some(user.id),
+        })
+        .await?;
+        let res = repo
+            .update_status(UpdateUserStatus {
+                user_id: user.id,
+                status: UserStatus::Deactivated,
+                changed_by: Some(user.id),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        repo.update_status(UpdateUserStatus {
+            user_id: other.id,
+            status: UserStatus::Active,
+            changed_by: Some(user.id),
+        })
+        .await?;
+        repo.update_status(UpdateUserStatus {
+            user_id: user.id,
+            status: UserStatus::Deactivated,
+            changed_by: Some(other.id),
+        })
+        .await?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_sync_role_members(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, auth, user, access_token) = setup(&pool).await?;
+        let other = repo
+            .create(CreateUser {
+                name: "Other Admin".into(),
+                email: "other@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        let roles = || async {
+            let mut roles = Vec::new();
+            for user_id in [user.id, other.id] {
+                roles.push(repo.find_current_user(user_id).await?.map(|u| u.role));
+            }
+            anyhow::Ok(roles)
+        };
+
+        repo.sync_role_members(SyncRoleMembers {
+            role: Role::Admin,
+            member_ids: vec![user.id, other.id, user.id],
+        })
+        .await?;
+        assert_eq!(roles().await?, vec![Some(Role::Admin), Some(Role::Admin)]);
+        assert_revoked(&pool, &auth, user.id, &access_token).await?;
+
+        // 存在しないユーザーが含まれる場合は、ほかのユーザーも変更しない
+        let res = repo
+            .sync_role_members(SyncRoleMembers {
+                role: Role::Admin,
+                member_ids: vec![other.id, UserId::new()],
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert_eq!(roles().await?, vec![Some(Role::Admin), Some(Role::Admin)]);
+
+        // 有効な管理者がいなくなる場合は、何も変更しない
+        let res = repo
+            .sync_role_members(SyncRoleMembers {
+                role: Role::Admin,
+                member_ids: vec![],
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        assert_eq!(roles().await?, vec![Some(Role::Admin), Some(Role::Admin)]);
+
+        // 外したユーザーは User ロールに戻す
+        repo.sync_role_members(SyncRoleMembers {
+            role: Role::Admin,
+            member_ids: vec![other.id],
+        })
+        .await?;
+        assert_eq!(roles().await?, vec![Some(Role::User), Some(Role::Admin)]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile_and_change_email(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, _auth, user, _access_token) = setup(&pool).await?;
+        let other = repo
+            .create(CreateUser {
+                name: "Other User".into(),
+                email: "other@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+
+        // 同じメールアドレスでユーザーを作成すると競合になる
+        let res = repo
+            .create(CreateUser {
+                name: "Duplicated User".into(),
+                email: other.email.clone(),
+                password: "test_password".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityConflict(_))));
+
+        // 名前のみの変更はすぐに反映され、確認は不要
+        let pending = repo
+            .update_profile(UpdateUserProfile {
+                user_id: user.id,
+                name: "Renamed User".into(),
+                email: user.email.clone(),
+            })
+            .await?;
+        assert!(pending.is_none());
+        let current = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(current.name, "Renamed User");
+
+        // 他のユーザーのメールアドレスには変更できず、名前も変わらない
+        let res = repo
+            .update_profile(UpdateUserProfile {
+                user_id: user.id,
+                name: "Another Name".into(),
+                email: other.email.clone(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityConflict(_))));
+        let current = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(current.name, "Renamed User");
+
+        // メールアドレスは確認が済むまで変わらない
+        let pending = repo
+            .update_profile(UpdateUserProfile {
+                user_id: user.id,
+                name: "Renamed User".into(),
+                email: "new@example.com".into(),
+            })
+            .await?
+            .unwrap();
+        assert_eq!(pending.new_email, "new@example.com");
+        let current = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(current.email, user.email);
+
+        assert_eq!(repo.confirm_email_change(&pending.token).await?, user.id);
+        let current = repo.find_current_user(user.id).await?.unwrap();
+        assert_eq!(current.email, "new@example.com");
+
+        // トークンは一度しか使えない
+        let res = repo.confirm_email_change(&pending.token).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 確認を待つ間に他のユーザーが使い始めたアドレスは確定できない
+        let pending = repo
+            .update_profile(UpdateUserProfile {
+                user_id: user.id,
+                name: "Renamed User".into(),
+                email: "taken@example.com".into(),
+            })
+            .await?
+            .unwrap();
+        let others_pending = repo
+            .update_profile(UpdateUserProfile {
+                user_id: other.id,
+                name: other.name.clone(),
+                email: "taken@example.com".into(),
+            })
+            .await?
+            .unwrap();
+        repo.confirm_email_change(&others_pending.token).await?;
+        let res = repo.confirm_email_change(&pending.token).await;
+        assert!(matches!(res, Err(AppError::EntityConflict(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_find_existing_emails(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, _, _, _) = setup(&pool).await?;
+
+        let existing = repo
+            .find_existing_emails(vec!["TEST@example.com".into(), "new@example.com".into()])
+            .await?;
+        assert_eq!(existing, vec!["test@example.com".to_string()]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_sync_profile(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, _, user, _) = setup(&pool).await?;
+
+        // 確認を待たずに、新しいメールアドレスで検索できる
+        let synced = repo
+            .sync_profile(SyncUserProfile {
+                user_id: user.id,
+                name: "Synced User".into(),
+                email: "synced@example.com".into(),
+            })
+            .await?;
+        assert_eq!(synced.name, "Synced User");
+        assert_eq!(
+            repo.find_by_email("SYNCED@example.com")
+                .await?
+                .map(|u| u.id),
+            Some(user.id)
+        );
+        assert!(repo.find_by_email("test@example.com").await?.is_none());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_find_all_with_options(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, _auth, user, _access_token) = setup(&pool).await?;
+        for (name, email) in [
+            ("Alice", "alice@example.com"),
+            ("Bob", "bob@example.org"),
+            ("Carol_100%", "carol@example.org"),
+        ] {
+            repo.create(CreateUser {
+                name: name.into(),
+                email: email.into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        }
+        repo.update_role(UpdateUserRole {
+            user_id: user.id,
+            role: Role::Admin,
+        })
+        .await?;
+
+        let options = |keyword: Option<&str>, search_email, role, sort| UserListOptions {
+            limit: 2,
+            offset: 0,
+            keyword: keyword.map(String::from),
+            search_email,
+            role,
+            status: None,
+            sort,
+        };
+        let names = |list: &PaginatedList<User>| {
+            list.items
+                .iter()
+                .map(|user| user.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // 件数はページに関わらずすべての件数を返す
+        let list = repo
+            .find_all(options(None, true, None, UserSort::NameAsc))
+            .await?;
+        assert_eq!(list.total, 4);
+        assert_eq!(names(&list), vec!["Alice", "Bob"]);
+        let list = repo
+            .find_all(options(None, true, None, UserSort::NameDesc))
+            .await?;
+        assert_eq!(names(&list), vec!["Test User", "Carol_100%"]);
+
+        // メールアドレスでの検索は search_email が true の場合のみ
+        let list = repo
+            .find_all(options(Some("example.org"), true, None, UserSort::EmailAsc))
+            .await?;
+        assert_eq!(names(&list), vec!["Bob", "Carol_100%"]);
+        let list = repo
+            .find_all(options(
+                Some("example.org"),
+                false,
+                None,
+                UserSort::EmailAsc,
+            ))
+            .await?;
+        assert_eq!(list.total, 0);
+
+        // LIKE の特殊文字はそのままの文字として検索する
+        let list = repo
+            .find_all(options(Some("_100%"), false, None, UserSort::default()))
+            .await?;
+        assert_eq!(names(&list), vec!["Carol_100%"]);
+        let list = repo
+            .find_all(options(Some("%"), false, None, UserSort::default()))
+            .await?;
+        assert_eq!(list.total, 1);
+
+        let list = repo
+            .find_all(options(None, true, Some(Role::Admin), UserSort::default()))
+            .await?;
+        assert_eq!(names(&list), vec!["Test User"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+// 推測できないランダムな値。OIDC の state, nonce, PKCE の code_verifier などに使う
+// code_verifier の要件（43 文字以上で、URL に使える文字のみ）を満たす
+pub fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+// URL などに含めてユーザーに渡すトークンはデータベースには平文で保存せず、
+// SHA-256 のハッシュ値のみを保存して照合に使う
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,179 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+
+// RFC 6238 の既定値。多くの認証アプリはこの組み合わせのみに対応している
+const DIGITS: u32 = 6;
+const STEP_SECONDS: i64 = 30;
+// 端末の時計のずれを考慮し、前後 1 ステップまでのコードを受け付ける
+const ALLOWED_DRIFT: i64 = 1;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+// 読み間違えやすい文字（0, 1, l, o など）を除いたリカバリーコードの文字
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// RFC 4226 で推奨される 160 ビットの共有鍵を生成する
+pub fn generate_secret() -> Vec<u8> {
+    rand::random::<[u8; 20]>().to_vec()
+}
+
+// 認証アプリに手入力する場合のための、パディングなしの Base32 (RFC 4648)
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    for chunk in bytes.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            encoded.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    encoded
+}
+
+// 認証アプリに QR コードなどで登録するための URI
+// https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        base32_encode(secret),
+        percent_encode(issuer),
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn time_step(unix_time: i64) -> i64 {
+    unix_time.div_euclid(STEP_SECONDS)
+}
+
+// RFC 4226 の HOTP
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    binary % 10u32.pow(DIGITS)
+}
+
+// コードが一致した時間ステップを返す。同じコードの再利用を防ぐために呼び出し元で記録する
+pub fn verify(secret: &[u8], code: &str, unix_time: i64) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let current = time_step(unix_time);
+    (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT)
+        .find(|&step| step >= 0 && hotp(secret, step as u64) == code)
+}
+
+// 認証アプリと同じく、指定した時刻のコードを生成する。リポジトリのテストで使う
+#[cfg(test)]
+pub fn generate(secret: &[u8], unix_time: i64) -> String {
+    format!(
+        "{:0width$}",
+        hotp(secret, time_step(unix_time) as u64),
+        width = DIGITS as usize
+    )
+}
+
+// `xxxxx-xxxxx` の形式のリカバリーコードを生成する
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut code: String = (0..10)
+                .map(|_| {
+                    RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char
+                })
+                .collect();
+            code.insert(5, '-');
+            code
+        })
+        .collect()
+}
+
+// 入力時の区切り文字や大文字小文字の違いは無視する
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 Appendix B の SHA-1 のテストベクタ（下 6 桁）
+    const RFC6238_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_verify() {
+        for (unix_time, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(
+                verify(RFC6238_SECRET, code, unix_time),
+                Some(time_step(unix_time))
+            );
+        }
+
+        // 前後 1 ステップのずれは許容する
+        assert_eq!(
+            verify(RFC6238_SECRET, "081804", 1111111109 + 30),
+            Some(37037036)
+        );
+        assert_eq!(verify(RFC6238_SECRET, "081804", 1111111109 + 90), None);
+        // 桁数や文字が不正なコードは受け付けない
+        assert_eq!(verify(RFC6238_SECRET, "81804", 1111111109), None);
+        assert_eq!(verify(RFC6238_SECRET, "08180a", 1111111109), None);
+    }
+
+    #[test]
+    fn test_base32_encode() {
+        assert_eq!(
+            base32_encode(RFC6238_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes.iter().all(|code| code.len() == 11));
+        assert_eq!(
+            normalize_recovery_code(" ABCDE-fghjk "),
+            normalize_recovery_code("abcdefghjk")
+        );
+    }
+}
@@ -1,14 +1,21 @@
-use axum::extract::FromRequestParts;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::USER_AGENT;
 use axum::http::request::Parts;
 use axum::{async_trait, RequestPartsExt};
 use axum_extra::headers::authorization::Bearer;
 use axum_extra::headers::Authorization;
 use axum_extra::TypedHeader;
 use kernel::model::auth::AccessToken;
-use kernel::model::id::UserId;
-use kernel::model::role::Role;
-use kernel::model::user::User;
-use shared::error::AppError;
+use kernel::model::id::{KioskId, PersonalAccessTokenId, UserId};
+use kernel::model::kiosk::{KioskDevice, KioskSecret};
+use kernel::model::personal_access_token::{PersonalAccessTokenSecret, Scope};
+use kernel::model::role::Permission;
+use kernel::model::user::{User, UserStatus};
+use kernel::policy::{Action, Subject};
+use shared::error::{AppError, AppResult};
 
 use registry::AppRegistry;
 
@@ -16,15 +23,110 @@ use registry::AppRegistry;
 pub struct AuthorizedUser {
     pub access_token: AccessToken,
     pub user: User,
+    // 許可された操作の範囲。ログインして発行したアクセストークンにはすべてのスコープが許可される
+    pub scopes: Vec<Scope>,
+    // パーソナルアクセストークンで認証した場合のトークンの ID
+    pub personal_access_token_id: Option<PersonalAccessTokenId>,
+    // ユーザーのロールに割り当てられた権限
+    pub permissions: Vec<Permission>,
 }
 
 impl AuthorizedUser {
+    fn from_session(access_token: AccessToken, user: User) -> Self {
+        Self {
+            access_token,
+            user,
+            scopes: Scope::all(),
+            personal_access_token_id: None,
+            permissions: Vec::new(),
+        }
+    }
+
+    // ロールの権限の変更がすぐに反映されるよう、トークンには含めずリクエストごとに取得する
+    async fn with_permissions(mut self, registry: &AppRegistry) -> AppResult<Self> {
+        self.permissions = registry
+            .role_repository()
+            .find_permissions(&self.user.role)
+            .await?;
+        Ok(self)
+    }
+
     pub fn id(&self) -> UserId {
         self.user.id
     }
 
-    pub fn is_admin(&self) -> bool {
-        self.user.role == Role::Admin
+    // 認可の判定に使う。パーソナルアクセストークンの場合は、admin スコープがなければ権限を持たないものとする
+    pub fn subject(&self) -> Subject {
+        let permissions = if self.has_scope(Scope::Admin) {
+            self.permissions.clone()
+        } else {
+            Vec::new()
+        };
+        Subject {
+            user_id: self.id(),
+            permissions,
+        }
+    }
+
+    // 操作の可否をポリシーで判定する。許可されない場合は ForbiddenOperation を返す
+    pub fn authorize(&self, registry: &AppRegistry, action: Action) -> AppResult<()> {
+        registry.policy().authorize(&self.subject(), action)
+    }
+
+    // ロールの作成や割り当ての際に、自身が持たない権限を他のユーザーに与えられないようにする
+    pub fn authorize_grant(
+        &self,
+        registry: &AppRegistry,
+        permissions: &[Permission],
+    ) -> AppResult<()> {
+        permissions.iter().try_for_each(|&permission| {
+            self.authorize(registry, Action::GrantPermission(permission))
+        })
+    }
+
+    // 他のユーザーのアカウントを操作する場合は、対象のユーザーのロールが持つ権限をすべて持っていることを求める
+    // user:manage 権限だけで、管理者などより強い権限を持つユーザーを乗っ取れないようにする
+    pub async fn authorize_manage_user(
+        &self,
+        registry: &AppRegistry,
+        target_user_id: UserId,
+    ) -> AppResult<()> {
+        self.authorize(registry, Action::ManageUsers)?;
+        let target = registry
+            .user_repository()
+            .find_current_user(target_user_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::EntityNotFound(format!(
+                    "ユーザー ({}) が見つかりませんでした",
+                    target_user_id
+                ))
+            })?;
+        let permissions = registry
+            .role_repository()
+            .find_permissions(&target.role)
+            .await?;
+        self.authorize_grant(registry, &permissions)
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn require_scope(&self, scope: Scope) -> AppResult<()> {
+        if !self.has_scope(scope) {
+            return Err(AppError::ForbiddenOperation);
+        }
+        Ok(())
+    }
+
+    // パスワードの変更やトークンの発行など、アカウント自体を操作する場合は
+    // パーソナルアクセストークンではなくログインして発行したアクセストークンを求める
+    pub fn require_session(&self) -> AppResult<()> {
+        if self.personal_access_token_id.is_some() {
+            return Err(AppError::ForbiddenOperation);
+        }
+        Ok(())
     }
 }
 
@@ -44,6 +146,41 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
             .map_err(|_| AppError::UnauthenticatedError)?;
         let access_token = AccessToken(bearer.token().to_string());
 
+        // パーソナルアクセストークンは接頭辞で見分け、データベースで照合する
+        if PersonalAccessTokenSecret::is_personal_access_token(&access_token.0) {
+            let token = registry
+                .personal_access_token_repository()
+                .authenticate(&PersonalAccessTokenSecret(access_token.0.clone()))
+                .await?
+                .ok_or(AppError::UnauthenticatedError)?;
+            let user = registry
+                .user_repository()
+                .find_current_user(token.user_id)
+                .await?
+                .filter(|user| user.status == UserStatus::Active)
+                .ok_or(AppError::UnauthorizedError)?;
+            return Self {
+                access_token,
+                user,
+                scopes: token.scopes,
+                personal_access_token_id: Some(token.id),
+                permissions: Vec::new(),
+            }
+            .with_permissions(registry)
+            .await;
+        }
+
+        // JWT のようにアクセストークンの内容だけでユーザーを特定できる場合は、データベースを参照しない
+        if let Some(user) = registry
+            .auth_repository()
+            .fetch_user_from_claims(&access_token)
+            .await?
+        {
+            return Self::from_session(access_token, user)
+                .with_permissions(registry)
+                .await;
+        }
+
         // アクセストークンが紐づくユーザーIDを抽出する
         let user_id = registry
             .auth_repository()
@@ -52,12 +189,145 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
             .ok_or(AppError::UnauthenticatedError)?;
 
         // ユーザーIDでデータベースからユーザーのレコードを引く
+        // 利用停止中や無効化されたユーザーのトークンは受け付けない
         let user = registry
             .user_repository()
             .find_current_user(user_id)
             .await?
+            .filter(|user| user.status == UserStatus::Active)
             .ok_or(AppError::UnauthorizedError)?;
 
-        Ok(Self { access_token, user })
+        Self::from_session(access_token, user)
+            .with_permissions(registry)
+            .await
+    }
+}
+
+// 貸出・返却用の端末からのリクエストで、handler に渡す構造体
+// 端末の認証情報ではユーザー向けの API は利用できない
+pub struct AuthorizedKiosk {
+    pub device: KioskDevice,
+}
+
+impl AuthorizedKiosk {
+    pub fn id(&self) -> KioskId {
+        self.device.id
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppRegistry> for AuthorizedKiosk {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        // HTTP ヘッダから端末の秘密情報を取り出す。
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AppError::UnauthenticatedError)?;
+        let secret = KioskSecret(bearer.token().to_string());
+
+        let device = registry
+            .kiosk_repository()
+            .authenticate(&secret)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        Ok(Self { device })
+    }
+}
+
+// SCIM クライアント（IdP）からのリクエスト。設定した専用のトークンで認証する
+// ユーザーとしての権限は持たず、SCIM のエンドポイントのみを利用できる
+pub struct AuthorizedScimClient;
+
+#[async_trait]
+impl FromRequestParts<AppRegistry> for AuthorizedScimClient {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AppError::UnauthorizedError)?;
+
+        let config = registry.app_config();
+        let Some(scim) = &config.scim else {
+            return Err(AppError::EntityNotFound("SCIM is disabled".into()));
+        };
+        if !constant_time_eq(bearer.token(), &scim.bearer_token) {
+            return Err(AppError::UnauthorizedError);
+        }
+
+        Ok(Self)
+    }
+}
+
+// 応答時間の差からトークンを推測されないよう、一致しない位置に関わらず同じ時間で比較する
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// リクエスト元のクライアントの情報。セッションの一覧への表示と、ログインの試行回数の制限に使う
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppRegistry> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let header = |name| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+
+        // X-Forwarded-For の値はクライアントが自由に設定できるため、信頼できるプロキシが
+        // 追加した末尾の要素のみを使う。プロキシを経由しない場合は接続元のアドレスを使う
+        let trusted_hops = registry.app_config().proxy.trusted_hops;
+        let forwarded_for =
+            header("x-forwarded-for").and_then(|value| forwarded_client(value, trusted_hops));
+        let ip_address = forwarded_for.or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        });
+        let user_agent = header(USER_AGENT.as_str()).map(String::from);
+
+        Ok(Self {
+            ip_address,
+            user_agent,
+        })
+    }
+}
+
+// trusted_hops 段のプロキシを経由する場合、X-Forwarded-For の末尾から trusted_hops 番目が
+// 最も外側のプロキシから見た接続元になる。それより前の要素はクライアントが偽装できる
+fn forwarded_client(value: &str, trusted_hops: usize) -> Option<String> {
+    if trusted_hops == 0 {
+        return None;
     }
+    value
+        .rsplit(',')
+        .nth(trusted_hops - 1)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
 }
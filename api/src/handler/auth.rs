@@ -1,11 +1,35 @@
 use axum::{extract::State, http::StatusCode, Json};
-use kernel::model::auth::event::CreateToken;
+use garde::Validate;
+use kernel::model::{
+    auth::{
+        event::{CreateSession, CreateToken, RotateRefreshToken},
+        IssuedRefreshToken, RefreshToken,
+    },
+    id::UserId,
+    login_throttle::LoginAttempt,
+    password_reset::PasswordResetToken,
+    role::Role,
+    signup::EmailVerificationToken,
+    two_factor::{TwoFactorChallenge, TwoFactorChallengePurpose},
+    user::{EmailChangeToken, UserStatus},
+};
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::error::{AppError, AppResult};
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::auth::{AccessTokenResponse, LoginRequest},
+    extractor::{AuthorizedUser, ClientInfo},
+    model::{
+        auth::{
+            AccessTokenResponse, ForgotPasswordRequest, JwksResponse, LoginRequest, LoginResponse,
+            PasswordChangeRequiredResponse, PasswordResetMail, RefreshTokenRequest,
+            ResetPasswordRequest, SignupRequest, VerificationMail, VerifyEmailRequest,
+        },
+        two_factor::{
+            TwoFactorEnrollmentRequiredResponse, TwoFactorLoginRequest, TwoFactorLoginResponse,
+            TwoFactorRequiredResponse,
+        },
+        user::{ConfirmEmailChangeRequest, PasswordContext},
+    },
 };
 
 #[cfg_attr(
@@ -15,35 +39,230 @@ use crate::{
         path="/auth/login",
         request_body = LoginRequest,
         responses(
-            (status = 200, description = "ログインに成功した場合。", body = AccessTokenResponse),
+            (status = 200, description = "ログインに成功した場合。パスワードの再設定が求められている場合は、アクセストークンの代わりに再設定用のトークンを返す。2 段階認証が必要な場合は、アクセストークンの代わりにチャレンジを返す。", body = LoginResponse),
             (status = 400, description = "リクエストの内容に問題があった場合。"),
-            (status = 403, description = "ログイン認証が通らなかった場合。ユーザーIDないしはパスワードに誤りがある可能性があります。")
+            (status = 403, description = "ログイン認証が通らなかった場合。ユーザーIDないしはパスワードに誤りがある可能性があります。"),
+            (status = 404, description = "パスワードでのログインが無効になっている場合。"),
+            (status = 429, description = "ログインの失敗が続いたため、一時的に試行できない場合。Retry-After ヘッダに次に試行できるまでの秒数を返す。"),
         )
     )
 )]
 #[tracing::instrument(
-    skip(registry, req),
+    skip(client, registry, req),
     fields(
         email_address = %req.email
     )
 )]
 pub async fn login(
+    client: ClientInfo,
     State(registry): State<AppRegistry>,
     Json(req): Json<LoginRequest>,
-) -> AppResult<Json<AccessTokenResponse>> {
-    let user_id = registry
+) -> AppResult<Json<LoginResponse>> {
+    if !registry.app_config().auth.password_login_enabled {
+        return Err(AppError::EntityNotFound(
+            "password login is disabled".into(),
+        ));
+    }
+
+    // パスワードの検証は重い処理のため、失敗が続いている場合は検証する前に拒否する
+    let attempt = LoginAttempt::new(req.email.clone(), client.ip_address.clone());
+    let login_throttle_repository = registry.login_throttle_repository();
+    login_throttle_repository.check(&attempt).await?;
+
+    let user_id = match registry
         .auth_repository()
         .verify_user(&req.email, &req.password)
+        .await
+    {
+        Ok(user_id) => user_id,
+        Err(e @ AppError::UnauthenticatedError) => {
+            login_throttle_repository.record_failure(&attempt).await?;
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+    login_throttle_repository.record_success(&attempt).await?;
+
+    // 管理者がパスワードをリセットしたユーザーには、新しいパスワードを設定させる
+    let password_reset_repository = registry.password_reset_repository();
+    if password_reset_repository
+        .is_password_change_required(user_id)
+        .await?
+    {
+        let token = password_reset_repository
+            .issue_token_for_user(user_id)
+            .await?;
+        return Ok(Json(LoginResponse::PasswordChangeRequired(
+            PasswordChangeRequiredResponse {
+                user_id,
+                password_reset_token: token.0,
+            },
+        )));
+    }
+
+    // 2 段階認証が有効なユーザーは、続けて /auth/login/two-factor でコードを送る
+    let two_factor_repository = registry.two_factor_repository();
+    if two_factor_repository.is_enabled(user_id).await? {
+        let challenge = two_factor_repository
+            .issue_challenge(user_id, TwoFactorChallengePurpose::Verify)
+            .await?;
+        return Ok(Json(LoginResponse::TwoFactorRequired(
+            TwoFactorRequiredResponse {
+                two_factor_challenge: challenge.0,
+            },
+        )));
+    }
+
+    // TOTP の登録が必須の管理者は、登録を済ませるまでアクセストークンを発行しない
+    if registry.app_config().two_factor.required_for_admin && is_admin(&registry, user_id).await? {
+        let enrollment = two_factor_repository.begin_enrollment(user_id).await?;
+        let challenge = two_factor_repository
+            .issue_challenge(user_id, TwoFactorChallengePurpose::Enroll)
+            .await?;
+        return Ok(Json(LoginResponse::TwoFactorEnrollmentRequired(
+            TwoFactorEnrollmentRequiredResponse {
+                two_factor_challenge: challenge.0,
+                enrollment: enrollment.into(),
+            },
+        )));
+    }
+
+    Ok(Json(LoginResponse::Authorized(
+        start_session(&registry, user_id, client).await?,
+    )))
+}
+
+async fn is_admin(registry: &AppRegistry, user_id: UserId) -> AppResult<bool> {
+    Ok(registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .is_some_and(|user| user.role == Role::Admin))
+}
+
+/// ログインの 2 段階目として、TOTP のコードかリカバリーコードを検証する
+/// 管理者がログインの途中で TOTP を登録する場合は、このコードの確認で登録が完了する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/login/two-factor",
+        request_body = TwoFactorLoginRequest,
+        responses(
+            (status = 200, description = "ログインに成功した場合。", body = TwoFactorLoginResponse),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 401, description = "チャレンジが無効か、有効期限が切れている場合。コードの誤りが続いた場合もチャレンジは無効になる。"),
+            (status = 403, description = "コードに誤りがある場合。"),
+            (status = 429, description = "チャレンジをまたいでコードの誤りが続いたため、一時的に試行できない場合。Retry-After ヘッダに次に試行できるまでの秒数を返す。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(client, registry, req))]
+pub async fn login_two_factor(
+    client: ClientInfo,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TwoFactorLoginRequest>,
+) -> AppResult<Json<TwoFactorLoginResponse>> {
+    req.validate(&())?;
+
+    let verified = registry
+        .two_factor_repository()
+        .verify_challenge(&TwoFactorChallenge(req.two_factor_challenge), &req.code)
+        .await?;
+
+    Ok(Json(TwoFactorLoginResponse {
+        token: start_session(&registry, verified.user_id, client).await?,
+        recovery_codes: verified
+            .recovery_codes
+            .into_iter()
+            .map(|code| code.0)
+            .collect(),
+    }))
+}
+
+// パスワード、2 段階認証、シングルサインオンのいずれのログインも、ここでセッションを開始する
+// 利用停止中や無効化されたユーザーは、認証に成功してもログインさせない
+pub(super) async fn start_session(
+    registry: &AppRegistry,
+    user_id: UserId,
+    client: ClientInfo,
+) -> AppResult<AccessTokenResponse> {
+    let is_active = registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .is_some_and(|user| user.status == UserStatus::Active);
+    if !is_active {
+        return Err(AppError::UnauthenticatedError);
+    }
+
+    let issued = registry
+        .auth_repository()
+        .create_session(CreateSession {
+            user_id,
+            ip_address: client.ip_address,
+            user_agent: client.user_agent,
+        })
+        .await?;
+
+    issue_access_token(registry, issued).await
+}
+
+/// リフレッシュトークンを使ってアクセストークンを再発行する
+/// リフレッシュトークンも新しいものに置き換わるため、以降は新しいトークンを使う
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/refresh",
+        request_body = RefreshTokenRequest,
+        responses(
+            (status = 200, description = "アクセストークンの再発行に成功した場合。", body = AccessTokenResponse),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 401, description = "リフレッシュトークンが無効か、有効期限が切れている場合。置き換え済みのトークンが使われた場合は、そのセッションが終了する。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(client, registry, req))]
+pub async fn refresh(
+    client: ClientInfo,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<RefreshTokenRequest>,
+) -> AppResult<Json<AccessTokenResponse>> {
+    req.validate(&())?;
+
+    let issued = registry
+        .auth_repository()
+        .rotate_refresh_token(RotateRefreshToken {
+            refresh_token: RefreshToken(req.refresh_token),
+            ip_address: client.ip_address,
+            user_agent: client.user_agent,
+        })
         .await?;
+
+    issue_access_token(&registry, issued).await.map(Json)
+}
+
+async fn issue_access_token(
+    registry: &AppRegistry,
+    issued: IssuedRefreshToken,
+) -> AppResult<AccessTokenResponse> {
+    let IssuedRefreshToken {
+        user_id,
+        session_id,
+        refresh_token,
+    } = issued;
     let access_token = registry
         .auth_repository()
-        .create_token(CreateToken::new(user_id))
+        .create_token(CreateToken::new(user_id, session_id))
         .await?;
 
-    Ok(Json(AccessTokenResponse {
+    Ok(AccessTokenResponse {
         user_id,
         access_token: access_token.0,
-    }))
+        expires_in: registry.app_config().auth.ttl,
+        refresh_token: refresh_token.0,
+    })
 }
 
 #[cfg_attr(
@@ -52,7 +271,7 @@ pub async fn login(
         post,
         path="/auth/logout",
         responses(
-            (status = 204, description = "ログアウトに成功した場合。"),
+            (status = 204, description = "ログアウトに成功した場合。現在のセッションが終了し、リフレッシュトークンも使えなくなる。"),
         )
     )
 )]
@@ -67,9 +286,251 @@ pub async fn logout(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
+    user.require_session()?;
+
     registry
         .auth_repository()
         .delete_token(user.access_token)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// 管理者を介さずにユーザー登録を申し込む
+/// 確認用のメールを送信し、メールアドレスの確認が済むまではログインできない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/signup",
+        request_body = SignupRequest,
+        responses(
+            (status = 202, description = "申し込みを受け付けた場合。登録済みのメールアドレスにはメールを送信しない。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "セルフサインアップかパスワードでのログインが無効になっている場合。"),
+            (status = 422, description = "登録が許可されていないドメインのメールアドレスの場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(registry, req),
+    fields(
+        email_address = %req.email
+    )
+)]
+pub async fn signup(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<SignupRequest>,
+) -> AppResult<StatusCode> {
+    let config = registry.app_config();
+    if !config.signup.enabled || !config.auth.password_login_enabled {
+        return Err(AppError::EntityNotFound("signup is disabled".into()));
+    }
+
+    req.validate(&req.password_context(registry.password_policy()))?;
+
+    if !config.signup.is_allowed_email(&req.email) {
+        return Err(AppError::UnprocessableEntity(
+            "このメールアドレスでは登録できません".into(),
+        ));
+    }
+
+    // 登録済みのメールアドレスかどうかを知られないよう、メールを送らない場合も同じ応答を返す
+    let Some(pending_user) = registry.signup_repository().register(req.into()).await? else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let mail = VerificationMail {
+        pending_user: &pending_user,
+        frontend_base_url: &config.frontend.base_url,
+        ttl: config.signup.verification_ttl,
+    };
+    registry.mailer().send(mail.into()).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// メールで受け取ったトークンを使って、メールアドレスの確認を完了する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/signup/verify",
+        request_body = VerifyEmailRequest,
+        responses(
+            (status = 200, description = "メールアドレスの確認に成功した場合。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "トークンが無効か、有効期限が切れている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry, req))]
+pub async fn verify_email(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let user_id = registry
+        .signup_repository()
+        .verify_email(&EmailVerificationToken(req.token))
+        .await?;
+    tracing::info!(user_id = %user_id, "Email address has been verified");
+
+    Ok(StatusCode::OK)
+}
+
+/// 新しいメールアドレスに届いたトークンを使って、メールアドレスの変更を完了する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/email-change/confirm",
+        request_body = ConfirmEmailChangeRequest,
+        responses(
+            (status = 200, description = "メールアドレスの変更に成功した場合。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "トークンが無効か、有効期限が切れている場合。"),
+            (status = 409, description = "確認を待つ間に、メールアドレスが他のユーザーに使われた場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry, req))]
+pub async fn confirm_email_change(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let user_id = registry
+        .user_repository()
+        .confirm_email_change(&EmailChangeToken(req.token))
+        .await?;
+    tracing::info!(user_id = %user_id, "Email address has been changed");
+
+    Ok(StatusCode::OK)
+}
+
+/// パスワードの再設定を申し込む
+/// メールアドレスが登録されているかどうかに関わらず、同じ応答を返す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/password/forgot",
+        request_body = ForgotPasswordRequest,
+        responses(
+            (status = 202, description = "申し込みを受け付けた場合。登録済みのメールアドレスであれば再設定用のメールを送信する。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "パスワードでのログインが無効になっている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry, req))]
+pub async fn forgot_password(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> AppResult<StatusCode> {
+    if !registry.app_config().auth.password_login_enabled {
+        return Err(AppError::EntityNotFound(
+            "password login is disabled".into(),
+        ));
+    }
+
+    req.validate(&())?;
+
+    // 応答時間の差からメールアドレスの登録有無を推測されないよう、
+    // トークンの発行とメールの送信は応答を返した後に行う
+    tokio::spawn(async move {
+        if let Err(e) = send_password_reset_mail(registry, req.email).await {
+            tracing::error!(error.message = %e, "Failed to send password reset mail");
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn send_password_reset_mail(registry: AppRegistry, email: String) -> AppResult<()> {
+    let Some(request) = registry
+        .password_reset_repository()
+        .issue_token(&email)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let config = registry.app_config();
+    let mail = PasswordResetMail {
+        request: &request,
+        frontend_base_url: &config.frontend.base_url,
+        ttl: config.password_reset.ttl,
+    };
+    registry.mailer().send(mail.into()).await
+}
+
+/// 再設定用のトークンを使って、新しいパスワードを設定する
+/// 再設定に成功すると、そのユーザーのすべてのセッションが無効になる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/password/reset",
+        request_body = ResetPasswordRequest,
+        responses(
+            (status = 200, description = "パスワードの再設定に成功した場合。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "トークンが無効か、有効期限が切れている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry, req))]
+pub async fn reset_password(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> AppResult<StatusCode> {
+    // トークンを確認するまではメールアドレスがわからないため、メールアドレスとの照合を除いて検証する
+    let policy = registry.password_policy();
+    req.validate(&PasswordContext::new(policy.clone(), String::new()))?;
+    let email = registry
+        .password_reset_repository()
+        .find_email(&PasswordResetToken(req.token.clone()))
+        .await?
+        .ok_or_else(|| {
+            AppError::EntityNotFound("再設定用のトークンが無効か、有効期限が切れています".into())
+        })?;
+    req.validate(&PasswordContext::new(policy, email))?;
+
+    let user_id = registry
+        .password_reset_repository()
+        .reset_password(req.into())
+        .await?;
+    registry
+        .auth_repository()
+        .delete_all_tokens(user_id)
+        .await?;
+    tracing::info!(user_id = %user_id, "Password has been reset");
+
+    Ok(StatusCode::OK)
+}
+
+/// アクセストークンの署名の検証に使う公開鍵を JWKS 形式で返す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        get,
+        path="/.well-known/jwks.json",
+        responses(
+            (status = 200, description = "公開鍵の一覧を取得できた場合。", body = JwksResponse),
+            (status = 404, description = "アクセストークンとして JWT を発行していない場合。"),
+        )
+    )
+)]
+pub async fn jwks(State(registry): State<AppRegistry>) -> AppResult<Json<JwksResponse>> {
+    let keys = registry.auth_repository().verifying_keys();
+    if keys.is_empty() {
+        return Err(AppError::EntityNotFound("JWKS is not available".into()));
+    }
+
+    Ok(Json(JwksResponse {
+        keys: keys.into_iter().map(Into::into).collect(),
+    }))
+}
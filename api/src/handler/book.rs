@@ -4,7 +4,14 @@ use axum::{
     Json,
 };
 use garde::Validate;
-use kernel::model::{book::event::DeleteBook, id::BookId};
+use kernel::{
+    model::{
+        book::event::DeleteBook,
+        id::{BookId, UserId},
+        personal_access_token::Scope,
+    },
+    policy::Action,
+};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
 
@@ -12,7 +19,7 @@ use crate::{
     extractor::AuthorizedUser,
     model::book::{
         BookListQuery, BookResponse, CreateBookRequest, PaginatedBookResponse, UpdateBookRequest,
-        UpdateBookRequestWithIds,
+        UpdateBookRequestWithId,
     },
 };
 
@@ -39,6 +46,8 @@ pub async fn register_book(
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateBookRequest>,
 ) -> AppResult<StatusCode> {
+    user.require_scope(Scope::BooksWrite)?;
+
     req.validate(&())?;
 
     registry
@@ -65,16 +74,18 @@ pub async fn register_book(
     )
 )]
 #[tracing::instrument(
-    skip(_user, registry),
+    skip(user, registry),
     fields(
-        user_id = %_user.user.id.to_string()
+        user_id = %user.user.id.to_string()
     )
 )]
 pub async fn show_book_list(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<PaginatedBookResponse>> {
+    user.require_scope(Scope::BooksRead)?;
+
     registry
         .book_repository()
         .find_all(query.into())
@@ -101,16 +112,18 @@ pub async fn show_book_list(
     )
 )]
 #[tracing::instrument(
-    skip(_user, registry),
+    skip(user, registry),
     fields(
-        user_id = %_user.user.id.to_string()
+        user_id = %user.user.id.to_string()
     )
 )]
 pub async fn show_book(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<BookResponse>> {
+    user.require_scope(Scope::BooksRead)?;
+
     registry
         .book_repository()
         .find_by_id(book_id)
@@ -128,6 +141,7 @@ pub async fn show_book(
         responses(
             (status = 200, description = "蔵書の更新に成功した場合。"),
             (status = 400, description = "リクエストのパラメータに不備があった場合。"),
+            (status = 403, description = "他のユーザーが登録した蔵書を、権限なしに変更しようとした場合。"),
             (status = 404, description = "変更対象の書籍が見つからなかった場合。")
         ),
         params(
@@ -147,9 +161,14 @@ pub async fn update_book(
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateBookRequest>,
 ) -> AppResult<StatusCode> {
+    user.require_scope(Scope::BooksWrite)?;
+
     req.validate(&())?;
 
-    let update_book = UpdateBookRequestWithIds::new(book_id, user.id(), req);
+    let owner = find_book_owner(&registry, book_id).await?;
+    user.authorize(&registry, Action::UpdateBook { owner })?;
+
+    let update_book = UpdateBookRequestWithId::new(book_id, req);
 
     registry
         .book_repository()
@@ -164,6 +183,7 @@ pub async fn update_book(
         responses(
             (status = 204, description = "書籍の削除に成功した場合。"),
             (status = 400, description = "リクエストのパラメータが不正だった場合。"),
+            (status = 403, description = "他のユーザーが登録した蔵書を、権限なしに削除しようとした場合。"),
             (status = 404, description = "削除対象の書籍が存在しなかった場合。"),
         ),
         params(
@@ -182,13 +202,25 @@ pub async fn delete_book(
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    let delete_book = DeleteBook {
-        book_id,
-        requested_user: user.id(),
-    };
+    user.require_scope(Scope::BooksWrite)?;
+
+    let owner = find_book_owner(&registry, book_id).await?;
+    user.authorize(&registry, Action::DeleteBook { owner })?;
+
+    let delete_book = DeleteBook { book_id };
     registry
         .book_repository()
         .delete(delete_book)
         .await
         .map(|_| StatusCode::OK)
 }
+
+// 権限の判定より先に蔵書の存在を確認し、存在しない蔵書には 403 ではなく 404 を返す
+async fn find_book_owner(registry: &AppRegistry, book_id: BookId) -> AppResult<UserId> {
+    registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .map(|book| book.owner.id)
+        .ok_or_else(|| AppError::EntityNotFound("specific book not found".into()))
+}
@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use kernel::model::calendar::CalendarFeedToken;
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::calendar::{CalendarFeedResponse, LoanCalendar},
+};
+
+/// ユーザーが自身の返却期限のカレンダーフィード用トークンを発行する
+/// すでに発行済みの場合は古いトークンを無効にして再発行する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/users/me/calendar-feed",
+        responses(
+            (status = 201, description = "トークンの発行に成功した場合。", body = CalendarFeedResponse),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn issue_calendar_feed_token(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<(StatusCode, Json<CalendarFeedResponse>)> {
+    user.require_session()?;
+
+    registry
+        .calendar_feed_repository()
+        .issue_token(user.id())
+        .await
+        .map(|token| (StatusCode::CREATED, Json(token.into())))
+}
+
+/// ユーザーが自身のカレンダーフィード用トークンを無効にする
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/me/calendar-feed",
+        responses(
+            (status = 204, description = "トークンの無効化に成功した場合。"),
+            (status = 404, description = "トークンが発行されていなかった場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn revoke_calendar_feed_token(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    registry
+        .calendar_feed_repository()
+        .revoke_token(user.id())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// 貸出中の書籍の返却期限を iCalendar 形式で取得する
+/// カレンダーアプリから参照されるため、アクセストークンではなく URL 内のトークンで認証する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/calendar/{token}/loans.ics",
+        responses(
+            (status = 200, description = "iCalendar 形式のフィードを取得できた場合。", content_type = "text/calendar"),
+            (status = 404, description = "トークンが無効な場合。"),
+        ),
+        params(
+            ("token" = String, Path, description = "カレンダーフィード用トークン")
+        )
+    )
+)]
+#[tracing::instrument(skip(token, registry))]
+pub async fn show_loan_calendar(
+    Path(token): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<impl IntoResponse> {
+    let user_id = registry
+        .calendar_feed_repository()
+        .find_user_id_by_token(&CalendarFeedToken(token))
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound("calendar feed not found".into()))?;
+
+    let checkouts = registry
+        .checkout_repository()
+        .find_unreturned_by_user_id(user_id)
+        .await?;
+
+    let config = registry.app_config();
+    let calendar = LoanCalendar {
+        checkouts,
+        loan_period_days: config.checkout.loan_period_days,
+        frontend_base_url: &config.frontend.base_url,
+        generated_at: chrono::Utc::now(),
+    };
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar.render(),
+    ))
+}
@@ -1,16 +1,27 @@
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use crate::{
+    extractor::AuthorizedUser,
+    model::checkout::{
+        BatchCheckoutRequest, BatchCheckoutRequestWithUser, BatchCheckoutResponse,
+        BatchReturnRequest, BatchReturnRequestWithUser, BatchReturnResponse, CheckoutsResponse,
+    },
+};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
     Json,
 };
-use kernel::model::{
-    checkout::event::{CreateCheckout, UpdateReturned},
-    id::{BookId, CheckoutId},
+use garde::Validate;
+use kernel::{
+    model::{
+        checkout::event::{CreateCheckout, UpdateReturned, UpdateReturnedBatch},
+        id::{BookId, CheckoutId},
+        personal_access_token::Scope,
+    },
+    policy::Action,
 };
 
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::error::{AppError, AppResult};
 
 #[cfg_attr(
     debug_assertions,
@@ -19,6 +30,7 @@ use shared::error::AppResult;
             (status = 201, description = "貸出の登録に成功した場合。"),
             (status = 400, description = "リクエストのパラメータが不正な場合。"),
             (status = 422, description = "リクエストされた処理が実行できない場合。"),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
             (status = 500, description = "貸出の登録に失敗した場合。")
         ),
         params(
@@ -37,6 +49,8 @@ pub async fn checkout_book(
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
+    user.require_scope(Scope::CheckoutsWrite)?;
+
     let create_checkout_history = CreateCheckout::new(book_id, user.id(), chrono::Utc::now());
 
     registry
@@ -52,7 +66,10 @@ pub async fn checkout_book(
         responses(
             (status = 200, description = "返却に成功した場合。"),
             (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "他のユーザーが借りている蔵書を、権限なしに返却しようとした場合。"),
+            (status = 404, description = "指定した蔵書が存在しない場合。"),
             (status = 422, description = "リクエストされた処理が実行できない場合。"),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
             (status = 500, description = "返却の登録に失敗した場合。")
         ),
         params(
@@ -72,7 +89,26 @@ pub async fn return_book(
     Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    let update_returned = UpdateReturned::new(checkout_id, book_id, user.id(), chrono::Utc::now());
+    user.require_scope(Scope::CheckoutsWrite)?;
+
+    // 権限の判定より先に蔵書の存在を確認し、存在しない蔵書には 404 を返す
+    let book = registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound("specific book not found".into()))?;
+    // 貸出中でない場合や貸出 ID が異なる場合は、返却処理で 422 を返す
+    let borrower = book
+        .checkout
+        .filter(|checkout| checkout.checkout_id == checkout_id)
+        .map(|checkout| checkout.checked_out_by.id);
+    if borrower.is_some() {
+        user.authorize(&registry, Action::ReturnCheckout { borrower })?;
+    }
+
+    // 借りたユーザーの確認は済んでいる
+    let update_returned =
+        UpdateReturned::new(checkout_id, book_id, user.id(), chrono::Utc::now(), true);
 
     registry
         .checkout_repository()
@@ -81,6 +117,99 @@ pub async fn return_book(
         .map(|_| StatusCode::OK)
 }
 
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/books/checkouts/batch",
+        request_body = BatchCheckoutRequest,
+        responses(
+            (status = 200, description = "貸出の一括登録を確定した場合。", body = BatchCheckoutResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 422, description = "一部の貸出に失敗し、すべての貸出を取り消した場合。", body = BatchCheckoutResponse),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
+            (status = 500, description = "貸出の一括登録に失敗した場合。")
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn checkout_books(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<BatchCheckoutRequest>,
+) -> AppResult<(StatusCode, Json<BatchCheckoutResponse>)> {
+    user.require_scope(Scope::CheckoutsWrite)?;
+
+    req.validate(&())?;
+
+    let create_checkouts = BatchCheckoutRequestWithUser::new(req, user.id(), chrono::Utc::now());
+
+    registry
+        .checkout_repository()
+        .create_batch(create_checkouts.into())
+        .await
+        .map(|result| (batch_status_code(result.committed), Json(result.into())))
+}
+
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/books/checkouts/batch/returned",
+        request_body = BatchReturnRequest,
+        responses(
+            (status = 200, description = "返却の一括登録を確定した場合。", body = BatchReturnResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 422, description = "一部の返却に失敗し、すべての返却を取り消した場合。", body = BatchReturnResponse),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
+            (status = 500, description = "返却の一括登録に失敗した場合。")
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn return_books(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<BatchReturnRequest>,
+) -> AppResult<(StatusCode, Json<BatchReturnResponse>)> {
+    user.require_scope(Scope::CheckoutsWrite)?;
+
+    req.validate(&())?;
+
+    let update_returned = UpdateReturnedBatch {
+        // 一括返却では貸出ごとに判定せず、他のユーザーの貸出も返却できるかどうかで判定する
+        return_any: user
+            .authorize(&registry, Action::ReturnCheckout { borrower: None })
+            .is_ok(),
+        ..UpdateReturnedBatch::from(BatchReturnRequestWithUser::new(
+            req,
+            user.id(),
+            chrono::Utc::now(),
+        ))
+    };
+
+    registry
+        .checkout_repository()
+        .update_returned_batch(update_returned)
+        .await
+        .map(|result| (batch_status_code(result.committed), Json(result.into())))
+}
+
+// すべて取り消された場合も、項目ごとの結果を返すためにレスポンスボディを付ける
+fn batch_status_code(committed: bool) -> StatusCode {
+    if committed {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+}
+
 #[cfg_attr(
     debug_assertions,
     utoipa::path(get, path="/api/v1/books/checkouts",
@@ -90,15 +219,17 @@ pub async fn return_book(
     )
 )]
 #[tracing::instrument(
-    skip(_user, registry),
+    skip(user, registry),
     fields(
-        user_id = %_user.user.id.to_string()
+        user_id = %user.user.id.to_string()
     )
 )]
 pub async fn show_checked_out_list(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
+    user.require_scope(Scope::CheckoutsRead)?;
+
     registry
         .checkout_repository()
         .find_unreturned_all()
@@ -119,16 +250,18 @@ pub async fn show_checked_out_list(
     )
 )]
 #[tracing::instrument(
-    skip(_user, registry),
+    skip(user, registry),
     fields(
-        user_id = %_user.user.id.to_string()
+        user_id = %user.user.id.to_string()
     )
 )]
 pub async fn checkout_history(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
+    user.require_scope(Scope::CheckoutsRead)?;
+
     registry
         .checkout_repository()
         .find_history_by_book_id(book_id)
@@ -0,0 +1,220 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::{
+    model::{
+        id::UserId,
+        invitation::{event::CreateInvitation, InvitationToken, IssuedInvitation},
+    },
+    policy::Action,
+};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::{
+        invitation::{
+            AcceptInvitationRequest, CreateInvitationRequest, CreateInvitationRequestWithInviter,
+            InvitationMail, InvitationResponse, InvitationsResponse,
+        },
+        user::PasswordContext,
+    },
+};
+
+/// メールアドレスとロールを指定してユーザーを招待する（user:manage 権限が必要）
+/// 招待されたユーザーは、メールで届いたリンクから名前とパスワードを設定する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/invitations",
+        request_body = CreateInvitationRequest,
+        responses(
+            (status = 201, description = "招待のメールを送信した場合。", body = InvitationResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合、または自身が持たない権限のロールを指定した場合。"),
+            (status = 404, description = "指定したロールが存在しない場合。"),
+            (status = 409, description = "メールアドレスがすでに登録されている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn create_invitation(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateInvitationRequest>,
+) -> AppResult<(StatusCode, Json<InvitationResponse>)> {
+    user.authorize(&registry, Action::ManageUsers)?;
+
+    req.validate(&())?;
+
+    let event: CreateInvitation = CreateInvitationRequestWithInviter::new(user.id(), req).into();
+
+    let permissions = registry
+        .role_repository()
+        .find_permissions(&event.role)
+        .await?;
+    user.authorize_grant(&registry, &permissions)?;
+
+    let issued = registry.invitation_repository().create(event).await?;
+    send_invitation_mail(&registry, &issued).await?;
+
+    Ok((StatusCode::CREATED, Json(issued.invitation.into())))
+}
+
+/// 受け入れられていない招待の一覧を取得する（user:manage 権限が必要）
+/// 有効期限が切れた招待も含む
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/invitations",
+        responses(
+            (status = 200, description = "招待の一覧の取得に成功した場合。", body = InvitationsResponse),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn list_invitations(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<InvitationsResponse>> {
+    user.authorize(&registry, Action::ManageUsers)?;
+
+    registry
+        .invitation_repository()
+        .find_pending()
+        .await
+        .map(InvitationsResponse::from)
+        .map(Json)
+}
+
+/// 招待のメールを送り直す（user:manage 権限が必要）
+/// 有効期限は送り直した時点から延長され、以前に送ったリンクは使えなくなる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/invitations/{user_id}/resend",
+        responses(
+            (status = 200, description = "招待のメールを送り直した場合。", body = InvitationResponse),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合。"),
+            (status = 404, description = "指定したユーザーへの招待が存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "招待したユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn resend_invitation(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<InvitationResponse>> {
+    user.authorize(&registry, Action::ManageUsers)?;
+
+    let issued = registry.invitation_repository().resend(user_id).await?;
+    send_invitation_mail(&registry, &issued).await?;
+
+    Ok(Json(issued.invitation.into()))
+}
+
+/// 招待を取り消す（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/invitations/{user_id}",
+        responses(
+            (status = 204, description = "招待を取り消した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合。"),
+            (status = 404, description = "指定したユーザーへの招待が存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "招待したユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn revoke_invitation(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize(&registry, Action::ManageUsers)?;
+
+    registry
+        .invitation_repository()
+        .revoke(user_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// 招待のメールで受け取ったトークンを使って、名前とパスワードを設定する
+/// 設定が済むとユーザーが有効になり、ログインできるようになる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/invitations/accept",
+        request_body = AcceptInvitationRequest,
+        responses(
+            (status = 200, description = "招待の受け入れに成功した場合。"),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 404, description = "トークンが無効か、有効期限が切れている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry, req))]
+pub async fn accept_invitation(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<AcceptInvitationRequest>,
+) -> AppResult<StatusCode> {
+    // トークンを確認するまではメールアドレスがわからないため、メールアドレスとの照合を除いて検証する
+    let policy = registry.password_policy();
+    req.validate(&PasswordContext::new(policy.clone(), String::new()))?;
+    let email = registry
+        .invitation_repository()
+        .find_email(&InvitationToken(req.token.clone()))
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound("招待が無効か、有効期限が切れています".into()))?;
+    req.validate(&PasswordContext::new(policy, email))?;
+
+    let user_id = registry.invitation_repository().accept(req.into()).await?;
+    tracing::info!(user_id = %user_id, "Invitation has been accepted");
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn send_invitation_mail(
+    registry: &AppRegistry,
+    issued: &IssuedInvitation,
+) -> AppResult<()> {
+    let config = registry.app_config();
+    let mail = InvitationMail {
+        issued,
+        frontend_base_url: &config.frontend.base_url,
+        ttl: config.invitation.ttl,
+    };
+    registry.mailer().send(mail.into()).await
+}
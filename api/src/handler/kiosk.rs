@@ -0,0 +1,237 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::{
+    model::id::{BookId, KioskId, UserId},
+    policy::Action,
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedKiosk, AuthorizedUser},
+    model::kiosk::{
+        CreateKioskDeviceRequest, KioskDevicesResponse, KioskScanRequest,
+        KioskScanRequestWithKioskId, KioskScanResponse, RegisteredKioskDeviceResponse,
+        UpdateBookBarcodeRequest, UpdateBookBarcodeRequestWithBookId, UpdateUserBadgeCodeRequest,
+        UpdateUserBadgeCodeRequestWithUserId,
+    },
+};
+
+/// 貸出・返却用の端末を登録する（kiosk:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/kiosk/devices",
+        request_body = CreateKioskDeviceRequest,
+        responses(
+            (status = 201, description = "端末の登録に成功した場合。", body = RegisteredKioskDeviceResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn register_kiosk_device(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateKioskDeviceRequest>,
+) -> AppResult<(StatusCode, Json<RegisteredKioskDeviceResponse>)> {
+    user.authorize(&registry, Action::ManageKiosks)?;
+
+    req.validate(&())?;
+
+    registry
+        .kiosk_repository()
+        .register_device(req.into())
+        .await
+        .map(|device| (StatusCode::CREATED, Json(device.into())))
+}
+
+/// 登録済みの端末の一覧を取得する（kiosk:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/kiosk/devices",
+        responses(
+            (status = 200, description = "端末の一覧を取得できた場合。", body = KioskDevicesResponse),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn list_kiosk_devices(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<KioskDevicesResponse>> {
+    user.authorize(&registry, Action::ManageKiosks)?;
+
+    registry
+        .kiosk_repository()
+        .find_all_devices()
+        .await
+        .map(KioskDevicesResponse::from)
+        .map(Json)
+}
+
+/// 端末の登録を解除する（kiosk:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/kiosk/devices/{kiosk_id}",
+        responses(
+            (status = 204, description = "端末の登録解除に成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+            (status = 404, description = "指定の端末が存在しない場合。"),
+        ),
+        params(
+            ("kiosk_id" = Uuid, Path, description = "端末ID")
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn delete_kiosk_device(
+    user: AuthorizedUser,
+    Path(kiosk_id): Path<KioskId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize(&registry, Action::ManageKiosks)?;
+
+    registry
+        .kiosk_repository()
+        .delete_device(kiosk_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// 端末で読み取った蔵書と利用者カードをもとに、貸出または返却をおこなう
+/// 蔵書が読み取った利用者に貸出中であれば返却し、そうでなければ貸し出す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/kiosk/scan",
+        request_body = KioskScanRequest,
+        responses(
+            (status = 200, description = "貸出または返却に成功した場合。", body = KioskScanResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "端末の認証情報が誤っている場合。"),
+            (status = 404, description = "蔵書または利用者カードが見つからない場合。"),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
+            (status = 422, description = "蔵書がすべて貸出中の場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(kiosk, registry, req),
+    fields(
+        kiosk_id = %kiosk.id().to_string()
+    )
+)]
+pub async fn scan(
+    kiosk: AuthorizedKiosk,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<KioskScanRequest>,
+) -> AppResult<Json<KioskScanResponse>> {
+    req.validate(&())?;
+
+    let scan = KioskScanRequestWithKioskId::new(kiosk.id(), req, chrono::Utc::now());
+
+    registry
+        .kiosk_repository()
+        .scan(scan.into())
+        .await
+        .map(KioskScanResponse::from)
+        .map(Json)
+}
+
+/// 蔵書にバーコードを割り当てる（kiosk:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/books/{book_id}/barcode",
+        request_body = UpdateBookBarcodeRequest,
+        responses(
+            (status = 200, description = "バーコードの割り当てに成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+            (status = 404, description = "指定の蔵書が存在しない場合。"),
+            (status = 422, description = "バーコードが他の蔵書に割り当て済みの場合。"),
+        ),
+        params(
+            ("book_id" = Uuid, Path, description = "蔵書ID")
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn update_book_barcode(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateBookBarcodeRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize(&registry, Action::ManageKiosks)?;
+
+    req.validate(&())?;
+
+    registry
+        .kiosk_repository()
+        .update_book_barcode(UpdateBookBarcodeRequestWithBookId::new(book_id, req).into())
+        .await
+        .map(|_| StatusCode::OK)
+}
+
+/// ユーザーに利用者カードを割り当てる（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/{user_id}/badge-code",
+        request_body = UpdateUserBadgeCodeRequest,
+        responses(
+            (status = 200, description = "利用者カードの割り当てに成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定のユーザーが存在しない場合。"),
+            (status = 422, description = "利用者カードが他のユーザーに割り当て済みの場合。"),
+        ),
+        params(
+            ("user_id" = Uuid, Path, description = "ユーザーID")
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn update_user_badge_code(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserBadgeCodeRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    req.validate(&())?;
+
+    registry
+        .kiosk_repository()
+        .update_user_badge_code(UpdateUserBadgeCodeRequestWithUserId::new(user_id, req).into())
+        .await
+        .map(|_| StatusCode::OK)
+}
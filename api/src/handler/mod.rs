@@ -1,5 +1,17 @@
 pub mod auth;
 pub mod book;
+pub mod calendar;
 pub mod checkout;
 pub mod health;
+pub mod invitation;
+pub mod kiosk;
+pub mod oidc;
+pub mod personal_access_token;
+pub mod role;
+pub mod scim;
+pub mod session;
+pub mod setup;
+pub mod statistics;
+pub mod two_factor;
 pub mod user;
+pub mod user_import;
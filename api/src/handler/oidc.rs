@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::header::SET_COOKIE,
+    response::{AppendHeaders, IntoResponse},
+    Json,
+};
+use axum_extra::{headers::Cookie, TypedHeader};
+use garde::Validate;
+use kernel::{model::user::event::UpdateUserRole, repository::oidc::OidcRepository};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use super::auth::start_session;
+use crate::{
+    extractor::ClientInfo,
+    model::{
+        auth::AccessTokenResponse,
+        oidc::{
+            browser_binding_cookie, OidcAuthorizationResponse, OidcCallbackRequest,
+            OidcCallbackRequestWithBrowserBinding, OIDC_BROWSER_BINDING_COOKIE,
+        },
+    },
+};
+
+/// シングルサインオンを開始する
+/// 返却した URL にブラウザを遷移させ、IdP での認証後にリダイレクト URI で code と state を受け取る
+/// ログインを開始したブラウザを確認するための Cookie を設定するため、Cookie を保存できるように呼び出す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        get,
+        path="/auth/oidc/authorize",
+        responses(
+            (status = 200, description = "認可リクエストの URL を生成できた場合。ログインを開始したブラウザを確認するための Cookie を設定する。", body = OidcAuthorizationResponse),
+            (status = 404, description = "シングルサインオンが無効になっている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry))]
+pub async fn oidc_authorize(State(registry): State<AppRegistry>) -> AppResult<impl IntoResponse> {
+    let request = oidc_repository(&registry)?.begin_login().await?;
+    let cookie = browser_binding_cookie(&request.browser_binding, request.expires_in);
+    Ok((
+        AppendHeaders([(SET_COOKIE, cookie)]),
+        Json(OidcAuthorizationResponse::from(request)),
+    ))
+}
+
+/// IdP から受け取った認可コードでログインする
+/// IdP のグループに対応する権限が設定されている場合は、ログインのたびにユーザーの権限を合わせる
+/// 多要素認証は IdP に任せるため、TOTP による 2 段階認証は求めない
+/// ログインを開始したときに設定した Cookie を送る必要がある
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/oidc/callback",
+        request_body = OidcCallbackRequest,
+        responses(
+            (status = 200, description = "ログインに成功した場合。", body = AccessTokenResponse),
+            (status = 400, description = "リクエストの内容に問題があった場合。"),
+            (status = 401, description = "state が無効か、有効期限が切れている場合。ログインを開始したブラウザの Cookie が送られなかった場合。"),
+            (status = 403, description = "IdP での認証結果を検証できなかった場合、または対応するユーザーが存在しない場合。"),
+            (status = 404, description = "シングルサインオンが無効になっている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(client, cookie, registry, req))]
+pub async fn oidc_callback(
+    client: ClientInfo,
+    cookie: Option<TypedHeader<Cookie>>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<OidcCallbackRequest>,
+) -> AppResult<impl IntoResponse> {
+    req.validate(&())?;
+    let browser_binding = cookie
+        .as_ref()
+        .and_then(|TypedHeader(cookie)| cookie.get(OIDC_BROWSER_BINDING_COOKIE))
+        .ok_or(AppError::UnauthorizedError)?
+        .to_string();
+
+    let oidc_repository = oidc_repository(&registry)?;
+    let identity = oidc_repository
+        .complete_login(OidcCallbackRequestWithBrowserBinding::new(browser_binding, req).into())
+        .await?;
+    let user = oidc_repository.find_or_provision_user(&identity).await?;
+
+    // 権限を変更すると、そのユーザーの既存のセッションはすべて無効になる
+    if let Some(role) = oidc_repository.role_for_groups(&identity.groups) {
+        if role != user.role {
+            registry
+                .user_repository()
+                .update_role(UpdateUserRole {
+                    user_id: user.id,
+                    role,
+                })
+                .await?;
+        }
+    }
+
+    // state とあわせて Cookie も使い終わったため削除させる
+    Ok((
+        AppendHeaders([(SET_COOKIE, browser_binding_cookie("", 0))]),
+        Json::<AccessTokenResponse>(start_session(&registry, user.id, client).await?),
+    ))
+}
+
+fn oidc_repository(registry: &AppRegistry) -> AppResult<Arc<dyn OidcRepository>> {
+    registry
+        .oidc_repository()
+        .ok_or_else(|| AppError::EntityNotFound("single sign-on is disabled".into()))
+}
@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use garde::Validate;
+use kernel::model::{
+    id::PersonalAccessTokenId,
+    personal_access_token::{event::DeletePersonalAccessToken, Scope},
+};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::personal_access_token::{
+        CreatePersonalAccessTokenRequest, CreatePersonalAccessTokenRequestWithUserId,
+        CreatedPersonalAccessTokenResponse, PersonalAccessTokensResponse,
+    },
+};
+
+/// ユーザーが自身のパーソナルアクセストークンを作成する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/users/me/tokens",
+        request_body = CreatePersonalAccessTokenRequest,
+        responses(
+            (status = 201, description = "トークンの作成に成功した場合。", body = CreatedPersonalAccessTokenResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "許可されていないスコープを指定した場合、またはパーソナルアクセストークンで実行した場合。"),
+            (status = 422, description = "有効期限に過去の日時を指定した場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn create_my_token(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreatePersonalAccessTokenRequest>,
+) -> AppResult<(StatusCode, Json<CreatedPersonalAccessTokenResponse>)> {
+    user.require_session()?;
+    req.validate(&())?;
+
+    // admin スコープは管理者向けの操作の権限を持つユーザーのみが付与できる
+    if req
+        .scopes
+        .iter()
+        .any(|&scope| Scope::from(scope) == Scope::Admin)
+        && user.permissions.is_empty()
+    {
+        return Err(AppError::ForbiddenOperation);
+    }
+    if req
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(AppError::UnprocessableEntity(
+            "expiresAt must be in the future".into(),
+        ));
+    }
+
+    registry
+        .personal_access_token_repository()
+        .create(CreatePersonalAccessTokenRequestWithUserId::new(user.id(), req).into())
+        .await
+        .map(|created| (StatusCode::CREATED, Json(created.into())))
+}
+
+/// ユーザーが自身のパーソナルアクセストークンの一覧を取得する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/users/me/tokens",
+        responses(
+            (status = 200, description = "トークンの一覧の取得に成功した場合。", body = PersonalAccessTokensResponse),
+            (status = 403, description = "パーソナルアクセストークンで実行した場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn list_my_tokens(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PersonalAccessTokensResponse>> {
+    user.require_session()?;
+
+    registry
+        .personal_access_token_repository()
+        .find_by_user(user.id())
+        .await
+        .map(PersonalAccessTokensResponse::from)
+        .map(Json)
+}
+
+/// ユーザーが自身のパーソナルアクセストークンを無効にする
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/me/tokens/{token_id}",
+        responses(
+            (status = 204, description = "トークンの削除に成功した場合。"),
+            (status = 403, description = "パーソナルアクセストークンで実行した場合。"),
+            (status = 404, description = "指定したトークンが存在しない場合。"),
+        ),
+        params(
+            ("token_id" = PersonalAccessTokenId, Path, description = "トークン ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn delete_my_token(
+    user: AuthorizedUser,
+    Path(token_id): Path<PersonalAccessTokenId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    registry
+        .personal_access_token_repository()
+        .delete(DeletePersonalAccessToken {
+            user_id: user.id(),
+            token_id,
+        })
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::{
+    model::role::event::{CreateRole, DeleteRole, UpdateRolePermissions},
+    policy::Action,
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::role::{
+        role_from_name, CreateRoleRequest, RoleResponse, RolesResponse,
+        UpdateRolePermissionsRequest, UpdateRolePermissionsRequestWithRole,
+    },
+};
+
+/// ロールと割り当てられた権限の一覧を取得する（role:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/roles",
+        responses(
+            (status = 200, description = "ロールの一覧の取得に成功した場合。", body = RolesResponse),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn list_roles(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<RolesResponse>> {
+    user.authorize(&registry, Action::ManageRoles)?;
+
+    registry
+        .role_repository()
+        .find_all()
+        .await
+        .map(RolesResponse::from)
+        .map(Json)
+}
+
+/// 権限を指定してロールを作成する（role:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/roles",
+        request_body = CreateRoleRequest,
+        responses(
+            (status = 201, description = "ロールの作成に成功した場合。", body = RoleResponse),
+            (status = 400, description = "リクエストのパラメータが不正な場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合、または自身が持たない権限を指定した場合。"),
+            (status = 422, description = "同じ名前のロールがすでに存在する場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn create_role(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateRoleRequest>,
+) -> AppResult<(StatusCode, Json<RoleResponse>)> {
+    user.authorize(&registry, Action::ManageRoles)?;
+
+    req.validate(&())?;
+
+    let event = CreateRole::from(req);
+    user.authorize_grant(&registry, &event.permissions)?;
+
+    registry
+        .role_repository()
+        .create(event)
+        .await
+        .map(|role| (StatusCode::CREATED, Json(role.into())))
+}
+
+/// ロールに割り当てる権限を置き換える（role:manage 権限が必要）
+/// 変更はそのロールのユーザーの次のリクエストから反映される
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/roles/{role_name}/permissions",
+        request_body = UpdateRolePermissionsRequest,
+        responses(
+            (status = 200, description = "権限の変更に成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合、または自身が持たない権限を指定した場合。"),
+            (status = 404, description = "指定したロールが存在しない場合。"),
+            (status = 422, description = "Admin の権限を変更しようとした場合。"),
+        ),
+        params(
+            ("role_name" = String, Path, description = "ロール名"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn update_role_permissions(
+    user: AuthorizedUser,
+    Path(role_name): Path<String>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateRolePermissionsRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize(&registry, Action::ManageRoles)?;
+
+    let event = UpdateRolePermissions::from(UpdateRolePermissionsRequestWithRole::new(
+        role_from_name(role_name),
+        req,
+    ));
+    user.authorize_grant(&registry, &event.permissions)?;
+
+    registry
+        .role_repository()
+        .update_permissions(event)
+        .await
+        .map(|_| StatusCode::OK)
+}
+
+/// ロールを削除する（role:manage 権限が必要）
+/// 組み込みのロールと、ユーザーが割り当てられているロールは削除できない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/roles/{role_name}",
+        responses(
+            (status = 204, description = "ロールの削除に成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+            (status = 404, description = "指定したロールが存在しない場合。"),
+            (status = 422, description = "組み込みのロール、またはユーザーが割り当てられているロールを指定した場合。"),
+        ),
+        params(
+            ("role_name" = String, Path, description = "ロール名"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn delete_role(
+    user: AuthorizedUser,
+    Path(role_name): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize(&registry, Action::ManageRoles)?;
+
+    registry
+        .role_repository()
+        .delete(DeleteRole {
+            role: role_from_name(role_name),
+        })
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
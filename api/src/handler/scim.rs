@@ -0,0 +1,627 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    role::{
+        event::{CreateRole, DeleteRole},
+        Role,
+    },
+    user::{
+        event::{ProvisionUser, SyncRoleMembers, SyncUserProfile, UpdateUserStatus},
+        User, UserListOptions, UserSort, UserStatus,
+    },
+};
+use registry::AppRegistry;
+use serde_json::{json, Value};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedScimClient,
+    model::{
+        role::role_from_name,
+        scim::{
+            eq_filter_value, ScimGroupRequest, ScimGroupResponse, ScimJson, ScimListQuery,
+            ScimListResponse, ScimMemberOperation, ScimPatchRequest, ScimResult,
+            ScimUserAttributes, ScimUserRequest, ScimUserResponse,
+        },
+    },
+};
+
+// グループのメンバーとして一度に返すユーザー数の上限
+const MAX_GROUP_MEMBERS: i64 = 10_000;
+
+// SCIM クライアントは IdP であり、管理者と同じようにすべてのユーザーとロールを操作できる
+// 削除の要求はユーザーを無効化し、蔵書や貸出の記録は残す
+
+/// SCIM サーバーとして対応している機能を返す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/scim/v2/ServiceProviderConfig",
+        responses(
+            (status = 200, description = "対応している機能を取得できた場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "SCIM が無効な場合。"),
+        )
+    )
+)]
+pub async fn show_service_provider_config(
+    _client: AuthorizedScimClient,
+) -> ScimResult<ScimJson<Value>> {
+    Ok(ScimJson(
+        StatusCode::OK,
+        json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"],
+            "patch": { "supported": true },
+            "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+            "filter": { "supported": true, "maxResults": 200 },
+            "changePassword": { "supported": false },
+            "sort": { "supported": false },
+            "etag": { "supported": false },
+            "authenticationSchemes": [{
+                "type": "oauthbearertoken",
+                "name": "Bearer Token",
+                "description": "SCIM 専用のベアラートークンによる認証",
+            }],
+        }),
+    ))
+}
+
+/// ユーザーの一覧を取得する
+/// フィルタは `userName eq "..."` の形式のみに対応する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/scim/v2/Users",
+        responses(
+            (status = 200, description = "ユーザーの一覧を取得できた場合。", body = ScimUserListResponse, content_type = "application/scim+json"),
+            (status = 400, description = "対応していないフィルタを指定した場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+        ),
+        params(
+            ("filter" = Option<String>, Query, description = "userName eq \"...\" の形式で絞り込む"),
+            ("startIndex" = Option<i64>, Query, description = "1 始まりの開始位置"),
+            ("count" = Option<i64>, Query, description = "取得するユーザー数の上限"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn list_scim_users(
+    _client: AuthorizedScimClient,
+    Query(query): Query<ScimListQuery>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<ScimJson<ScimListResponse<ScimUserResponse>>> {
+    let start_index = query.start_index();
+
+    // IdP は作成の前に、userName で既存のユーザーを検索する
+    if let Some(filter) = &query.filter {
+        let email = eq_filter_value(filter, &["userName", "emails.value"])?;
+        let users = registry
+            .user_repository()
+            .find_by_email(&email)
+            .await?
+            .into_iter()
+            .map(ScimUserResponse::from)
+            .collect::<Vec<_>>();
+        return Ok(ScimJson(
+            StatusCode::OK,
+            ScimListResponse::new(users.len() as i64, 1, users),
+        ));
+    }
+
+    let list = registry
+        .user_repository()
+        .find_all(UserListOptions {
+            limit: query.count(),
+            offset: start_index - 1,
+            keyword: None,
+            search_email: false,
+            role: None,
+            status: None,
+            // ページを跨いでも順序が変わらないよう、登録順に並べる
+            sort: UserSort::CreatedAtAsc,
+        })
+        .await?;
+
+    Ok(ScimJson(
+        StatusCode::OK,
+        ScimListResponse::new(
+            list.total,
+            start_index,
+            list.items.into_iter().map(ScimUserResponse::from).collect(),
+        ),
+    ))
+}
+
+/// ユーザーを取得する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/scim/v2/Users/{user_id}",
+        responses(
+            (status = 200, description = "ユーザーを取得できた場合。", body = ScimUserResponse, content_type = "application/scim+json"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "ユーザーが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn show_scim_user(
+    _client: AuthorizedScimClient,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<ScimJson<ScimUserResponse>> {
+    let user = find_user(&registry, user_id).await?;
+    Ok(ScimJson(StatusCode::OK, user.into()))
+}
+
+/// IdP で作成されたユーザーを作成する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/scim/v2/Users",
+        request_body(content = ScimUserRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 201, description = "ユーザーを作成した場合。", body = ScimUserResponse, content_type = "application/scim+json"),
+            (status = 400, description = "名前やメールアドレスが不正な場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 409, description = "メールアドレスがすでに使われている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn create_scim_user(
+    _client: AuthorizedScimClient,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimUserRequest>,
+) -> ScimResult<ScimJson<ScimUserResponse>> {
+    let attributes = ScimUserAttributes::from(req);
+    attributes.validate(&()).map_err(AppError::from)?;
+
+    // IdP で認証するユーザーのため、パスワードは設定せずに作成する
+    let user = registry
+        .user_repository()
+        .provision(ProvisionUser {
+            name: attributes.name.clone(),
+            email: attributes.email.clone(),
+        })
+        .await?;
+    tracing::info!(user_id = %user.id, "User has been provisioned via SCIM");
+
+    update_active(&registry, &user, attributes.active).await?;
+
+    let user = find_user(&registry, user.id).await?;
+    Ok(ScimJson(StatusCode::CREATED, user.into()))
+}
+
+/// ユーザーの名前、メールアドレス、有効かどうかを置き換える
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/scim/v2/Users/{user_id}",
+        request_body(content = ScimUserRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 200, description = "ユーザーを更新した場合。", body = ScimUserResponse, content_type = "application/scim+json"),
+            (status = 400, description = "値が不正な場合、または無効化したユーザーを有効にしようとした場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "ユーザーが存在しない場合。"),
+            (status = 409, description = "メールアドレスがすでに使われている場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn replace_scim_user(
+    _client: AuthorizedScimClient,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimUserRequest>,
+) -> ScimResult<ScimJson<ScimUserResponse>> {
+    let user = find_user(&registry, user_id).await?;
+    let user = update_user(&registry, user, req.into()).await?;
+    Ok(ScimJson(StatusCode::OK, user.into()))
+}
+
+/// ユーザーの属性を部分的に更新する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(patch, path="/scim/v2/Users/{user_id}",
+        request_body(content = ScimPatchRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 200, description = "ユーザーを更新した場合。", body = ScimUserResponse, content_type = "application/scim+json"),
+            (status = 400, description = "対応していない操作や不正な値を指定した場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "ユーザーが存在しない場合。"),
+            (status = 409, description = "メールアドレスがすでに使われている場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn patch_scim_user(
+    _client: AuthorizedScimClient,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimPatchRequest>,
+) -> ScimResult<ScimJson<ScimUserResponse>> {
+    let user = find_user(&registry, user_id).await?;
+
+    let mut attributes = ScimUserAttributes::from(&user);
+    for operation in req.operations {
+        attributes.apply(operation)?;
+    }
+
+    let user = update_user(&registry, user, attributes).await?;
+    Ok(ScimJson(StatusCode::OK, user.into()))
+}
+
+/// ユーザーを無効化する。蔵書や貸出の記録を残すため、ユーザーは削除しない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/scim/v2/Users/{user_id}",
+        responses(
+            (status = 204, description = "ユーザーを無効化した場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "ユーザーが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザーの ID"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn delete_scim_user(
+    _client: AuthorizedScimClient,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<StatusCode> {
+    let user = find_user(&registry, user_id).await?;
+
+    // 無効化済みのユーザーへの削除の要求は、何もせず成功として扱う
+    if user.status != UserStatus::Deactivated {
+        registry
+            .user_repository()
+            .update_status(UpdateUserStatus {
+                user_id,
+                status: UserStatus::Deactivated,
+                changed_by: None,
+            })
+            .await?;
+        tracing::info!(user_id = %user_id, "User has been deprovisioned via SCIM");
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// グループ（ロール）の一覧を取得する
+/// フィルタは `displayName eq "..."` の形式のみに対応する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/scim/v2/Groups",
+        responses(
+            (status = 200, description = "グループの一覧を取得できた場合。", body = ScimGroupListResponse, content_type = "application/scim+json"),
+            (status = 400, description = "対応していないフィルタを指定した場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+        ),
+        params(
+            ("filter" = Option<String>, Query, description = "displayName eq \"...\" の形式で絞り込む"),
+            ("startIndex" = Option<i64>, Query, description = "1 始まりの開始位置"),
+            ("count" = Option<i64>, Query, description = "取得するグループ数の上限"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn list_scim_groups(
+    _client: AuthorizedScimClient,
+    Query(query): Query<ScimListQuery>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<ScimJson<ScimListResponse<ScimGroupResponse>>> {
+    let display_name = query
+        .filter
+        .as_deref()
+        .map(|filter| eq_filter_value(filter, &["displayName"]))
+        .transpose()?;
+
+    // ロールの数は少ないため、すべて取得してから絞り込む
+    let roles = registry
+        .role_repository()
+        .find_all()
+        .await?
+        .into_iter()
+        .map(|role| role.role)
+        .filter(|role| {
+            display_name
+                .as_deref()
+                .map_or(true, |name| role.as_ref() == name)
+        })
+        .collect::<Vec<_>>();
+
+    let start_index = query.start_index();
+    let mut groups = Vec::new();
+    for role in roles
+        .iter()
+        .skip((start_index - 1) as usize)
+        .take(query.count() as usize)
+    {
+        let members = find_members(&registry, role).await?;
+        groups.push(ScimGroupResponse::new(role, members));
+    }
+
+    Ok(ScimJson(
+        StatusCode::OK,
+        ScimListResponse::new(roles.len() as i64, start_index, groups),
+    ))
+}
+
+/// グループ（ロール）と、そのメンバーを取得する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/scim/v2/Groups/{group_id}",
+        responses(
+            (status = 200, description = "グループを取得できた場合。", body = ScimGroupResponse, content_type = "application/scim+json"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "グループが存在しない場合。"),
+        ),
+        params(
+            ("group_id" = String, Path, description = "グループの ID（ロール名）"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn show_scim_group(
+    _client: AuthorizedScimClient,
+    Path(group_id): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<ScimJson<ScimGroupResponse>> {
+    let role = find_role(&registry, group_id).await?;
+    let members = find_members(&registry, &role).await?;
+    Ok(ScimJson(
+        StatusCode::OK,
+        ScimGroupResponse::new(&role, members),
+    ))
+}
+
+/// 権限を持たないロールとしてグループを作成し、メンバーにロールを割り当てる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/scim/v2/Groups",
+        request_body(content = ScimGroupRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 201, description = "グループを作成した場合。", body = ScimGroupResponse, content_type = "application/scim+json"),
+            (status = 400, description = "名前が不正な場合、または同じ名前のロールが存在する場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "メンバーに指定したユーザーが存在しない場合。"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn create_scim_group(
+    _client: AuthorizedScimClient,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimGroupRequest>,
+) -> ScimResult<ScimJson<ScimGroupResponse>> {
+    req.validate(&()).map_err(AppError::from)?;
+    let member_ids = req.member_ids()?;
+
+    // 権限は管理画面から割り当てるため、権限を持たないロールとして作成する
+    let created = registry
+        .role_repository()
+        .create(CreateRole {
+            name: req.display_name,
+            permissions: vec![],
+        })
+        .await?;
+
+    let members = sync_members(&registry, &created.role, member_ids).await?;
+    Ok(ScimJson(
+        StatusCode::CREATED,
+        ScimGroupResponse::new(&created.role, members),
+    ))
+}
+
+/// グループのメンバーを置き換える。外したメンバーは User ロールに戻す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/scim/v2/Groups/{group_id}",
+        request_body(content = ScimGroupRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 200, description = "グループを更新した場合。", body = ScimGroupResponse, content_type = "application/scim+json"),
+            (status = 400, description = "名前を変更しようとした場合、または有効な管理者がいなくなる場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "グループまたはメンバーに指定したユーザーが存在しない場合。"),
+        ),
+        params(
+            ("group_id" = String, Path, description = "グループの ID（ロール名）"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn replace_scim_group(
+    _client: AuthorizedScimClient,
+    Path(group_id): Path<String>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimGroupRequest>,
+) -> ScimResult<ScimJson<ScimGroupResponse>> {
+    req.validate(&()).map_err(AppError::from)?;
+    let role = find_role(&registry, group_id).await?;
+    if req.display_name != role.as_ref() {
+        return Err(AppError::UnprocessableEntity("グループの名前は変更できません".into()).into());
+    }
+
+    let members = sync_members(&registry, &role, req.member_ids()?).await?;
+    Ok(ScimJson(
+        StatusCode::OK,
+        ScimGroupResponse::new(&role, members),
+    ))
+}
+
+/// グループのメンバーを追加・削除する。外したメンバーは User ロールに戻す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(patch, path="/scim/v2/Groups/{group_id}",
+        request_body(content = ScimPatchRequest, content_type = "application/scim+json"),
+        responses(
+            (status = 200, description = "グループを更新した場合。", body = ScimGroupResponse, content_type = "application/scim+json"),
+            (status = 400, description = "対応していない操作を指定した場合、または有効な管理者がいなくなる場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "グループまたはメンバーに指定したユーザーが存在しない場合。"),
+        ),
+        params(
+            ("group_id" = String, Path, description = "グループの ID（ロール名）"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry, req))]
+pub async fn patch_scim_group(
+    _client: AuthorizedScimClient,
+    Path(group_id): Path<String>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ScimPatchRequest>,
+) -> ScimResult<ScimJson<ScimGroupResponse>> {
+    let role = find_role(&registry, group_id).await?;
+
+    let mut member_ids = find_members(&registry, &role)
+        .await?
+        .into_iter()
+        .map(|user| user.id)
+        .collect::<Vec<_>>();
+    for operation in req.operations {
+        ScimMemberOperation::try_from(operation)?.apply_to(&mut member_ids);
+    }
+
+    let members = sync_members(&registry, &role, member_ids).await?;
+    Ok(ScimJson(
+        StatusCode::OK,
+        ScimGroupResponse::new(&role, members),
+    ))
+}
+
+/// グループ（ロール）を削除する。組み込みのロールと、ユーザーが割り当てられているロールは削除できない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/scim/v2/Groups/{group_id}",
+        responses(
+            (status = 204, description = "グループを削除した場合。"),
+            (status = 400, description = "組み込みのロール、またはユーザーが割り当てられているロールの場合。"),
+            (status = 401, description = "SCIM 用のトークンが誤っている場合。"),
+            (status = 404, description = "グループが存在しない場合。"),
+        ),
+        params(
+            ("group_id" = String, Path, description = "グループの ID（ロール名）"),
+        )
+    )
+)]
+#[tracing::instrument(skip(_client, registry))]
+pub async fn delete_scim_group(
+    _client: AuthorizedScimClient,
+    Path(group_id): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> ScimResult<StatusCode> {
+    let role = find_role(&registry, group_id).await?;
+    registry
+        .role_repository()
+        .delete(DeleteRole { role })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_user(registry: &AppRegistry, user_id: UserId) -> AppResult<User> {
+    registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))
+}
+
+// 名前とメールアドレス、有効かどうかを反映し、反映後のユーザーを返す
+async fn update_user(
+    registry: &AppRegistry,
+    user: User,
+    attributes: ScimUserAttributes,
+) -> AppResult<User> {
+    attributes.validate(&())?;
+
+    if attributes.name != user.name || attributes.email != user.email {
+        registry
+            .user_repository()
+            .sync_profile(SyncUserProfile {
+                user_id: user.id,
+                name: attributes.name.clone(),
+                email: attributes.email.clone(),
+            })
+            .await?;
+    }
+    update_active(registry, &user, attributes.active).await?;
+
+    find_user(registry, user.id).await
+}
+
+// active を false にした場合は利用停止とし、true に戻すと利用を再開する
+// 無効化したユーザーは元に戻せないため、有効にしようとするとエラーになる
+async fn update_active(registry: &AppRegistry, user: &User, active: bool) -> AppResult<()> {
+    let status = match (user.status, active) {
+        (UserStatus::Active, false) => UserStatus::Suspended,
+        (UserStatus::Active, true) | (_, false) => return Ok(()),
+        (_, true) => UserStatus::Active,
+    };
+    registry
+        .user_repository()
+        .update_status(UpdateUserStatus {
+            user_id: user.id,
+            status,
+            changed_by: None,
+        })
+        .await
+}
+
+async fn find_role(registry: &AppRegistry, group_id: String) -> AppResult<Role> {
+    let role = role_from_name(group_id);
+    registry
+        .role_repository()
+        .find_all()
+        .await?
+        .into_iter()
+        .map(|role| role.role)
+        .find(|found| *found == role)
+        .ok_or_else(|| AppError::EntityNotFound("Specified group not found".into()))
+}
+
+async fn find_members(registry: &AppRegistry, role: &Role) -> AppResult<Vec<User>> {
+    let list = registry
+        .user_repository()
+        .find_all(UserListOptions {
+            limit: MAX_GROUP_MEMBERS,
+            offset: 0,
+            keyword: None,
+            search_email: false,
+            role: Some(role.clone()),
+            status: None,
+            sort: UserSort::CreatedAtAsc,
+        })
+        .await?;
+    Ok(list.items)
+}
+
+// グループのメンバーを member_ids のとおりにし、反映後のメンバーを返す
+// 途中で失敗しても一部のメンバーだけが変更されることのないよう、まとめて反映する
+async fn sync_members(
+    registry: &AppRegistry,
+    role: &Role,
+    member_ids: Vec<UserId>,
+) -> AppResult<Vec<User>> {
+    registry
+        .user_repository()
+        .sync_role_members(SyncRoleMembers {
+            role: role.clone(),
+            member_ids,
+        })
+        .await?;
+
+    find_members(registry, role).await
+}
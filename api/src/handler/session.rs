@@ -0,0 +1,210 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use kernel::model::id::{SessionId, UserId};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{extractor::AuthorizedUser, model::session::SessionsResponse};
+
+/// ユーザーが自身のログイン中のセッションの一覧を取得する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/users/me/sessions",
+        responses(
+            (status = 200, description = "セッションの一覧の取得に成功した場合。", body = SessionsResponse),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn list_my_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    user.require_session()?;
+
+    let auth_repository = registry.auth_repository();
+    let current = auth_repository
+        .fetch_session_id_from_token(&user.access_token)
+        .await?;
+    let sessions = auth_repository.find_sessions(user.id()).await?;
+
+    Ok(Json(SessionsResponse::new(sessions, current)))
+}
+
+/// ユーザーが自身のセッションを指定してログアウトさせる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/me/sessions/{session_id}",
+        responses(
+            (status = 204, description = "セッションの削除に成功した場合。"),
+            (status = 404, description = "指定したセッションが存在しない場合。"),
+        ),
+        params(
+            ("session_id" = SessionId, Path, description = "セッション ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn delete_my_session(
+    user: AuthorizedUser,
+    Path(session_id): Path<SessionId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    registry
+        .auth_repository()
+        .delete_session(user.id(), session_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// ユーザーが現在のセッション以外のすべてのセッションをログアウトさせる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/me/sessions",
+        responses(
+            (status = 204, description = "セッションの削除に成功した場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn delete_my_other_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    let auth_repository = registry.auth_repository();
+    let current = auth_repository
+        .fetch_session_id_from_token(&user.access_token)
+        .await?
+        .ok_or(AppError::UnauthorizedError)?;
+
+    auth_repository
+        .delete_other_sessions(user.id(), current)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// 指定したユーザーのセッションの一覧を取得する（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/users/{user_id}/sessions",
+        responses(
+            (status = 200, description = "セッションの一覧の取得に成功した場合。", body = SessionsResponse),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn list_user_sessions(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    let sessions = registry.auth_repository().find_sessions(user_id).await?;
+
+    Ok(Json(SessionsResponse::new(sessions, None)))
+}
+
+/// 指定したユーザーのセッションを指定してログアウトさせる（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/{user_id}/sessions/{session_id}",
+        responses(
+            (status = 204, description = "セッションの削除に成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーまたはセッションが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+            ("session_id" = SessionId, Path, description = "セッション ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn delete_user_session(
+    user: AuthorizedUser,
+    Path((user_id, session_id)): Path<(UserId, SessionId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    registry
+        .auth_repository()
+        .delete_session(user_id, session_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// 指定したユーザーのすべてのセッションをログアウトさせる（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/{user_id}/sessions",
+        responses(
+            (status = 204, description = "セッションの削除に成功した場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn delete_user_sessions(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    registry
+        .auth_repository()
+        .delete_all_tokens(user_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,70 @@
+use axum::{extract::State, http::StatusCode, Json};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::model::{
+    setup::{SetupRequest, SetupStatusResponse},
+    user::UserResponse,
+};
+
+/// 初期設定が必要かどうかを取得する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        get,
+        path="/auth/setup",
+        responses(
+            (status = 200, description = "初期設定の要否を取得できた場合。", body = SetupStatusResponse),
+        )
+    )
+)]
+#[tracing::instrument(skip(registry))]
+pub async fn get_setup_status(
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SetupStatusResponse>> {
+    let required = registry.setup_repository().is_required().await?;
+    Ok(Json(SetupStatusResponse { required }))
+}
+
+/// 最初の管理者を作成する
+/// 管理者が1人もいない間だけ、ログインせずに利用できる
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path="/auth/setup",
+        request_body = SetupRequest,
+        responses(
+            (status = 201, description = "管理者を作成できた場合。", body = UserResponse),
+            (status = 400, description = "リクエストの内容に問題があった場合や、パスワードが条件を満たさない場合。"),
+            (status = 409, description = "初期設定がすでに完了している場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(registry, req),
+    fields(
+        email_address = %req.email
+    )
+)]
+pub async fn setup(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<SetupRequest>,
+) -> AppResult<(StatusCode, Json<UserResponse>)> {
+    // 同時に作成された場合も、リポジトリで同じエラーを返す
+    if !registry.setup_repository().is_required().await? {
+        return Err(AppError::EntityConflict(
+            "setup is already completed".into(),
+        ));
+    }
+
+    req.validate_with(registry.password_policy())?;
+
+    let admin = registry
+        .setup_repository()
+        .create_initial_admin(req.into())
+        .await?;
+    tracing::info!(user_id = %admin.id, "Initial admin has been created");
+
+    Ok((StatusCode::CREATED, Json(admin.into())))
+}
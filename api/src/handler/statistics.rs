@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use garde::Validate;
+use kernel::policy::Action;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::statistics::{StatisticsFormat, StatisticsQuery, StatisticsResponse},
+};
+
+/// 蔵書の利用状況の統計を取得する（statistics:read 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(get, path="/api/v1/statistics",
+        responses(
+            (status = 200, description = "統計情報の取得に成功した場合。format=csv を指定した場合は CSV で返す。", body = StatisticsResponse),
+            (status = 400, description = "指定されたクエリの値に不備があった場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーがアクセスした場合。"),
+        ),
+        params(
+            ("from" = Option<String>, Query, description = "集計期間の開始日（YYYY-MM-DD）"),
+            ("to" = Option<String>, Query, description = "集計期間の終了日（YYYY-MM-DD、当日を含む）"),
+            ("includeBorrowers" = Option<bool>, Query, description = "よく借りている利用者の集計を含めるかどうか（集計への参加に同意した利用者のみが対象）"),
+            ("limit" = Option<i64>, Query, description = "ランキング形式の集計で返す件数の上限値"),
+            ("format" = Option<String>, Query, description = "json または csv"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn show_statistics(
+    user: AuthorizedUser,
+    Query(query): Query<StatisticsQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Response> {
+    user.authorize(&registry, Action::ReadStatistics)?;
+
+    query.validate(&())?;
+
+    let statistics = registry
+        .statistics_repository()
+        .summarize((&query).into())
+        .await
+        .map(StatisticsResponse::from)?;
+
+    let response = match query.format {
+        StatisticsFormat::Json => Json(statistics).into_response(),
+        StatisticsFormat::Csv => (
+            [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            statistics.to_csv(),
+        )
+            .into_response(),
+    };
+    Ok(response)
+}
@@ -0,0 +1,114 @@
+use axum::{extract::State, http::StatusCode, Json};
+use garde::Validate;
+use kernel::model::role::Role;
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::two_factor::{RecoveryCodesResponse, TotpCodeRequest, TotpEnrollmentResponse},
+};
+
+/// ユーザーが自身の TOTP の登録を開始する
+/// 返された URI を認証アプリに登録し、生成されたコードで有効化するまでは 2 段階認証は有効にならない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/users/me/two-factor",
+        responses(
+            (status = 201, description = "登録の開始に成功した場合。", body = TotpEnrollmentResponse),
+            (status = 422, description = "2 段階認証がすでに有効な場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn begin_totp_enrollment(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<(StatusCode, Json<TotpEnrollmentResponse>)> {
+    user.require_session()?;
+
+    registry
+        .two_factor_repository()
+        .begin_enrollment(user.id())
+        .await
+        .map(|enrollment| (StatusCode::CREATED, Json(enrollment.into())))
+}
+
+/// 認証アプリが生成したコードを確認して 2 段階認証を有効にし、リカバリーコードを発行する
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/users/me/two-factor/activate",
+        request_body = TotpCodeRequest,
+        responses(
+            (status = 200, description = "有効化に成功した場合。", body = RecoveryCodesResponse),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "コードに誤りがある場合。"),
+            (status = 422, description = "登録が開始されていない場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn activate_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpCodeRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    user.require_session()?;
+
+    req.validate(&())?;
+
+    registry
+        .two_factor_repository()
+        .activate(user.id(), &req.code)
+        .await
+        .map(|codes| Json(codes.into()))
+}
+
+/// ユーザーが自身の 2 段階認証を無効にする
+/// TOTP の登録が必須の場合、管理者は無効にできない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/me/two-factor",
+        request_body = TotpCodeRequest,
+        responses(
+            (status = 204, description = "無効化に成功した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "コードに誤りがある場合、または管理者に登録が必須とされている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn disable_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpCodeRequest>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    req.validate(&())?;
+
+    if registry.app_config().two_factor.required_for_admin && user.user.role == Role::Admin {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .two_factor_repository()
+        .disable(user.id(), &req.code)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -1,10 +1,20 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use garde::Validate;
-use kernel::model::{id::UserId, user::event::DeleteUser};
+use kernel::{
+    model::{
+        id::UserId,
+        personal_access_token::Scope,
+        user::{
+            event::{UpdateUserProfile, UpdateUserRole, UpdateUserStatus},
+            UserStatus,
+        },
+    },
+    policy::Action,
+};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
 
@@ -13,13 +23,19 @@ use crate::{
     model::{
         checkout::CheckoutsResponse,
         user::{
-            CreateUserRequest, UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
-            UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserResponse, UsersResponse,
+            CreateUserRequest, EmailChangeMail, ForcePasswordResetRequest,
+            ForcePasswordResetRequestWithUserId, PaginatedUserResponse, PasswordContext,
+            UpdateLoanStatisticsSharingRequest, UpdateLoanStatisticsSharingRequestWithUserId,
+            UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
+            UpdateUserProfileRequest, UpdateUserProfileRequestWithUserId, UpdateUserRoleRequest,
+            UpdateUserRoleRequestWithUserId, UpdateUserStatusRequest,
+            UpdateUserStatusRequestWithUserIds, UserListQuery, UserListQueryWithVisibility,
+            UserResponse,
         },
     },
 };
 
-/// ユーザーを追加する(Admin only)
+/// ユーザーを追加する（user:manage 権限が必要）
 #[tracing::instrument(
 skip(user, registry, req),
 fields(
@@ -31,12 +47,9 @@ pub async fn register_user(
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateUserRequest>,
 ) -> AppResult<Json<UserResponse>> {
-    // AutorizedUser の権限が Admin のときのみ実行可能とする
-    if !user.is_admin() {
-        return Err(AppError::ForbiddenOperation);
-    }
+    user.authorize(&registry, Action::ManageUsers)?;
 
-    req.validate(&())?;
+    req.validate(&req.password_context(registry.password_policy()))?;
 
     let registed_user = registry.user_repository().create(req.into()).await?;
 
@@ -44,31 +57,56 @@ pub async fn register_user(
 }
 
 /// ユーザーの一覧を取得する
+/// user:manage 権限を持たないユーザーには、名前のみの公開プロフィールを返す
 #[cfg_attr(
     debug_assertions,
     utoipa::path(get, path="/api/v1/users",
         responses(
-            (status = 200, description = "ユーザーの一覧を取得できた場合。"),
+            (status = 200, description = "ユーザーの一覧を取得できた場合。", body = PaginatedUserResponse),
+            (status = 400, description = "指定されたクエリの値に不備があった場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが、ロールや状態での絞り込み、メールアドレスでの並べ替えを指定した場合。"),
             (status = 500, description = "サーバーサイドエラーが発生した場合。")
+        ),
+        params(
+            ("limit" = i64, Query, description = "一度に取得するユーザー数の上限値の指定"),
+            ("offset" = i64, Query, description = "取得対象とするユーザー一覧の開始位置"),
+            ("q" = Option<String>, Query, description = "名前（user:manage 権限を持つ場合はメールアドレスも）の部分一致で絞り込む"),
+            ("role" = Option<String>, Query, description = "ロール名で絞り込む（user:manage 権限が必要）"),
+            ("status" = Option<crate::model::user::UserStatusName>, Query, description = "状態で絞り込む（user:manage 権限が必要）"),
+            ("sort" = Option<crate::model::user::UserSortName>, Query, description = "並び順。デフォルトは登録日時の新しい順"),
         )
     )
 )]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
 pub async fn list_users(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
+    Query(query): Query<UserListQuery>,
     State(registry): State<AppRegistry>,
-) -> AppResult<Json<UsersResponse>> {
-    let items = registry
-        .user_repository()
-        .find_all()
-        .await?
-        .into_iter()
-        .map(UserResponse::from)
-        .collect();
+) -> AppResult<Json<PaginatedUserResponse>> {
+    query.validate(&())?;
+
+    let full_profile = user.authorize(&registry, Action::ManageUsers).is_ok();
+    // 公開プロフィールに含まれない項目で、絞り込みや並べ替えはできない
+    if !full_profile && (query.role.is_some() || query.status.is_some() || query.sort.uses_email())
+    {
+        return Err(AppError::ForbiddenOperation);
+    }
 
-    Ok(Json(UsersResponse { items }))
+    registry
+        .user_repository()
+        .find_all(UserListQueryWithVisibility::new(full_profile, query).into())
+        .await
+        .map(|list| PaginatedUserResponse::new(list, full_profile))
+        .map(Json)
 }
 
-/// ユーザーを削除する(Admin only)
+/// ユーザーを無効化する（user:manage 権限が必要）
+/// 蔵書や貸出の記録を残すため、ユーザーのレコードは削除しない
 #[tracing::instrument(
     skip(user, registry),
     fields(
@@ -80,34 +118,221 @@ pub async fn delete_user(
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    // AuthorizedUser の権限が Admin のときのみ実行可能とする
-    if !user.is_admin() {
-        return Err(AppError::ForbiddenOperation);
-    }
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    registry
+        .user_repository()
+        .update_status(UpdateUserStatus {
+            user_id,
+            status: UserStatus::Deactivated,
+            changed_by: Some(user.id()),
+        })
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// ユーザーの状態を変更する（user:manage 権限が必要）
+/// 利用停止中や無効化したユーザーは、ログインも貸出もできない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/{user_id}/status",
+        request_body = UpdateUserStatusRequest,
+        responses(
+            (status = 200, description = "変更に成功した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+            (status = 422, description = "現在の状態から変更できない場合や、有効な管理者がいなくなる場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn update_user_status(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserStatusRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    req.validate(&())?;
 
     registry
         .user_repository()
-        .delete(DeleteUser { user_id })
+        .update_status(UpdateUserStatusRequestWithUserIds::new(user_id, user.id(), req).into())
         .await?;
 
     Ok(StatusCode::OK)
 }
 
-/// ユーザーのロールを変更する（Admin only）
+/// ユーザーのロールを変更する（user:manage 権限が必要）
+/// 対象のユーザーのすべてのセッションを無効にする
 pub async fn change_role(
     user: AuthorizedUser,
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateUserRoleRequest>,
 ) -> AppResult<StatusCode> {
-    // AuthorizedUser の権限が Admin のときのみ実行可能とする
-    if !user.is_admin() {
-        return Err(AppError::ForbiddenOperation);
-    }
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    req.validate(&())?;
+
+    let event: UpdateUserRole = UpdateUserRoleRequestWithUserId::new(user_id, req).into();
+
+    let permissions = registry
+        .role_repository()
+        .find_permissions(&event.role)
+        .await?;
+    user.authorize_grant(&registry, &permissions)?;
+
+    registry.user_repository().update_role(event).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// ユーザーの名前とメールアドレスを変更する（user:manage 権限が必要）
+/// メールアドレスは、新しいアドレスに送った確認用のリンクを開くまで変更されない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/{user_id}/profile",
+        request_body = UpdateUserProfileRequest,
+        responses(
+            (status = 200, description = "変更に成功した場合。"),
+            (status = 202, description = "名前の変更に成功し、新しいメールアドレスに確認用のメールを送信した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+            (status = 409, description = "メールアドレスが他のユーザーに使われている場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn update_user_profile(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserProfileRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    req.validate(&())?;
+
+    update_profile(
+        &registry,
+        UpdateUserProfileRequestWithUserId::new(user_id, req).into(),
+    )
+    .await
+}
+
+// メールアドレスが変更される場合は、新しいアドレスに確認用のメールを送る
+async fn update_profile(registry: &AppRegistry, event: UpdateUserProfile) -> AppResult<StatusCode> {
+    let Some(pending) = registry.user_repository().update_profile(event).await? else {
+        return Ok(StatusCode::OK);
+    };
+
+    let config = registry.app_config();
+    let mail = EmailChangeMail {
+        pending: &pending,
+        frontend_base_url: &config.frontend.base_url,
+        ttl: config.signup.verification_ttl,
+    };
+    registry.mailer().send(mail.into()).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// ログインの失敗が続いたユーザーのロックを解除する（user:manage 権限が必要）
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(delete, path="/api/v1/users/{user_id}/login-lock",
+        responses(
+            (status = 204, description = "ロックの解除に成功した場合。ロックされていなかった場合も含む。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+        ),
+        params(
+            ("user_id" = UserId, Path, description = "ユーザー ID"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn unlock_user(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
 
     registry
-        .user_repository()
-        .update_role(UpdateUserRoleRequestWithUserId::new(user_id, req).into())
+        .login_throttle_repository()
+        .unlock(user_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// ユーザーのパスワードを仮のパスワードにリセットする（user:manage 権限が必要）
+/// 対象のユーザーのすべてのセッションを無効にし、次回ログイン時にパスワードの再設定を求める
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/{user_id}/password",
+        request_body = ForcePasswordResetRequest,
+        responses(
+            (status = 200, description = "パスワードのリセットに成功した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合や、対象のユーザーのロールが持つ権限を持たない場合。"),
+            (status = 404, description = "指定したユーザーが存在しない場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string(),
+        target_user_id = %user_id
+    )
+)]
+pub async fn force_password_reset(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ForcePasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    user.authorize_manage_user(&registry, user_id).await?;
+
+    req.validate(&())?;
+
+    registry
+        .password_reset_repository()
+        .force_reset(ForcePasswordResetRequestWithUserId::new(user_id, req).into())
+        .await?;
+    registry
+        .auth_repository()
+        .delete_all_tokens(user_id)
         .await?;
 
     Ok(StatusCode::OK)
@@ -134,12 +359,14 @@ pub async fn get_current_user(user: AuthorizedUser) -> Json<UserResponse> {
 }
 
 /// ユーザーが自分自身のパスワードを変更する
+/// 変更後はすべてのセッションが無効になるため、再度ログインが必要になる
 #[cfg_attr(
     debug_assertions,
     utoipa::path(get, path="/api/v1/users/me/password",
         responses(
             (status = 200, description = "パスワードの変更に成功した場合。"),
-            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合や、新しいパスワードが条件を満たさない場合。"),
+            (status = 409, description = "他の処理と競合し、再試行しても完了できなかった場合。"),
             (status = 500, description = "サーバーサイドエラーが発生した場合。")
         )
     )
@@ -155,7 +382,12 @@ pub async fn change_password(
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateUserPasswordRequest>,
 ) -> AppResult<StatusCode> {
-    req.validate(&())?;
+    user.require_session()?;
+
+    req.validate(&PasswordContext::new(
+        registry.password_policy(),
+        user.user.email.clone(),
+    ))?;
 
     registry
         .user_repository()
@@ -165,6 +397,79 @@ pub async fn change_password(
     Ok(StatusCode::OK)
 }
 
+/// ユーザーが自分自身の名前とメールアドレスを変更する
+/// メールアドレスは、新しいアドレスに送った確認用のリンクを開くまで変更されない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/me/profile",
+        request_body = UpdateUserProfileRequest,
+        responses(
+            (status = 200, description = "変更に成功した場合。"),
+            (status = 202, description = "名前の変更に成功し、新しいメールアドレスに確認用のメールを送信した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "パーソナルアクセストークンで実行した場合。"),
+            (status = 409, description = "メールアドレスが他のユーザーに使われている場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string(),
+    )
+)]
+pub async fn update_my_profile(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserProfileRequest>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    req.validate(&())?;
+
+    update_profile(
+        &registry,
+        UpdateUserProfileRequestWithUserId::new(user.id(), req).into(),
+    )
+    .await
+}
+
+/// 利用統計の「よく借りている利用者」に自分を含めることに同意する、または同意を取り消す
+/// 同意していないユーザーは、貸出数によらず集計に含まれない
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(put, path="/api/v1/users/me/loan-statistics",
+        request_body = UpdateLoanStatisticsSharingRequest,
+        responses(
+            (status = 204, description = "変更に成功した場合。"),
+            (status = 400, description = "リクエストの形式に誤りがある場合。"),
+            (status = 403, description = "パーソナルアクセストークンで実行した場合。"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, req),
+    fields(
+        user_id = %user.user.id.to_string(),
+    )
+)]
+pub async fn update_my_loan_statistics_sharing(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateLoanStatisticsSharingRequest>,
+) -> AppResult<StatusCode> {
+    user.require_session()?;
+
+    registry
+        .user_repository()
+        .update_loan_statistics_sharing(
+            UpdateLoanStatisticsSharingRequestWithUserId::new(user.id(), req).into(),
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// ユーザーが自身の借りている書籍の一覧を取得する
 #[cfg_attr(
     debug_assertions,
@@ -185,6 +490,8 @@ pub async fn get_checkouts(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
+    user.require_scope(Scope::CheckoutsRead)?;
+
     registry
         .checkout_repository()
         .find_unreturned_by_user_id(user.id())
@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use kernel::policy::Action;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::AuthorizedUser,
+    handler::invitation::send_invitation_mail,
+    model::user_import::{parse_user_import, UserImportItem, UserImportQuery, UserImportResponse},
+};
+
+/// name,email,role の列を持つ CSV からユーザーをまとめて招待する（user:manage 権限が必要）
+/// すべての行に問題がない場合のみ、1つのトランザクションで招待中のユーザーを作成し、招待のメールを送る
+/// dryRun=true を指定した場合は、検証結果のみを返す
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(post, path="/api/v1/users/import",
+        request_body(content = String, content_type = "text/csv"),
+        responses(
+            (status = 200, description = "ドライランで、すべての行に問題がなかった場合。", body = UserImportResponse),
+            (status = 201, description = "すべての行のユーザーを招待した場合。", body = UserImportResponse),
+            (status = 403, description = "必要な権限を持たないユーザーが実行した場合。"),
+            (status = 422, description = "CSV を読み込めなかった場合、または問題のある行があり、ユーザーを作成しなかった場合。", body = UserImportResponse),
+        ),
+        params(
+            ("dryRun" = Option<bool>, Query, description = "true の場合、検証のみを行いユーザーは作成しない"),
+        )
+    )
+)]
+#[tracing::instrument(
+    skip(user, registry, body),
+    fields(
+        user_id = %user.user.id.to_string()
+    )
+)]
+pub async fn import_users(
+    user: AuthorizedUser,
+    Query(query): Query<UserImportQuery>,
+    State(registry): State<AppRegistry>,
+    body: String,
+) -> AppResult<(StatusCode, Json<UserImportResponse>)> {
+    user.authorize(&registry, Action::ManageUsers)?;
+
+    let mut items = parse_user_import(&body)?;
+
+    // 存在しないロールや、自身が持たない権限を含むロールは指定できない
+    let roles = registry.role_repository().find_all().await?;
+    for item in items.iter_mut() {
+        match roles.iter().find(|role| role.role == item.role) {
+            Some(role) => {
+                if user.authorize_grant(&registry, &role.permissions).is_err() {
+                    item.errors.push(format!(
+                        "自身が持たない権限を含むロール ({}) は指定できません",
+                        item.role.as_ref()
+                    ));
+                }
+            }
+            None => item
+                .errors
+                .push(format!("ロール ({}) が存在しません", item.role.as_ref())),
+        }
+    }
+
+    let emails = items.iter().map(|item| item.email.clone()).collect();
+    let existing = registry
+        .user_repository()
+        .find_existing_emails(emails)
+        .await?
+        .into_iter()
+        .map(|email| email.to_lowercase())
+        .collect::<HashSet<_>>();
+    for item in items
+        .iter_mut()
+        .filter(|item| existing.contains(&item.email.to_lowercase()))
+    {
+        item.errors
+            .push("このメールアドレスはすでに登録されています".into());
+    }
+
+    let valid = items.iter().all(UserImportItem::is_valid);
+    if query.dry_run || !valid {
+        let status = if valid {
+            StatusCode::OK
+        } else {
+            StatusCode::UNPROCESSABLE_ENTITY
+        };
+        return Ok((
+            status,
+            Json(UserImportResponse::not_committed(query.dry_run, items)),
+        ));
+    }
+
+    let rows = items.iter().map(|item| item.row).collect();
+    let events = items
+        .into_iter()
+        .map(|item| item.into_event(user.id()))
+        .collect();
+    let issued = registry
+        .invitation_repository()
+        .create_batch(events)
+        .await?;
+
+    // 招待はすでに作成されているため、メールの送信に失敗しても処理は続ける
+    // 届かなかった招待は、招待の一覧から再送できる
+    for issued in &issued {
+        if let Err(e) = send_invitation_mail(&registry, issued).await {
+            tracing::error!(
+                error.message = %e,
+                invited_user_id = %issued.invitation.user_id,
+                "Failed to send invitation mail"
+            );
+        }
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UserImportResponse::committed(rows, &issued)),
+    ))
+}
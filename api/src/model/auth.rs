@@ -1,8 +1,23 @@
-use kernel::model::id::UserId;
+use std::sync::Arc;
+
+use garde::Validate;
+use kernel::model::{
+    auth::VerifyingKey,
+    id::UserId,
+    mail::Mail,
+    password_reset::{event::ResetPassword, PasswordResetRequest, PasswordResetToken},
+    signup::PendingUser,
+    user::{event::CreateUser, password::PasswordPolicy},
+};
 use serde::{Deserialize, Serialize};
 #[cfg(debug_assertions)]
 use utoipa::ToSchema;
 
+use super::{
+    two_factor::{TwoFactorEnrollmentRequiredResponse, TwoFactorRequiredResponse},
+    user::{validate_password, PasswordContext},
+};
+
 #[derive(Deserialize)]
 #[cfg_attr(debug_assertions, derive(ToSchema))]
 #[serde(rename_all = "camelCase")]
@@ -17,4 +32,214 @@ pub struct LoginRequest {
 pub struct AccessTokenResponse {
     pub user_id: UserId,
     pub access_token: String,
+    // アクセストークンの有効期間（秒）
+    pub expires_in: u64,
+    pub refresh_token: String,
+}
+
+// RFC 7517 の JSON Web Key Set
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct JwksResponse {
+    pub keys: Vec<JwkResponse>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct JwkResponse {
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub use_: String,
+    pub kid: String,
+    pub x: String,
+}
+
+impl From<VerifyingKey> for JwkResponse {
+    fn from(value: VerifyingKey) -> Self {
+        let VerifyingKey { kid, x } = value;
+        Self {
+            kty: "OKP".into(),
+            crv: "Ed25519".into(),
+            alg: "EdDSA".into(),
+            use_: "sig".into(),
+            kid,
+            x,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    #[garde(length(min = 1))]
+    pub refresh_token: String,
+}
+
+// ログインに成功しても、パスワードの再設定が求められている場合はアクセストークンを発行せず、
+// 再設定用のトークンを返す。2 段階認証が必要な場合も同様にチャレンジを返す
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authorized(AccessTokenResponse),
+    PasswordChangeRequired(PasswordChangeRequiredResponse),
+    TwoFactorRequired(TwoFactorRequiredResponse),
+    TwoFactorEnrollmentRequired(TwoFactorEnrollmentRequiredResponse),
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordChangeRequiredResponse {
+    pub user_id: UserId,
+    pub password_reset_token: String,
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
+pub struct SignupRequest {
+    #[garde(length(min = 1))]
+    pub name: String,
+    #[garde(email)]
+    pub email: String,
+    #[garde(custom(validate_password))]
+    pub password: String,
+}
+
+impl SignupRequest {
+    pub fn password_context(&self, policy: Arc<PasswordPolicy>) -> PasswordContext {
+        PasswordContext::new(policy, self.email.clone())
+    }
+}
+
+impl From<SignupRequest> for CreateUser {
+    fn from(value: SignupRequest) -> Self {
+        let SignupRequest {
+            name,
+            email,
+            password,
+        } = value;
+        Self {
+            name,
+            email,
+            password,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+}
+
+// メールアドレスの確認を依頼するメール
+pub struct VerificationMail<'a> {
+    pub pending_user: &'a PendingUser,
+    pub frontend_base_url: &'a str,
+    pub ttl: u64,
+}
+
+impl From<VerificationMail<'_>> for Mail {
+    fn from(value: VerificationMail<'_>) -> Self {
+        let VerificationMail {
+            pending_user,
+            frontend_base_url,
+            ttl,
+        } = value;
+        let PendingUser {
+            user,
+            verification_token,
+        } = pending_user;
+        Mail {
+            to: user.email.clone(),
+            subject: "メールアドレスの確認".into(),
+            body: format!(
+                "{} 様\n\n\
+                 蔵書管理アプリケーションへのご登録ありがとうございます。\n\
+                 以下のリンクを開いて、メールアドレスの確認を完了してください。\n\n\
+                 {}/signup/verify?token={}\n\n\
+                 このリンクの有効期限は {} 時間です。\n\
+                 お心当たりのない場合は、このメールを破棄してください。\n",
+                user.name,
+                frontend_base_url.trim_end_matches('/'),
+                verification_token.0,
+                ttl / 3600
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ForgotPasswordRequest {
+    #[garde(email)]
+    pub email: String,
+}
+
+// トークンに対応するユーザーのメールアドレスは、トークンを確認してから PasswordContext に設定する
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
+pub struct ResetPasswordRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+    #[garde(custom(validate_password))]
+    pub new_password: String,
+}
+
+impl From<ResetPasswordRequest> for ResetPassword {
+    fn from(value: ResetPasswordRequest) -> Self {
+        let ResetPasswordRequest {
+            token,
+            new_password,
+        } = value;
+        ResetPassword {
+            token: PasswordResetToken(token),
+            new_password,
+        }
+    }
+}
+
+// パスワードの再設定を案内するメール
+pub struct PasswordResetMail<'a> {
+    pub request: &'a PasswordResetRequest,
+    pub frontend_base_url: &'a str,
+    pub ttl: u64,
+}
+
+impl From<PasswordResetMail<'_>> for Mail {
+    fn from(value: PasswordResetMail<'_>) -> Self {
+        let PasswordResetMail {
+            request,
+            frontend_base_url,
+            ttl,
+        } = value;
+        let PasswordResetRequest { user, token } = request;
+        Mail {
+            to: user.email.clone(),
+            subject: "パスワードの再設定".into(),
+            body: format!(
+                "{} 様\n\n\
+                 パスワードの再設定を受け付けました。\n\
+                 以下のリンクを開いて、新しいパスワードを設定してください。\n\n\
+                 {}/password/reset?token={}\n\n\
+                 このリンクの有効期限は {} 分です。\n\
+                 お心当たりのない場合は、このメールを破棄してください。\n",
+                user.name,
+                frontend_base_url.trim_end_matches('/'),
+                token.0,
+                ttl / 60
+            ),
+        }
+    }
 }
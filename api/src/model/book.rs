@@ -6,7 +6,7 @@ use kernel::model::{
         event::{CreateBook, UpdateBook},
         Book, BookListOptions, Checkout,
     },
-    id::{BookId, CheckoutId, UserId},
+    id::{BookId, CheckoutId},
     list::PaginatedList,
     user::CheckoutUser,
 };
@@ -64,13 +64,12 @@ pub struct UpdateBookRequest {
 }
 
 #[derive(new)]
-pub struct UpdateBookRequestWithIds(BookId, UserId, UpdateBookRequest);
+pub struct UpdateBookRequestWithId(BookId, UpdateBookRequest);
 
-impl From<UpdateBookRequestWithIds> for UpdateBook {
-    fn from(value: UpdateBookRequestWithIds) -> Self {
-        let UpdateBookRequestWithIds(
+impl From<UpdateBookRequestWithId> for UpdateBook {
+    fn from(value: UpdateBookRequestWithId) -> Self {
+        let UpdateBookRequestWithId(
             book_id,
-            user_id,
             UpdateBookRequest {
                 title,
                 author,
@@ -84,7 +83,6 @@ impl From<UpdateBookRequestWithIds> for UpdateBook {
             author,
             isbn,
             description,
-            requested_user: user_id,
         }
     }
 }
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Days, Duration, NaiveDate, Utc};
+use kernel::model::{calendar::CalendarFeedToken, checkout::Checkout};
+use serde::Serialize;
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarFeedResponse {
+    pub token: String,
+    // カレンダーアプリに登録する URL のパス部分
+    pub path: String,
+}
+
+impl From<CalendarFeedToken> for CalendarFeedResponse {
+    fn from(value: CalendarFeedToken) -> Self {
+        let CalendarFeedToken(token) = value;
+        Self {
+            path: format!("/api/v1/calendar/{}/loans.ics", token),
+            token,
+        }
+    }
+}
+
+// 貸出中の蔵書を、返却期限日の終日イベントとして iCalendar (RFC 5545) 形式で出力する
+pub struct LoanCalendar<'a> {
+    pub checkouts: Vec<Checkout>,
+    pub loan_period_days: i64,
+    pub frontend_base_url: &'a str,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl LoanCalendar<'_> {
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//book-manager//loans//JA".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            format!("X-WR-CALNAME:{}", escape_text("貸出中の書籍の返却期限")),
+        ];
+
+        let dtstamp = self.generated_at.format("%Y%m%dT%H%M%SZ").to_string();
+        for checkout in &self.checkouts {
+            let due_date = self.due_date(checkout);
+            let book = &checkout.book;
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}@book-manager", checkout.id),
+                format!("DTSTAMP:{}", dtstamp),
+                format!("DTSTART;VALUE=DATE:{}", due_date.format("%Y%m%d")),
+                format!(
+                    "DTEND;VALUE=DATE:{}",
+                    due_date
+                        .checked_add_days(Days::new(1))
+                        .unwrap_or(due_date)
+                        .format("%Y%m%d")
+                ),
+                format!(
+                    "SUMMARY:{}",
+                    escape_text(&format!("返却期限: {}", book.title))
+                ),
+                format!(
+                    "DESCRIPTION:{}",
+                    escape_text(&format!(
+                        "{}\n著者: {}\nISBN: {}\n貸出日: {}",
+                        book.title,
+                        book.author,
+                        book.isbn,
+                        checkout.checked_out_at.format("%Y-%m-%d")
+                    ))
+                ),
+                format!(
+                    "URL:{}/books/{}",
+                    self.frontend_base_url.trim_end_matches('/'),
+                    book.book_id
+                ),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines
+            .iter()
+            .map(|line| fold_line(line))
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            + "\r\n"
+    }
+
+    fn due_date(&self, checkout: &Checkout) -> NaiveDate {
+        (checkout.checked_out_at + Duration::days(self.loan_period_days)).date_naive()
+    }
+}
+
+// TEXT 型の値ではバックスラッシュ、セミコロン、カンマ、改行をエスケープする
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// 1行は 75 オクテットまでとし、それを超える場合は CRLF と空白で折り返す
+// マルチバイト文字の途中では折り返さない
+fn fold_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if octets + len > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += len;
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use kernel::model::{
+        checkout::CheckoutBook,
+        id::{BookId, CheckoutId, UserId},
+    };
+
+    use super::*;
+
+    fn unfold(text: &str) -> String {
+        text.replace("\r\n ", "")
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("Rust入門"), "Rust入門");
+        assert_eq!(escape_text("Rust, 第2版"), r"Rust\, 第2版");
+        assert_eq!(escape_text("上巻; 下巻"), r"上巻\; 下巻");
+        assert_eq!(escape_text("1行目\n2行目\r\n3行目"), r"1行目\n2行目\n3行目");
+        // エスケープに使うバックスラッシュ自体を先にエスケープする
+        assert_eq!(escape_text(r"C:\books;"), r"C:\\books\;");
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = "a".repeat(75);
+        assert_eq!(fold_line(&line), line);
+
+        let line = "a".repeat(76);
+        assert_eq!(fold_line(&line), format!("{}\r\n a", "a".repeat(75)));
+
+        // 3 オクテットの文字が 75 オクテット目をまたぐ場合は、その文字の前で折り返す
+        let line = format!("a{}", "あ".repeat(25));
+        assert_eq!(fold_line(&line), format!("a{}\r\n あ", "あ".repeat(24)));
+
+        let line = format!("SUMMARY:{}", "返却期限".repeat(40));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert!(folded.split("\r\n").skip(1).all(|l| l.starts_with(' ')));
+        assert_eq!(unfold(&folded), line);
+    }
+
+    #[test]
+    fn test_render() {
+        let checkout_id = CheckoutId::new();
+        let book_id = BookId::new();
+        let title = format!("Rust; 入門, 第2版 {}", "とても長い書名".repeat(5));
+        let calendar = LoanCalendar {
+            checkouts: vec![Checkout {
+                id: checkout_id,
+                checked_out_by: UserId::new(),
+                checked_out_at: Utc.with_ymd_and_hms(2024, 4, 1, 10, 0, 0).unwrap(),
+                returned_at: None,
+                book: CheckoutBook {
+                    book_id,
+                    title: title.clone(),
+                    author: "山田, 太郎".into(),
+                    isbn: "978-4-00-000000-0".into(),
+                },
+            }],
+            loan_period_days: 14,
+            frontend_base_url: "https://books.example.com/",
+            generated_at: Utc.with_ymd_and_hms(2024, 4, 2, 9, 30, 0).unwrap(),
+        };
+
+        let rendered = calendar.render();
+        assert!(rendered.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(rendered.ends_with("END:VCALENDAR\r\n"));
+        assert!(rendered.split("\r\n").all(|line| line.len() <= 75));
+
+        let lines = unfold(&rendered);
+        let lines = lines.split("\r\n").collect::<Vec<_>>();
+        let escaped_title = format!(r"Rust\; 入門\, 第2版 {}", "とても長い書名".repeat(5));
+        for expected in [
+            format!("UID:{checkout_id}@book-manager"),
+            "DTSTAMP:20240402T093000Z".to_string(),
+            "DTSTART;VALUE=DATE:20240415".to_string(),
+            "DTEND;VALUE=DATE:20240416".to_string(),
+            format!("SUMMARY:返却期限: {escaped_title}"),
+            format!(
+                r"DESCRIPTION:{escaped_title}\n著者: 山田\, 太郎\nISBN: 978-4-00-000000-0\n貸出日: 2024-04-01"
+            ),
+            format!("URL:https://books.example.com/books/{book_id}"),
+        ] {
+            assert!(lines.contains(&expected.as_str()), "{expected}");
+        }
+    }
+}
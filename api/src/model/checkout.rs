@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
+    checkout::{
+        event::{CreateCheckouts, UpdateReturnedBatch},
+        BatchItem, BatchItemStatus, BatchMode, BatchResult, Checkout, CheckoutBook,
+    },
     id::{BookId, CheckoutId, UserId},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[cfg(debug_assertions)]
 use utoipa::ToSchema;
@@ -79,3 +84,189 @@ impl From<CheckoutBook> for CheckoutBookResponse {
         }
     }
 }
+
+// 一度に処理できる件数の上限
+const MAX_BATCH_SIZE: usize = 20;
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCheckoutRequest {
+    #[garde(length(min = 1, max = MAX_BATCH_SIZE))]
+    pub book_ids: Vec<BookId>,
+    // true の場合、失敗した蔵書があっても成功した分の貸出は確定する
+    #[garde(skip)]
+    #[serde(default)]
+    pub best_effort: bool,
+}
+
+#[derive(new)]
+pub struct BatchCheckoutRequestWithUser(pub BatchCheckoutRequest, pub UserId, pub DateTime<Utc>);
+
+impl From<BatchCheckoutRequestWithUser> for CreateCheckouts {
+    fn from(value: BatchCheckoutRequestWithUser) -> Self {
+        let BatchCheckoutRequestWithUser(
+            BatchCheckoutRequest {
+                book_ids,
+                best_effort,
+            },
+            user_id,
+            checked_out_at,
+        ) = value;
+        CreateCheckouts::new(book_ids, user_id, checked_out_at, batch_mode(best_effort))
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReturnRequest {
+    #[garde(length(min = 1, max = MAX_BATCH_SIZE))]
+    pub checkout_ids: Vec<CheckoutId>,
+    // true の場合、失敗した貸出があっても成功した分の返却は確定する
+    #[garde(skip)]
+    #[serde(default)]
+    pub best_effort: bool,
+}
+
+#[derive(new)]
+pub struct BatchReturnRequestWithUser(pub BatchReturnRequest, pub UserId, pub DateTime<Utc>);
+
+impl From<BatchReturnRequestWithUser> for UpdateReturnedBatch {
+    fn from(value: BatchReturnRequestWithUser) -> Self {
+        let BatchReturnRequestWithUser(
+            BatchReturnRequest {
+                checkout_ids,
+                best_effort,
+            },
+            user_id,
+            returned_at,
+        ) = value;
+        UpdateReturnedBatch::new(
+            checkout_ids,
+            user_id,
+            returned_at,
+            batch_mode(best_effort),
+            false,
+        )
+    }
+}
+
+fn batch_mode(best_effort: bool) -> BatchMode {
+    if best_effort {
+        BatchMode::BestEffort
+    } else {
+        BatchMode::AllOrNothing
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum BatchItemStatusResponse {
+    Succeeded,
+    Failed,
+    // 処理自体は成功したが、他の項目の失敗によって取り消された場合
+    RolledBack,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCheckoutResponse {
+    // false の場合、すべての貸出が取り消されている
+    pub committed: bool,
+    pub items: Vec<BatchCheckoutItemResponse>,
+}
+
+impl From<BatchResult<BookId>> for BatchCheckoutResponse {
+    fn from(value: BatchResult<BookId>) -> Self {
+        let BatchResult { committed, items } = value;
+        Self {
+            committed,
+            items: items
+                .into_iter()
+                .map(|BatchItem { key, status }| {
+                    let (status, checkout_id, error) = split_status(status);
+                    BatchCheckoutItemResponse {
+                        book_id: key,
+                        status,
+                        checkout_id,
+                        error,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCheckoutItemResponse {
+    pub book_id: BookId,
+    pub status: BatchItemStatusResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkout_id: Option<CheckoutId>,
+    // 失敗した理由のコード（not_found, unavailable, internal_error）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReturnResponse {
+    // false の場合、すべての返却が取り消されている
+    pub committed: bool,
+    pub items: Vec<BatchReturnItemResponse>,
+}
+
+impl From<BatchResult<CheckoutId>> for BatchReturnResponse {
+    fn from(value: BatchResult<CheckoutId>) -> Self {
+        let BatchResult { committed, items } = value;
+        Self {
+            committed,
+            items: items
+                .into_iter()
+                .map(|BatchItem { key, status }| {
+                    let (status, _, error) = split_status(status);
+                    BatchReturnItemResponse {
+                        checkout_id: key,
+                        status,
+                        error,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReturnItemResponse {
+    pub checkout_id: CheckoutId,
+    pub status: BatchItemStatusResponse,
+    // 失敗した理由のコード（not_found, unavailable, internal_error）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn split_status(
+    status: BatchItemStatus,
+) -> (BatchItemStatusResponse, Option<CheckoutId>, Option<String>) {
+    match status {
+        BatchItemStatus::Succeeded { checkout_id } => {
+            (BatchItemStatusResponse::Succeeded, Some(checkout_id), None)
+        }
+        BatchItemStatus::RolledBack { checkout_id } => {
+            (BatchItemStatusResponse::RolledBack, Some(checkout_id), None)
+        }
+        BatchItemStatus::Failed { reason } => (
+            BatchItemStatusResponse::Failed,
+            None,
+            Some(reason.as_ref().to_string()),
+        ),
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    invitation::{
+        event::{AcceptInvitation, CreateInvitation},
+        Invitation, InvitationToken, IssuedInvitation,
+    },
+    mail::Mail,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+use super::{
+    role::role_from_name,
+    user::{validate_password, PasswordContext},
+};
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInvitationRequest {
+    #[garde(email, length(max = 255))]
+    pub email: String,
+    // 存在しないロール名を指定した場合は 404 を返す
+    #[garde(length(min = 1))]
+    pub role: String,
+}
+
+// 招待するユーザーと、招待したユーザー
+#[derive(new)]
+pub struct CreateInvitationRequestWithInviter(UserId, CreateInvitationRequest);
+
+impl From<CreateInvitationRequestWithInviter> for CreateInvitation {
+    fn from(value: CreateInvitationRequestWithInviter) -> Self {
+        let CreateInvitationRequestWithInviter(invited_by, CreateInvitationRequest { email, role }) =
+            value;
+        Self {
+            email,
+            name: None,
+            role: role_from_name(role),
+            invited_by,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationsResponse {
+    pub items: Vec<InvitationResponse>,
+}
+
+impl From<Vec<Invitation>> for InvitationsResponse {
+    fn from(value: Vec<Invitation>) -> Self {
+        Self {
+            items: value.into_iter().map(InvitationResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationResponse {
+    pub user_id: UserId,
+    pub email: String,
+    pub role: String,
+    pub invited_by: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(value: Invitation) -> Self {
+        let Invitation {
+            user_id,
+            email,
+            role,
+            invited_by,
+            expires_at,
+            created_at,
+        } = value;
+        Self {
+            user_id,
+            email,
+            role: role.as_ref().to_string(),
+            invited_by,
+            expires_at,
+            created_at,
+        }
+    }
+}
+
+// 招待されたメールアドレスは、トークンを確認してから PasswordContext に設定する
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
+pub struct AcceptInvitationRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+    #[garde(custom(validate_password))]
+    pub password: String,
+}
+
+impl From<AcceptInvitationRequest> for AcceptInvitation {
+    fn from(value: AcceptInvitationRequest) -> Self {
+        let AcceptInvitationRequest {
+            token,
+            name,
+            password,
+        } = value;
+        Self {
+            token: InvitationToken(token),
+            name,
+            password,
+        }
+    }
+}
+
+// 招待を受け入れるためのリンクを送るメール
+pub struct InvitationMail<'a> {
+    pub issued: &'a IssuedInvitation,
+    pub frontend_base_url: &'a str,
+    pub ttl: u64,
+}
+
+impl From<InvitationMail<'_>> for Mail {
+    fn from(value: InvitationMail<'_>) -> Self {
+        let InvitationMail {
+            issued,
+            frontend_base_url,
+            ttl,
+        } = value;
+        let IssuedInvitation { invitation, token } = issued;
+        Mail {
+            to: invitation.email.clone(),
+            subject: "蔵書管理アプリケーションへの招待".into(),
+            body: format!(
+                "蔵書管理アプリケーションに招待されました。\n\
+                 以下のリンクを開いて、名前とパスワードを設定してください。\n\n\
+                 {}/invitations/accept?token={}\n\n\
+                 このリンクの有効期限は {} 時間です。\n\
+                 お心当たりのない場合は、このメールを破棄してください。\n",
+                frontend_base_url.trim_end_matches('/'),
+                token.0,
+                ttl / 3600
+            ),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::{BookId, CheckoutId, KioskId, UserId},
+    kiosk::{
+        event::{CreateKioskDevice, KioskScan, UpdateBookBarcode, UpdateUserBadgeCode},
+        KioskAction, KioskDevice, KioskScanResult, RegisteredKioskDevice,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateKioskDeviceRequest {
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+}
+
+impl From<CreateKioskDeviceRequest> for CreateKioskDevice {
+    fn from(value: CreateKioskDeviceRequest) -> Self {
+        let CreateKioskDeviceRequest { name } = value;
+        Self { name }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct KioskDevicesResponse {
+    pub items: Vec<KioskDeviceResponse>,
+}
+
+impl From<Vec<KioskDevice>> for KioskDevicesResponse {
+    fn from(value: Vec<KioskDevice>) -> Self {
+        Self {
+            items: value.into_iter().map(KioskDeviceResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct KioskDeviceResponse {
+    pub id: KioskId,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<KioskDevice> for KioskDeviceResponse {
+    fn from(value: KioskDevice) -> Self {
+        let KioskDevice {
+            id,
+            name,
+            created_at,
+            last_used_at,
+        } = value;
+        Self {
+            id,
+            name,
+            created_at,
+            last_used_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredKioskDeviceResponse {
+    pub device: KioskDeviceResponse,
+    // 端末に設定する秘密情報。再表示はできないため、登録時に控えておく
+    pub secret: String,
+}
+
+impl From<RegisteredKioskDevice> for RegisteredKioskDeviceResponse {
+    fn from(value: RegisteredKioskDevice) -> Self {
+        let RegisteredKioskDevice { device, secret } = value;
+        Self {
+            device: device.into(),
+            secret: secret.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct KioskScanRequest {
+    // 蔵書のバーコードまたは ISBN
+    #[garde(length(min = 1, max = 255))]
+    pub book_code: String,
+    // 利用者カードのコード
+    #[garde(length(min = 1, max = 255))]
+    pub badge_code: String,
+}
+
+#[derive(new)]
+pub struct KioskScanRequestWithKioskId(KioskId, KioskScanRequest, DateTime<Utc>);
+
+impl From<KioskScanRequestWithKioskId> for KioskScan {
+    fn from(value: KioskScanRequestWithKioskId) -> Self {
+        let KioskScanRequestWithKioskId(
+            kiosk_id,
+            KioskScanRequest {
+                book_code,
+                badge_code,
+            },
+            scanned_at,
+        ) = value;
+        KioskScan::new(
+            kiosk_id,
+            book_code.trim().to_string(),
+            badge_code.trim().to_string(),
+            scanned_at,
+        )
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum KioskActionResponse {
+    CheckedOut,
+    Returned,
+}
+
+impl From<KioskAction> for KioskActionResponse {
+    fn from(value: KioskAction) -> Self {
+        match value {
+            KioskAction::CheckedOut => Self::CheckedOut,
+            KioskAction::Returned => Self::Returned,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct KioskScanResponse {
+    pub action: KioskActionResponse,
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub book_title: String,
+    pub user_id: UserId,
+    pub user_name: String,
+}
+
+impl From<KioskScanResult> for KioskScanResponse {
+    fn from(value: KioskScanResult) -> Self {
+        let KioskScanResult {
+            action,
+            checkout_id,
+            book_id,
+            book_title,
+            user_id,
+            user_name,
+        } = value;
+        Self {
+            action: action.into(),
+            checkout_id,
+            book_id,
+            book_title,
+            user_id,
+            user_name,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBookBarcodeRequest {
+    // null を指定するとバーコードの割り当てを解除する
+    #[garde(length(min = 1, max = 255))]
+    pub barcode: Option<String>,
+}
+
+#[derive(new)]
+pub struct UpdateBookBarcodeRequestWithBookId(BookId, UpdateBookBarcodeRequest);
+
+impl From<UpdateBookBarcodeRequestWithBookId> for UpdateBookBarcode {
+    fn from(value: UpdateBookBarcodeRequestWithBookId) -> Self {
+        let UpdateBookBarcodeRequestWithBookId(book_id, UpdateBookBarcodeRequest { barcode }) =
+            value;
+        UpdateBookBarcode::new(book_id, barcode)
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserBadgeCodeRequest {
+    // null を指定すると利用者カードの割り当てを解除する
+    #[garde(length(min = 1, max = 255))]
+    pub badge_code: Option<String>,
+}
+
+#[derive(new)]
+pub struct UpdateUserBadgeCodeRequestWithUserId(UserId, UpdateUserBadgeCodeRequest);
+
+impl From<UpdateUserBadgeCodeRequestWithUserId> for UpdateUserBadgeCode {
+    fn from(value: UpdateUserBadgeCodeRequestWithUserId) -> Self {
+        let UpdateUserBadgeCodeRequestWithUserId(
+            user_id,
+            UpdateUserBadgeCodeRequest { badge_code },
+        ) = value;
+        UpdateUserBadgeCode::new(user_id, badge_code)
+    }
+}
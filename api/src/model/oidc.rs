@@ -0,0 +1,65 @@
+use derive_new::new;
+use garde::Validate;
+use kernel::model::oidc::{OidcAuthorizationRequest, OidcCallback};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcAuthorizationResponse {
+    // ブラウザを遷移させる IdP の認可エンドポイントの URL
+    pub authorization_url: String,
+}
+
+// state を発行したブラウザに保存させる Cookie の名前
+pub const OIDC_BROWSER_BINDING_COOKIE: &str = "oidc_browser_binding";
+
+// スクリプトからは読めないよう HttpOnly にし、シングルサインオンの API にだけ送らせる
+// IdP からのリダイレクトを受けたフロントエンドが API を呼び出す際に送られるよう、SameSite は Lax とする
+pub fn browser_binding_cookie(value: &str, max_age: u64) -> String {
+    format!(
+        "{}={}; Max-Age={}; Path=/auth/oidc; HttpOnly; Secure; SameSite=Lax",
+        OIDC_BROWSER_BINDING_COOKIE, value, max_age
+    )
+}
+
+impl From<OidcAuthorizationRequest> for OidcAuthorizationResponse {
+    fn from(value: OidcAuthorizationRequest) -> Self {
+        let OidcAuthorizationRequest {
+            authorization_url, ..
+        } = value;
+        Self { authorization_url }
+    }
+}
+
+// IdP からリダイレクト URI に付与された code と state をそのまま送る
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcCallbackRequest {
+    #[garde(length(min = 1))]
+    pub code: String,
+    #[garde(length(min = 1))]
+    pub state: String,
+}
+
+// ログインを開始したブラウザの Cookie に保存した値と合わせて扱う
+#[derive(new)]
+pub struct OidcCallbackRequestWithBrowserBinding(String, OidcCallbackRequest);
+
+impl From<OidcCallbackRequestWithBrowserBinding> for OidcCallback {
+    fn from(value: OidcCallbackRequestWithBrowserBinding) -> Self {
+        let OidcCallbackRequestWithBrowserBinding(
+            browser_binding,
+            OidcCallbackRequest { code, state },
+        ) = value;
+        Self {
+            code,
+            state,
+            browser_binding,
+        }
+    }
+}
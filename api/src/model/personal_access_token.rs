@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::{PersonalAccessTokenId, UserId},
+    personal_access_token::{
+        event::CreatePersonalAccessToken, CreatedPersonalAccessToken, PersonalAccessToken, Scope,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub enum ScopeName {
+    #[serde(rename = "books:read")]
+    BooksRead,
+    #[serde(rename = "books:write")]
+    BooksWrite,
+    #[serde(rename = "checkouts:read")]
+    CheckoutsRead,
+    #[serde(rename = "checkouts:write")]
+    CheckoutsWrite,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl From<Scope> for ScopeName {
+    fn from(value: Scope) -> Self {
+        match value {
+            Scope::BooksRead => Self::BooksRead,
+            Scope::BooksWrite => Self::BooksWrite,
+            Scope::CheckoutsRead => Self::CheckoutsRead,
+            Scope::CheckoutsWrite => Self::CheckoutsWrite,
+            Scope::Admin => Self::Admin,
+        }
+    }
+}
+
+impl From<ScopeName> for Scope {
+    fn from(value: ScopeName) -> Self {
+        match value {
+            ScopeName::BooksRead => Self::BooksRead,
+            ScopeName::BooksWrite => Self::BooksWrite,
+            ScopeName::CheckoutsRead => Self::CheckoutsRead,
+            ScopeName::CheckoutsWrite => Self::CheckoutsWrite,
+            ScopeName::Admin => Self::Admin,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePersonalAccessTokenRequest {
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+    #[garde(length(min = 1))]
+    pub scopes: Vec<ScopeName>,
+    // 省略した場合は無期限とする
+    #[garde(skip)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(new)]
+pub struct CreatePersonalAccessTokenRequestWithUserId(UserId, CreatePersonalAccessTokenRequest);
+
+impl From<CreatePersonalAccessTokenRequestWithUserId> for CreatePersonalAccessToken {
+    fn from(value: CreatePersonalAccessTokenRequestWithUserId) -> Self {
+        let CreatePersonalAccessTokenRequestWithUserId(
+            user_id,
+            CreatePersonalAccessTokenRequest {
+                name,
+                scopes,
+                expires_at,
+            },
+        ) = value;
+        // 重複して指定されたスコープは1つにまとめる
+        let scopes = scopes
+            .into_iter()
+            .map(Scope::from)
+            .fold(Vec::new(), |mut scopes, scope| {
+                if !scopes.contains(&scope) {
+                    scopes.push(scope);
+                }
+                scopes
+            });
+        Self {
+            user_id,
+            name,
+            scopes,
+            expires_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalAccessTokensResponse {
+    pub items: Vec<PersonalAccessTokenResponse>,
+}
+
+impl From<Vec<PersonalAccessToken>> for PersonalAccessTokensResponse {
+    fn from(value: Vec<PersonalAccessToken>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(PersonalAccessTokenResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalAccessTokenResponse {
+    pub id: PersonalAccessTokenId,
+    pub name: String,
+    pub scopes: Vec<ScopeName>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PersonalAccessToken> for PersonalAccessTokenResponse {
+    fn from(value: PersonalAccessToken) -> Self {
+        let PersonalAccessToken {
+            id,
+            name,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+            ..
+        } = value;
+        Self {
+            id,
+            name,
+            scopes: scopes.into_iter().map(ScopeName::from).collect(),
+            expires_at,
+            last_used_at,
+            created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedPersonalAccessTokenResponse {
+    pub token: PersonalAccessTokenResponse,
+    // API の呼び出しに使うトークンの値。再表示はできないため、作成時に控えておく
+    pub secret: String,
+}
+
+impl From<CreatedPersonalAccessToken> for CreatedPersonalAccessTokenResponse {
+    fn from(value: CreatedPersonalAccessToken) -> Self {
+        let CreatedPersonalAccessToken { token, secret } = value;
+        Self {
+            token: token.into(),
+            secret: secret.0,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use derive_new::new;
+use garde::Validate;
+use kernel::model::role::{
+    event::{CreateRole, UpdateRolePermissions},
+    Permission, Role, RolePermissions,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub enum PermissionName {
+    #[serde(rename = "book:update:any")]
+    BookUpdateAny,
+    #[serde(rename = "checkout:return:any")]
+    CheckoutReturnAny,
+    #[serde(rename = "kiosk:manage")]
+    KioskManage,
+    #[serde(rename = "statistics:read")]
+    StatisticsRead,
+    #[serde(rename = "user:manage")]
+    UserManage,
+    #[serde(rename = "role:manage")]
+    RoleManage,
+}
+
+impl From<Permission> for PermissionName {
+    fn from(value: Permission) -> Self {
+        match value {
+            Permission::BookUpdateAny => Self::BookUpdateAny,
+            Permission::CheckoutReturnAny => Self::CheckoutReturnAny,
+            Permission::KioskManage => Self::KioskManage,
+            Permission::StatisticsRead => Self::StatisticsRead,
+            Permission::UserManage => Self::UserManage,
+            Permission::RoleManage => Self::RoleManage,
+        }
+    }
+}
+
+impl From<PermissionName> for Permission {
+    fn from(value: PermissionName) -> Self {
+        match value {
+            PermissionName::BookUpdateAny => Self::BookUpdateAny,
+            PermissionName::CheckoutReturnAny => Self::CheckoutReturnAny,
+            PermissionName::KioskManage => Self::KioskManage,
+            PermissionName::StatisticsRead => Self::StatisticsRead,
+            PermissionName::UserManage => Self::UserManage,
+            PermissionName::RoleManage => Self::RoleManage,
+        }
+    }
+}
+
+// 重複して指定された権限は1つにまとめる
+fn into_permissions(names: Vec<PermissionName>) -> Vec<Permission> {
+    names
+        .into_iter()
+        .map(Permission::from)
+        .fold(Vec::new(), |mut permissions, permission| {
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+            permissions
+        })
+}
+
+pub fn role_from_name(name: String) -> Role {
+    Role::from_str(&name).unwrap_or_else(|_| Role::Custom(name))
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RolesResponse {
+    pub items: Vec<RoleResponse>,
+}
+
+impl From<Vec<RolePermissions>> for RolesResponse {
+    fn from(value: Vec<RolePermissions>) -> Self {
+        Self {
+            items: value.into_iter().map(RoleResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RoleResponse {
+    pub name: String,
+    pub built_in: bool,
+    pub permissions: Vec<PermissionName>,
+}
+
+impl From<RolePermissions> for RoleResponse {
+    fn from(value: RolePermissions) -> Self {
+        let RolePermissions {
+            role,
+            built_in,
+            permissions,
+        } = value;
+        Self {
+            name: role.as_ref().to_string(),
+            built_in,
+            permissions: permissions.into_iter().map(PermissionName::from).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRoleRequest {
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+    #[garde(skip)]
+    pub permissions: Vec<PermissionName>,
+}
+
+impl From<CreateRoleRequest> for CreateRole {
+    fn from(value: CreateRoleRequest) -> Self {
+        let CreateRoleRequest { name, permissions } = value;
+        Self {
+            name,
+            permissions: into_permissions(permissions),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRolePermissionsRequest {
+    pub permissions: Vec<PermissionName>,
+}
+
+#[derive(new)]
+pub struct UpdateRolePermissionsRequestWithRole(Role, UpdateRolePermissionsRequest);
+
+impl From<UpdateRolePermissionsRequestWithRole> for UpdateRolePermissions {
+    fn from(value: UpdateRolePermissionsRequestWithRole) -> Self {
+        let UpdateRolePermissionsRequestWithRole(
+            role,
+            UpdateRolePermissionsRequest { permissions },
+        ) = value;
+        Self {
+            role,
+            permissions: into_permissions(permissions),
+        }
+    }
+}
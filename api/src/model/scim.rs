@@ -0,0 +1,786 @@
+use std::str::FromStr;
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    role::Role,
+    user::{User, UserStatus},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::error::{AppError, AppResult};
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+pub const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+// 一度に返すリソース数の上限。これより大きい count を指定された場合は上限まで返す
+const MAX_COUNT: i64 = 200;
+const DEFAULT_COUNT: i64 = 100;
+
+// SCIM のレスポンスは application/scim+json で返す
+pub struct ScimJson<T>(pub StatusCode, pub T);
+
+impl<T: Serialize> IntoResponse for ScimJson<T> {
+    fn into_response(self) -> Response {
+        let ScimJson(status, body) = self;
+        (
+            status,
+            [(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)],
+            Json(body),
+        )
+            .into_response()
+    }
+}
+
+// SCIM のエラー形式（RFC 7644 3.12）で返すためのラッパー
+#[derive(Debug)]
+pub struct ScimError {
+    error: AppError,
+    scim_type: Option<ScimType>,
+}
+
+pub type ScimResult<T> = Result<T, ScimError>;
+
+// エラーの詳細な種類（RFC 7644 3.12 の scimType）。IdP はこの値で再試行するかどうかなどを判断する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScimType {
+    InvalidFilter,
+    Uniqueness,
+}
+
+impl ScimError {
+    pub fn invalid_filter(filter: &str) -> Self {
+        Self {
+            error: AppError::UnprocessableEntity(format!("対応していないフィルタです: {filter}")),
+            scim_type: Some(ScimType::InvalidFilter),
+        }
+    }
+
+    pub fn scim_type(&self) -> Option<ScimType> {
+        self.scim_type
+    }
+}
+
+impl From<AppError> for ScimError {
+    fn from(value: AppError) -> Self {
+        // 一意制約に違反した場合は、IdP が既存のリソースを探せるよう uniqueness を返す
+        let scim_type = match value {
+            AppError::EntityConflict(_) => Some(ScimType::Uniqueness),
+            _ => None,
+        };
+        Self {
+            error: value,
+            scim_type,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimErrorResponse {
+    schemas: Vec<&'static str>,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scim_type: Option<ScimType>,
+    detail: String,
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> Response {
+        let Self { error, scim_type } = self;
+        let detail = error.to_string();
+        // ステータスコードの判定とサーバーエラーのログ出力は AppError に任せる
+        // SCIM では 422 を使わないため、400 として返す
+        let status = match error.into_response().status() {
+            StatusCode::UNPROCESSABLE_ENTITY => StatusCode::BAD_REQUEST,
+            status => status,
+        };
+        ScimJson(
+            status,
+            ScimErrorResponse {
+                schemas: vec![ERROR_SCHEMA],
+                status: status.as_u16().to_string(),
+                scim_type,
+                detail,
+            },
+        )
+        .into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    pub filter: Option<String>,
+    // 1 始まりの開始位置
+    pub start_index: Option<i64>,
+    pub count: Option<i64>,
+}
+
+impl ScimListQuery {
+    // 範囲外の値は、エラーにせず有効な範囲に丸める（RFC 7644 3.4.2.4）
+    pub fn start_index(&self) -> i64 {
+        self.start_index.unwrap_or(1).max(1)
+    }
+
+    pub fn count(&self) -> i64 {
+        self.count.unwrap_or(DEFAULT_COUNT).clamp(0, MAX_COUNT)
+    }
+}
+
+// `属性名 eq "値"` の形式のフィルタのみに対応する
+pub fn eq_filter_value(filter: &str, attributes: &[&str]) -> ScimResult<String> {
+    let invalid = || ScimError::invalid_filter(filter);
+
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (Some(attribute), Some(operator), Some(value)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if !operator.eq_ignore_ascii_case("eq")
+        || !attributes
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(attribute))
+    {
+        return Err(invalid());
+    }
+    value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .map(|value| value.replace("\\\"", "\""))
+        .ok_or_else(invalid)
+}
+
+#[derive(Serialize)]
+#[cfg_attr(
+    debug_assertions,
+    derive(ToSchema),
+    aliases(
+        ScimUserListResponse = ScimListResponse<ScimUserResponse>,
+        ScimGroupListResponse = ScimListResponse<ScimGroupResponse>
+    )
+)]
+pub struct ScimListResponse<T> {
+    schemas: Vec<&'static str>,
+    #[serde(rename = "totalResults")]
+    total_results: i64,
+    #[serde(rename = "startIndex")]
+    start_index: i64,
+    #[serde(rename = "itemsPerPage")]
+    items_per_page: i64,
+    #[serde(rename = "Resources")]
+    resources: Vec<T>,
+}
+
+impl<T> ScimListResponse<T> {
+    pub fn new(total_results: i64, start_index: i64, resources: Vec<T>) -> Self {
+        Self {
+            schemas: vec![LIST_RESPONSE_SCHEMA],
+            total_results,
+            start_index,
+            items_per_page: resources.len() as i64,
+            resources,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    resource_type: &'static str,
+    location: String,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimName {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+}
+
+impl ScimName {
+    fn display(&self) -> Option<String> {
+        self.formatted
+            .clone()
+            .filter(|name| !name.is_empty())
+            .or_else(|| {
+                let name = [&self.given_name, &self.family_name]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some(name).filter(|name| !name.is_empty())
+            })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct ScimEmail {
+    pub value: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+// 主に使うメールアドレス。primary の指定がなければ先頭のものを使う
+fn primary_email(emails: &[ScimEmail]) -> Option<String> {
+    emails
+        .iter()
+        .find(|email| email.primary)
+        .or_else(|| emails.first())
+        .map(|email| email.value.clone())
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct ScimMember {
+    pub value: String,
+    pub display: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserResponse {
+    schemas: Vec<&'static str>,
+    id: UserId,
+    user_name: String,
+    display_name: String,
+    name: ScimName,
+    emails: Vec<ScimEmail>,
+    active: bool,
+    // ユーザーが属するグループ（ロール）。ユーザーは必ず1つのロールを持つ
+    groups: Vec<ScimMember>,
+    meta: ScimMeta,
+}
+
+impl From<User> for ScimUserResponse {
+    fn from(value: User) -> Self {
+        let User {
+            id,
+            name,
+            email,
+            role,
+            status,
+        } = value;
+        Self {
+            schemas: vec![USER_SCHEMA],
+            id,
+            user_name: email.clone(),
+            display_name: name.clone(),
+            name: ScimName {
+                formatted: Some(name),
+                ..Default::default()
+            },
+            emails: vec![ScimEmail {
+                value: email,
+                primary: true,
+            }],
+            active: status == UserStatus::Active,
+            groups: vec![ScimMember {
+                value: role.as_ref().to_string(),
+                display: role.as_ref().to_string(),
+            }],
+            meta: ScimMeta {
+                resource_type: "User",
+                location: format!("/scim/v2/Users/{}", id),
+            },
+        }
+    }
+}
+
+// 作成・置換の際に受け取るユーザー。対応していない属性は無視する
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserRequest {
+    pub user_name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub name: Option<ScimName>,
+    #[serde(default)]
+    pub emails: Vec<ScimEmail>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl From<ScimUserRequest> for ScimUserAttributes {
+    fn from(value: ScimUserRequest) -> Self {
+        let ScimUserRequest {
+            user_name,
+            display_name,
+            name,
+            emails,
+            active,
+        } = value;
+        // 名前の指定がなければ、userName を名前として使う
+        let name = display_name
+            .filter(|name| !name.is_empty())
+            .or_else(|| name.and_then(|name| name.display()))
+            .unwrap_or_else(|| user_name.clone());
+        Self {
+            name,
+            email: primary_email(&emails).unwrap_or(user_name),
+            active,
+        }
+    }
+}
+
+// このアプリケーションで扱うユーザーの属性。作成・置換・パッチのいずれもこの形にしてから反映する
+#[derive(Validate)]
+pub struct ScimUserAttributes {
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+    #[garde(email, length(max = 255))]
+    pub email: String,
+    #[garde(skip)]
+    pub active: bool,
+}
+
+impl From<&User> for ScimUserAttributes {
+    fn from(value: &User) -> Self {
+        Self {
+            name: value.name.clone(),
+            email: value.email.clone(),
+            active: value.status == UserStatus::Active,
+        }
+    }
+}
+
+impl ScimUserAttributes {
+    pub fn apply(&mut self, operation: ScimPatchOperation) -> AppResult<()> {
+        let ScimPatchOperation { op, path, value } = operation;
+        if !op.eq_ignore_ascii_case("add") && !op.eq_ignore_ascii_case("replace") {
+            return Err(unsupported_operation(&op, path.as_deref()));
+        }
+        let value = value.ok_or_else(|| missing_value(&op))?;
+
+        match path {
+            Some(path) => self.set(&path, value),
+            // パスを省略した場合は、属性名をキーとするオブジェクトで値を受け取る
+            None => match value {
+                Value::Object(attributes) => attributes
+                    .into_iter()
+                    .try_for_each(|(path, value)| self.set(&path, value)),
+                _ => Err(AppError::UnprocessableEntity(
+                    "パスを省略する場合は、値にオブジェクトを指定してください".into(),
+                )),
+            },
+        }
+    }
+
+    fn set(&mut self, path: &str, value: Value) -> AppResult<()> {
+        let path = path.to_ascii_lowercase();
+        match path.as_str() {
+            "active" => self.active = parse_bool(&value)?,
+            "username" => self.email = parse_string(&path, value)?,
+            "displayname" | "name.formatted" => self.name = parse_string(&path, value)?,
+            "name" => {
+                if let Some(name) = parse_object::<ScimName>(&path, value)?.display() {
+                    self.name = name;
+                }
+            }
+            "emails" => {
+                if let Some(email) = primary_email(&parse_object::<Vec<ScimEmail>>(&path, value)?) {
+                    self.email = email;
+                }
+            }
+            // emails[type eq "work"].value のように、メールアドレスを1つだけ指定する場合
+            path if path.starts_with("emails[") && path.ends_with("].value") => {
+                self.email = parse_string(path, value)?
+            }
+            // 保存していない属性（externalId や title など）は無視する
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// IdP によっては真偽値を文字列で送ってくるため、どちらも受け付ける
+fn parse_bool(value: &Value) -> AppResult<bool> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        Value::String(value) => value
+            .parse::<bool>()
+            .or_else(|_| value.to_ascii_lowercase().parse::<bool>())
+            .map_err(|_| invalid_value("active")),
+        _ => Err(invalid_value("active")),
+    }
+}
+
+fn parse_string(path: &str, value: Value) -> AppResult<String> {
+    match value {
+        Value::String(value) => Ok(value),
+        _ => Err(invalid_value(path)),
+    }
+}
+
+fn parse_object<T: serde::de::DeserializeOwned>(path: &str, value: Value) -> AppResult<T> {
+    serde_json::from_value(value).map_err(|_| invalid_value(path))
+}
+
+fn invalid_value(path: &str) -> AppError {
+    AppError::UnprocessableEntity(format!("{path} の値が不正です"))
+}
+
+fn missing_value(op: &str) -> AppError {
+    AppError::UnprocessableEntity(format!("{op} の操作には値が必要です"))
+}
+
+fn unsupported_operation(op: &str, path: Option<&str>) -> AppError {
+    AppError::UnprocessableEntity(format!(
+        "対応していない操作です: {op} {}",
+        path.unwrap_or_default()
+    ))
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct ScimPatchRequest {
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct ScimPatchOperation {
+    // IdP によって大文字・小文字が異なるため、比較の際に区別しない
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupResponse {
+    schemas: Vec<&'static str>,
+    // ロール名をそのまま ID として使う
+    id: String,
+    display_name: String,
+    members: Vec<ScimMember>,
+    meta: ScimMeta,
+}
+
+impl ScimGroupResponse {
+    pub fn new(role: &Role, members: Vec<User>) -> Self {
+        let name = role.as_ref().to_string();
+        Self {
+            schemas: vec![GROUP_SCHEMA],
+            meta: ScimMeta {
+                resource_type: "Group",
+                location: format!("/scim/v2/Groups/{}", name),
+            },
+            id: name.clone(),
+            display_name: name,
+            members: members
+                .into_iter()
+                .map(|user| ScimMember {
+                    value: user.id.to_string(),
+                    display: user.name,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupRequest {
+    #[garde(length(min = 1, max = 255))]
+    pub display_name: String,
+    #[garde(skip)]
+    #[serde(default)]
+    pub members: Vec<ScimMemberRef>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct ScimMemberRef {
+    pub value: String,
+}
+
+impl ScimGroupRequest {
+    pub fn member_ids(&self) -> AppResult<Vec<UserId>> {
+        self.members
+            .iter()
+            .map(|member| UserId::from_str(&member.value))
+            .collect()
+    }
+}
+
+// グループのメンバーに対する操作。ロールを割り当てるユーザーの集合として扱う
+#[derive(Debug)]
+pub enum ScimMemberOperation {
+    Add(Vec<UserId>),
+    Remove(Vec<UserId>),
+    RemoveAll,
+    Replace(Vec<UserId>),
+}
+
+impl ScimMemberOperation {
+    pub fn apply_to(self, members: &mut Vec<UserId>) {
+        match self {
+            Self::Add(user_ids) => {
+                for user_id in user_ids {
+                    if !members.contains(&user_id) {
+                        members.push(user_id);
+                    }
+                }
+            }
+            Self::Remove(user_ids) => members.retain(|user_id| !user_ids.contains(user_id)),
+            Self::RemoveAll => members.clear(),
+            Self::Replace(user_ids) => *members = user_ids,
+        }
+    }
+}
+
+impl TryFrom<ScimPatchOperation> for ScimMemberOperation {
+    type Error = ScimError;
+
+    // グループの名前（ロール名）は変更できないため、メンバーの操作のみを受け付ける
+    fn try_from(value: ScimPatchOperation) -> Result<Self, Self::Error> {
+        let ScimPatchOperation { op, path, value } = value;
+        let op_name = op.to_ascii_lowercase();
+
+        // パスを省略した場合は、{"members": [...]} の形式で値を受け取る
+        let (path, value) = match (path, value) {
+            (Some(path), value) => (path, value),
+            (None, Some(Value::Object(mut attributes))) => {
+                ("members".to_string(), attributes.remove("members"))
+            }
+            (None, _) => return Err(missing_value(&op).into()),
+        };
+
+        if path.eq_ignore_ascii_case("members") {
+            let user_ids = value.map(member_ids).transpose()?;
+            return match (op_name.as_str(), user_ids) {
+                ("add", Some(user_ids)) => Ok(Self::Add(user_ids)),
+                ("replace", Some(user_ids)) => Ok(Self::Replace(user_ids)),
+                ("remove", Some(user_ids)) => Ok(Self::Remove(user_ids)),
+                ("remove", None) => Ok(Self::RemoveAll),
+                ("add" | "replace", None) => Err(missing_value(&op).into()),
+                _ => Err(unsupported_operation(&op, Some(&path)).into()),
+            };
+        }
+
+        // members[value eq "ユーザー ID"] の形式で、外すメンバーを指定する場合
+        if op_name == "remove" {
+            if let Some(filter) = path
+                .strip_prefix("members[")
+                .and_then(|filter| filter.strip_suffix(']'))
+            {
+                let user_id = eq_filter_value(filter, &["value"])?;
+                return Ok(Self::Remove(vec![UserId::from_str(&user_id)?]));
+            }
+        }
+
+        Err(unsupported_operation(&op, Some(&path)).into())
+    }
+}
+
+fn member_ids(value: Value) -> AppResult<Vec<UserId>> {
+    parse_object::<Vec<ScimMemberRef>>("members", value)?
+        .iter()
+        .map(|member| UserId::from_str(&member.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn operation(value: Value) -> ScimPatchOperation {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn attributes() -> ScimUserAttributes {
+        ScimUserAttributes {
+            name: "Yamada Taro".into(),
+            email: "taro@example.com".into(),
+            active: true,
+        }
+    }
+
+    #[test]
+    fn test_eq_filter_value() {
+        let attributes = ["userName", "emails.value"];
+
+        assert_eq!(
+            eq_filter_value(r#"userName eq "taro@example.com""#, &attributes).unwrap(),
+            "taro@example.com"
+        );
+        // 属性名と演算子は大文字・小文字を区別しない
+        assert_eq!(
+            eq_filter_value(r#"  username EQ "taro@example.com" "#, &attributes).unwrap(),
+            "taro@example.com"
+        );
+        assert_eq!(
+            eq_filter_value(r#"emails.value eq "a b@example.com""#, &attributes).unwrap(),
+            "a b@example.com"
+        );
+        assert_eq!(
+            eq_filter_value(r#"userName eq "say \"hi\"""#, &attributes).unwrap(),
+            r#"say "hi""#
+        );
+
+        for filter in [
+            "",
+            "userName",
+            r#"userName eq"#,
+            r#"userName eq taro@example.com"#,
+            r#"userName co "taro""#,
+            r#"displayName eq "taro""#,
+            r#"userName eq "taro@example.com" and active eq true"#,
+        ] {
+            let err = eq_filter_value(filter, &attributes).unwrap_err();
+            assert_eq!(err.scim_type(), Some(ScimType::InvalidFilter), "{filter}");
+        }
+    }
+
+    #[test]
+    fn test_scim_type_from_app_error() {
+        let err = ScimError::from(AppError::EntityConflict("conflict".into()));
+        assert_eq!(err.scim_type(), Some(ScimType::Uniqueness));
+
+        let err = ScimError::from(AppError::EntityNotFound("not found".into()));
+        assert_eq!(err.scim_type(), None);
+    }
+
+    #[test]
+    fn test_apply_patch_with_path() {
+        let mut attributes = attributes();
+        for value in [
+            json!({"op": "Replace", "path": "active", "value": "False"}),
+            json!({"op": "replace", "path": "displayName", "value": "Yamada Hanako"}),
+            json!({"op": "add", "path": "emails[type eq \"work\"].value", "value": "hanako@example.com"}),
+            // 保存していない属性は無視する
+            json!({"op": "replace", "path": "title", "value": "Manager"}),
+        ] {
+            attributes.apply(operation(value)).unwrap();
+        }
+
+        assert!(!attributes.active);
+        assert_eq!(attributes.name, "Yamada Hanako");
+        assert_eq!(attributes.email, "hanako@example.com");
+    }
+
+    #[test]
+    fn test_apply_patch_without_path() {
+        let mut attributes = attributes();
+        attributes
+            .apply(operation(json!({
+                "op": "replace",
+                "value": {
+                    "active": false,
+                    "name": {"givenName": "Hanako", "familyName": "Yamada"},
+                    "emails": [
+                        {"value": "other@example.com"},
+                        {"value": "hanako@example.com", "primary": true},
+                    ],
+                },
+            })))
+            .unwrap();
+
+        assert!(!attributes.active);
+        assert_eq!(attributes.name, "Hanako Yamada");
+        assert_eq!(attributes.email, "hanako@example.com");
+    }
+
+    #[test]
+    fn test_apply_invalid_patch() {
+        for value in [
+            json!({"op": "remove", "path": "displayName"}),
+            json!({"op": "replace", "path": "displayName"}),
+            json!({"op": "replace", "path": "active", "value": "yes"}),
+            json!({"op": "replace", "path": "displayName", "value": 1}),
+            json!({"op": "replace", "value": "Yamada Hanako"}),
+        ] {
+            let mut attributes = attributes();
+            assert!(
+                attributes.apply(operation(value.clone())).is_err(),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_member_operations() {
+        let (a, b, c) = (UserId::new(), UserId::new(), UserId::new());
+        let mut members = vec![a, b];
+
+        let add = operation(json!({
+            "op": "add",
+            "path": "members",
+            "value": [{"value": b.to_string()}, {"value": c.to_string()}],
+        }));
+        ScimMemberOperation::try_from(add)
+            .unwrap()
+            .apply_to(&mut members);
+        assert_eq!(members, vec![a, b, c]);
+
+        let remove = operation(json!({
+            "op": "remove",
+            "path": format!("members[value eq \"{a}\"]"),
+        }));
+        ScimMemberOperation::try_from(remove)
+            .unwrap()
+            .apply_to(&mut members);
+        assert_eq!(members, vec![b, c]);
+
+        let replace = operation(json!({
+            "op": "replace",
+            "value": {"members": [{"value": a.to_string()}]},
+        }));
+        ScimMemberOperation::try_from(replace)
+            .unwrap()
+            .apply_to(&mut members);
+        assert_eq!(members, vec![a]);
+
+        let remove_all = operation(json!({"op": "remove", "path": "members"}));
+        ScimMemberOperation::try_from(remove_all)
+            .unwrap()
+            .apply_to(&mut members);
+        assert!(members.is_empty());
+
+        // グループの名前は変更できない
+        let rename = operation(json!({"op": "replace", "path": "displayName", "value": "Staff"}));
+        assert!(ScimMemberOperation::try_from(rename).is_err());
+
+        let invalid_filter = operation(json!({
+            "op": "remove",
+            "path": format!("members[display eq \"{a}\"]"),
+        }));
+        let err = ScimMemberOperation::try_from(invalid_filter).unwrap_err();
+        assert_eq!(err.scim_type(), Some(ScimType::InvalidFilter));
+    }
+}
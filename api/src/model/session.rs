@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{auth::Session, id::SessionId};
+use serde::Serialize;
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponse {
+    pub items: Vec<SessionResponse>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: SessionId,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    // リクエストに使われたアクセストークンのセッションかどうか
+    pub current: bool,
+}
+
+impl SessionsResponse {
+    pub fn new(sessions: Vec<Session>, current: Option<SessionId>) -> Self {
+        let items = sessions
+            .into_iter()
+            .map(|session| {
+                let Session {
+                    id,
+                    user_id: _,
+                    created_at,
+                    last_used_at,
+                    ip_address,
+                    user_agent,
+                } = session;
+                SessionResponse {
+                    id,
+                    created_at,
+                    last_used_at,
+                    ip_address,
+                    user_agent,
+                    current: current == Some(id),
+                }
+            })
+            .collect();
+        Self { items }
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use garde::Validate;
+use kernel::model::user::{event::CreateUser, password::PasswordPolicy};
+use serde::{Deserialize, Serialize};
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+use super::user::{validate_password, PasswordContext};
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStatusResponse {
+    // 管理者がまだおらず、初期設定が必要な場合は true
+    pub required: bool,
+}
+
+#[derive(Clone, Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
+pub struct SetupRequest {
+    #[garde(length(min = 1, max = 255))]
+    pub name: String,
+    #[garde(email)]
+    pub email: String,
+    #[garde(custom(validate_password))]
+    pub password: String,
+}
+
+impl SetupRequest {
+    // 起動時に環境変数から作成する場合にも、同じ条件で入力をチェックする
+    pub fn validate_with(&self, policy: Arc<PasswordPolicy>) -> Result<(), garde::Report> {
+        self.validate(&PasswordContext::new(policy, self.email.clone()))
+    }
+}
+
+impl From<SetupRequest> for CreateUser {
+    fn from(value: SetupRequest) -> Self {
+        let SetupRequest {
+            name,
+            email,
+            password,
+        } = value;
+        Self {
+            name,
+            email,
+            password,
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    id::{BookId, UserId},
+    statistics::{
+        BookLoanCount, BorrowerLoanCount, LibraryStatistics, MonthlyLoanCount, StatisticsBook,
+        StatisticsOptions, Utilization,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticsFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsQuery {
+    #[garde(skip)]
+    pub from: Option<NaiveDate>,
+    #[garde(skip)]
+    pub to: Option<NaiveDate>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub include_borrowers: bool,
+    #[garde(range(min = 1, max = 100))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[garde(skip)]
+    #[serde(default)]
+    pub format: StatisticsFormat,
+}
+
+const DEFAULT_LIMIT: i64 = 10;
+const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+impl From<&StatisticsQuery> for StatisticsOptions {
+    fn from(value: &StatisticsQuery) -> Self {
+        let StatisticsQuery {
+            from,
+            to,
+            include_borrowers,
+            limit,
+            ..
+        } = value;
+        // to に指定された日付はその日の終わりまでを集計対象に含める
+        let start_of = |d: NaiveDate| d.and_time(NaiveTime::MIN).and_utc();
+        Self {
+            from: from.map(start_of),
+            to: to
+                .and_then(|d| d.checked_add_days(Days::new(1)))
+                .map(start_of),
+            include_borrowers: *include_borrowers,
+            limit: *limit,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsResponse {
+    pub most_borrowed_books: Vec<BookLoanCountResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub most_active_borrowers: Option<Vec<BorrowerLoanCountResponse>>,
+    pub average_loan_duration_seconds: Option<f64>,
+    pub loans_per_month: Vec<MonthlyLoanCountResponse>,
+    pub never_borrowed_books: Vec<StatisticsBookResponse>,
+    pub utilization: UtilizationResponse,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl From<LibraryStatistics> for StatisticsResponse {
+    fn from(value: LibraryStatistics) -> Self {
+        let LibraryStatistics {
+            most_borrowed_books,
+            most_active_borrowers,
+            average_loan_duration_seconds,
+            loans_per_month,
+            never_borrowed_books,
+            utilization,
+            generated_at,
+        } = value;
+        Self {
+            most_borrowed_books: most_borrowed_books.into_iter().map(Into::into).collect(),
+            most_active_borrowers: most_active_borrowers
+                .map(|borrowers| borrowers.into_iter().map(Into::into).collect()),
+            average_loan_duration_seconds,
+            loans_per_month: loans_per_month.into_iter().map(Into::into).collect(),
+            never_borrowed_books: never_borrowed_books.into_iter().map(Into::into).collect(),
+            utilization: utilization.into(),
+            generated_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsBookResponse {
+    pub id: BookId,
+    pub title: String,
+    pub author: String,
+}
+
+impl From<StatisticsBook> for StatisticsBookResponse {
+    fn from(value: StatisticsBook) -> Self {
+        let StatisticsBook {
+            book_id,
+            title,
+            author,
+        } = value;
+        Self {
+            id: book_id,
+            title,
+            author,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BookLoanCountResponse {
+    pub book: StatisticsBookResponse,
+    pub loan_count: i64,
+}
+
+impl From<BookLoanCount> for BookLoanCountResponse {
+    fn from(value: BookLoanCount) -> Self {
+        let BookLoanCount { book, loan_count } = value;
+        Self {
+            book: book.into(),
+            loan_count,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowerLoanCountResponse {
+    pub user_id: UserId,
+    pub user_name: String,
+    pub loan_count: i64,
+}
+
+impl From<BorrowerLoanCount> for BorrowerLoanCountResponse {
+    fn from(value: BorrowerLoanCount) -> Self {
+        let BorrowerLoanCount {
+            user_id,
+            user_name,
+            loan_count,
+        } = value;
+        Self {
+            user_id,
+            user_name,
+            loan_count,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyLoanCountResponse {
+    pub month: DateTime<Utc>,
+    pub loan_count: i64,
+}
+
+impl From<MonthlyLoanCount> for MonthlyLoanCountResponse {
+    fn from(value: MonthlyLoanCount) -> Self {
+        let MonthlyLoanCount { month, loan_count } = value;
+        Self { month, loan_count }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UtilizationResponse {
+    pub total_books: i64,
+    pub checked_out_books: i64,
+    pub rate: f64,
+}
+
+impl From<Utilization> for UtilizationResponse {
+    fn from(value: Utilization) -> Self {
+        let rate = value.rate();
+        let Utilization {
+            total_books,
+            checked_out_books,
+        } = value;
+        Self {
+            total_books,
+            checked_out_books,
+            rate,
+        }
+    }
+}
+
+impl StatisticsResponse {
+    // 表計算ソフトで扱いやすいよう、すべての指標を
+    // 「指標名, キー, ラベル, 値」の縦持ちの形式で出力する
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec![["metric", "key", "label", "value"].map(String::from)];
+
+        for item in &self.most_borrowed_books {
+            rows.push([
+                "most_borrowed_books".into(),
+                item.book.id.to_string(),
+                item.book.title.clone(),
+                item.loan_count.to_string(),
+            ]);
+        }
+        for item in self.most_active_borrowers.iter().flatten() {
+            rows.push([
+                "most_active_borrowers".into(),
+                item.user_id.to_string(),
+                item.user_name.clone(),
+                item.loan_count.to_string(),
+            ]);
+        }
+        if let Some(seconds) = self.average_loan_duration_seconds {
+            rows.push([
+                "average_loan_duration_seconds".into(),
+                String::new(),
+                String::new(),
+                seconds.to_string(),
+            ]);
+        }
+        for item in &self.loans_per_month {
+            rows.push([
+                "loans_per_month".into(),
+                item.month.format("%Y-%m").to_string(),
+                String::new(),
+                item.loan_count.to_string(),
+            ]);
+        }
+        for book in &self.never_borrowed_books {
+            rows.push([
+                "never_borrowed_books".into(),
+                book.id.to_string(),
+                book.title.clone(),
+                String::new(),
+            ]);
+        }
+        rows.push([
+            "utilization_rate".into(),
+            String::new(),
+            format!(
+                "{}/{}",
+                self.utilization.checked_out_books, self.utilization.total_books
+            ),
+            self.utilization.rate.to_string(),
+        ]);
+
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|field| escape_csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            + "\r\n"
+    }
+}
+
+fn escape_csv_field(field: &str) -> String {
+    // 書名などが数式として実行されないよう、数式の開始とみなされる文字で始まる場合は ' を前置する
+    // 負の数などの数値はそのまま出力する
+    let field =
+        if field.starts_with(['=', '+', '-', '@', '\t', '\r']) && field.parse::<f64>().is_err() {
+            format!("'{field}")
+        } else {
+            field.to_string()
+        };
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("Rust入門"), "Rust入門");
+        assert_eq!(escape_csv_field(""), "");
+        assert_eq!(escape_csv_field("Rust, 第2版"), "\"Rust, 第2版\"");
+        assert_eq!(escape_csv_field("\"実践\" Rust"), "\"\"\"実践\"\" Rust\"");
+        assert_eq!(escape_csv_field("上巻\n下巻"), "\"上巻\n下巻\"");
+        assert_eq!(escape_csv_field("上巻\r\n下巻"), "\"上巻\r\n下巻\"");
+    }
+
+    #[test]
+    fn test_escape_csv_field_formula() {
+        assert_eq!(escape_csv_field("=1+1"), "'=1+1");
+        assert_eq!(escape_csv_field("+SUM(A1:A2)"), "'+SUM(A1:A2)");
+        assert_eq!(escape_csv_field("-2+3"), "'-2+3");
+        assert_eq!(escape_csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_csv_field("\tcmd"), "'\tcmd");
+        // 前置した上で、区切り文字を含む場合は引用符で囲む
+        assert_eq!(
+            escape_csv_field("=HYPERLINK(\"http://example.com\", \"本\")"),
+            "\"'=HYPERLINK(\"\"http://example.com\"\", \"\"本\"\")\""
+        );
+        // 数値や途中に記号を含む文字列はそのまま出力する
+        assert_eq!(escape_csv_field("-1.5"), "-1.5");
+        assert_eq!(escape_csv_field("0.25"), "0.25");
+        assert_eq!(escape_csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let book = |title: &str| StatisticsBookResponse {
+            id: BookId::new(),
+            title: title.into(),
+            author: "著者".into(),
+        };
+        let borrowed = book("Rust, 第2版");
+        let never_borrowed = book("=cmd|' /C calc'!A0");
+        let user_id = UserId::new();
+        let response = StatisticsResponse {
+            most_borrowed_books: vec![BookLoanCountResponse {
+                book: borrowed,
+                loan_count: 3,
+            }],
+            most_active_borrowers: Some(vec![BorrowerLoanCountResponse {
+                user_id,
+                user_name: "@山田".into(),
+                loan_count: 2,
+            }]),
+            average_loan_duration_seconds: Some(86400.5),
+            loans_per_month: vec![MonthlyLoanCountResponse {
+                month: NaiveDate::from_ymd_opt(2024, 4, 1)
+                    .unwrap()
+                    .and_time(NaiveTime::MIN)
+                    .and_utc(),
+                loan_count: 5,
+            }],
+            never_borrowed_books: vec![never_borrowed],
+            utilization: UtilizationResponse {
+                total_books: 4,
+                checked_out_books: 1,
+                rate: 0.25,
+            },
+            generated_at: Utc::now(),
+        };
+        let borrowed_id = response.most_borrowed_books[0].book.id;
+        let never_borrowed_id = response.never_borrowed_books[0].id;
+
+        let expected = [
+            "metric,key,label,value".to_string(),
+            format!("most_borrowed_books,{borrowed_id},\"Rust, 第2版\",3"),
+            format!("most_active_borrowers,{user_id},'@山田,2"),
+            "average_loan_duration_seconds,,,86400.5".to_string(),
+            "loans_per_month,2024-04,,5".to_string(),
+            format!("never_borrowed_books,{never_borrowed_id},'=cmd|' /C calc'!A0,"),
+            "utilization_rate,,1/4,0.25".to_string(),
+        ]
+        .join("\r\n")
+            + "\r\n";
+        assert_eq!(response.to_csv(), expected);
+    }
+}
@@ -0,0 +1,96 @@
+use garde::Validate;
+use kernel::model::two_factor::{RecoveryCode, TotpEnrollment};
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+use super::auth::AccessTokenResponse;
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrollmentResponse {
+    // 認証アプリに手入力する場合の共有鍵（Base32）
+    pub secret: String,
+    // QR コードにして認証アプリで読み取る URI
+    pub provisioning_uri: String,
+}
+
+impl From<TotpEnrollment> for TotpEnrollmentResponse {
+    fn from(value: TotpEnrollment) -> Self {
+        let TotpEnrollment {
+            secret,
+            provisioning_uri,
+        } = value;
+        Self {
+            secret,
+            provisioning_uri,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCodeRequest {
+    // 認証アプリが生成した 6 桁のコード。無効にする際はリカバリーコードも使える
+    #[garde(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    // 平文で返すのはこのときだけのため、ユーザーに控えてもらう
+    pub recovery_codes: Vec<String>,
+}
+
+impl From<Vec<RecoveryCode>> for RecoveryCodesResponse {
+    fn from(value: Vec<RecoveryCode>) -> Self {
+        Self {
+            recovery_codes: value.into_iter().map(|code| code.0).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorLoginRequest {
+    #[garde(length(min = 1))]
+    pub two_factor_challenge: String,
+    // TOTP のコードかリカバリーコード
+    #[garde(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorLoginResponse {
+    #[serde(flatten)]
+    pub token: AccessTokenResponse,
+    // ログインの途中で TOTP を登録した場合のみ、発行したリカバリーコードを返す
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recovery_codes: Vec<String>,
+}
+
+// 2 段階認証が有効なユーザーには、アクセストークンの代わりにチャレンジを返す
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorRequiredResponse {
+    pub two_factor_challenge: String,
+}
+
+// TOTP の登録が必須の管理者には、チャレンジとあわせて登録に必要な情報を返す
+// 認証アプリに登録して生成したコードで 2 段階目の認証を行うと、登録が完了する
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorEnrollmentRequiredResponse {
+    pub two_factor_challenge: String,
+    pub enrollment: TotpEnrollmentResponse,
+}
@@ -1,48 +1,252 @@
+use std::sync::Arc;
+
 use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     id::UserId,
-    role::Role,
+    list::PaginatedList,
+    mail::Mail,
+    password_reset::event::ForcePasswordReset,
     user::{
-        event::{CreateUser, UpdateUserPassword, UpdateUserRole},
-        User,
+        event::{
+            CreateUser, UpdateLoanStatisticsSharing, UpdateUserPassword, UpdateUserProfile,
+            UpdateUserRole, UpdateUserStatus,
+        },
+        password::PasswordPolicy,
+        PendingEmailChange, User, UserListOptions, UserSort, UserStatus,
     },
 };
 use serde::{Deserialize, Serialize};
-use strum::VariantNames;
 #[cfg(debug_assertions)]
 use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, VariantNames)]
-#[strum(serialize_all = "kebab-case")]
-pub enum RoleName {
-    Admin,
-    User,
+use super::role::role_from_name;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortName {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+    NameAsc,
+    NameDesc,
+    EmailAsc,
+    EmailDesc,
+}
+
+impl UserSortName {
+    pub fn uses_email(self) -> bool {
+        matches!(self, Self::EmailAsc | Self::EmailDesc)
+    }
 }
 
-impl From<Role> for RoleName {
-    fn from(value: Role) -> Self {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatusName {
+    Invited,
+    Active,
+    Suspended,
+    Deactivated,
+}
+
+impl From<UserStatus> for UserStatusName {
+    fn from(value: UserStatus) -> Self {
         match value {
-            Role::Admin => Self::Admin,
-            Role::User => Self::User,
+            UserStatus::Invited => Self::Invited,
+            UserStatus::Active => Self::Active,
+            UserStatus::Suspended => Self::Suspended,
+            UserStatus::Deactivated => Self::Deactivated,
         }
     }
 }
 
-impl From<RoleName> for Role {
-    fn from(value: RoleName) -> Self {
+impl From<UserStatusName> for UserStatus {
+    fn from(value: UserStatusName) -> Self {
         match value {
-            RoleName::Admin => Role::Admin,
-            RoleName::User => Role::User,
+            UserStatusName::Invited => Self::Invited,
+            UserStatusName::Active => Self::Active,
+            UserStatusName::Suspended => Self::Suspended,
+            UserStatusName::Deactivated => Self::Deactivated,
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+impl From<UserSortName> for UserSort {
+    fn from(value: UserSortName) -> Self {
+        match value {
+            UserSortName::CreatedAtDesc => Self::CreatedAtDesc,
+            UserSortName::CreatedAtAsc => Self::CreatedAtAsc,
+            UserSortName::NameAsc => Self::NameAsc,
+            UserSortName::NameDesc => Self::NameDesc,
+            UserSortName::EmailAsc => Self::EmailAsc,
+            UserSortName::EmailDesc => Self::EmailDesc,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+pub struct UserListQuery {
+    #[garde(range(min = 0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[garde(range(min = 0))]
+    #[serde(default)]
+    pub offset: i64,
+    // 名前（user:manage 権限を持つ場合はメールアドレスも）の部分一致で絞り込む
+    #[garde(length(min = 1, max = 255))]
+    pub q: Option<String>,
+    #[garde(length(min = 1))]
+    pub role: Option<String>,
+    #[garde(skip)]
+    pub status: Option<UserStatusName>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub sort: UserSortName,
+}
+
+const DEFAULT_LIMIT: i64 = 20;
+const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+// 一覧を取得するユーザーが、メールアドレスやロールを参照できるかどうか
+#[derive(new)]
+pub struct UserListQueryWithVisibility(bool, UserListQuery);
+
+impl From<UserListQueryWithVisibility> for UserListOptions {
+    fn from(value: UserListQueryWithVisibility) -> Self {
+        let UserListQueryWithVisibility(
+            full_profile,
+            UserListQuery {
+                limit,
+                offset,
+                q,
+                role,
+                status,
+                sort,
+            },
+        ) = value;
+        // メールアドレスを参照できない場合は、並び順からメールアドレスを推測されないよう名前順にする
+        let sort = match (full_profile, UserSort::from(sort)) {
+            (false, UserSort::EmailAsc) => UserSort::NameAsc,
+            (false, UserSort::EmailDesc) => UserSort::NameDesc,
+            (_, sort) => sort,
+        };
+        Self {
+            limit,
+            offset,
+            keyword: q,
+            search_email: full_profile,
+            role: role.map(role_from_name),
+            status: status.map(UserStatus::from),
+            sort,
+        }
+    }
+}
+
+#[derive(Serialize)]
 #[cfg_attr(debug_assertions, derive(ToSchema))]
 #[serde(rename_all = "camelCase")]
-pub struct UsersResponse {
-    pub items: Vec<UserResponse>,
+pub struct PaginatedUserResponse {
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub items: Vec<UserDirectoryResponse>,
+}
+
+impl PaginatedUserResponse {
+    // full_profile が false の場合は、各ユーザーの公開プロフィールのみを返す
+    pub fn new(list: PaginatedList<User>, full_profile: bool) -> Self {
+        let PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        } = list;
+        Self {
+            total,
+            limit,
+            offset,
+            items: items
+                .into_iter()
+                .map(|user| {
+                    if full_profile {
+                        UserDirectoryResponse::from(user)
+                    } else {
+                        UserDirectoryResponse::public(user)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UserDirectoryResponse {
+    pub id: UserId,
+    pub name: String,
+    // 公開プロフィールには含めない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<UserStatusName>,
+}
+
+// 無効化したユーザーは、名前も含めて一覧には表示しない
+const DEACTIVATED_USER_NAME: &str = "退会したユーザー";
+
+impl UserDirectoryResponse {
+    pub fn public(user: User) -> Self {
+        let User {
+            id, name, status, ..
+        } = user;
+        let name = if status == UserStatus::Deactivated {
+            DEACTIVATED_USER_NAME.into()
+        } else {
+            name
+        };
+        Self {
+            id,
+            name,
+            email: None,
+            role: None,
+            status: None,
+        }
+    }
+}
+
+impl From<User> for UserDirectoryResponse {
+    // 管理者にも、無効化したユーザーは状態のみを返す
+    fn from(value: User) -> Self {
+        let status = value.status;
+        if status == UserStatus::Deactivated {
+            return Self {
+                status: Some(status.into()),
+                ..Self::public(value)
+            };
+        }
+        let User {
+            id,
+            name,
+            email,
+            role,
+            ..
+        } = value;
+        Self {
+            id,
+            name,
+            email: Some(email),
+            role: Some(role.as_ref().to_string()),
+            status: Some(status.into()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -52,7 +256,9 @@ pub struct UserResponse {
     pub id: UserId,
     pub name: String,
     pub email: String,
-    pub role: RoleName,
+    // ロールは追加できるため、ロール名をそのまま返す
+    pub role: String,
+    pub status: UserStatusName,
 }
 
 impl From<User> for UserResponse {
@@ -62,12 +268,14 @@ impl From<User> for UserResponse {
             name,
             email,
             role,
+            status,
         } = value;
         Self {
             id,
             name,
             email,
-            role: RoleName::from(role),
+            role: role.as_ref().to_string(),
+            status: status.into(),
         }
     }
 }
@@ -75,10 +283,117 @@ impl From<User> for UserResponse {
 #[derive(Deserialize, Validate)]
 #[cfg_attr(debug_assertions, derive(ToSchema))]
 #[serde(rename_all = "camelCase")]
+pub struct UpdateUserProfileRequest {
+    #[garde(length(min = 1, max = 255))]
+    name: String,
+    #[garde(email, length(max = 255))]
+    email: String,
+}
+
+#[derive(new)]
+pub struct UpdateUserProfileRequestWithUserId(UserId, UpdateUserProfileRequest);
+
+impl From<UpdateUserProfileRequestWithUserId> for UpdateUserProfile {
+    fn from(value: UpdateUserProfileRequestWithUserId) -> Self {
+        let UpdateUserProfileRequestWithUserId(user_id, UpdateUserProfileRequest { name, email }) =
+            value;
+        UpdateUserProfile {
+            user_id,
+            name,
+            email,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateLoanStatisticsSharingRequest {
+    // 利用統計の「よく借りている利用者」に自分を含めてよいかどうか
+    share: bool,
+}
+
+#[derive(new)]
+pub struct UpdateLoanStatisticsSharingRequestWithUserId(UserId, UpdateLoanStatisticsSharingRequest);
+
+impl From<UpdateLoanStatisticsSharingRequestWithUserId> for UpdateLoanStatisticsSharing {
+    fn from(value: UpdateLoanStatisticsSharingRequestWithUserId) -> Self {
+        let UpdateLoanStatisticsSharingRequestWithUserId(
+            user_id,
+            UpdateLoanStatisticsSharingRequest { share },
+        ) = value;
+        UpdateLoanStatisticsSharing { user_id, share }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChangeRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+}
+
+// メールアドレスの変更を確定するためのリンクを、新しいアドレスへ送るメール
+pub struct EmailChangeMail<'a> {
+    pub pending: &'a PendingEmailChange,
+    pub frontend_base_url: &'a str,
+    pub ttl: u64,
+}
+
+impl From<EmailChangeMail<'_>> for Mail {
+    fn from(value: EmailChangeMail<'_>) -> Self {
+        let EmailChangeMail {
+            pending,
+            frontend_base_url,
+            ttl,
+        } = value;
+        let PendingEmailChange {
+            user,
+            new_email,
+            token,
+        } = pending;
+        Mail {
+            to: new_email.clone(),
+            subject: "メールアドレス変更の確認".into(),
+            body: format!(
+                "{} 様\n\n\
+                 蔵書管理アプリケーションに登録されたメールアドレスの変更を受け付けました。\n\
+                 以下のリンクを開いて、変更を完了してください。\n\n\
+                 {}/email-change/confirm?token={}\n\n\
+                 このリンクの有効期限は {} 時間です。\n\
+                 お心当たりのない場合は、このメールを破棄してください。\n",
+                user.name,
+                frontend_base_url.trim_end_matches('/'),
+                token.0,
+                ttl / 3600
+            ),
+        }
+    }
+}
+
+// 新しいパスワードの検証に使う、パスワードの条件と対象のユーザーのメールアドレス
+#[derive(new)]
+pub struct PasswordContext {
+    policy: Arc<PasswordPolicy>,
+    email: String,
+}
+
+pub fn validate_password(value: &str, context: &PasswordContext) -> garde::Result {
+    context
+        .policy
+        .check(value, &context.email)
+        .map_err(garde::Error::new)
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
 pub struct UpdateUserPasswordRequest {
     #[garde(length(min = 1))]
     current_password: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_password))]
     new_password: String,
 }
 
@@ -105,15 +420,46 @@ impl From<UpdateUserPasswordRequestWithUserId> for UpdateUserPassword {
 #[derive(Deserialize, Validate)]
 #[cfg_attr(debug_assertions, derive(ToSchema))]
 #[serde(rename_all = "camelCase")]
+pub struct ForcePasswordResetRequest {
+    #[garde(length(min = 1))]
+    temporary_password: String,
+}
+
+#[derive(new)]
+pub struct ForcePasswordResetRequestWithUserId(UserId, ForcePasswordResetRequest);
+
+impl From<ForcePasswordResetRequestWithUserId> for ForcePasswordReset {
+    fn from(value: ForcePasswordResetRequestWithUserId) -> Self {
+        let ForcePasswordResetRequestWithUserId(
+            user_id,
+            ForcePasswordResetRequest { temporary_password },
+        ) = value;
+        ForcePasswordReset {
+            user_id,
+            temporary_password,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[garde(context(PasswordContext))]
 pub struct CreateUserRequest {
     #[garde(length(min = 1))]
     name: String,
     #[garde(email)]
     email: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_password))]
     password: String,
 }
 
+impl CreateUserRequest {
+    pub fn password_context(&self, policy: Arc<PasswordPolicy>) -> PasswordContext {
+        PasswordContext::new(policy, self.email.clone())
+    }
+}
+
 impl From<CreateUserRequest> for CreateUser {
     fn from(value: CreateUserRequest) -> Self {
         let CreateUserRequest {
@@ -129,11 +475,13 @@ impl From<CreateUserRequest> for CreateUser {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 #[cfg_attr(debug_assertions, derive(ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserRoleRequest {
-    role: RoleName,
+    // 存在しないロール名を指定した場合は 404 を返す
+    #[garde(length(min = 1))]
+    role: String,
 }
 
 #[derive(new)]
@@ -144,7 +492,35 @@ impl From<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
         let UpdateUserRoleRequestWithUserId(user_id, UpdateUserRoleRequest { role }) = value;
         Self {
             user_id,
-            role: Role::from(role),
+            role: role_from_name(role),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserStatusRequest {
+    // 変更できる状態は、現在の状態によって決まる
+    #[garde(skip)]
+    status: UserStatusName,
+}
+
+// 状態を変更するユーザーと、変更を操作したユーザー
+#[derive(new)]
+pub struct UpdateUserStatusRequestWithUserIds(UserId, UserId, UpdateUserStatusRequest);
+
+impl From<UpdateUserStatusRequestWithUserIds> for UpdateUserStatus {
+    fn from(value: UpdateUserStatusRequestWithUserIds) -> Self {
+        let UpdateUserStatusRequestWithUserIds(
+            user_id,
+            changed_by,
+            UpdateUserStatusRequest { status },
+        ) = value;
+        Self {
+            user_id,
+            status: status.into(),
+            changed_by: Some(changed_by),
         }
     }
 }
@@ -176,3 +552,46 @@ impl From<kernel::model::user::CheckoutUser> for CheckoutUser {
         Self { id, name }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(full_profile: bool, sort: UserSortName) -> UserListOptions {
+        let query = UserListQuery {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+            q: None,
+            role: None,
+            status: None,
+            sort,
+        };
+        UserListQueryWithVisibility::new(full_profile, query).into()
+    }
+
+    #[test]
+    fn test_sort_by_email_requires_full_profile() {
+        assert_eq!(
+            options(true, UserSortName::EmailAsc).sort,
+            UserSort::EmailAsc
+        );
+        assert_eq!(
+            options(true, UserSortName::EmailDesc).sort,
+            UserSort::EmailDesc
+        );
+
+        assert_eq!(
+            options(false, UserSortName::EmailAsc).sort,
+            UserSort::NameAsc
+        );
+        assert_eq!(
+            options(false, UserSortName::EmailDesc).sort,
+            UserSort::NameDesc
+        );
+        assert_eq!(
+            options(false, UserSortName::CreatedAtAsc).sort,
+            UserSort::CreatedAtAsc
+        );
+        assert!(!options(false, UserSortName::NameAsc).search_email);
+    }
+}
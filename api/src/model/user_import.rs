@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    invitation::{event::CreateInvitation, IssuedInvitation},
+    role::Role,
+};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+#[cfg(debug_assertions)]
+use utoipa::ToSchema;
+
+use super::role::role_from_name;
+
+// 一度に取り込めるユーザー数の上限
+const MAX_IMPORT_ROWS: usize = 500;
+
+#[derive(Deserialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportQuery {
+    // true の場合、検証結果のみを返し、ユーザーは作成しない
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// CSV の1行分。ロールの列は省略でき、空欄の場合は User として扱う
+#[derive(Deserialize, Validate)]
+struct UserImportRecord {
+    #[garde(length(min = 1, max = 255))]
+    name: String,
+    #[garde(email, length(max = 255))]
+    email: String,
+    #[garde(skip)]
+    #[serde(default)]
+    role: Option<String>,
+}
+
+// 取り込む行と、検証で見つかった問題
+pub struct UserImportItem {
+    // ヘッダー行を1行目とした行番号
+    pub row: usize,
+    pub name: String,
+    pub email: String,
+    pub role: Role,
+    pub errors: Vec<String>,
+}
+
+impl UserImportItem {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn into_event(self, invited_by: UserId) -> CreateInvitation {
+        CreateInvitation {
+            email: self.email,
+            name: Some(self.name),
+            role: self.role,
+            invited_by,
+        }
+    }
+}
+
+// name,email,role のヘッダーを持つ CSV を読み込み、行ごとの入力チェックとファイル内の重複チェックを行う
+// ヘッダーが不正な場合など、ファイル全体を読み込めない場合のみエラーを返す
+pub fn parse_user_import(body: &str) -> AppResult<Vec<UserImportItem>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::UnprocessableEntity(format!("CSV を読み込めませんでした: {e}")))?;
+    if !["name", "email"]
+        .iter()
+        .all(|column| headers.iter().any(|header| header == *column))
+    {
+        return Err(AppError::UnprocessableEntity(
+            "CSV のヘッダーには name と email の列が必要です".into(),
+        ));
+    }
+
+    let mut items = Vec::new();
+    // 小文字にしたメールアドレスと、最初に現れた行番号
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (index, record) in reader.deserialize::<UserImportRecord>().enumerate() {
+        let row = index + 2;
+        if items.len() >= MAX_IMPORT_ROWS {
+            return Err(AppError::UnprocessableEntity(format!(
+                "一度に取り込めるユーザーは {} 件までです",
+                MAX_IMPORT_ROWS
+            )));
+        }
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                items.push(UserImportItem {
+                    row,
+                    name: String::new(),
+                    email: String::new(),
+                    role: Role::User,
+                    errors: vec![format!("行を読み込めませんでした: {e}")],
+                });
+                continue;
+            }
+        };
+
+        let mut errors = match record.validate(&()) {
+            Ok(()) => Vec::new(),
+            Err(report) => report
+                .iter()
+                .map(|(path, error)| format!("{path}: {error}"))
+                .collect(),
+        };
+        if let Some(first_row) = seen.get(&record.email.to_lowercase()) {
+            errors.push(format!(
+                "メールアドレスが {} 行目と重複しています",
+                first_row
+            ));
+        } else {
+            seen.insert(record.email.to_lowercase(), row);
+        }
+
+        let role = match record.role {
+            Some(role) if !role.is_empty() => role_from_name(role),
+            _ => Role::User,
+        };
+        items.push(UserImportItem {
+            row,
+            name: record.name,
+            email: record.email,
+            role,
+            errors,
+        });
+    }
+
+    if items.is_empty() {
+        return Err(AppError::UnprocessableEntity(
+            "取り込むユーザーが含まれていません".into(),
+        ));
+    }
+    Ok(items)
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum UserImportItemStatus {
+    // 問題はないが、ドライランまたは他の行の問題によって作成しなかった場合
+    Valid,
+    Invalid,
+    Invited,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportResponse {
+    pub dry_run: bool,
+    // false の場合、ユーザーは1人も作成されていない
+    pub committed: bool,
+    pub items: Vec<UserImportItemResponse>,
+}
+
+impl UserImportResponse {
+    // 作成しなかった場合の結果
+    pub fn not_committed(dry_run: bool, items: Vec<UserImportItem>) -> Self {
+        Self {
+            dry_run,
+            committed: false,
+            items: items
+                .into_iter()
+                .map(|item| {
+                    let status = if item.is_valid() {
+                        UserImportItemStatus::Valid
+                    } else {
+                        UserImportItemStatus::Invalid
+                    };
+                    UserImportItemResponse {
+                        row: item.row,
+                        email: item.email,
+                        status,
+                        user_id: None,
+                        errors: item.errors,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    // 作成した招待は、取り込んだ行と同じ順に並んでいる
+    pub fn committed(rows: Vec<usize>, issued: &[IssuedInvitation]) -> Self {
+        Self {
+            dry_run: false,
+            committed: true,
+            items: rows
+                .into_iter()
+                .zip(issued)
+                .map(
+                    |(row, IssuedInvitation { invitation, .. })| UserImportItemResponse {
+                        row,
+                        email: invitation.email.clone(),
+                        status: UserImportItemStatus::Invited,
+                        user_id: Some(invitation.user_id),
+                        errors: Vec::new(),
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(debug_assertions, derive(ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportItemResponse {
+    pub row: usize,
+    pub email: String,
+    pub status: UserImportItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<UserId>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
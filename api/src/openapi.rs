@@ -26,10 +26,76 @@ Rust でしたいと思った際に、どのようなクレートや実装を利
         handler::book::delete_book,
         handler::checkout::checkout_book,
         handler::checkout::return_book,
+        handler::checkout::checkout_books,
+        handler::checkout::return_books,
         handler::checkout::checkout_history,
+        handler::user::list_users,
         handler::user::get_current_user,
+        handler::user::update_my_profile,
+        handler::user::update_my_loan_statistics_sharing,
+        handler::user::update_user_profile,
+        handler::user::update_user_status,
+        handler::user::force_password_reset,
+        handler::user::unlock_user,
+        handler::user_import::import_users,
+        handler::statistics::show_statistics,
+        handler::calendar::issue_calendar_feed_token,
+        handler::calendar::revoke_calendar_feed_token,
+        handler::calendar::show_loan_calendar,
+        handler::kiosk::register_kiosk_device,
+        handler::kiosk::list_kiosk_devices,
+        handler::kiosk::delete_kiosk_device,
+        handler::kiosk::scan,
+        handler::kiosk::update_book_barcode,
+        handler::kiosk::update_user_badge_code,
+        handler::two_factor::begin_totp_enrollment,
+        handler::two_factor::activate_totp,
+        handler::two_factor::disable_totp,
+        handler::personal_access_token::create_my_token,
+        handler::personal_access_token::list_my_tokens,
+        handler::personal_access_token::delete_my_token,
+        handler::session::list_my_sessions,
+        handler::session::delete_my_session,
+        handler::session::delete_my_other_sessions,
+        handler::session::list_user_sessions,
+        handler::session::delete_user_session,
+        handler::session::delete_user_sessions,
+        handler::role::list_roles,
+        handler::role::create_role,
+        handler::role::update_role_permissions,
+        handler::role::delete_role,
+        handler::invitation::create_invitation,
+        handler::invitation::list_invitations,
+        handler::invitation::resend_invitation,
+        handler::invitation::revoke_invitation,
+        handler::invitation::accept_invitation,
         handler::auth::login,
+        handler::auth::login_two_factor,
+        handler::oidc::oidc_authorize,
+        handler::oidc::oidc_callback,
+        handler::auth::refresh,
         handler::auth::logout,
+        handler::auth::signup,
+        handler::auth::verify_email,
+        handler::auth::confirm_email_change,
+        handler::auth::forgot_password,
+        handler::auth::reset_password,
+        handler::auth::jwks,
+        handler::setup::get_setup_status,
+        handler::setup::setup,
+        handler::scim::show_service_provider_config,
+        handler::scim::list_scim_users,
+        handler::scim::show_scim_user,
+        handler::scim::create_scim_user,
+        handler::scim::replace_scim_user,
+        handler::scim::patch_scim_user,
+        handler::scim::delete_scim_user,
+        handler::scim::list_scim_groups,
+        handler::scim::show_scim_group,
+        handler::scim::create_scim_group,
+        handler::scim::replace_scim_group,
+        handler::scim::patch_scim_group,
+        handler::scim::delete_scim_group,
     ),
     components(schemas(
         model::book::CreateBookRequest,
@@ -40,13 +106,100 @@ Rust でしたいと思った際に、どのようなクレートや実装を利
         model::checkout::CheckoutsResponse,
         model::checkout::CheckoutResponse,
         model::checkout::CheckoutBookResponse,
+        model::checkout::BatchCheckoutRequest,
+        model::checkout::BatchReturnRequest,
+        model::checkout::BatchItemStatusResponse,
+        model::checkout::BatchCheckoutResponse,
+        model::checkout::BatchCheckoutItemResponse,
+        model::checkout::BatchReturnResponse,
+        model::checkout::BatchReturnItemResponse,
         model::user::BookOwner,
         model::user::CheckoutUser,
+        model::user::ForcePasswordResetRequest,
+        model::user::UserSortName,
+        model::user::PaginatedUserResponse,
+        model::user::UserDirectoryResponse,
+        model::user::UserStatusName,
+        model::user::UpdateUserStatusRequest,
+        model::user_import::UserImportResponse,
+        model::user_import::UserImportItemResponse,
+        model::user_import::UserImportItemStatus,
+        model::user::UpdateUserProfileRequest,
+        model::user::UpdateLoanStatisticsSharingRequest,
+        model::user::ConfirmEmailChangeRequest,
         model::auth::LoginRequest,
         model::auth::AccessTokenResponse,
+        model::auth::RefreshTokenRequest,
+        model::auth::JwksResponse,
+        model::auth::JwkResponse,
+        model::auth::SignupRequest,
+        model::auth::VerifyEmailRequest,
+        model::auth::LoginResponse,
+        model::auth::PasswordChangeRequiredResponse,
+        model::auth::ForgotPasswordRequest,
+        model::auth::ResetPasswordRequest,
+        model::calendar::CalendarFeedResponse,
+        model::kiosk::CreateKioskDeviceRequest,
+        model::kiosk::KioskDevicesResponse,
+        model::kiosk::KioskDeviceResponse,
+        model::kiosk::RegisteredKioskDeviceResponse,
+        model::kiosk::KioskScanRequest,
+        model::kiosk::KioskActionResponse,
+        model::kiosk::KioskScanResponse,
+        model::kiosk::UpdateBookBarcodeRequest,
+        model::kiosk::UpdateUserBadgeCodeRequest,
+        model::two_factor::TotpEnrollmentResponse,
+        model::two_factor::TotpCodeRequest,
+        model::two_factor::RecoveryCodesResponse,
+        model::two_factor::TwoFactorLoginRequest,
+        model::two_factor::TwoFactorLoginResponse,
+        model::two_factor::TwoFactorRequiredResponse,
+        model::two_factor::TwoFactorEnrollmentRequiredResponse,
+        model::oidc::OidcAuthorizationResponse,
+        model::oidc::OidcCallbackRequest,
+        model::personal_access_token::ScopeName,
+        model::personal_access_token::CreatePersonalAccessTokenRequest,
+        model::personal_access_token::PersonalAccessTokensResponse,
+        model::personal_access_token::PersonalAccessTokenResponse,
+        model::personal_access_token::CreatedPersonalAccessTokenResponse,
+        model::role::PermissionName,
+        model::role::RolesResponse,
+        model::invitation::CreateInvitationRequest,
+        model::invitation::InvitationsResponse,
+        model::invitation::InvitationResponse,
+        model::invitation::AcceptInvitationRequest,
+        model::role::RoleResponse,
+        model::setup::SetupStatusResponse,
+        model::setup::SetupRequest,
+        model::scim::ScimUserListResponse,
+        model::scim::ScimGroupListResponse,
+        model::scim::ScimUserResponse,
+        model::scim::ScimUserRequest,
+        model::scim::ScimName,
+        model::scim::ScimEmail,
+        model::scim::ScimMember,
+        model::scim::ScimMeta,
+        model::scim::ScimPatchRequest,
+        model::scim::ScimPatchOperation,
+        model::scim::ScimGroupResponse,
+        model::scim::ScimGroupRequest,
+        model::scim::ScimMemberRef,
+        model::role::CreateRoleRequest,
+        model::role::UpdateRolePermissionsRequest,
+        model::session::SessionsResponse,
+        model::session::SessionResponse,
+        model::statistics::StatisticsResponse,
+        model::statistics::StatisticsBookResponse,
+        model::statistics::BookLoanCountResponse,
+        model::statistics::BorrowerLoanCountResponse,
+        model::statistics::MonthlyLoanCountResponse,
+        model::statistics::UtilizationResponse,
         kernel::model::id::BookId,
         kernel::model::id::UserId,
         kernel::model::id::CheckoutId,
+        kernel::model::id::KioskId,
+        kernel::model::id::SessionId,
+        kernel::model::id::PersonalAccessTokenId,
     ))
 )]
 pub struct ApiDoc;
\ No newline at end of file
@@ -1,11 +1,35 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 use registry::AppRegistry;
 
-use crate::handler::auth::{login, logout};
+use crate::handler::{
+    auth::{
+        confirm_email_change, forgot_password, jwks, login, login_two_factor, logout, refresh,
+        reset_password, signup, verify_email,
+    },
+    invitation::accept_invitation,
+    oidc::{oidc_authorize, oidc_callback},
+    setup::{get_setup_status, setup},
+};
 
 pub fn routes() -> Router<AppRegistry> {
     let auth_router = Router::new()
         .route("/login", post(login))
-        .route("/logout", post(logout));
-    Router::new().nest("/auth", auth_router)
+        .route("/login/two-factor", post(login_two_factor))
+        .route("/oidc/authorize", get(oidc_authorize))
+        .route("/oidc/callback", post(oidc_callback))
+        .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
+        .route("/signup", post(signup))
+        .route("/signup/verify", post(verify_email))
+        .route("/email-change/confirm", post(confirm_email_change))
+        .route("/invitations/accept", post(accept_invitation))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/setup", get(get_setup_status).post(setup));
+    Router::new()
+        .nest("/auth", auth_router)
+        .route("/.well-known/jwks.json", get(jwks))
 }
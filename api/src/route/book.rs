@@ -6,7 +6,11 @@ use registry::AppRegistry;
 
 use crate::handler::{
     book::{delete_book, register_book, show_book, show_book_list, update_book},
-    checkout::{checkout_book, checkout_history, return_book, show_checked_out_list},
+    checkout::{
+        checkout_book, checkout_books, checkout_history, return_book, return_books,
+        show_checked_out_list,
+    },
+    kiosk::update_book_barcode,
 };
 
 pub fn build_book_routers() -> Router<AppRegistry> {
@@ -15,10 +19,13 @@ pub fn build_book_routers() -> Router<AppRegistry> {
         .route("/", get(show_book_list))
         .route("/:book_id", get(show_book))
         .route("/:book_id", put(update_book))
-        .route("/:book_id", delete(delete_book));
+        .route("/:book_id", delete(delete_book))
+        .route("/:book_id/barcode", put(update_book_barcode));
 
     let checkout_router = Router::new()
         .route("/checkouts", get(show_checked_out_list))
+        .route("/checkouts/batch", post(checkout_books))
+        .route("/checkouts/batch/returned", put(return_books))
         .route("/:book_id/checkouts", post(checkout_book))
         .route(
             "/:book_id/checkouts/:checkout_id/returned",
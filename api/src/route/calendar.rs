@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::calendar::show_loan_calendar;
+
+pub fn build_calendar_routers() -> Router<AppRegistry> {
+    let routers = Router::new().route("/:token/loans.ics", get(show_loan_calendar));
+    Router::new().nest("/calendar", routers)
+}
@@ -0,0 +1,17 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use registry::AppRegistry;
+
+use crate::handler::invitation::{
+    create_invitation, list_invitations, resend_invitation, revoke_invitation,
+};
+
+pub fn build_invitation_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route("/", get(list_invitations).post(create_invitation))
+        .route("/:user_id", delete(revoke_invitation))
+        .route("/:user_id/resend", post(resend_invitation));
+    Router::new().nest("/invitations", routers)
+}
@@ -0,0 +1,19 @@
+use axum::{
+    routing::{delete, post},
+    Router,
+};
+use registry::AppRegistry;
+
+use crate::handler::kiosk::{delete_kiosk_device, list_kiosk_devices, register_kiosk_device, scan};
+
+pub fn build_kiosk_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route(
+            "/devices",
+            post(register_kiosk_device).get(list_kiosk_devices),
+        )
+        .route("/devices/:kiosk_id", delete(delete_kiosk_device))
+        .route("/scan", post(scan));
+
+    Router::new().nest("/kiosk", routers)
+}
@@ -1,5 +1,11 @@
 pub mod auth;
 pub mod book;
+pub mod calendar;
 pub mod health;
+pub mod invitation;
+pub mod kiosk;
+pub mod role;
+pub mod scim;
+pub mod statistics;
 pub mod user;
 pub mod v1;
@@ -0,0 +1,15 @@
+use axum::{
+    routing::{delete, get, put},
+    Router,
+};
+use registry::AppRegistry;
+
+use crate::handler::role::{create_role, delete_role, list_roles, update_role_permissions};
+
+pub fn build_role_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route("/", get(list_roles).post(create_role))
+        .route("/:role_name", delete(delete_role))
+        .route("/:role_name/permissions", put(update_role_permissions));
+    Router::new().nest("/roles", routers)
+}
@@ -0,0 +1,31 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::scim::{
+    create_scim_group, create_scim_user, delete_scim_group, delete_scim_user, list_scim_groups,
+    list_scim_users, patch_scim_group, patch_scim_user, replace_scim_group, replace_scim_user,
+    show_scim_group, show_scim_user, show_service_provider_config,
+};
+
+// IdP からのプロビジョニング用のエンドポイント。/api/v1 とは別の認証を使う
+pub fn routes() -> Router<AppRegistry> {
+    let scim_router = Router::new()
+        .route("/ServiceProviderConfig", get(show_service_provider_config))
+        .route("/Users", get(list_scim_users).post(create_scim_user))
+        .route(
+            "/Users/:user_id",
+            get(show_scim_user)
+                .put(replace_scim_user)
+                .patch(patch_scim_user)
+                .delete(delete_scim_user),
+        )
+        .route("/Groups", get(list_scim_groups).post(create_scim_group))
+        .route(
+            "/Groups/:group_id",
+            get(show_scim_group)
+                .put(replace_scim_group)
+                .patch(patch_scim_group)
+                .delete(delete_scim_group),
+        );
+    Router::new().nest("/scim/v2", scim_router)
+}
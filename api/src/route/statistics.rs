@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::statistics::show_statistics;
+
+pub fn build_statistics_routers() -> Router<AppRegistry> {
+    let routers = Router::new().route("/", get(show_statistics));
+    Router::new().nest("/statistics", routers)
+}
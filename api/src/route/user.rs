@@ -1,18 +1,69 @@
 use axum::{
-    routing::{delete, get, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use registry::AppRegistry;
 
-use crate::handler::user::{
-    change_password, change_role, delete_user, get_current_user, list_users, register_user,
+use crate::handler::{
+    calendar::{issue_calendar_feed_token, revoke_calendar_feed_token},
+    kiosk::update_user_badge_code,
+    personal_access_token::{create_my_token, delete_my_token, list_my_tokens},
+    session::{
+        delete_my_other_sessions, delete_my_session, delete_user_session, delete_user_sessions,
+        list_my_sessions, list_user_sessions,
+    },
+    two_factor::{activate_totp, begin_totp_enrollment, disable_totp},
+    user::{
+        change_password, change_role, delete_user, force_password_reset, get_current_user,
+        list_users, register_user, unlock_user, update_my_loan_statistics_sharing,
+        update_my_profile, update_user_profile, update_user_status,
+    },
+    user_import::import_users,
 };
 
 pub fn build_user_router() -> Router<AppRegistry> {
     Router::new()
         .route("/users/me", get(get_current_user))
         .route("/users/me/password", put(change_password))
+        .route("/users/me/profile", put(update_my_profile))
+        .route(
+            "/users/me/loan-statistics",
+            put(update_my_loan_statistics_sharing),
+        )
+        .route(
+            "/users/me/calendar-feed",
+            post(issue_calendar_feed_token).delete(revoke_calendar_feed_token),
+        )
+        .route(
+            "/users/me/sessions",
+            get(list_my_sessions).delete(delete_my_other_sessions),
+        )
+        .route("/users/me/sessions/:session_id", delete(delete_my_session))
+        .route(
+            "/users/me/two-factor",
+            post(begin_totp_enrollment).delete(disable_totp),
+        )
+        .route("/users/me/two-factor/activate", post(activate_totp))
+        .route(
+            "/users/me/tokens",
+            get(list_my_tokens).post(create_my_token),
+        )
+        .route("/users/me/tokens/:token_id", delete(delete_my_token))
         .route("/users", get(list_users).post(register_user))
+        .route("/users/import", post(import_users))
         .route("/users/:user_id", delete(delete_user))
         .route("/users/:user_id/role", put(change_role))
+        .route("/users/:user_id/status", put(update_user_status))
+        .route("/users/:user_id/profile", put(update_user_profile))
+        .route("/users/:user_id/password", put(force_password_reset))
+        .route("/users/:user_id/login-lock", delete(unlock_user))
+        .route("/users/:user_id/badge-code", put(update_user_badge_code))
+        .route(
+            "/users/:user_id/sessions",
+            get(list_user_sessions).delete(delete_user_sessions),
+        )
+        .route(
+            "/users/:user_id/sessions/:session_id",
+            delete(delete_user_session),
+        )
 }
@@ -2,14 +2,21 @@ use axum::Router;
 use registry::AppRegistry;
 
 use super::{
-    book::build_book_routers, health::build_health_check_routers, user::build_user_router,
+    book::build_book_routers, calendar::build_calendar_routers, health::build_health_check_routers,
+    invitation::build_invitation_routers, kiosk::build_kiosk_routers, role::build_role_routers,
+    statistics::build_statistics_routers, user::build_user_router,
 };
 
 pub fn routes() -> Router<AppRegistry> {
     let router = Router::new()
         .merge(build_health_check_routers())
         .merge(build_book_routers())
-        .merge(build_user_router());
+        .merge(build_user_router())
+        .merge(build_statistics_routers())
+        .merge(build_calendar_routers())
+        .merge(build_kiosk_routers())
+        .merge(build_role_routers())
+        .merge(build_invitation_routers());
 
     Router::new().nest("/api/v1", router)
 }
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use api::model::auth::SignupRequest;
+use axum::{body::Body, http::Request};
+use garde::Validate;
+use kernel::{
+    model::{id::UserId, user::password::PasswordPolicy},
+    repository::{auth::MockAuthRepository, password_reset::MockPasswordResetRepository},
+};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::helper::{fixture_registry, make_router, TestRequestExt};
+
+fn weak_password_policy() -> Arc<PasswordPolicy> {
+    Arc::new(PasswordPolicy::new(12, ["password1234".to_string()]))
+}
+
+#[rstest]
+#[case("short", false)]
+#[case("password1234", false)]
+#[case("User@Example.com", false)]
+#[case("correct horse battery", true)]
+fn signup_request_applies_password_policy(#[case] password: &str, #[case] valid: bool) {
+    let req: SignupRequest = serde_json::from_value(serde_json::json!({
+        "name": "New User",
+        "email": "user@example.com",
+        "password": password,
+    }))
+    .unwrap();
+    let res = req.validate(&req.password_context(weak_password_policy()));
+    assert_eq!(res.is_ok(), valid);
+}
+
+#[rstest]
+#[case("short")]
+#[case("password1234")]
+#[case("User@Example.com")]
+#[tokio::test]
+async fn reset_password_with_weak_password_400(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] password: &str,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_password_policy()
+        .returning(weak_password_policy);
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(|| {
+            let mut mock = MockPasswordResetRepository::new();
+            mock.expect_find_email()
+                .returning(|_| Ok(Some("user@example.com".to_string())));
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let body = serde_json::json!({
+        "token": "dummy",
+        "newPassword": password,
+    });
+    let req = Request::post("/auth/password/reset")
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn reset_password_200(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry
+        .expect_password_policy()
+        .returning(weak_password_policy);
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(move || {
+            let mut mock = MockPasswordResetRepository::new();
+            mock.expect_find_email()
+                .withf(|token| token.0 == "reset-token")
+                .returning(|_| Ok(Some("user@example.com".to_string())));
+            mock.expect_reset_password()
+                .withf(|event| {
+                    event.token.0 == "reset-token" && event.new_password == "correct horse battery"
+                })
+                .returning(move |_| Ok(user_id));
+            Arc::new(mock)
+        });
+    // 再設定の前にログインしたセッションはすべて終了する
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_delete_all_tokens()
+                .withf(move |id| *id == user_id)
+                .times(1)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let body = serde_json::json!({
+        "token": "reset-token",
+        "newPassword": "correct horse battery",
+    });
+    let req = Request::post("/auth/password/reset")
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn reset_password_with_invalid_token_404(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_password_policy()
+        .returning(weak_password_policy);
+    // 使用済みや有効期限切れのトークンではメールアドレスが見つからず、再設定は行わない
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(|| {
+            let mut mock = MockPasswordResetRepository::new();
+            mock.expect_find_email().returning(|_| Ok(None));
+            mock.expect_reset_password().never();
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let body = serde_json::json!({
+        "token": "used-token",
+        "newPassword": "correct horse battery",
+    });
+    let req = Request::post("/auth/password/reset")
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
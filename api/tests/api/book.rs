@@ -17,6 +17,7 @@ use kernel::{
         list::PaginatedList,
         user::BookOwner,
     },
+    policy::{Action, MockPolicy, RolePolicy},
     repository::book::MockBookRepository,
 };
 
@@ -77,3 +78,107 @@ async fn show_book_list_with_query_200(
     // 6. テストが成功していることを示す
     Ok(())
 }
+
+fn book_owned_by(book_id: BookId, owner: UserId) -> Book {
+    Book {
+        id: book_id,
+        title: "RustによるWebアプリケーション開発".to_string(),
+        isbn: "".to_string(),
+        author: "Yuki Toyoda".to_string(),
+        description: "RustによるWebアプリケーション開発".to_string(),
+        owner: BookOwner {
+            id: owner,
+            name: "Yuki Toyoda".to_string(),
+        },
+        checkout: None,
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn update_book_of_other_user_403(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id()
+            .returning(move |id| Ok(Some(book_owned_by(id, UserId::new()))));
+        mock.expect_update().never();
+        Arc::new(mock)
+    });
+    // 権限を持たないユーザーは、他のユーザーが登録した蔵書を変更できない
+    fixture.expect_policy().returning(|| Arc::new(RolePolicy));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::put(&v1(&format!("/books/{}", book_id)))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"title": "title", "author": "author", "isbn": "isbn", "description": ""}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_book_not_found_404(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(|_| Ok(None));
+        mock.expect_delete().never();
+        Arc::new(mock)
+    });
+    // 存在しない蔵書の場合は、権限を判定せずに 404 を返す
+    fixture.expect_policy().never();
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::delete(&v1(&format!("/books/{}", BookId::new())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_book_allowed_by_policy_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let owner = UserId::new();
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id()
+            .returning(move |id| Ok(Some(book_owned_by(id, owner))));
+        mock.expect_delete().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    fixture.expect_policy().returning(move || {
+        let mut mock = MockPolicy::new();
+        mock.expect_authorize()
+            .withf(move |_, action| *action == Action::DeleteBook { owner })
+            .returning(|_, _| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::delete(&v1(&format!("/books/{}", BookId::new())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    Ok(())
+}
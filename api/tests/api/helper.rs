@@ -1,10 +1,15 @@
 use std::sync::Arc;
 
-use api::route::{auth, v1};
+use api::route::{auth, scim, v1};
 use axum::{http::request::Builder, Router};
 use kernel::{
-    model::{auth::AccessToken, id::UserId, role::Role, user::User},
-    repository::{auth::MockAuthRepository, user::MockUserRepository},
+    model::{
+        auth::AccessToken,
+        id::UserId,
+        role::Role,
+        user::{User, UserStatus},
+    },
+    repository::{auth::MockAuthRepository, role::MockRoleRepository, user::MockUserRepository},
 };
 use registry::MockAppRegistryExt;
 use rstest::fixture;
@@ -17,6 +22,7 @@ pub fn make_router(registry: MockAppRegistryExt) -> Router {
     Router::new()
         .merge(v1::routes())
         .merge(auth::routes())
+        .merge(scim::routes())
         .with_state(Arc::new(registry))
 }
 
@@ -32,6 +38,9 @@ pub fn fixture_auth(mut fixture_registry: MockAppRegistryExt) -> MockAppRegistry
         mock_auth_repository
             .expect_fetch_user_id_from_token()
             .returning(|_| Ok(Some(UserId::new())));
+        mock_auth_repository
+            .expect_fetch_user_from_claims()
+            .returning(|_| Ok(None));
         mock_auth_repository
             .expect_verify_user()
             .returning(|_, _| Ok(UserId::new()));
@@ -55,10 +64,18 @@ pub fn fixture(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
                     name: "dummy-user".to_string(),
                     email: "dummy@example.com".to_string(),
                     role: Role::User,
+                    status: UserStatus::Active,
                 }))
             });
         Arc::new(mock_user_repository)
     });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock_role_repository = MockRoleRepository::new();
+        mock_role_repository
+            .expect_find_permissions()
+            .returning(|_| Ok(Vec::new()));
+        Arc::new(mock_role_repository)
+    });
     fixture_auth
 }
 
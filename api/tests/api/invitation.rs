@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use chrono::{Duration, Utc};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, fixture_auth, fixture_registry, make_router, v1, TestRequestExt},
+};
+
+use kernel::{
+    model::{
+        id::UserId,
+        invitation::Invitation,
+        role::{Permission, Role, RolePermissions},
+        user::{password::PasswordPolicy, User, UserStatus},
+    },
+    policy::RolePolicy,
+    repository::{
+        invitation::MockInvitationRepository, role::MockRoleRepository, user::MockUserRepository,
+    },
+};
+
+#[rstest]
+#[tokio::test]
+async fn create_invitation_without_permission_403(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_policy().returning(|| Arc::new(RolePolicy));
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(&v1("/invitations"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"invitee@example.com","role":"User"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_invitations_200(
+    mut fixture_auth: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-admin".to_string(),
+                email: "admin@example.com".to_string(),
+                role: Role::Admin,
+                status: UserStatus::Active,
+            }))
+        });
+        Arc::new(mock)
+    });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions()
+            .returning(|_| Ok(Permission::all()));
+        Arc::new(mock)
+    });
+    fixture_auth
+        .expect_policy()
+        .returning(|| Arc::new(RolePolicy));
+    fixture_auth.expect_invitation_repository().returning(|| {
+        let mut mock = MockInvitationRepository::new();
+        mock.expect_find_pending().returning(|| {
+            Ok(vec![Invitation {
+                user_id: UserId::new(),
+                email: "invitee@example.com".to_string(),
+                role: Role::Librarian,
+                invited_by: None,
+                expires_at: Utc::now() + Duration::days(7),
+                created_at: Utc::now(),
+            }])
+        });
+        Arc::new(mock)
+    });
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::get(&v1("/invitations"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    let invitation = &result["items"][0];
+    assert_eq!(invitation["email"], "invitee@example.com");
+    assert_eq!(invitation["role"], "Librarian");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(r#"{"token":"dummy","name":"","password":"password"}"#)]
+#[case(r#"{"token":"","name":"Invitee","password":"password"}"#)]
+#[tokio::test]
+async fn accept_invitation_with_invalid_body_400(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] body: &'static str,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_password_policy()
+        .returning(|| Arc::new(PasswordPolicy::default()));
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/invitations/accept")
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("short")]
+#[case("password1234")]
+#[case("Invitee@Example.com")]
+#[tokio::test]
+async fn accept_invitation_with_weak_password_400(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] password: &str,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_password_policy()
+        .returning(|| Arc::new(PasswordPolicy::new(12, ["password1234".to_string()])));
+    fixture_registry
+        .expect_invitation_repository()
+        .returning(|| {
+            let mut mock = MockInvitationRepository::new();
+            mock.expect_find_email()
+                .returning(|_| Ok(Some("invitee@example.com".to_string())));
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let body = serde_json::json!({
+        "token": "dummy",
+        "name": "Invitee",
+        "password": password,
+    });
+    let req = Request::post("/auth/invitations/accept")
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn import_users_without_permission_403(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_policy().returning(|| Arc::new(RolePolicy));
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(&v1("/users/import?dryRun=true"))
+        .bearer()
+        .header("Content-Type", "text/csv")
+        .body(Body::from("name,email\nInvitee,invitee@example.com\n"))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn import_users_reports_each_row_422(
+    mut fixture_auth: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-admin".to_string(),
+                email: "admin@example.com".to_string(),
+                role: Role::Admin,
+                status: UserStatus::Active,
+            }))
+        });
+        mock.expect_find_existing_emails()
+            .returning(|_| Ok(vec!["Existing@example.com".to_string()]));
+        Arc::new(mock)
+    });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions()
+            .returning(|_| Ok(Permission::all()));
+        mock.expect_find_all().returning(|| {
+            Ok(vec![
+                RolePermissions {
+                    role: Role::Admin,
+                    built_in: true,
+                    permissions: Permission::all(),
+                },
+                RolePermissions {
+                    role: Role::User,
+                    built_in: true,
+                    permissions: vec![],
+                },
+            ])
+        });
+        Arc::new(mock)
+    });
+    fixture_auth
+        .expect_policy()
+        .returning(|| Arc::new(RolePolicy));
+    // 問題のある行が含まれるため、招待は作成しない（invitation_repository は呼ばれない）
+    let app: axum::Router = make_router(fixture_auth);
+
+    let csv = "name,email,role\n\
+               Alice,alice@example.com,\n\
+               Bob,not-an-email,User\n\
+               Alice Again,ALICE@example.com,User\n\
+               Existing,existing@example.com,User\n\
+               Carol,carol@example.com,Missing\n";
+    let req = Request::post(&v1("/users/import"))
+        .bearer()
+        .header("Content-Type", "text/csv")
+        .body(Body::from(csv))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["committed"], false);
+    let statuses = result["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| (item["row"].as_u64().unwrap(), item["status"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            (2, "valid".into()),
+            (3, "invalid".into()),
+            (4, "invalid".into()),
+            (5, "invalid".into()),
+            (6, "invalid".into()),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{body::Body, http::Request};
+use kernel::{
+    model::{
+        id::{BookId, CheckoutId, KioskId, UserId},
+        kiosk::{KioskAction, KioskDevice, KioskScanResult},
+    },
+    repository::kiosk::MockKioskRepository,
+};
+use rstest::rstest;
+use serde_json::{json, Value};
+use shared::error::AppError;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, v1, TestRequestExt},
+};
+
+const UNKNOWN_BADGE: &str = "BADGE-UNKNOWN";
+const SUSPENDED_BADGE: &str = "BADGE-SUSPENDED";
+
+// 端末の認証は常に成功させ、貸出中かどうかを記録して貸出と返却を切り替えるモックを設定する
+fn kiosk_registry(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    scanned: Arc<Mutex<Vec<(String, String)>>>,
+) -> registry::MockAppRegistryExt {
+    let checkout = Arc::new(Mutex::new(None::<CheckoutId>));
+    fixture_registry
+        .expect_kiosk_repository()
+        .returning(move || {
+            let scanned = scanned.clone();
+            let checkout = checkout.clone();
+            let mut mock = MockKioskRepository::new();
+            mock.expect_authenticate().returning(|_| {
+                Ok(Some(KioskDevice {
+                    id: KioskId::new(),
+                    name: "Front desk".into(),
+                    created_at: chrono::Utc::now(),
+                    last_used_at: None,
+                }))
+            });
+            mock.expect_scan().returning(move |event| {
+                scanned
+                    .lock()
+                    .unwrap()
+                    .push((event.book_code.clone(), event.badge_code.clone()));
+                match event.badge_code.as_str() {
+                    UNKNOWN_BADGE => {
+                        return Err(AppError::EntityNotFound("badge not found".into()))
+                    }
+                    SUSPENDED_BADGE => {
+                        return Err(AppError::UnprocessableEntity("user suspended".into()))
+                    }
+                    _ => {}
+                }
+                let mut checkout = checkout.lock().unwrap();
+                let (action, checkout_id) = match checkout.take() {
+                    Some(checkout_id) => (KioskAction::Returned, checkout_id),
+                    None => {
+                        let checkout_id = CheckoutId::new();
+                        *checkout = Some(checkout_id);
+                        (KioskAction::CheckedOut, checkout_id)
+                    }
+                };
+                Ok(KioskScanResult {
+                    action,
+                    checkout_id,
+                    book_id: BookId::new(),
+                    book_title: "実践Rustプログラミング入門".into(),
+                    user_id: UserId::new(),
+                    user_name: "Yamada Taro".into(),
+                })
+            });
+            Arc::new(mock)
+        });
+    fixture_registry
+}
+
+fn scan_request(book_code: &str, badge_code: &str) -> anyhow::Result<Request<Body>> {
+    Ok(Request::post(&v1("/kiosk/scan"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            json!({"bookCode": book_code, "badgeCode": badge_code}).to_string(),
+        ))?)
+}
+
+#[rstest]
+#[tokio::test]
+async fn kiosk_scan_toggles_checkout_200(
+    fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let scanned = Arc::new(Mutex::new(Vec::new()));
+    let app: axum::Router = make_router(kiosk_registry(fixture_registry, scanned.clone()));
+
+    // 1回目の読み取りで貸し出し、同じ利用者の2回目の読み取りで返却する
+    let resp = app
+        .clone()
+        .oneshot(scan_request(" BOOK-0001 ", "BADGE-0001")?)
+        .await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+    let checked_out = deserialize_json!(resp, Value);
+    assert_eq!(checked_out["action"], "checkedOut");
+
+    let resp = app
+        .oneshot(scan_request("BOOK-0001", " BADGE-0001")?)
+        .await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+    let returned = deserialize_json!(resp, Value);
+    assert_eq!(returned["action"], "returned");
+    assert_eq!(returned["checkoutId"], checked_out["checkoutId"]);
+
+    // 読み取ったコードの前後の空白は取り除いて渡す
+    assert_eq!(
+        *scanned.lock().unwrap(),
+        vec![
+            ("BOOK-0001".to_string(), "BADGE-0001".to_string()),
+            ("BOOK-0001".to_string(), "BADGE-0001".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[case(UNKNOWN_BADGE, axum::http::StatusCode::NOT_FOUND)]
+// 利用停止中などの利用者には貸し出さない
+#[case(SUSPENDED_BADGE, axum::http::StatusCode::UNPROCESSABLE_ENTITY)]
+#[tokio::test]
+async fn kiosk_scan_with_unavailable_badge(
+    fixture_registry: registry::MockAppRegistryExt,
+    #[case] badge_code: &'static str,
+    #[case] expected: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let scanned = Arc::new(Mutex::new(Vec::new()));
+    let app: axum::Router = make_router(kiosk_registry(fixture_registry, scanned));
+
+    let resp = app.oneshot(scan_request("BOOK-0001", badge_code)?).await?;
+    assert_eq!(resp.status(), expected);
+
+    Ok(())
+}
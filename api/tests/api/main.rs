@@ -1,2 +1,8 @@
+mod auth;
 mod book;
 mod helper;
+mod invitation;
+mod kiosk;
+mod scim;
+mod setup;
+mod user;
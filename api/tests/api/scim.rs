@@ -0,0 +1,321 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{body::Body, http::Request};
+use kernel::{
+    model::{
+        id::UserId,
+        role::Role,
+        user::{User, UserStatus},
+    },
+    repository::user::MockUserRepository,
+};
+use rstest::{fixture, rstest};
+use serde_json::{json, Value};
+use shared::{
+    config::{
+        AccessTokenFormat, AppConfig, AuthConfig, CheckoutConfig, DatabaseConfig, FrontendConfig,
+        InvitationConfig, LoginThrottleConfig, MailTransport, MailerConfig, PasswordPolicyConfig,
+        PasswordResetConfig, ProxyConfig, RedisConfig, ScimConfig, SignupConfig, StatisticsConfig,
+        TwoFactorConfig,
+    },
+    error::AppError,
+};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, TestRequestExt},
+};
+
+const SCIM_TOKEN: &str = "scim-test-token-0123456789abcdef";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+fn scim_app_config() -> AppConfig {
+    AppConfig {
+        database: DatabaseConfig {
+            host: "localhost".into(),
+            port: 5432,
+            username: "app".into(),
+            password: "passwd".into(),
+            database: "app".into(),
+            max_transaction_retries: 0,
+        },
+        redis: RedisConfig {
+            host: "localhost".into(),
+            port: 6379,
+        },
+        auth: AuthConfig {
+            ttl: 900,
+            refresh_ttl: 86400,
+            token_format: AccessTokenFormat::Opaque,
+            password_login_enabled: true,
+        },
+        statistics: StatisticsConfig { cache_ttl: 60 },
+        checkout: CheckoutConfig {
+            loan_period_days: 14,
+        },
+        frontend: FrontendConfig {
+            base_url: "http://localhost:3000".into(),
+        },
+        signup: SignupConfig {
+            enabled: false,
+            allowed_email_domains: vec![],
+            verification_ttl: 3600,
+        },
+        mailer: MailerConfig {
+            from: "noreply@example.com".into(),
+            transport: MailTransport::File { dir: "mail".into() },
+        },
+        password_reset: PasswordResetConfig { ttl: 3600 },
+        password_policy: PasswordPolicyConfig {
+            min_length: 8,
+            common_passwords: vec![],
+        },
+        invitation: InvitationConfig { ttl: 3600 },
+        login_throttle: LoginThrottleConfig {
+            max_failures_per_email: 5,
+            max_failures_per_ip: 20,
+            backoff_base: 1,
+            lockout: 900,
+        },
+        proxy: ProxyConfig { trusted_hops: 0 },
+        two_factor: TwoFactorConfig {
+            issuer: "rusty-book-manager".into(),
+            challenge_ttl: 300,
+            required_for_admin: false,
+            max_failures: 5,
+            lockout: 900,
+        },
+        oidc: None,
+        scim: Some(ScimConfig {
+            bearer_token: SCIM_TOKEN.into(),
+        }),
+        initial_admin: None,
+    }
+}
+
+#[fixture]
+fn fixture_scim(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> registry::MockAppRegistryExt {
+    fixture_registry
+        .expect_app_config()
+        .returning(|| Arc::new(scim_app_config()));
+    fixture_registry
+}
+
+fn scim_request(method: &str, path: String) -> axum::http::request::Builder {
+    Request::builder()
+        .method(method)
+        .uri(path)
+        .header("Authorization", format!("Bearer {SCIM_TOKEN}"))
+}
+
+// ユーザーの状態を status として返し、変更の要求を記録するモックを設定する
+fn user_registry(
+    mut fixture_scim: registry::MockAppRegistryExt,
+    status: UserStatus,
+    statuses: Arc<Mutex<Vec<UserStatus>>>,
+    profiles: Arc<Mutex<Vec<(String, String)>>>,
+) -> registry::MockAppRegistryExt {
+    fixture_scim.expect_user_repository().returning(move || {
+        let statuses = statuses.clone();
+        let profiles = profiles.clone();
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "Yamada Taro".to_string(),
+                email: "taro@example.com".to_string(),
+                role: Role::User,
+                status,
+            }))
+        });
+        mock.expect_update_status().returning(move |event| {
+            statuses.lock().unwrap().push(event.status);
+            Ok(())
+        });
+        mock.expect_sync_profile().returning(move |event| {
+            profiles
+                .lock()
+                .unwrap()
+                .push((event.name.clone(), event.email.clone()));
+            Ok(User {
+                id: event.user_id,
+                name: event.name,
+                email: event.email,
+                role: Role::User,
+                status,
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture_scim
+}
+
+#[rstest]
+#[case("/scim/v2/Users")]
+#[case("/scim/v2/Groups")]
+#[tokio::test]
+async fn scim_without_token_401(
+    fixture_registry: registry::MockAppRegistryExt,
+    #[case] path: &'static str,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(path).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/scim/v2/Users?filter=userName%20co%20%22taro%22")]
+#[case("/scim/v2/Users?filter=displayName%20eq%20%22taro%22")]
+#[case("/scim/v2/Groups?filter=displayName%20eq%20Admin")]
+#[tokio::test]
+async fn scim_invalid_filter_400(
+    fixture_scim: registry::MockAppRegistryExt,
+    #[case] path: &'static str,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture_scim);
+
+    let req = scim_request("GET", path.to_string()).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["schemas"], json!([ERROR_SCHEMA]));
+    assert_eq!(body["status"], "400");
+    assert_eq!(body["scimType"], "invalidFilter");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn scim_create_user_with_used_email_409(
+    mut fixture_scim: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_scim.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_provision()
+            .returning(|_| Err(AppError::EntityConflict("email already used".into())));
+        Arc::new(mock)
+    });
+    let app: axum::Router = make_router(fixture_scim);
+
+    let req = scim_request("POST", "/scim/v2/Users".into())
+        .application_json()
+        .body(Body::from(
+            json!({"userName": "taro@example.com", "active": true}).to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CONFLICT);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["schemas"], json!([ERROR_SCHEMA]));
+    assert_eq!(body["status"], "409");
+    assert_eq!(body["scimType"], "uniqueness");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn scim_patch_user_200(fixture_scim: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let profiles = Arc::new(Mutex::new(Vec::new()));
+    let app: axum::Router = make_router(user_registry(
+        fixture_scim,
+        UserStatus::Active,
+        statuses.clone(),
+        profiles.clone(),
+    ));
+
+    let req = scim_request("PATCH", format!("/scim/v2/Users/{}", UserId::new()))
+        .application_json()
+        .body(Body::from(
+            json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [
+                    {"op": "Replace", "path": "displayName", "value": "Yamada Hanako"},
+                    {"op": "replace", "value": {"active": "False"}},
+                ],
+            })
+            .to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    // 名前を更新し、active を false にしたユーザーは利用停止にする
+    assert_eq!(
+        *profiles.lock().unwrap(),
+        vec![("Yamada Hanako".to_string(), "taro@example.com".to_string())]
+    );
+    assert_eq!(*statuses.lock().unwrap(), vec![UserStatus::Suspended]);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn scim_patch_user_with_unsupported_operation_400(
+    fixture_scim: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let profiles = Arc::new(Mutex::new(Vec::new()));
+    let app: axum::Router = make_router(user_registry(
+        fixture_scim,
+        UserStatus::Active,
+        statuses.clone(),
+        profiles.clone(),
+    ));
+
+    let req = scim_request("PATCH", format!("/scim/v2/Users/{}", UserId::new()))
+        .application_json()
+        .body(Body::from(
+            json!({"Operations": [{"op": "remove", "path": "displayName"}]}).to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["schemas"], json!([ERROR_SCHEMA]));
+    assert!(profiles.lock().unwrap().is_empty());
+    assert!(statuses.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[rstest]
+#[case(UserStatus::Active, vec![UserStatus::Deactivated])]
+#[case(UserStatus::Suspended, vec![UserStatus::Deactivated])]
+// 無効化済みのユーザーは、何もせず成功として扱う
+#[case(UserStatus::Deactivated, vec![])]
+#[tokio::test]
+async fn scim_delete_user_deactivates_204(
+    fixture_scim: registry::MockAppRegistryExt,
+    #[case] status: UserStatus,
+    #[case] expected: Vec<UserStatus>,
+) -> anyhow::Result<()> {
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let profiles = Arc::new(Mutex::new(Vec::new()));
+    let app: axum::Router = make_router(user_registry(
+        fixture_scim,
+        status,
+        statuses.clone(),
+        profiles.clone(),
+    ));
+
+    let req =
+        scim_request("DELETE", format!("/scim/v2/Users/{}", UserId::new())).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NO_CONTENT);
+
+    assert_eq!(*statuses.lock().unwrap(), expected);
+    assert!(profiles.lock().unwrap().is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use kernel::{
+    model::{
+        id::UserId,
+        role::Role,
+        user::{password::PasswordPolicy, User, UserStatus},
+    },
+    repository::setup::MockSetupRepository,
+};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, TestRequestExt},
+};
+
+fn setup_request() -> Body {
+    let body = serde_json::json!({
+        "name": "Admin",
+        "email": "admin@example.com",
+        "password": "correct horse battery",
+    });
+    Body::from(body.to_string())
+}
+
+#[rstest]
+#[tokio::test]
+async fn setup_creates_initial_admin_201(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_setup_repository().returning(|| {
+        let mut mock = MockSetupRepository::new();
+        mock.expect_is_required().returning(|| Ok(true));
+        mock.expect_create_initial_admin().returning(|event| {
+            Ok(User {
+                id: UserId::new(),
+                name: event.name,
+                email: event.email,
+                role: Role::Admin,
+                status: UserStatus::Active,
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_password_policy()
+        .returning(|| Arc::new(PasswordPolicy::default()));
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/setup")
+        .application_json()
+        .body(setup_request())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CREATED);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["email"], "admin@example.com");
+    assert_eq!(result["role"], "Admin");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn setup_after_admin_exists_409(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_setup_repository().returning(|| {
+        let mut mock = MockSetupRepository::new();
+        mock.expect_is_required().returning(|| Ok(false));
+        mock.expect_create_initial_admin().never();
+        Arc::new(mock)
+    });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/setup")
+        .application_json()
+        .body(setup_request())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CONFLICT);
+
+    Ok(())
+}
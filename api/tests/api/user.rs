@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_auth, make_router, v1, TestRequestExt},
+};
+
+use kernel::{
+    model::{
+        id::UserId,
+        list::PaginatedList,
+        role::{Permission, Role},
+        user::{password::PasswordPolicy, User, UserStatus},
+    },
+    policy::RolePolicy,
+    repository::{role::MockRoleRepository, user::MockUserRepository},
+};
+use shared::error::AppError;
+
+// ログイン中のユーザーのロールと、一覧に含まれるユーザーを設定する
+fn directory_registry(
+    mut fixture_auth: registry::MockAppRegistryExt,
+    role: Role,
+) -> registry::MockAppRegistryExt {
+    fixture_auth.expect_user_repository().returning(move || {
+        let role = role.clone();
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: role.clone(),
+                status: UserStatus::Active,
+            }))
+        });
+        mock.expect_find_all().returning(|opt| {
+            Ok(PaginatedList {
+                total: 1,
+                limit: opt.limit,
+                offset: opt.offset,
+                items: vec![User {
+                    id: UserId::new(),
+                    name: "Yuki Toyoda".to_string(),
+                    email: "yuki@example.com".to_string(),
+                    role: Role::User,
+                    status: UserStatus::Active,
+                }],
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions().returning(|role| {
+            Ok(match role {
+                Role::Admin => Permission::all(),
+                _ => Vec::new(),
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture_auth
+        .expect_policy()
+        .returning(|| Arc::new(RolePolicy));
+    fixture_auth
+}
+
+#[rstest]
+#[case(Role::Admin, true)]
+#[case(Role::User, false)]
+#[tokio::test]
+async fn list_users_hides_email_without_permission_200(
+    fixture_auth: registry::MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] full_profile: bool,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(directory_registry(fixture_auth, role));
+
+    let req = Request::get(&v1("/users?limit=10&q=yuki"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["limit"], 10);
+    let user = &result["items"][0];
+    assert_eq!(user["name"], "Yuki Toyoda");
+    // 権限を持たないユーザーには、名前のみの公開プロフィールを返す
+    assert_eq!(user.get("email").is_some(), full_profile);
+    assert_eq!(user.get("role").is_some(), full_profile);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/users?role=Admin")]
+#[case("/users?sort=email_asc")]
+#[case("/users?status=suspended")]
+#[tokio::test]
+async fn list_users_by_private_fields_403(
+    fixture_auth: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(directory_registry(fixture_auth, Role::User));
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/users?sort=unknown")]
+#[case("/users?limit=-1")]
+#[tokio::test]
+async fn list_users_with_invalid_query_400(
+    fixture_auth: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(directory_registry(fixture_auth, Role::Admin));
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("short")]
+#[case("password1234")]
+#[case("new-user@example.com")]
+#[tokio::test]
+async fn register_user_with_weak_password_400(
+    fixture_auth: registry::MockAppRegistryExt,
+    #[case] password: &str,
+) -> anyhow::Result<()> {
+    let mut fixture = directory_registry(fixture_auth, Role::Admin);
+    fixture
+        .expect_password_policy()
+        .returning(|| Arc::new(PasswordPolicy::new(12, ["password1234".to_string()])));
+    let app: axum::Router = make_router(fixture);
+
+    let body = serde_json::json!({
+        "name": "New User",
+        "email": "new-user@example.com",
+        "password": password,
+    });
+    let req = Request::post(&v1("/users"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(UserStatus::Suspended)]
+#[case(UserStatus::Deactivated)]
+#[tokio::test]
+async fn inactive_user_cannot_use_token_401(
+    mut fixture_auth: registry::MockAppRegistryExt,
+    #[case] status: UserStatus,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(move || {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: Role::User,
+                status,
+            }))
+        });
+        Arc::new(mock)
+    });
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::get(&v1("/users/me"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+const ADMIN_USER_ID: &str = "5b4c96ac-316a-4bee-8e69-cac5eb84ff4c";
+
+#[rstest]
+#[case("PUT", "/password", serde_json::json!({"temporaryPassword": "temporary-password"}))]
+#[case("PUT", "/profile", serde_json::json!({"name": "Admin", "email": "attacker@example.com"}))]
+#[case("PUT", "/status", serde_json::json!({"status": "suspended"}))]
+#[case("DELETE", "", serde_json::json!({}))]
+#[case("DELETE", "/login-lock", serde_json::json!({}))]
+#[case("PUT", "/badge-code", serde_json::json!({"badgeCode": "BADGE-0001"}))]
+#[case("GET", "/sessions", serde_json::json!({}))]
+#[case("DELETE", "/sessions", serde_json::json!({}))]
+#[case("DELETE", "/sessions/0f6a3c4e-8d1b-4a57-9c1e-2b7d5e9f3a10", serde_json::json!({}))]
+#[tokio::test]
+async fn user_manager_cannot_manage_admin_403(
+    mut fixture_auth: registry::MockAppRegistryExt,
+    #[case] method: &str,
+    #[case] path: &str,
+    #[case] body: serde_json::Value,
+) -> anyhow::Result<()> {
+    // ログイン中のユーザーは user:manage 権限だけを持つロールとし、操作の対象は管理者とする
+    let admin_user_id: UserId = ADMIN_USER_ID.parse()?;
+    fixture_auth.expect_user_repository().returning(move || {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(move |id| {
+            let role = if id == admin_user_id {
+                Role::Admin
+            } else {
+                Role::Custom("UserManager".into())
+            };
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role,
+                status: UserStatus::Active,
+            }))
+        });
+        Arc::new(mock)
+    });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions().returning(|role| {
+            Ok(match role {
+                Role::Admin => Permission::all(),
+                _ => vec![Permission::UserManage],
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture_auth
+        .expect_policy()
+        .returning(|| Arc::new(RolePolicy));
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(&format!("/users/{}{}", ADMIN_USER_ID, path)))
+        .bearer()
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_password_conflict_409(
+    mut fixture_auth: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 競合によるやり直しが上限に達した場合は、再試行を促すため 409 を返す
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: Role::User,
+                status: UserStatus::Active,
+            }))
+        });
+        mock.expect_update_password()
+            .returning(|_| Err(AppError::TransactionConflict));
+        Arc::new(mock)
+    });
+    fixture_auth.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions().returning(|_| Ok(Vec::new()));
+        Arc::new(mock)
+    });
+    fixture_auth
+        .expect_password_policy()
+        .returning(|| Arc::new(PasswordPolicy::default()));
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::put(&v1("/users/me/password"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            serde_json::json!({
+                "currentPassword": "current-password",
+                "newPassword": "new-password",
+            })
+            .to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CONFLICT);
+
+    Ok(())
+}
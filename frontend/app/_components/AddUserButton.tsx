@@ -22,6 +22,7 @@ import useLocalStorageState from "use-local-storage-state";
 import { SubmitHandler, useForm } from "react-hook-form";
 import { post } from "../_lib/client";
 import { useSWRConfig } from "swr";
+import { isUsersKey } from "../_contexts/user";
 
 type UserInput = {
   name: string;
@@ -56,7 +57,7 @@ const AddUserButton: FC = () => {
       reset();
       onClose();
       setIsSuccessModalOpen(true);
-      mutate(["/api/v1/users", accessToken]);
+      mutate(isUsersKey);
     } else {
       toast({
         title: "ユーザーを作成できませんでした",
@@ -70,11 +71,9 @@ const AddUserButton: FC = () => {
 
   return (
     <>
-      <Flex justifyContent="flex-end" mb="4">
-        <Button colorScheme="green" onClick={onOpen}>
-          Add User
-        </Button>
-      </Flex>
+      <Button colorScheme="green" onClick={onOpen}>
+        Add User
+      </Button>
 
       {/* ユーザー追加モーダル */}
       <Modal isOpen={isOpen} onClose={onClose}>
@@ -19,7 +19,8 @@ const CheckoutHistory: FC<CheckoutHistoryProps> = ({
   bookId,
 }: CheckoutHistoryProps) => {
   const { checkouts } = useBookCheckouts(bookId);
-  const { users } = useUsers();
+  // 貸出者の名前を引くため、ページを分けずに取得する
+  const { users } = useUsers({ limit: 1000, offset: 0 });
   const userItems = users?.items;
 
   return (
@@ -14,12 +14,13 @@ import {
 } from "@chakra-ui/react";
 import { DeleteIcon } from "@chakra-ui/icons";
 import useLocalStorageState from "use-local-storage-state";
-import { User } from "../_types/user";
+import { UserDirectoryEntry } from "../_types/user";
+import { isUsersKey } from "../_contexts/user";
 import { del } from "../_lib/client";
 import { useSWRConfig } from "swr";
 
 type DeleteUserButtonProps = {
-  user: User;
+  user: UserDirectoryEntry;
 };
 
 const DeleteUserButton: FC<DeleteUserButtonProps> = ({
@@ -39,17 +40,17 @@ const DeleteUserButton: FC<DeleteUserButtonProps> = ({
 
     if (res.ok) {
       toast({
-        title: "ユーザーを削除しました",
-        description: `ユーザー「${user.name}」を削除しました`,
+        title: "ユーザーを無効化しました",
+        description: `ユーザー「${user.name}」を無効化しました`,
         status: "success",
         duration: 5000,
         isClosable: true,
       });
       onClose();
-      mutate(["/api/v1/users", accessToken]);
+      mutate(isUsersKey);
     } else {
       toast({
-        title: "ユーザーを削除できませんでした",
+        title: "ユーザーを無効化できませんでした",
         description: "サーバーからエラー応答が返却されました。",
         status: "error",
         duration: 5000,
@@ -75,13 +76,13 @@ const DeleteUserButton: FC<DeleteUserButtonProps> = ({
         <AlertDialogOverlay>
           <AlertDialogContent>
             <AlertDialogHeader fontWeight="bold"></AlertDialogHeader>
-            <AlertDialogBody>{`ユーザー「${user.name}」を削除しますか？`}</AlertDialogBody>
+            <AlertDialogBody>{`ユーザー「${user.name}」を無効化しますか？ログインや貸出ができなくなり、元に戻すことはできません。`}</AlertDialogBody>
             <AlertDialogFooter>
               <Button ref={cancelRef} onClick={onClose}>
                 Cancel
               </Button>
               <Button colorScheme="red" onClick={handleDelete} ml={3}>
-                Delete
+                Deactivate
               </Button>
             </AlertDialogFooter>
           </AlertDialogContent>
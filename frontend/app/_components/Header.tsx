@@ -18,7 +18,7 @@ import {
 import NextLink from "next/link";
 import { useRouter } from "next/navigation";
 import useLocalStorageState from "use-local-storage-state";
-import { ACCESS_TOKEN_KEY } from "./auth";
+import { ACCESS_TOKEN_KEY, clearTokens } from "./auth";
 import { FC } from "react";
 import { useCurrentUser } from "../_contexts/user";
 import { post } from "../_lib/client";
@@ -30,6 +30,7 @@ const Header: FC = () => {
   const onClickLogout = async () => {
     // TODO: components内でリクエストを飛ばさないようにしたい。外からpropsで渡す。
     await post({ destination: "/auth/logout", token: accessToken });
+    clearTokens();
     router.push("/login");
   };
 
@@ -96,6 +97,9 @@ const Header: FC = () => {
                 </MenuItem>
               </MenuGroup>
               <MenuGroup title="個人設定">
+                <MenuItem as={NextLink} href="/users/profile">
+                  プロフィール変更
+                </MenuItem>
                 <MenuItem as={NextLink} href="/users/password">
                   パスワード変更
                 </MenuItem>
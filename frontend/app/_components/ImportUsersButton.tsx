@@ -0,0 +1,186 @@
+import { ACCESS_TOKEN_KEY } from "@/app/_components/auth";
+import { ChangeEvent, FC, useState } from "react";
+import {
+  Modal,
+  ModalOverlay,
+  ModalContent,
+  ModalHeader,
+  ModalFooter,
+  ModalBody,
+  ModalCloseButton,
+  Button,
+  useDisclosure,
+  FormControl,
+  FormLabel,
+  FormHelperText,
+  Input,
+  Flex,
+  Table,
+  Thead,
+  Tbody,
+  Tr,
+  Th,
+  Td,
+  Badge,
+  Text,
+  useToast,
+} from "@chakra-ui/react";
+import useLocalStorageState from "use-local-storage-state";
+import { postCsv } from "../_lib/client";
+import { useSWRConfig } from "swr";
+import { isInvitationsKey } from "../_contexts/invitation";
+import { UserImportResult } from "../_types/invitation";
+
+const IMPORT_PATH = "/api/v1/users/import";
+
+const statusColor = {
+  valid: "green",
+  invalid: "red",
+  invited: "blue",
+};
+
+const ImportUsersButton: FC = () => {
+  const { isOpen, onOpen, onClose } = useDisclosure();
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const toast = useToast();
+  const { mutate } = useSWRConfig();
+  const [csv, setCsv] = useState<string>();
+  const [result, setResult] = useState<UserImportResult>();
+  const [isSubmitting, setIsSubmitting] = useState(false);
+
+  const onFileChange = async (e: ChangeEvent<HTMLInputElement>) => {
+    setResult(undefined);
+    setCsv(await e.target.files?.[0]?.text());
+  };
+
+  const onClosed = () => {
+    setCsv(undefined);
+    setResult(undefined);
+    onClose();
+  };
+
+  const submit = async (dryRun: boolean) => {
+    if (!csv) return;
+    setIsSubmitting(true);
+    const res = await postCsv({
+      destination: `${IMPORT_PATH}?dryRun=${dryRun}`,
+      token: accessToken,
+      body: csv,
+    });
+    setIsSubmitting(false);
+
+    if (res.ok || res.status === 422) {
+      const body = await res.json().catch(() => undefined);
+      if (body?.items) {
+        setResult(body);
+        if (body.committed) {
+          toast({
+            title: `${body.items.length}人のユーザーを招待しました`,
+            status: "success",
+            duration: 5000,
+            isClosable: true,
+          });
+          mutate(isInvitationsKey);
+        }
+        return;
+      }
+    }
+    toast({
+      title: "CSV を取り込めませんでした",
+      description: "ファイルの形式を確認してください。",
+      status: "error",
+      duration: 5000,
+      isClosable: true,
+    });
+  };
+
+  const allValid = result?.items.every((item) => item.status === "valid");
+
+  return (
+    <>
+      <Button colorScheme="teal" variant="outline" onClick={onOpen}>
+        Import CSV
+      </Button>
+
+      <Modal isOpen={isOpen} onClose={onClosed} size="3xl">
+        <ModalOverlay />
+        <ModalContent>
+          <ModalHeader>Import Users</ModalHeader>
+          <ModalCloseButton />
+          <ModalBody>
+            <FormControl my={4}>
+              <FormLabel>CSV File</FormLabel>
+              <Input
+                type="file"
+                accept=".csv,text/csv"
+                onChange={onFileChange}
+              />
+              <FormHelperText>
+                name,email,role の列を持つ CSV
+                を指定してください。取り込んだユーザーには招待のメールを送信します。
+              </FormHelperText>
+            </FormControl>
+            {result && (
+              <Table size="sm" my={4}>
+                <Thead>
+                  <Tr>
+                    <Th>Row</Th>
+                    <Th>Email</Th>
+                    <Th>Status</Th>
+                    <Th>Errors</Th>
+                  </Tr>
+                </Thead>
+                <Tbody>
+                  {result.items.map((item) => (
+                    <Tr key={item.row}>
+                      <Td>{item.row}</Td>
+                      <Td>{item.email}</Td>
+                      <Td>
+                        <Badge colorScheme={statusColor[item.status]}>
+                          {item.status}
+                        </Badge>
+                      </Td>
+                      <Td>
+                        {item.errors?.map((error) => (
+                          <Text key={error} fontSize="sm">
+                            {error}
+                          </Text>
+                        ))}
+                      </Td>
+                    </Tr>
+                  ))}
+                </Tbody>
+              </Table>
+            )}
+            <Flex justifyContent="flex-end">
+              <Button
+                isDisabled={!csv || result?.committed}
+                isLoading={isSubmitting}
+                onClick={() => submit(true)}
+                mr={3}
+              >
+                Validate
+              </Button>
+              <Button
+                colorScheme="blue"
+                isDisabled={!csv || !result?.dryRun || !allValid}
+                isLoading={isSubmitting}
+                onClick={() => submit(false)}
+                mr={3}
+              >
+                Import
+              </Button>
+              <Button variant="ghost" onClick={onClosed}>
+                Close
+              </Button>
+            </Flex>
+          </ModalBody>
+
+          <ModalFooter />
+        </ModalContent>
+      </Modal>
+    </>
+  );
+};
+
+export default ImportUsersButton;
@@ -0,0 +1,113 @@
+import {
+  Button,
+  Heading,
+  Table,
+  Thead,
+  Tbody,
+  Tr,
+  Th,
+  Td,
+  TableContainer,
+  useToast,
+} from "@chakra-ui/react";
+import useLocalStorageState from "use-local-storage-state";
+import { useSWRConfig } from "swr";
+import { FC } from "react";
+import { ACCESS_TOKEN_KEY } from "./auth";
+import { Invitation } from "../_types/invitation";
+import {
+  INVITATIONS_PATH,
+  isInvitationsKey,
+  useInvitations,
+} from "../_contexts/invitation";
+import { del, post } from "../_lib/client";
+
+// 受け入れられていない招待の一覧。管理者にのみ表示する
+const InvitationTable: FC = () => {
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const toast = useToast();
+  const { mutate } = useSWRConfig();
+  const { invitations } = useInvitations();
+
+  const notify = (ok: boolean, title: string) => {
+    toast({
+      title: ok ? title : `${title}できませんでした`,
+      description: ok ? undefined : "サーバーからエラー応答が返却されました。",
+      status: ok ? "success" : "error",
+      duration: 5000,
+      isClosable: true,
+    });
+    mutate(isInvitationsKey);
+  };
+
+  const handleResend = async (invitation: Invitation) => {
+    const res = await post({
+      destination: `${INVITATIONS_PATH}/${invitation.userId}/resend`,
+      token: accessToken,
+    });
+    notify(res.ok, "招待を再送");
+  };
+
+  const handleRevoke = async (invitation: Invitation) => {
+    const res = await del({
+      destination: `${INVITATIONS_PATH}/${invitation.userId}`,
+      token: accessToken,
+    });
+    notify(res.ok, "招待を取り消し");
+  };
+
+  if (!invitations || invitations.items.length === 0) {
+    return null;
+  }
+
+  return (
+    <>
+      <Heading as="h3" size="md" mt={10}>
+        招待中のユーザー
+      </Heading>
+      <TableContainer>
+        <Table variant="simple">
+          <Thead>
+            <Tr>
+              <Th>Email</Th>
+              <Th>Role</Th>
+              <Th>Expires At</Th>
+              <Th></Th>
+            </Tr>
+          </Thead>
+          <Tbody>
+            {invitations.items.map((invitation) => (
+              <Tr key={invitation.userId}>
+                <Td>{invitation.email}</Td>
+                <Td>{invitation.role}</Td>
+                <Td>
+                  {new Date(invitation.expiresAt) < new Date()
+                    ? "期限切れ"
+                    : new Date(invitation.expiresAt).toLocaleString()}
+                </Td>
+                <Td>
+                  <Button
+                    size="sm"
+                    mr={2}
+                    onClick={() => handleResend(invitation)}
+                  >
+                    Resend
+                  </Button>
+                  <Button
+                    size="sm"
+                    colorScheme="red"
+                    onClick={() => handleRevoke(invitation)}
+                  >
+                    Revoke
+                  </Button>
+                </Td>
+              </Tr>
+            ))}
+          </Tbody>
+        </Table>
+      </TableContainer>
+    </>
+  );
+};
+
+export default InvitationTable;
@@ -0,0 +1,138 @@
+import { ACCESS_TOKEN_KEY } from "@/app/_components/auth";
+import { FC } from "react";
+import {
+  Modal,
+  ModalOverlay,
+  ModalContent,
+  ModalHeader,
+  ModalFooter,
+  ModalBody,
+  ModalCloseButton,
+  Button,
+  useDisclosure,
+  FormControl,
+  FormLabel,
+  Input,
+  Select,
+  Flex,
+  useToast,
+  FormErrorMessage,
+} from "@chakra-ui/react";
+import useLocalStorageState from "use-local-storage-state";
+import { SubmitHandler, useForm } from "react-hook-form";
+import { post } from "../_lib/client";
+import { useSWRConfig } from "swr";
+import { INVITATIONS_PATH, isInvitationsKey } from "../_contexts/invitation";
+
+type InvitationInput = {
+  email: string;
+  role: string;
+};
+
+const InviteUserButton: FC = () => {
+  const { isOpen, onOpen, onClose } = useDisclosure();
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const toast = useToast();
+  const { mutate } = useSWRConfig();
+
+  const {
+    handleSubmit,
+    register,
+    reset,
+    formState: { errors, isSubmitting },
+  } = useForm<InvitationInput>({ defaultValues: { role: "User" } });
+
+  const onSubmit: SubmitHandler<InvitationInput> = async (values) => {
+    const res = await post({
+      destination: INVITATIONS_PATH,
+      token: accessToken,
+      body: values,
+    });
+
+    if (res.ok) {
+      toast({
+        title: "招待のメールを送信しました",
+        description: `${values.email}に招待のメールを送信しました`,
+        status: "success",
+        duration: 5000,
+        isClosable: true,
+      });
+      reset();
+      onClose();
+      mutate(isInvitationsKey);
+    } else {
+      toast({
+        title: "ユーザーを招待できませんでした",
+        description:
+          res.status === 409
+            ? "このメールアドレスはすでに登録されています。"
+            : "サーバーからエラー応答が返却されました。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    }
+  };
+
+  return (
+    <>
+      <Button colorScheme="teal" onClick={onOpen}>
+        Invite User
+      </Button>
+
+      <Modal isOpen={isOpen} onClose={onClose}>
+        <ModalOverlay />
+        <ModalContent>
+          <ModalHeader>Invite User</ModalHeader>
+          <ModalCloseButton />
+          <ModalBody>
+            <form onSubmit={handleSubmit(onSubmit)}>
+              <FormControl my={4} isInvalid={!!errors.email} isRequired>
+                <FormLabel>Email</FormLabel>
+                <Input
+                  id="email"
+                  placeholder="User Email"
+                  {...register("email", {
+                    required: "Eメールアドレスは必須です",
+                    pattern: {
+                      value: /\S+@\S+\.\S+/,
+                      message: "Eメールアドレス形式で入力してください",
+                    },
+                  })}
+                />
+                <FormErrorMessage>{errors.email?.message}</FormErrorMessage>
+              </FormControl>
+              <FormControl my={4} isRequired>
+                <FormLabel>Role</FormLabel>
+                <Select id="role" {...register("role")}>
+                  {["Admin", "Librarian", "User"].map((r) => (
+                    <option key={r} value={r}>
+                      {r}
+                    </option>
+                  ))}
+                </Select>
+              </FormControl>
+              <Flex justifyContent="flex-end">
+                <Button
+                  type="submit"
+                  isLoading={isSubmitting}
+                  colorScheme="blue"
+                  mr={3}
+                >
+                  Send
+                </Button>
+                <Button variant="ghost" onClick={onClose}>
+                  Cancel
+                </Button>
+              </Flex>
+            </form>
+          </ModalBody>
+
+          <ModalFooter />
+        </ModalContent>
+      </Modal>
+    </>
+  );
+};
+
+export default InviteUserButton;
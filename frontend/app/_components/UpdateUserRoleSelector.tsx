@@ -1,13 +1,14 @@
 import { Select, useToast } from "@chakra-ui/react";
 import useLocalStorageState from "use-local-storage-state";
 import { ACCESS_TOKEN_KEY } from "./auth";
-import { User } from "../_types/user";
+import { UserDirectoryEntry } from "../_types/user";
+import { isUsersKey } from "../_contexts/user";
 import { useSWRConfig } from "swr";
 import { put } from "../_lib/client";
 import { FC } from "react";
 
 type UpdateUserRoleSelectorProps = {
-  user: User;
+  user: UserDirectoryEntry;
   isCurrentUser: boolean;
 };
 
@@ -35,7 +36,7 @@ const UpdateUserRoleSelector: FC<UpdateUserRoleSelectorProps> = ({
           duration: 5000,
           isClosable: true,
         });
-        mutate(["/api/v1/users", accessToken]);
+        mutate(isUsersKey);
       } else {
         toast({
           title: "ユーザーのロールを更新できませんでした",
@@ -56,7 +57,7 @@ const UpdateUserRoleSelector: FC<UpdateUserRoleSelectorProps> = ({
         handleUpdateRole(e.target.value);
       }}
     >
-      {["Admin", "User"].map((r) => (
+      {["Admin", "Librarian", "User"].map((r) => (
         <option
           key={`${user.id}-${r}`}
           {...{
@@ -0,0 +1,81 @@
+import { Select, useToast } from "@chakra-ui/react";
+import useLocalStorageState from "use-local-storage-state";
+import { ACCESS_TOKEN_KEY } from "./auth";
+import { UserDirectoryEntry, UserStatus } from "../_types/user";
+import { isUsersKey } from "../_contexts/user";
+import { useSWRConfig } from "swr";
+import { put } from "../_lib/client";
+import { FC } from "react";
+
+// 現在の状態から変更できる状態。無効化はユーザーの削除ボタンからおこなう
+const TRANSITIONS: Record<UserStatus, UserStatus[]> = {
+  invited: ["active"],
+  active: ["suspended"],
+  suspended: ["active"],
+  deactivated: [],
+};
+
+const LABELS: Record<UserStatus, string> = {
+  invited: "招待中",
+  active: "有効",
+  suspended: "利用停止",
+  deactivated: "無効",
+};
+
+type UpdateUserStatusSelectorProps = {
+  user: UserDirectoryEntry;
+  isCurrentUser: boolean;
+};
+
+const UpdateUserStatusSelector: FC<UpdateUserStatusSelectorProps> = ({
+  user,
+  isCurrentUser,
+}: UpdateUserStatusSelectorProps) => {
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const toast = useToast();
+  const { mutate } = useSWRConfig();
+  const current = user.status ?? "active";
+
+  const handleUpdateStatus = async (status: UserStatus) => {
+    const res = await put({
+      destination: `/api/v1/users/${user.id}/status`,
+      token: accessToken,
+      body: { status: status },
+    });
+
+    if (res.ok) {
+      toast({
+        title: "ユーザーの状態を更新しました",
+        description: `${user.name}の状態を${LABELS[status]}に変更しました`,
+        status: "success",
+        duration: 5000,
+        isClosable: true,
+      });
+    } else {
+      toast({
+        title: "ユーザーの状態を更新できませんでした",
+        description: "サーバーからエラー応答が返却されました。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    }
+    mutate(isUsersKey);
+  };
+
+  return (
+    <Select
+      disabled={isCurrentUser || TRANSITIONS[current].length === 0}
+      value={current}
+      onChange={(e) => {
+        handleUpdateStatus(e.target.value as UserStatus);
+      }}
+    >
+      {[current, ...TRANSITIONS[current]].map((s) => (
+        <option key={`${user.id}-${s}`} value={s} label={LABELS[s]} />
+      ))}
+    </Select>
+  );
+};
+
+export default UpdateUserStatusSelector;
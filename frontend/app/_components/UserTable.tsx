@@ -7,13 +7,14 @@ import {
   Td,
   TableContainer,
 } from "@chakra-ui/react";
-import { User } from "../_types/user";
+import { User, UserDirectoryEntry } from "../_types/user";
 import DeleteUserButton from "./DeleteUserButton";
 import UpdateUserRoleSelector from "./UpdateUserRoleSelector";
+import UpdateUserStatusSelector from "./UpdateUserStatusSelector";
 import { FC } from "react";
 
 type UserTableProps = {
-  users: User[];
+  users: UserDirectoryEntry[];
   currentUser: User;
 };
 
@@ -29,8 +30,9 @@ const UserTable: FC<UserTableProps> = ({
         <Thead>
           <Tr>
             <Th>Name</Th>
-            <Th>Email</Th>
-            <Th>Role</Th>
+            {isAdmin && <Th>Email</Th>}
+            {isAdmin && <Th>Role</Th>}
+            {isAdmin && <Th>Status</Th>}
             {isAdmin && <Th></Th>}
           </Tr>
         </Thead>
@@ -38,22 +40,31 @@ const UserTable: FC<UserTableProps> = ({
           {users.map((user) => (
             <Tr key={user.id}>
               <Td>{user.name}</Td>
-              <Td>{user.email}</Td>
-              <Td>
-                {isAdmin ? (
-                  <UpdateUserRoleSelector
+              {isAdmin && <Td>{user.email}</Td>}
+              {isAdmin && (
+                <Td>
+                  {user.status !== "deactivated" && (
+                    <UpdateUserRoleSelector
+                      user={user}
+                      isCurrentUser={user.id === currentUser.id}
+                    />
+                  )}
+                </Td>
+              )}
+              {isAdmin && (
+                <Td>
+                  <UpdateUserStatusSelector
                     user={user}
                     isCurrentUser={user.id === currentUser.id}
                   />
-                ) : (
-                  user.role
-                )}
-              </Td>
+                </Td>
+              )}
               {isAdmin && (
                 <Td>
-                  {user.id !== currentUser.id && (
-                    <DeleteUserButton user={user} />
-                  )}
+                  {user.id !== currentUser.id &&
+                    user.status !== "deactivated" && (
+                      <DeleteUserButton user={user} />
+                    )}
                 </Td>
               )}
             </Tr>
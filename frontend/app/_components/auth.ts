@@ -1,6 +1,7 @@
 import { redirect } from "next/navigation";
 import { FC } from "react";
 import useLocalStorageState from "use-local-storage-state";
+import { AccessTokenResponse } from "../_types/auth";
 
 const AuthProvider: FC<{ children: React.ReactNode }> = ({ children }) => {
   const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
@@ -15,3 +16,33 @@ const AuthProvider: FC<{ children: React.ReactNode }> = ({ children }) => {
 export default AuthProvider;
 
 export const ACCESS_TOKEN_KEY = "access-token";
+export const REFRESH_TOKEN_KEY = "refresh-token";
+
+// useLocalStorageState と同じ形式（JSON）で保存し、同じタブで使っているフックにも変更を知らせる
+const writeStorage = (key: string, value?: string) => {
+  if (value === undefined) {
+    localStorage.removeItem(key);
+  } else {
+    localStorage.setItem(key, JSON.stringify(value));
+  }
+  window.dispatchEvent(
+    new StorageEvent("storage", { key, storageArea: localStorage }),
+  );
+};
+
+export const readRefreshToken = (): string | undefined => {
+  const value = localStorage.getItem(REFRESH_TOKEN_KEY);
+  return value === null ? undefined : JSON.parse(value);
+};
+
+// ログインやトークンの再発行で受け取ったトークンを保存する
+export const saveTokens = (tokens: AccessTokenResponse) => {
+  writeStorage(ACCESS_TOKEN_KEY, tokens.accessToken);
+  writeStorage(REFRESH_TOKEN_KEY, tokens.refreshToken);
+};
+
+// ログアウトした場合や、リフレッシュトークンが使えなくなった場合に削除する
+export const clearTokens = () => {
+  writeStorage(ACCESS_TOKEN_KEY);
+  writeStorage(REFRESH_TOKEN_KEY);
+};
@@ -0,0 +1,24 @@
+import useSWR from "swr";
+import useLocalStorageState from "use-local-storage-state";
+import { ACCESS_TOKEN_KEY } from "../_components/auth";
+import { fetchWithToken } from "../_lib/client";
+import { Invitations } from "../_types/invitation";
+
+export const INVITATIONS_PATH = "/api/v1/invitations";
+
+// 招待を作成・再送・取り消した後に、一覧のキャッシュを破棄するために使う
+export const isInvitationsKey = (key: unknown) =>
+  Array.isArray(key) && key[0] === INVITATIONS_PATH;
+
+export const useInvitations = () => {
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const { data, error } = useSWR<Invitations>(
+    [INVITATIONS_PATH, accessToken],
+    ([destination, token]) => fetchWithToken(destination, token),
+  );
+  return {
+    invitations: data,
+    isLoading: !error && !data,
+    isError: error,
+  };
+};
@@ -17,10 +17,27 @@ export const useCurrentUser = () => {
   };
 };
 
-export const useUsers = () => {
+export type UsersQuery = {
+  limit: number;
+  offset: number;
+  q?: string;
+};
+
+// 一覧を更新した後に、検索条件やページによらずキャッシュを破棄するために使う
+export const isUsersKey = (key: unknown) =>
+  Array.isArray(key) && String(key[0]).startsWith("/api/v1/users?");
+
+export const useUsers = (query: UsersQuery) => {
   const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const params = new URLSearchParams({
+    limit: query.limit.toString(),
+    offset: query.offset.toString(),
+  });
+  if (query.q) {
+    params.set("q", query.q);
+  }
   const { data, error } = useSWR<Users>(
-    ["/api/v1/users", accessToken],
+    [`/api/v1/users?${params.toString()}`, accessToken],
     ([destination, token]) => fetchWithToken(destination, token),
   );
   return {
@@ -1,10 +1,16 @@
+import {
+  clearTokens,
+  readRefreshToken,
+  saveTokens,
+} from "../_components/auth";
+import { AccessTokenResponse } from "../_types/auth";
+
 export const fetchWithToken = async (
   destination: string,
   token: string | unknown,
 ) => {
-  return fetcher(destination, {
+  return fetcherWithToken(destination, token, {
     headers: {
-      Authorization: `Bearer ${token}`,
       "Content-Type": "application/json",
     },
   }).then((res) => res.json());
@@ -23,6 +29,51 @@ const fetcher = async (destination: string, init: RequestInit) => {
   return res;
 };
 
+// 同時に複数のリクエストが 401 になっても、リフレッシュトークンは一度だけ使う
+// 置き換え済みのリフレッシュトークンを使うと、セッションが終了してしまうため
+let refreshing: Promise<string | undefined> | undefined;
+
+const refreshAccessToken = async (): Promise<string | undefined> => {
+  const refreshToken = readRefreshToken();
+  if (refreshToken === undefined) return undefined;
+
+  const res = await fetcher("/auth/refresh", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ refreshToken }),
+  });
+  if (!res.ok) {
+    // 再発行できない場合はログインからやり直す
+    clearTokens();
+    return undefined;
+  }
+  const tokens: AccessTokenResponse = await res.json();
+  saveTokens(tokens);
+  return tokens.accessToken;
+};
+
+// アクセストークンの有効期限が切れていた場合は、リフレッシュトークンで再発行してから送り直す
+const fetcherWithToken = async (
+  destination: string,
+  token: string | unknown,
+  init: RequestInit,
+) => {
+  const send = (token: string | unknown) =>
+    fetcher(destination, {
+      ...init,
+      headers: { ...init.headers, Authorization: `Bearer ${token}` },
+    });
+
+  const res = await send(token);
+  if (res.status !== 401) return res;
+
+  refreshing ??= refreshAccessToken().finally(() => {
+    refreshing = undefined;
+  });
+  const newToken = await refreshing;
+  return newToken === undefined ? res : send(newToken);
+};
+
 type RequestInfo<T> = {
   destination: string;
   token?: string | unknown;
@@ -33,20 +84,18 @@ const sender = async <T>(
   info: RequestInfo<T>,
   method: "POST" | "PUT" | "DELETE",
 ) => {
-  const basicHeaders = {
-    "Content-Type": "application/json",
-  };
-  const headers = info.token
-    ? { Authorization: `Bearer ${info.token}`, ...basicHeaders }
-    : basicHeaders;
   const basicInit = {
     method: method,
-    headers: headers,
+    headers: {
+      "Content-Type": "application/json",
+    },
   };
   const init = info.body
     ? { ...basicInit, body: JSON.stringify(info.body) }
     : basicInit;
-  return fetcher(info.destination, init);
+  return info.token
+    ? fetcherWithToken(info.destination, info.token, init)
+    : fetcher(info.destination, init);
 };
 
 export const post = async <T>(info: RequestInfo<T>) => {
@@ -60,3 +109,17 @@ export const put = async <T>(info: RequestInfo<T>) => {
 export const del = async <T>(info: RequestInfo<T>) => {
   return sender(info, "DELETE");
 };
+
+export const postCsv = async (info: {
+  destination: string;
+  token: string | unknown;
+  body: string;
+}) => {
+  return fetcherWithToken(info.destination, info.token, {
+    method: "POST",
+    headers: {
+      "Content-Type": "text/csv",
+    },
+    body: info.body,
+  });
+};
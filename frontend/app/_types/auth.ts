@@ -0,0 +1,31 @@
+// /auth/login のレスポンス。パスワードの再設定や 2 段階認証が必要な場合は、アクセストークンの代わりに返る
+export type AccessTokenResponse = {
+  userId: string;
+  accessToken: string;
+  expiresIn: number;
+  refreshToken: string;
+};
+
+export type PasswordChangeRequiredResponse = {
+  userId: string;
+  passwordResetToken: string;
+};
+
+export type TotpEnrollment = {
+  secret: string;
+  provisioningUri: string;
+};
+
+export type TwoFactorRequiredResponse = {
+  twoFactorChallenge: string;
+  enrollment?: TotpEnrollment;
+};
+
+export type LoginResponse =
+  | AccessTokenResponse
+  | PasswordChangeRequiredResponse
+  | TwoFactorRequiredResponse;
+
+export type TwoFactorLoginResponse = AccessTokenResponse & {
+  recoveryCodes?: string[];
+};
@@ -0,0 +1,26 @@
+export type Invitation = {
+  userId: string;
+  email: string;
+  role: string;
+  invitedBy?: string;
+  expiresAt: string;
+  createdAt: string;
+};
+
+export type Invitations = {
+  items: Invitation[];
+};
+
+export type UserImportItem = {
+  row: number;
+  email: string;
+  status: "valid" | "invalid" | "invited";
+  userId?: string;
+  errors?: string[];
+};
+
+export type UserImportResult = {
+  dryRun: boolean;
+  committed: boolean;
+  items: UserImportItem[];
+};
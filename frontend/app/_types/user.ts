@@ -1,10 +1,22 @@
+import { PaginatedList } from "./book";
+
+export type UserStatus = "invited" | "active" | "suspended" | "deactivated";
+
 export type User = {
   id: string;
   name: string;
   email: string;
   role: string;
+  status: UserStatus;
 };
 
-export type Users = {
-  items: User[];
+// ユーザー一覧の項目。管理者以外には、メールアドレスとロールは返らない
+export type UserDirectoryEntry = {
+  id: string;
+  name: string;
+  email?: string;
+  role?: string;
+  status?: UserStatus;
 };
+
+export type Users = PaginatedList<UserDirectoryEntry>;
@@ -0,0 +1,61 @@
+"use client";
+
+import { Container, Heading, Spinner, Text } from "@chakra-ui/react";
+import NextLink from "next/link";
+import { useSearchParams } from "next/navigation";
+import { useEffect, useState } from "react";
+import { post } from "@/app/_lib/client";
+
+type ConfirmationStatus = "confirming" | "confirmed" | "conflict" | "invalid";
+
+export default function ConfirmEmailChange() {
+  const searchParams = useSearchParams();
+  const token = searchParams.get("token");
+  const [status, setStatus] = useState<ConfirmationStatus>("confirming");
+
+  useEffect(() => {
+    if (!token) {
+      setStatus("invalid");
+      return;
+    }
+    // 新しいメールアドレスのメールから開かれるため、ログインしていなくても確認できる
+    post({
+      destination: "/auth/email-change/confirm",
+      body: { token },
+    }).then((res) => {
+      if (res.ok) {
+        setStatus("confirmed");
+      } else if (res.status === 409) {
+        setStatus("conflict");
+      } else {
+        setStatus("invalid");
+      }
+    });
+  }, [token]);
+
+  return (
+    <Container maxW="container.md" my={20}>
+      <Heading as="h2" size="xl" mb={5}>
+        メールアドレスの変更
+      </Heading>
+      {status === "confirming" && <Spinner />}
+      {status === "confirmed" && (
+        <Text>
+          メールアドレスの変更が完了しました。
+          <NextLink href="/login">ログイン</NextLink>
+          には新しいメールアドレスを使ってください。
+        </Text>
+      )}
+      {status === "conflict" && (
+        <Text>
+          このメールアドレスはすでに他のユーザーに使われているため、変更できませんでした。
+        </Text>
+      )}
+      {status === "invalid" && (
+        <Text>
+          リンクが無効か、有効期限が切れています。もう一度プロフィールの変更からやり直してください。
+        </Text>
+      )}
+    </Container>
+  );
+}
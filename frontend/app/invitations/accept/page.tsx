@@ -0,0 +1,107 @@
+"use client";
+
+import {
+  Button,
+  Container,
+  FormControl,
+  FormErrorMessage,
+  FormLabel,
+  Heading,
+  Input,
+  Text,
+  useToast,
+} from "@chakra-ui/react";
+import NextLink from "next/link";
+import { useSearchParams } from "next/navigation";
+import { useState } from "react";
+import { SubmitHandler, useForm } from "react-hook-form";
+import { post } from "@/app/_lib/client";
+
+type AcceptInvitationInput = {
+  name: string;
+  password: string;
+};
+
+export default function AcceptInvitation() {
+  const searchParams = useSearchParams();
+  const token = searchParams.get("token");
+  const [accepted, setAccepted] = useState(false);
+  const toast = useToast();
+
+  const {
+    handleSubmit,
+    register,
+    formState: { errors, isSubmitting },
+  } = useForm<AcceptInvitationInput>();
+
+  // 招待のメールから開かれるため、ログインしていなくても受け入れられる
+  const onSubmit: SubmitHandler<AcceptInvitationInput> = async (values) => {
+    const res = await post({
+      destination: "/auth/invitations/accept",
+      body: { token, ...values },
+    });
+
+    if (res.ok) {
+      setAccepted(true);
+    } else {
+      toast({
+        title: "招待を受け入れられませんでした",
+        description:
+          "リンクが無効か、有効期限が切れています。管理者に招待の再送を依頼してください。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    }
+  };
+
+  return (
+    <Container maxW="container.md" my={20}>
+      <Heading as="h2" size="xl" mb={5}>
+        招待の受け入れ
+      </Heading>
+      {!token && <Text>リンクが無効です。</Text>}
+      {token && accepted && (
+        <Text>
+          登録が完了しました。設定したパスワードで
+          <NextLink href="/login">ログイン</NextLink>
+          してください。
+        </Text>
+      )}
+      {token && !accepted && (
+        <form onSubmit={handleSubmit(onSubmit)}>
+          <FormControl isInvalid={!!errors.name} mb={5} isRequired>
+            <FormLabel htmlFor="name">名前</FormLabel>
+            <Input
+              id="name"
+              placeholder="名前を入力してください"
+              {...register("name", {
+                required: "入力必須です",
+                maxLength: {
+                  value: 255,
+                  message: "255 文字以内で入力してください",
+                },
+              })}
+            />
+            <FormErrorMessage>{errors.name?.message}</FormErrorMessage>
+          </FormControl>
+          <FormControl isInvalid={!!errors.password} mb={5} isRequired>
+            <FormLabel htmlFor="password">パスワード</FormLabel>
+            <Input
+              id="password"
+              type="password"
+              placeholder="パスワードを入力してください"
+              {...register("password", {
+                required: "入力必須です",
+              })}
+            />
+            <FormErrorMessage>{errors.password?.message}</FormErrorMessage>
+          </FormControl>
+          <Button type="submit" isLoading={isSubmitting} colorScheme="blue">
+            登録する
+          </Button>
+        </form>
+      )}
+    </Container>
+  );
+}
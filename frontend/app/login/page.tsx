@@ -16,41 +16,148 @@ import {
   Alert,
   AlertIcon,
   AlertDescription,
+  Code,
+  List,
+  ListItem,
 } from "@chakra-ui/react";
 import { useState } from "react";
 import { ViewIcon, ViewOffIcon } from "@chakra-ui/icons";
 import { useRouter } from "next/navigation";
-import { ACCESS_TOKEN_KEY } from "../_components/auth";
-import useLocalStorageState from "use-local-storage-state";
+import { saveTokens } from "../_components/auth";
 import { SubmitHandler, useForm } from "react-hook-form";
 import { post } from "../_lib/client";
+import {
+  LoginResponse,
+  TotpEnrollment,
+  TwoFactorLoginResponse,
+} from "../_types/auth";
 
 type LoginInput = {
   email: string;
   password: string;
 };
 
+type PasswordChangeInput = {
+  newPassword: string;
+};
+
+type TwoFactorInput = {
+  code: string;
+};
+
+// ログインの段階。パスワードの再設定や 2 段階認証が求められた場合は、続けてその入力を求める
+type LoginStep =
+  | { kind: "credentials" }
+  | { kind: "passwordChange"; passwordResetToken: string }
+  | {
+      kind: "twoFactor";
+      twoFactorChallenge: string;
+      enrollment?: TotpEnrollment;
+    }
+  | { kind: "recoveryCodes"; recoveryCodes: string[] };
+
 export default function Login() {
   const [showPassword, setShowPassword] = useState(false);
   const [error, setError] = useState("");
+  const [message, setMessage] = useState("");
+  const [step, setStep] = useState<LoginStep>({ kind: "credentials" });
   const router = useRouter();
-  const [_accessToken, setAccessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
 
   const {
     handleSubmit,
     register,
     formState: { errors, isSubmitting },
   } = useForm<LoginInput>();
+  const passwordChangeForm = useForm<PasswordChangeInput>();
+  const twoFactorForm = useForm<TwoFactorInput>();
 
   const onSubmit: SubmitHandler<LoginInput> = async (input) => {
     const res = await post({ destination: "/auth/login", body: input });
 
-    if (res.ok) {
-      const json = await res.json();
-      setAccessToken(json.accessToken);
+    if (res.status === 429) {
+      setError(
+        "ログインの失敗が続いたため、一時的にログインできません。しばらくしてから再度お試しください。",
+      );
+      return;
+    }
+    if (!res.ok) {
+      setError("メールアドレスまたはパスワードが間違っています。");
+      return;
+    }
+
+    setError("");
+    setMessage("");
+    const json: LoginResponse = await res.json();
+    if ("accessToken" in json) {
+      saveTokens(json);
       router.push("/");
+    } else if ("passwordResetToken" in json) {
+      setStep({
+        kind: "passwordChange",
+        passwordResetToken: json.passwordResetToken,
+      });
     } else {
-      setError("メールアドレスまたはパスワードが間違っています。");
+      setStep({
+        kind: "twoFactor",
+        twoFactorChallenge: json.twoFactorChallenge,
+        enrollment: json.enrollment,
+      });
+    }
+  };
+
+  // 管理者がパスワードを仮のものに置き換えた場合は、新しいパスワードを設定してからログインし直す
+  const onSubmitPasswordChange: SubmitHandler<PasswordChangeInput> = async (
+    input,
+  ) => {
+    if (step.kind !== "passwordChange") return;
+    const res = await post({
+      destination: "/auth/password/reset",
+      body: { token: step.passwordResetToken, newPassword: input.newPassword },
+    });
+
+    if (res.ok) {
+      setError("");
+      setMessage(
+        "パスワードを変更しました。新しいパスワードでログインしてください。",
+      );
+      setStep({ kind: "credentials" });
+    } else if (res.status === 400) {
+      setError(
+        "パスワードが条件を満たしていません。推測されやすいパスワードや、メールアドレスと同じパスワードは使えません。",
+      );
+    } else {
+      setError(
+        "パスワードを変更できませんでした。もう一度ログインからやり直してください。",
+      );
+      setStep({ kind: "credentials" });
+    }
+  };
+
+  const onSubmitTwoFactor: SubmitHandler<TwoFactorInput> = async (input) => {
+    if (step.kind !== "twoFactor") return;
+    const res = await post({
+      destination: "/auth/login/two-factor",
+      body: { twoFactorChallenge: step.twoFactorChallenge, code: input.code },
+    });
+
+    if (res.ok) {
+      const json: TwoFactorLoginResponse = await res.json();
+      saveTokens(json);
+      // 登録と同時に発行されたリカバリーコードは、このときしか表示できない
+      if (json.recoveryCodes && json.recoveryCodes.length > 0) {
+        setError("");
+        setStep({ kind: "recoveryCodes", recoveryCodes: json.recoveryCodes });
+      } else {
+        router.push("/");
+      }
+    } else if (res.status === 403) {
+      setError("コードが間違っています。");
+    } else {
+      // チャレンジが無効になった場合は、メールアドレスとパスワードの入力からやり直す
+      setError(
+        "認証を完了できませんでした。もう一度ログインからやり直してください。",
+      );
+      setStep({ kind: "credentials" });
     }
   };
 
@@ -76,66 +183,198 @@ export default function Login() {
           boxShadow={"lg"}
           p={8}
         >
-          <form onSubmit={handleSubmit(onSubmit)}>
-            <Stack spacing={4}>
-              {error && (
-                <Alert status="error">
-                  <AlertIcon />
-                  <AlertDescription>{error}</AlertDescription>
-                </Alert>
-              )}
-              <FormControl
-                id="email"
-                isInvalid={errors.email ? true : false}
-                isRequired
-              >
-                <FormLabel htmlFor="email">メールアドレス</FormLabel>
-                <Input
-                  type="email"
-                  {...register("email", { required: true })}
-                />
-              </FormControl>
-              <FormControl
-                id="password"
-                isInvalid={errors.password ? true : false}
-                isRequired
-              >
-                <FormLabel htmlFor="password">パスワード</FormLabel>
-                <InputGroup>
+          {step.kind === "credentials" && (
+            <form onSubmit={handleSubmit(onSubmit)}>
+              <Stack spacing={4}>
+                {error && (
+                  <Alert status="error">
+                    <AlertIcon />
+                    <AlertDescription>{error}</AlertDescription>
+                  </Alert>
+                )}
+                {message && (
+                  <Alert status="success">
+                    <AlertIcon />
+                    <AlertDescription>{message}</AlertDescription>
+                  </Alert>
+                )}
+                <FormControl
+                  id="email"
+                  isInvalid={errors.email ? true : false}
+                  isRequired
+                >
+                  <FormLabel htmlFor="email">メールアドレス</FormLabel>
                   <Input
-                    type={showPassword ? "text" : "password"}
-                    {...register("password", { required: true })}
+                    type="email"
+                    {...register("email", { required: true })}
                   />
-                  <InputRightElement h={"full"}>
-                    <Button
-                      variant={"ghost"}
-                      onClick={() =>
-                        setShowPassword((showPassword) => !showPassword)
-                      }
-                      type="submit"
-                    >
-                      {showPassword ? <ViewIcon /> : <ViewOffIcon />}
-                    </Button>
-                  </InputRightElement>
-                </InputGroup>
-              </FormControl>
-              <Stack spacing={10} pt={2}>
-                <Button
-                  loadingText="ログイン中..."
-                  size="lg"
-                  bg={"blue.400"}
-                  color={"white"}
-                  _hover={{
-                    bg: "blue.500",
-                  }}
-                  type="submit"
-                  isLoading={isSubmitting}
+                </FormControl>
+                <FormControl
+                  id="password"
+                  isInvalid={errors.password ? true : false}
+                  isRequired
                 >
-                  ログイン
-                </Button>
+                  <FormLabel htmlFor="password">パスワード</FormLabel>
+                  <InputGroup>
+                    <Input
+                      type={showPassword ? "text" : "password"}
+                      {...register("password", { required: true })}
+                    />
+                    <InputRightElement h={"full"}>
+                      <Button
+                        variant={"ghost"}
+                        onClick={() =>
+                          setShowPassword((showPassword) => !showPassword)
+                        }
+                        type="submit"
+                      >
+                        {showPassword ? <ViewIcon /> : <ViewOffIcon />}
+                      </Button>
+                    </InputRightElement>
+                  </InputGroup>
+                </FormControl>
+                <Stack spacing={10} pt={2}>
+                  <Button
+                    loadingText="ログイン中..."
+                    size="lg"
+                    bg={"blue.400"}
+                    color={"white"}
+                    _hover={{
+                      bg: "blue.500",
+                    }}
+                    type="submit"
+                    isLoading={isSubmitting}
+                  >
+                    ログイン
+                  </Button>
+                </Stack>
               </Stack>
+            </form>
+          )}
+          {step.kind === "passwordChange" && (
+            <form
+              onSubmit={passwordChangeForm.handleSubmit(onSubmitPasswordChange)}
+            >
+              <Stack spacing={4}>
+                <Text>
+                  パスワードの再設定が必要です。新しいパスワードを入力してください。
+                </Text>
+                {error && (
+                  <Alert status="error">
+                    <AlertIcon />
+                    <AlertDescription>{error}</AlertDescription>
+                  </Alert>
+                )}
+                <FormControl
+                  id="newPassword"
+                  isInvalid={
+                    passwordChangeForm.formState.errors.newPassword
+                      ? true
+                      : false
+                  }
+                  isRequired
+                >
+                  <FormLabel htmlFor="newPassword">新しいパスワード</FormLabel>
+                  <Input
+                    type="password"
+                    {...passwordChangeForm.register("newPassword", {
+                      required: true,
+                    })}
+                  />
+                </FormControl>
+                <Stack spacing={10} pt={2}>
+                  <Button
+                    loadingText="変更中..."
+                    size="lg"
+                    bg={"blue.400"}
+                    color={"white"}
+                    _hover={{
+                      bg: "blue.500",
+                    }}
+                    type="submit"
+                    isLoading={passwordChangeForm.formState.isSubmitting}
+                  >
+                    パスワードを変更する
+                  </Button>
+                </Stack>
+              </Stack>
+            </form>
+          )}
+          {step.kind === "twoFactor" && (
+            <form onSubmit={twoFactorForm.handleSubmit(onSubmitTwoFactor)}>
+              <Stack spacing={4}>
+                {step.enrollment ? (
+                  <Text>
+                    管理者は 2 段階認証の設定が必要です。認証アプリに次の鍵を登録し、表示されたコードを入力してください。
+                    <Code display="block" mt={2}>
+                      {step.enrollment.secret}
+                    </Code>
+                  </Text>
+                ) : (
+                  <Text>
+                    認証アプリに表示されたコードか、リカバリーコードを入力してください。
+                  </Text>
+                )}
+                {error && (
+                  <Alert status="error">
+                    <AlertIcon />
+                    <AlertDescription>{error}</AlertDescription>
+                  </Alert>
+                )}
+                <FormControl
+                  id="code"
+                  isInvalid={twoFactorForm.formState.errors.code ? true : false}
+                  isRequired
+                >
+                  <FormLabel htmlFor="code">コード</FormLabel>
+                  <Input
+                    autoComplete="one-time-code"
+                    {...twoFactorForm.register("code", { required: true })}
+                  />
+                </FormControl>
+                <Stack spacing={10} pt={2}>
+                  <Button
+                    loadingText="確認中..."
+                    size="lg"
+                    bg={"blue.400"}
+                    color={"white"}
+                    _hover={{
+                      bg: "blue.500",
+                    }}
+                    type="submit"
+                    isLoading={twoFactorForm.formState.isSubmitting}
+                  >
+                    確認する
+                  </Button>
+                </Stack>
+              </Stack>
+            </form>
+          )}
+          {step.kind === "recoveryCodes" && (
+            <Stack spacing={4}>
+              <Text>
+                2 段階認証を設定しました。認証アプリが使えなくなった場合に備え、次のリカバリーコードを控えておいてください。コードは再び表示できません。
+              </Text>
+              <List spacing={1}>
+                {step.recoveryCodes.map((code) => (
+                  <ListItem key={code}>
+                    <Code>{code}</Code>
+                  </ListItem>
+                ))}
+              </List>
+              <Button
+                size="lg"
+                bg={"blue.400"}
+                color={"white"}
+                _hover={{
+                  bg: "blue.500",
+                }}
+                onClick={() => router.push("/")}
+              >
+                控えました
+              </Button>
             </Stack>
-          </form>
+          )}
         </Box>
       </Stack>
     </Flex>
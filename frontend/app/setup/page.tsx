@@ -0,0 +1,120 @@
+"use client";
+
+import {
+  Button,
+  Container,
+  FormControl,
+  FormErrorMessage,
+  FormLabel,
+  Heading,
+  Input,
+  Text,
+  useToast,
+} from "@chakra-ui/react";
+import NextLink from "next/link";
+import { useState } from "react";
+import { SubmitHandler, useForm } from "react-hook-form";
+import { post } from "@/app/_lib/client";
+
+type SetupInput = {
+  name: string;
+  email: string;
+  password: string;
+};
+
+export default function Setup() {
+  const [completed, setCompleted] = useState(false);
+  const toast = useToast();
+
+  const {
+    handleSubmit,
+    register,
+    formState: { errors, isSubmitting },
+  } = useForm<SetupInput>();
+
+  // 管理者がまだいない間だけ、ログインせずに最初の管理者を作成できる
+  const onSubmit: SubmitHandler<SetupInput> = async (values) => {
+    const res = await post({ destination: "/auth/setup", body: values });
+
+    if (res.ok) {
+      setCompleted(true);
+    } else if (res.status === 409) {
+      toast({
+        title: "初期設定はすでに完了しています",
+        description: "管理者のアカウントでログインしてください。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    } else {
+      toast({
+        title: "管理者を作成できませんでした",
+        description:
+          "入力内容を確認してください。推測されやすいパスワードや、メールアドレスと同じパスワードは使えません。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    }
+  };
+
+  return (
+    <Container maxW="container.md" my={20}>
+      <Heading as="h2" size="xl" mb={5}>
+        初期設定
+      </Heading>
+      {completed ? (
+        <Text>
+          管理者を作成しました。設定したパスワードで
+          <NextLink href="/login">ログイン</NextLink>
+          してください。
+        </Text>
+      ) : (
+        <form onSubmit={handleSubmit(onSubmit)}>
+          <FormControl isInvalid={!!errors.name} mb={5} isRequired>
+            <FormLabel htmlFor="name">名前</FormLabel>
+            <Input
+              id="name"
+              placeholder="名前を入力してください"
+              {...register("name", {
+                required: "入力必須です",
+                maxLength: {
+                  value: 255,
+                  message: "255 文字以内で入力してください",
+                },
+              })}
+            />
+            <FormErrorMessage>{errors.name?.message}</FormErrorMessage>
+          </FormControl>
+          <FormControl isInvalid={!!errors.email} mb={5} isRequired>
+            <FormLabel htmlFor="email">メールアドレス</FormLabel>
+            <Input
+              id="email"
+              type="email"
+              placeholder="メールアドレスを入力してください"
+              {...register("email", {
+                required: "入力必須です",
+              })}
+            />
+            <FormErrorMessage>{errors.email?.message}</FormErrorMessage>
+          </FormControl>
+          <FormControl isInvalid={!!errors.password} mb={5} isRequired>
+            <FormLabel htmlFor="password">パスワード</FormLabel>
+            <Input
+              id="password"
+              type="password"
+              placeholder="パスワードを入力してください"
+              {...register("password", {
+                required: "入力必須です",
+              })}
+            />
+            <FormErrorMessage>{errors.password?.message}</FormErrorMessage>
+          </FormControl>
+          <Button type="submit" isLoading={isSubmitting} colorScheme="blue">
+            管理者を作成する
+          </Button>
+        </form>
+      )}
+    </Container>
+  );
+}
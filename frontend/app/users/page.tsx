@@ -1,14 +1,53 @@
 "use client";
 
 import Header from "@/app/_components/Header";
-import { Container, Heading, Stack, Text } from "@chakra-ui/react";
+import {
+  Container,
+  Flex,
+  Heading,
+  Input,
+  InputGroup,
+  InputLeftElement,
+  Stack,
+  Text,
+} from "@chakra-ui/react";
+import { SearchIcon } from "@chakra-ui/icons";
+import { NextPage } from "next";
+import { usePathname, useRouter } from "next/navigation";
+import { useState } from "react";
 import { useCurrentUser, useUsers } from "@/app/_contexts/user";
 import UserTable from "@/app/_components/UserTable";
 import AddUserButton from "@/app/_components/AddUserButton";
+import InviteUserButton from "@/app/_components/InviteUserButton";
+import ImportUsersButton from "@/app/_components/ImportUsersButton";
+import InvitationTable from "@/app/_components/InvitationTable";
+import Pagination from "@/app/_components/Pagination";
+
+const USERS_PER_PAGE = 20;
+
+const ListUser: NextPage = ({
+  searchParams,
+}: {
+  searchParams?: {
+    limit?: string;
+    offset?: string;
+  };
+}) => {
+  const currentLimit = Number(searchParams?.limit) || USERS_PER_PAGE;
+  const currentOffset = Number(searchParams?.offset) || 0;
+  const [keyword, setKeyword] = useState("");
+  const pathname = usePathname();
+  const { replace } = useRouter();
 
-export default function ListUser() {
   const { currentUser } = useCurrentUser();
-  const { users } = useUsers();
+  const { users } = useUsers({
+    limit: currentLimit,
+    offset: currentOffset,
+    q: keyword,
+  });
+  const limit = users?.limit ?? USERS_PER_PAGE;
+  const offset = users?.offset ?? 0;
+  const total = users?.total ?? 0;
 
   return (
     <>
@@ -20,8 +59,34 @@ export default function ListUser() {
         <Container maxW="container.md" my={20}>
           {users && currentUser ? (
             <Stack>
-              {currentUser.role === "Admin" && <AddUserButton />}
+              {currentUser.role === "Admin" && (
+                <Flex justifyContent="flex-end" gap={2}>
+                  <ImportUsersButton />
+                  <InviteUserButton />
+                  <AddUserButton />
+                </Flex>
+              )}
+              <InputGroup>
+                <InputLeftElement pointerEvents="none">
+                  <SearchIcon />
+                </InputLeftElement>
+                <Input
+                  placeholder={
+                    currentUser.role === "Admin"
+                      ? "名前またはメールアドレスで検索"
+                      : "名前で検索"
+                  }
+                  value={keyword}
+                  onChange={(e) => {
+                    setKeyword(e.target.value);
+                    // 検索条件を変えた場合は先頭のページから表示する
+                    replace(pathname);
+                  }}
+                />
+              </InputGroup>
               <UserTable users={users.items} currentUser={currentUser} />
+              <Pagination limit={limit} offset={offset} total={total} />
+              {currentUser.role === "Admin" && <InvitationTable />}
             </Stack>
           ) : (
             <Text>ユーザーの一覧を取得できませんでした</Text>
@@ -30,4 +95,6 @@ export default function ListUser() {
       </Container>
     </>
   );
-}
+};
+
+export default ListUser;
@@ -0,0 +1,126 @@
+"use client";
+
+import { ACCESS_TOKEN_KEY } from "@/app/_components/auth";
+import Header from "@/app/_components/Header";
+import { useCurrentUser } from "@/app/_contexts/user";
+import {
+  Button,
+  Container,
+  FormControl,
+  FormErrorMessage,
+  FormHelperText,
+  FormLabel,
+  Heading,
+  Input,
+  useToast,
+} from "@chakra-ui/react";
+import { useRouter } from "next/navigation";
+import { SubmitHandler, useForm } from "react-hook-form";
+import useLocalStorageState from "use-local-storage-state";
+import { put } from "@/app/_lib/client";
+
+type UserProfileInput = {
+  name: string;
+  email: string;
+};
+
+export default function UpdateUserProfile() {
+  const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
+  const { currentUser } = useCurrentUser();
+  const router = useRouter();
+  const toast = useToast();
+
+  const {
+    handleSubmit,
+    register,
+    formState: { errors, isSubmitting },
+  } = useForm<UserProfileInput>({
+    values: currentUser && {
+      name: currentUser.name,
+      email: currentUser.email,
+    },
+  });
+
+  const onSubmit: SubmitHandler<UserProfileInput> = async (values) => {
+    const res = await put({
+      destination: "/api/v1/users/me/profile",
+      token: accessToken,
+      body: values,
+    });
+
+    if (res.status === 202) {
+      toast({
+        title: "確認メールを送信しました",
+        description:
+          "新しいメールアドレスに届いたリンクを開くと、メールアドレスの変更が完了します",
+        status: "info",
+        duration: 5000,
+        isClosable: true,
+      });
+      router.push("/");
+    } else if (res.ok) {
+      toast({
+        title: "プロフィールを変更しました",
+        status: "success",
+        duration: 5000,
+        isClosable: true,
+      });
+      router.push("/");
+    } else {
+      toast({
+        title: "プロフィールを変更できません",
+        description:
+          res.status === 409
+            ? "このメールアドレスはすでに使われています。"
+            : "プロフィールの変更に失敗しました。入力内容を確認するか、管理者に連絡してください。",
+        status: "error",
+        duration: 5000,
+        isClosable: true,
+      });
+    }
+  };
+
+  return (
+    <>
+      <Header></Header>
+      <Container maxW="container.xl" my={20}>
+        <Heading as="h2" size="2xl" mb={2}>
+          プロフィール変更
+        </Heading>
+        <Container maxW="container.md" my={20}>
+          <form onSubmit={handleSubmit(onSubmit)}>
+            <FormControl isInvalid={!!errors.name} mb={5} isRequired>
+              <FormLabel htmlFor="name">名前</FormLabel>
+              <Input
+                id="name"
+                placeholder="名前を入力してください"
+                {...register("name", {
+                  required: "入力必須です",
+                })}
+              />
+              <FormErrorMessage>{errors.name?.message}</FormErrorMessage>
+            </FormControl>
+            <FormControl isInvalid={!!errors.email} mb={5} isRequired>
+              <FormLabel htmlFor="email">メールアドレス</FormLabel>
+              <Input
+                id="email"
+                type="email"
+                placeholder="メールアドレスを入力してください"
+                {...register("email", {
+                  required: "入力必須です",
+                })}
+              />
+              <FormHelperText>
+                変更すると新しいメールアドレスに確認メールが届きます。確認が済むまでは現在のメールアドレスが使われます。
+              </FormHelperText>
+              <FormErrorMessage>{errors.email?.message}</FormErrorMessage>
+            </FormControl>
+            <Button type="submit" isLoading={isSubmitting}>
+              変更
+            </Button>
+          </form>
+        </Container>
+      </Container>
+    </>
+  );
+}
@@ -1,17 +1,34 @@
-use crate::model::id::UserId;
+use crate::model::id::{SessionId, UserId};
 use uuid::Uuid;
 
+use super::RefreshToken;
+
 pub struct CreateToken {
     pub user_id: UserId,
+    pub session_id: SessionId,
     pub access_token: String,
 }
 
 impl CreateToken {
-    pub fn new(user_id: UserId) -> Self {
+    pub fn new(user_id: UserId, session_id: SessionId) -> Self {
         let access_token = Uuid::new_v4().simple().to_string();
         Self {
             user_id,
+            session_id,
             access_token,
         }
     }
 }
+
+// ログイン時にセッションを作成する。IP アドレスなどはセッションの一覧に表示するために保存する
+pub struct CreateSession {
+    pub user_id: UserId,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+pub struct RotateRefreshToken {
+    pub refresh_token: RefreshToken,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
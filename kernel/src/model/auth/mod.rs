@@ -1,3 +1,53 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::id::{SessionId, UserId};
+
 pub mod event;
 
 pub struct AccessToken(pub String);
+
+// アクセストークンを再発行するためのトークン。使うたびに新しいトークンに置き換わる
+pub struct RefreshToken(pub String);
+
+impl RefreshToken {
+    pub fn new() -> Self {
+        Self(format!(
+            "{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ))
+    }
+}
+
+impl Default for RefreshToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// セッションの作成時や、リフレッシュトークンの置き換え時に発行したトークン
+pub struct IssuedRefreshToken {
+    pub user_id: UserId,
+    pub session_id: SessionId,
+    pub refresh_token: RefreshToken,
+}
+
+// ログインごとに作られるセッション。リフレッシュトークンのファミリーに対応する
+pub struct Session {
+    pub id: SessionId,
+    pub user_id: UserId,
+    pub created_at: DateTime<Utc>,
+    // リフレッシュトークンを最後に使った日時
+    pub last_used_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+// アクセストークンの署名の検証に使う公開鍵（Ed25519）
+// JWKS として公開し、トークンの利用者が署名を検証できるようにする
+pub struct VerifyingKey {
+    pub kid: String,
+    // base64url でエンコードした公開鍵
+    pub x: String,
+}
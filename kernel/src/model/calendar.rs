@@ -0,0 +1,17 @@
+use uuid::Uuid;
+
+// カレンダーフィードの URL に含めるトークン
+// API へのアクセスには使えないため、カレンダーアプリに貼り付けてもよい
+pub struct CalendarFeedToken(pub String);
+
+impl CalendarFeedToken {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl Default for CalendarFeedToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
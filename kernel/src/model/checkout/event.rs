@@ -1,18 +1,43 @@
 use chrono::{DateTime, Utc};
 use derive_new::new;
 
-use crate::model::id::{BookId, CheckoutId, UserId};
+use crate::model::{
+    checkout::BatchMode,
+    id::{BookId, CheckoutId, UserId},
+};
 
-#[derive(new)]
+#[derive(new, Clone)]
 pub struct CreateCheckout {
     pub book_id: BookId,
     pub checked_out_by: UserId,
     pub checked_out_at: DateTime<Utc>,
 }
-#[derive(new)]
+#[derive(new, Clone)]
 pub struct UpdateReturned {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
     pub returned_by: UserId,
     pub returned_at: DateTime<Utc>,
+    // 他のユーザーが借りている蔵書も返却できるかどうか
+    pub return_any: bool,
+}
+
+// 複数の蔵書をまとめて貸し出す
+#[derive(new, Clone)]
+pub struct CreateCheckouts {
+    pub book_ids: Vec<BookId>,
+    pub checked_out_by: UserId,
+    pub checked_out_at: DateTime<Utc>,
+    pub mode: BatchMode,
+}
+
+// 複数の貸出をまとめて返却する
+#[derive(new, Clone)]
+pub struct UpdateReturnedBatch {
+    pub checkout_ids: Vec<CheckoutId>,
+    pub returned_by: UserId,
+    pub returned_at: DateTime<Utc>,
+    pub mode: BatchMode,
+    // 他のユーザーが借りている蔵書も返却できるかどうか
+    pub return_any: bool,
 }
@@ -1,5 +1,6 @@
 use crate::model::id::{BookId, CheckoutId, UserId};
 use chrono::{DateTime, Utc};
+use strum::AsRefStr;
 
 pub mod event;
 
@@ -19,3 +20,54 @@ pub struct CheckoutBook {
     pub author: String,
     pub isbn: String,
 }
+
+// 一括での貸出・返却操作の失敗時の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchMode {
+    // 1件でも失敗したらすべての操作を取り消す
+    #[default]
+    AllOrNothing,
+    // 成功した操作のみを確定する
+    BestEffort,
+}
+
+#[derive(Debug)]
+pub struct BatchResult<K> {
+    // 一括操作の結果がデータベースに反映されたかどうか
+    pub committed: bool,
+    pub items: Vec<BatchItem<K>>,
+}
+
+#[derive(Debug)]
+pub struct BatchItem<K> {
+    pub key: K,
+    pub status: BatchItemStatus,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BatchItemStatus {
+    Succeeded { checkout_id: CheckoutId },
+    Failed { reason: BatchFailureReason },
+    // 操作自体は成功したが、他の操作の失敗により取り消された
+    RolledBack { checkout_id: CheckoutId },
+}
+
+// 項目ごとの操作が失敗した理由。API の利用者が判別に使うため、コードは変更しないこと
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum BatchFailureReason {
+    // 蔵書または貸出が存在しない
+    NotFound,
+    // 貸出中の蔵書を貸し出そうとした場合など、現在の状態では操作できない
+    Unavailable,
+    // データベースのエラーなど、利用者側では対処できない失敗
+    InternalError,
+}
+
+impl<K> BatchResult<K> {
+    pub fn has_failure(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item.status, BatchItemStatus::Failed { .. }))
+    }
+}
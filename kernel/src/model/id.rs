@@ -65,3 +65,6 @@ macro_rules! define_id {
 define_id!(UserId);
 define_id!(BookId);
 define_id!(CheckoutId);
+define_id!(KioskId);
+define_id!(SessionId);
+define_id!(PersonalAccessTokenId);
@@ -0,0 +1,18 @@
+use super::InvitationToken;
+use crate::model::{id::UserId, role::Role};
+
+#[derive(Debug)]
+pub struct CreateInvitation {
+    pub email: String,
+    // 指定した場合は、招待を受け入れるまでの仮の名前として使う（未指定ならメールアドレス）
+    pub name: Option<String>,
+    pub role: Role,
+    pub invited_by: UserId,
+}
+
+// 招待されたユーザーは、名前とパスワードを自分で設定する
+pub struct AcceptInvitation {
+    pub token: InvitationToken,
+    pub name: String,
+    pub password: String,
+}
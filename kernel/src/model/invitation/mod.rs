@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::{id::UserId, role::Role};
+
+pub mod event;
+
+// 招待したユーザーは招待中（Invited）の状態で作成し、招待を受け入れると有効になる
+#[derive(Debug)]
+pub struct Invitation {
+    pub user_id: UserId,
+    pub email: String,
+    pub role: Role,
+    // 招待したユーザーが削除された場合は None
+    pub invited_by: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 招待を受け入れるためのトークン。メールで送信し、データベースにはハッシュ値のみを保存する
+pub struct InvitationToken(pub String);
+
+impl InvitationToken {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl Default for InvitationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 作成または再送した招待と、メールで送るトークン
+pub struct IssuedInvitation {
+    pub invitation: Invitation,
+    pub token: InvitationToken,
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+
+use crate::model::id::{BookId, KioskId, UserId};
+
+pub struct CreateKioskDevice {
+    pub name: String,
+}
+
+// 端末で読み取った蔵書のコードと利用者カードのコード
+#[derive(new, Clone)]
+pub struct KioskScan {
+    pub kiosk_id: KioskId,
+    pub book_code: String,
+    pub badge_code: String,
+    pub scanned_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct UpdateBookBarcode {
+    pub book_id: BookId,
+    pub barcode: Option<String>,
+}
+
+#[derive(new)]
+pub struct UpdateUserBadgeCode {
+    pub user_id: UserId,
+    pub badge_code: Option<String>,
+}
@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::id::{BookId, CheckoutId, KioskId, UserId};
+
+pub mod event;
+
+// 書架の横に設置する貸出・返却用の端末
+#[derive(Debug)]
+pub struct KioskDevice {
+    pub id: KioskId,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+// 端末の認証に使う秘密情報
+// 登録時に一度だけ返却し、データベースにはハッシュ値のみを保存する
+pub struct KioskSecret(pub String);
+
+impl KioskSecret {
+    // ユーザーのアクセストークンと区別しやすいよう、接頭辞を付ける
+    const PREFIX: &'static str = "kiosk_";
+
+    pub fn new() -> Self {
+        Self(format!(
+            "{}{}{}",
+            Self::PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ))
+    }
+}
+
+impl Default for KioskSecret {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct RegisteredKioskDevice {
+    pub device: KioskDevice,
+    pub secret: KioskSecret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KioskAction {
+    CheckedOut,
+    Returned,
+}
+
+#[derive(Debug)]
+pub struct KioskScanResult {
+    pub action: KioskAction,
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub book_title: String,
+    pub user_id: UserId,
+    pub user_name: String,
+}
+
+// 読み取ったコードを ISBN とみなした場合に、データベース上の ISBN と比較する候補を返す
+// ハイフンの有無や ISBN-10/ISBN-13 の違いを吸収するため、数字（と X）のみの形式で両方を返す
+pub fn isbn_candidates(code: &str) -> Vec<String> {
+    let digits: String = code
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if !digits.is_ascii() {
+        return Vec::new();
+    }
+
+    let isbn13 = match digits.len() {
+        13 if is_valid_isbn13(&digits) => digits,
+        10 if is_valid_isbn10(&digits) => {
+            let body = format!("978{}", &digits[..9]);
+            let check = isbn13_check_digit(&body);
+            format!("{}{}", body, check)
+        }
+        _ => return Vec::new(),
+    };
+
+    let mut candidates = vec![isbn13.clone()];
+    // 978 から始まるものだけが ISBN-10 に対応する
+    if let Some(body) = isbn13.strip_prefix("978") {
+        let body = &body[..9];
+        candidates.push(format!("{}{}", body, isbn10_check_digit(body)));
+    }
+    candidates
+}
+
+fn is_valid_isbn13(digits: &str) -> bool {
+    digits.chars().all(|c| c.is_ascii_digit())
+        && (digits.starts_with("978") || digits.starts_with("979"))
+        && isbn13_check_digit(&digits[..12]) == digits.chars().last().unwrap_or_default()
+}
+
+fn is_valid_isbn10(digits: &str) -> bool {
+    digits[..9].chars().all(|c| c.is_ascii_digit())
+        && isbn10_check_digit(&digits[..9]) == digits.chars().last().unwrap_or_default()
+}
+
+fn isbn13_check_digit(body: &str) -> char {
+    let sum: u32 = body
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d } else { d * 3 })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+fn isbn10_check_digit(body: &str) -> char {
+    let sum: u32 = body
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| d * (10 - i as u32))
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        n => char::from_digit(n, 10).unwrap_or('0'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isbn_candidates_from_isbn13() {
+        assert_eq!(
+            isbn_candidates("978-4798061702"),
+            vec!["9784798061702".to_string(), "4798061700".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_isbn_candidates_from_isbn10() {
+        assert_eq!(
+            isbn_candidates("4-7980-6170-0"),
+            vec!["9784798061702".to_string(), "4798061700".to_string()]
+        );
+        // チェックディジットが X の場合
+        assert_eq!(
+            isbn_candidates("080442957x"),
+            vec!["9780804429573".to_string(), "080442957X".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_isbn_candidates_rejects_invalid_code() {
+        assert!(isbn_candidates("978-4798061703").is_empty());
+        assert!(isbn_candidates("LIB-000123").is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+use derive_new::new;
+
+// ログインの試行。失敗した回数はメールアドレスごと、接続元の IP アドレスごとに数える
+#[derive(new)]
+pub struct LoginAttempt {
+    pub email: String,
+    pub ip_address: Option<String>,
+}
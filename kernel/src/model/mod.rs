@@ -1,7 +1,18 @@
 pub mod auth;
 pub mod book;
+pub mod calendar;
 pub mod checkout;
 pub mod id;
+pub mod invitation;
+pub mod kiosk;
 pub mod list;
+pub mod login_throttle;
+pub mod mail;
+pub mod oidc;
+pub mod password_reset;
+pub mod personal_access_token;
 pub mod role;
+pub mod signup;
+pub mod statistics;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,26 @@
+// IdP の認可エンドポイントへの URL。state と PKCE の code_challenge を含む
+// browser_binding はログインを開始したブラウザに Cookie として保存させ、認可コードを受け取る際に照合する
+pub struct OidcAuthorizationRequest {
+    pub authorization_url: String,
+    pub browser_binding: String,
+    // state の有効期間（秒）
+    pub expires_in: u64,
+}
+
+// IdP からリダイレクトで受け取った認可コードと、ブラウザの Cookie に保存した値
+pub struct OidcCallback {
+    pub code: String,
+    pub state: String,
+    pub browser_binding: String,
+}
+
+// 検証済みの ID トークンから取り出した、IdP 上のユーザーの情報
+#[derive(Debug)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub groups: Vec<String>,
+}
@@ -0,0 +1,18 @@
+use derive_new::new;
+
+use crate::model::id::UserId;
+
+use super::PasswordResetToken;
+
+#[derive(new)]
+pub struct ResetPassword {
+    pub token: PasswordResetToken,
+    pub new_password: String,
+}
+
+// 管理者がユーザーのパスワードを仮のパスワードに置き換え、次回ログイン時に再設定させる
+#[derive(new)]
+pub struct ForcePasswordReset {
+    pub user_id: UserId,
+    pub temporary_password: String,
+}
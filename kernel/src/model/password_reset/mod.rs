@@ -0,0 +1,31 @@
+use uuid::Uuid;
+
+use crate::model::user::User;
+
+pub mod event;
+
+// パスワードの再設定に使うトークン。一度使うと無効になる
+pub struct PasswordResetToken(pub String);
+
+impl PasswordResetToken {
+    // 推測されにくいよう、UUID v4 を2つ連結した値とする
+    pub fn new() -> Self {
+        Self(format!(
+            "{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ))
+    }
+}
+
+impl Default for PasswordResetToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// パスワードの再設定を受け付けたユーザーと、再設定用のトークン
+pub struct PasswordResetRequest {
+    pub user: User,
+    pub token: PasswordResetToken,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+
+use super::Scope;
+use crate::model::id::{PersonalAccessTokenId, UserId};
+
+pub struct CreatePersonalAccessToken {
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub struct DeletePersonalAccessToken {
+    pub user_id: UserId,
+    pub token_id: PersonalAccessTokenId,
+}
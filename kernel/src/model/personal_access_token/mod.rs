@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+use uuid::Uuid;
+
+use crate::model::id::{PersonalAccessTokenId, UserId};
+
+pub mod event;
+
+// パーソナルアクセストークンに許可する操作の範囲
+// ログインして発行したアクセストークンには、すべてのスコープが許可される
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, EnumIter)]
+pub enum Scope {
+    #[strum(serialize = "books:read")]
+    BooksRead,
+    #[strum(serialize = "books:write")]
+    BooksWrite,
+    #[strum(serialize = "checkouts:read")]
+    CheckoutsRead,
+    #[strum(serialize = "checkouts:write")]
+    CheckoutsWrite,
+    // ロールの権限を必要とする操作。権限を持つユーザーのトークンにのみ許可できる
+    #[strum(serialize = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub fn all() -> Vec<Self> {
+        Self::iter().collect()
+    }
+}
+
+// スクリプトなどから API を利用するためのトークン
+#[derive(Debug)]
+pub struct PersonalAccessToken {
+    pub id: PersonalAccessTokenId,
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// トークンの値。作成時に一度だけ返却し、データベースにはハッシュ値のみを保存する
+pub struct PersonalAccessTokenSecret(pub String);
+
+impl PersonalAccessTokenSecret {
+    // ログインして発行したアクセストークンと区別できるよう、接頭辞を付ける
+    pub const PREFIX: &'static str = "pat_";
+
+    pub fn new() -> Self {
+        Self(format!(
+            "{}{}{}",
+            Self::PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ))
+    }
+
+    pub fn is_personal_access_token(token: &str) -> bool {
+        token.starts_with(Self::PREFIX)
+    }
+}
+
+impl Default for PersonalAccessTokenSecret {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct CreatedPersonalAccessToken {
+    pub token: PersonalAccessToken,
+    pub secret: PersonalAccessTokenSecret,
+}
@@ -0,0 +1,18 @@
+use super::{Permission, Role};
+
+#[derive(Debug)]
+pub struct CreateRole {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Debug)]
+pub struct UpdateRolePermissions {
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Debug)]
+pub struct DeleteRole {
+    pub role: Role,
+}
@@ -0,0 +1,58 @@
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+
+pub mod event;
+
+// roles テーブルの name に対応する。組み込み以外のロールは Custom として扱う
+#[derive(Debug, Clone, EnumString, Default, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    Librarian,
+    #[default]
+    User,
+    #[strum(default)]
+    Custom(String),
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        match self {
+            Role::Admin => "Admin",
+            Role::Librarian => "Librarian",
+            Role::User => "User",
+            Role::Custom(name) => name,
+        }
+    }
+}
+
+// ロールに割り当てる権限。自身の蔵書や貸出に対する操作には権限を必要としない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, EnumIter)]
+pub enum Permission {
+    // 他のユーザーが登録した蔵書の更新・削除
+    #[strum(serialize = "book:update:any")]
+    BookUpdateAny,
+    // 他のユーザーが借りている蔵書の返却
+    #[strum(serialize = "checkout:return:any")]
+    CheckoutReturnAny,
+    #[strum(serialize = "kiosk:manage")]
+    KioskManage,
+    #[strum(serialize = "statistics:read")]
+    StatisticsRead,
+    #[strum(serialize = "user:manage")]
+    UserManage,
+    #[strum(serialize = "role:manage")]
+    RoleManage,
+}
+
+impl Permission {
+    pub fn all() -> Vec<Self> {
+        Self::iter().collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct RolePermissions {
+    pub role: Role,
+    // 組み込みのロールは削除できない
+    pub built_in: bool,
+    pub permissions: Vec<Permission>,
+}
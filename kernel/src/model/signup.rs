@@ -0,0 +1,24 @@
+use uuid::Uuid;
+
+use crate::model::user::User;
+
+// メールアドレスの確認用に送信するトークン。一度使うと無効になる
+pub struct EmailVerificationToken(pub String);
+
+impl EmailVerificationToken {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl Default for EmailVerificationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// メールアドレスの確認が済んでいないユーザー
+pub struct PendingUser {
+    pub user: User,
+    pub verification_token: EmailVerificationToken,
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::id::{BookId, UserId};
+
+// 集計対象とする期間などの条件を指定するための型
+// from は含み、to は含まない（半開区間）として扱う
+#[derive(Debug)]
+pub struct StatisticsOptions {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub include_borrowers: bool,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryStatistics {
+    pub most_borrowed_books: Vec<BookLoanCount>,
+    // 利用者個人の情報を含むため、明示的に指定された場合のみ集計する
+    // 集計の対象は、集計に含めることに同意したユーザーに限る
+    pub most_active_borrowers: Option<Vec<BorrowerLoanCount>>,
+    pub average_loan_duration_seconds: Option<f64>,
+    pub loans_per_month: Vec<MonthlyLoanCount>,
+    pub never_borrowed_books: Vec<StatisticsBook>,
+    pub utilization: Utilization,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatisticsBook {
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookLoanCount {
+    pub book: StatisticsBook,
+    pub loan_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BorrowerLoanCount {
+    pub user_id: UserId,
+    pub user_name: String,
+    pub loan_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthlyLoanCount {
+    pub month: DateTime<Utc>,
+    pub loan_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Utilization {
+    pub total_books: i64,
+    pub checked_out_books: i64,
+}
+
+impl Utilization {
+    // 蔵書が1冊もない場合は 0 とする
+    pub fn rate(&self) -> f64 {
+        if self.total_books == 0 {
+            return 0.0;
+        }
+        self.checked_out_books as f64 / self.total_books as f64
+    }
+}
@@ -0,0 +1,43 @@
+use uuid::Uuid;
+
+use crate::model::id::UserId;
+
+// パスワードでの認証に成功し、2 段階目の認証を待っている状態を表すトークン
+pub struct TwoFactorChallenge(pub String);
+
+impl TwoFactorChallenge {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl Default for TwoFactorChallenge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoFactorChallengePurpose {
+    // 登録済みの TOTP かリカバリーコードで認証する
+    Verify,
+    // 登録が必須の管理者が、ログインの途中で TOTP を登録する
+    Enroll,
+}
+
+// TOTP の登録を開始したときに返す、認証アプリに登録するための情報
+pub struct TotpEnrollment {
+    // Base32 でエンコードした共有鍵
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+// TOTP を使えない場合に代わりに使う、一度限りのコード
+pub struct RecoveryCode(pub String);
+
+// 2 段階目の認証に成功したユーザー
+// ログインの途中で TOTP を登録した場合は、発行したリカバリーコードを含む
+pub struct TwoFactorVerified {
+    pub user_id: UserId,
+    pub recovery_codes: Vec<RecoveryCode>,
+}
@@ -1,5 +1,7 @@
 use crate::model::{id::UserId, role::Role};
 
+use super::UserStatus;
+
 #[derive(Debug)]
 pub struct CreateUser {
     pub name: String,
@@ -7,12 +9,36 @@ pub struct CreateUser {
     pub password: String,
 }
 
+// パスワードを設定せずにユーザーを作成する。IdP など外部で認証するユーザーに使う
+// パスワードでログインする場合は、パスワードのリセットから設定する
+#[derive(Debug)]
+pub struct ProvisionUser {
+    pub name: String,
+    pub email: String,
+}
+
 #[derive(Debug)]
 pub struct UpdateUserRole {
     pub user_id: UserId,
     pub role: Role,
 }
 
+// ロールを持つユーザーを member_ids のとおりにする
+// 外したユーザーは既定の User ロールに戻す
+#[derive(Debug)]
+pub struct SyncRoleMembers {
+    pub role: Role,
+    pub member_ids: Vec<UserId>,
+}
+
+// 名前はすぐに変更し、メールアドレスは新しいアドレスでの確認が済んでから変更する
+#[derive(Debug)]
+pub struct UpdateUserProfile {
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+}
+
 #[derive(Debug)]
 pub struct UpdateUserPassword {
     pub user_id: UserId,
@@ -20,7 +46,26 @@ pub struct UpdateUserPassword {
     pub new_password: String,
 }
 
+// ユーザーの状態を変更する。ユーザーの削除は無効化として扱う
+#[derive(Debug)]
+pub struct UpdateUserStatus {
+    pub user_id: UserId,
+    pub status: UserStatus,
+    // SCIM などで外部のシステムから変更した場合は None
+    pub changed_by: Option<UserId>,
+}
+
+// IdP などの管理元から、名前とメールアドレスを確認なしで反映する
+#[derive(Debug)]
+pub struct SyncUserProfile {
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+}
+
+// 利用統計の「よく借りている利用者」に自分を含めることに同意するかどうか
 #[derive(Debug)]
-pub struct DeleteUser {
+pub struct UpdateLoanStatisticsSharing {
     pub user_id: UserId,
+    pub share: bool,
 }
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+use uuid::Uuid;
 
 use crate::model::{id::UserId, role::Role};
 
 pub mod event;
+pub mod password;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct User {
@@ -10,6 +13,82 @@ pub struct User {
     pub name: String,
     pub email: String,
     pub role: Role,
+    pub status: UserStatus,
+}
+
+// users テーブルの status に対応する。ログインや貸出ができるのは Active のユーザーのみ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum UserStatus {
+    Invited,
+    #[default]
+    Active,
+    Suspended,
+    // 無効化したユーザーは元に戻せず、一覧では匿名化して表示する
+    Deactivated,
+}
+
+impl UserStatus {
+    // invited → active → suspended ↔ active → deactivated の順に遷移する
+    // 招待中と利用停止中のユーザーも、そのまま無効化できる
+    pub fn can_transition_to(self, to: UserStatus) -> bool {
+        use UserStatus::*;
+        matches!(
+            (self, to),
+            (Invited, Active)
+                | (Invited, Deactivated)
+                | (Active, Suspended)
+                | (Active, Deactivated)
+                | (Suspended, Active)
+                | (Suspended, Deactivated)
+        )
+    }
+}
+
+// ユーザー一覧の並び順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UserSort {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+    NameAsc,
+    NameDesc,
+    EmailAsc,
+    EmailDesc,
+}
+
+#[derive(Debug)]
+pub struct UserListOptions {
+    pub limit: i64,
+    pub offset: i64,
+    // 名前（search_email が true の場合はメールアドレスも）の部分一致で絞り込む
+    pub keyword: Option<String>,
+    pub search_email: bool,
+    pub role: Option<Role>,
+    pub status: Option<UserStatus>,
+    pub sort: UserSort,
+}
+
+// メールアドレスの変更を確定するために、新しいアドレスへ送信するトークン。一度使うと無効になる
+pub struct EmailChangeToken(pub String);
+
+impl EmailChangeToken {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl Default for EmailChangeToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 確認を待っているメールアドレスの変更。user は変更前のメールアドレスを持つ
+pub struct PendingEmailChange {
+    pub user: User,
+    pub new_email: String,
+    pub token: EmailChangeToken,
 }
 
 #[derive(Debug)]
@@ -24,3 +103,27 @@ pub struct CheckoutUser {
     pub id: UserId,
     pub name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_status_transitions() {
+        use UserStatus::*;
+
+        assert!(Invited.can_transition_to(Active));
+        assert!(Active.can_transition_to(Suspended));
+        assert!(Suspended.can_transition_to(Active));
+        assert!(Active.can_transition_to(Deactivated));
+        assert!(Suspended.can_transition_to(Deactivated));
+
+        assert!(!Active.can_transition_to(Invited));
+        assert!(!Invited.can_transition_to(Suspended));
+        assert!(!Active.can_transition_to(Active));
+        // 無効化したユーザーは元に戻せない
+        for to in [Invited, Active, Suspended, Deactivated] {
+            assert!(!Deactivated.can_transition_to(to));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+// 新しく設定するパスワードに求める条件
+pub struct PasswordPolicy {
+    min_length: usize,
+    // 大文字・小文字を区別せずに照合するため、小文字にして保持する
+    common_passwords: HashSet<String>,
+}
+
+impl PasswordPolicy {
+    pub fn new(min_length: usize, common_passwords: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            min_length,
+            common_passwords: common_passwords
+                .into_iter()
+                .map(|password| password.to_lowercase())
+                .collect(),
+        }
+    }
+
+    // 条件を満たさない場合は、その理由を返す
+    pub fn check(&self, password: &str, email: &str) -> Result<(), String> {
+        if password.chars().count() < self.min_length {
+            return Err(format!(
+                "パスワードは {} 文字以上にしてください",
+                self.min_length
+            ));
+        }
+        let lowercase = password.to_lowercase();
+        if self.common_passwords.contains(&lowercase) {
+            return Err("推測されやすいパスワードは使えません".into());
+        }
+        if !email.is_empty() && lowercase == email.to_lowercase() {
+            return Err("メールアドレスと同じパスワードは使えません".into());
+        }
+        Ok(())
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::new(8, [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_policy() {
+        let policy = PasswordPolicy::new(8, ["Password1".to_string()]);
+
+        assert!(policy.check("correct horse", "user@example.com").is_ok());
+        assert!(policy.check("short", "user@example.com").is_err());
+        // 一覧との照合では大文字・小文字を区別しない
+        assert!(policy.check("PASSWORD1", "user@example.com").is_err());
+        assert!(policy
+            .check("User@Example.com", "user@example.com")
+            .is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use shared::error::{AppError, AppResult};
+
+use crate::model::{id::UserId, role::Permission};
+
+// 操作を行うユーザー。権限はパーソナルアクセストークンのスコープを考慮した後のもの
+#[derive(Debug, Clone)]
+pub struct Subject {
+    pub user_id: UserId,
+    pub permissions: Vec<Permission>,
+}
+
+impl Subject {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+// 認可の対象となる操作。対象のリソースによって可否が変わる場合は、その所有者を含める
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    UpdateBook { owner: UserId },
+    DeleteBook { owner: UserId },
+    // borrower が None の場合は、借りたユーザーによらず返却できるかを判定する
+    ReturnCheckout { borrower: Option<UserId> },
+    // ロールの作成や割り当てを通して、他のユーザーに権限を与える
+    GrantPermission(Permission),
+    ManageKiosks,
+    ReadStatistics,
+    ManageUsers,
+    ManageRoles,
+}
+
+#[mockall::automock]
+pub trait Policy: Send + Sync {
+    // 許可されない場合は ForbiddenOperation を返す
+    // 対象のリソースが存在するかどうかは、呼び出す前に確認しておく
+    fn authorize(&self, subject: &Subject, action: Action) -> AppResult<()>;
+}
+
+// ロールに割り当てられた権限に基づいて判定する
+#[derive(Default)]
+pub struct RolePolicy;
+
+impl Policy for RolePolicy {
+    fn authorize(&self, subject: &Subject, action: Action) -> AppResult<()> {
+        let allowed = match action {
+            Action::UpdateBook { owner } | Action::DeleteBook { owner } => {
+                owner == subject.user_id || subject.has_permission(Permission::BookUpdateAny)
+            }
+            Action::ReturnCheckout { borrower } => {
+                borrower == Some(subject.user_id)
+                    || subject.has_permission(Permission::CheckoutReturnAny)
+            }
+            Action::GrantPermission(permission) => subject.has_permission(permission),
+            Action::ManageKiosks => subject.has_permission(Permission::KioskManage),
+            Action::ReadStatistics => subject.has_permission(Permission::StatisticsRead),
+            Action::ManageUsers => subject.has_permission(Permission::UserManage),
+            Action::ManageRoles => subject.has_permission(Permission::RoleManage),
+        };
+        if !allowed {
+            return Err(AppError::ForbiddenOperation);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(permissions: Vec<Permission>) -> Subject {
+        Subject {
+            user_id: UserId::new(),
+            permissions,
+        }
+    }
+
+    #[test]
+    fn test_book_owner_or_permission() {
+        let policy = RolePolicy;
+        let user = subject(vec![]);
+        let admin = subject(Permission::all());
+
+        let own = Action::UpdateBook {
+            owner: user.user_id,
+        };
+        let others = Action::DeleteBook {
+            owner: UserId::new(),
+        };
+        assert!(policy.authorize(&user, own).is_ok());
+        assert!(matches!(
+            policy.authorize(&user, others),
+            Err(AppError::ForbiddenOperation)
+        ));
+        assert!(policy.authorize(&admin, own).is_ok());
+        assert!(policy.authorize(&admin, others).is_ok());
+    }
+
+    #[test]
+    fn test_return_checkout() {
+        let policy = RolePolicy;
+        let user = subject(vec![]);
+        let librarian = subject(vec![Permission::CheckoutReturnAny, Permission::KioskManage]);
+
+        let own = Action::ReturnCheckout {
+            borrower: Some(user.user_id),
+        };
+        let any = Action::ReturnCheckout { borrower: None };
+        assert!(policy.authorize(&user, own).is_ok());
+        assert!(policy.authorize(&user, any).is_err());
+        assert!(policy.authorize(&librarian, own).is_ok());
+        assert!(policy.authorize(&librarian, any).is_ok());
+    }
+
+    #[test]
+    fn test_permission_actions() {
+        let policy = RolePolicy;
+        let librarian = subject(vec![Permission::KioskManage, Permission::StatisticsRead]);
+
+        assert!(policy.authorize(&librarian, Action::ManageKiosks).is_ok());
+        assert!(policy.authorize(&librarian, Action::ReadStatistics).is_ok());
+        assert!(policy.authorize(&librarian, Action::ManageUsers).is_err());
+        assert!(policy.authorize(&librarian, Action::ManageRoles).is_err());
+        assert!(policy
+            .authorize(&librarian, Action::GrantPermission(Permission::KioskManage))
+            .is_ok());
+        assert!(policy
+            .authorize(&librarian, Action::GrantPermission(Permission::UserManage))
+            .is_err());
+    }
+}
@@ -2,8 +2,12 @@ use async_trait::async_trait;
 use shared::error::AppResult;
 
 use crate::model::{
-    auth::{event::CreateToken, AccessToken},
-    id::UserId,
+    auth::{
+        event::{CreateSession, CreateToken, RotateRefreshToken},
+        AccessToken, IssuedRefreshToken, Session, VerifyingKey,
+    },
+    id::{SessionId, UserId},
+    user::User,
 };
 
 #[mockall::automock]
@@ -14,9 +18,45 @@ pub trait AuthRepository: Send + Sync {
         access_token: &AccessToken,
     ) -> AppResult<Option<UserId>>;
 
+    // アクセストークンに含まれる情報だけでユーザーを特定できる場合は、そのユーザーを返す
+    // 特定できない場合は None を返し、呼び出し側でデータベースを参照する
+    async fn fetch_user_from_claims(&self, access_token: &AccessToken) -> AppResult<Option<User>>;
+
+    // アクセストークンを発行したセッションを返す
+    async fn fetch_session_id_from_token(
+        &self,
+        access_token: &AccessToken,
+    ) -> AppResult<Option<SessionId>>;
+
     async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId>;
 
     async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken>;
 
+    // アクセストークンを発行したセッションを終了する
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()>;
+
+    // ユーザーのすべてのセッションを終了し、パーソナルアクセストークンを含む発行済みのトークンを無効にする
+    async fn delete_all_tokens(&self, user_id: UserId) -> AppResult<()>;
+
+    // セッションを作成し、最初のリフレッシュトークンを発行する
+    async fn create_session(&self, event: CreateSession) -> AppResult<IssuedRefreshToken>;
+
+    // リフレッシュトークンを新しいものに置き換える
+    // すでに置き換え済みのトークンが使われた場合は盗用とみなし、セッションを終了する
+    async fn rotate_refresh_token(
+        &self,
+        event: RotateRefreshToken,
+    ) -> AppResult<IssuedRefreshToken>;
+
+    // ユーザーのセッションの一覧を、作成日時の新しい順に返す
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>>;
+
+    // ユーザーのセッションを終了する
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()>;
+
+    // 指定したセッション以外のユーザーのセッションをすべて終了する
+    async fn delete_other_sessions(&self, user_id: UserId, keep: SessionId) -> AppResult<()>;
+
+    // アクセストークンの署名の検証に使う公開鍵の一覧
+    fn verifying_keys(&self) -> Vec<VerifyingKey>;
 }
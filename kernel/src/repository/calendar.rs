@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{calendar::CalendarFeedToken, id::UserId};
+
+#[mockall::automock]
+#[async_trait]
+pub trait CalendarFeedRepository: Send + Sync {
+    // 既存のトークンがある場合は無効にし、新しいトークンを発行する
+    async fn issue_token(&self, user_id: UserId) -> AppResult<CalendarFeedToken>;
+    async fn revoke_token(&self, user_id: UserId) -> AppResult<()>;
+    async fn find_user_id_by_token(&self, token: &CalendarFeedToken) -> AppResult<Option<UserId>>;
+}
@@ -1,9 +1,9 @@
 use crate::model::{
     checkout::{
-        event::{CreateCheckout, UpdateReturned},
-        Checkout,
+        event::{CreateCheckout, CreateCheckouts, UpdateReturned, UpdateReturnedBatch},
+        BatchResult, Checkout,
     },
-    id::{BookId, UserId},
+    id::{BookId, CheckoutId, UserId},
 };
 use async_trait::async_trait;
 use shared::error::AppResult;
@@ -12,7 +12,12 @@ use shared::error::AppResult;
 #[async_trait]
 pub trait CheckouRepository: Send + Sync {
     async fn create(&self, event: CreateCheckout) -> AppResult<()>;
+    async fn create_batch(&self, event: CreateCheckouts) -> AppResult<BatchResult<BookId>>;
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>;
+    async fn update_returned_batch(
+        &self,
+        event: UpdateReturnedBatch,
+    ) -> AppResult<BatchResult<CheckoutId>>;
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>>;
     async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>>;
     async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>>;
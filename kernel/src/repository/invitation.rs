@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    invitation::{
+        event::{AcceptInvitation, CreateInvitation},
+        Invitation, InvitationToken, IssuedInvitation,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait InvitationRepository: Send + Sync {
+    // 招待中のユーザーを作成し、招待を受け入れるためのトークンを発行する
+    async fn create(&self, event: CreateInvitation) -> AppResult<IssuedInvitation>;
+    // 複数のユーザーを1つのトランザクションで招待する。1件でも失敗した場合はすべて取り消す
+    async fn create_batch(&self, events: Vec<CreateInvitation>)
+        -> AppResult<Vec<IssuedInvitation>>;
+    // 受け入れられていない招待を、有効期限が切れたものも含めて返す
+    async fn find_pending(&self) -> AppResult<Vec<Invitation>>;
+    // トークンを発行し直し、有効期限を延長する。以前のトークンは使えなくなる
+    async fn resend(&self, user_id: UserId) -> AppResult<IssuedInvitation>;
+    // 招待を取り消し、招待中のユーザーを削除する
+    async fn revoke(&self, user_id: UserId) -> AppResult<()>;
+    // 有効な招待のトークンに対応するメールアドレスを返す。新しいパスワードの検証に使う
+    async fn find_email(&self, token: &InvitationToken) -> AppResult<Option<String>>;
+    // 名前とパスワードを設定してユーザーを有効にする。トークンは一度しか使えない
+    async fn accept(&self, event: AcceptInvitation) -> AppResult<UserId>;
+}
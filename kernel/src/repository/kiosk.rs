@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::KioskId,
+    kiosk::{
+        event::{CreateKioskDevice, KioskScan, UpdateBookBarcode, UpdateUserBadgeCode},
+        KioskDevice, KioskScanResult, KioskSecret, RegisteredKioskDevice,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait KioskRepository: Send + Sync {
+    async fn register_device(&self, event: CreateKioskDevice) -> AppResult<RegisteredKioskDevice>;
+    async fn find_all_devices(&self) -> AppResult<Vec<KioskDevice>>;
+    async fn delete_device(&self, kiosk_id: KioskId) -> AppResult<()>;
+    // 秘密情報に対応する端末を返す。見つかった場合は最終利用日時を更新する
+    async fn authenticate(&self, secret: &KioskSecret) -> AppResult<Option<KioskDevice>>;
+    async fn update_book_barcode(&self, event: UpdateBookBarcode) -> AppResult<()>;
+    async fn update_user_badge_code(&self, event: UpdateUserBadgeCode) -> AppResult<()>;
+    // 蔵書が読み取った利用者に貸出中であれば返却し、そうでなければ貸し出す
+    async fn scan(&self, event: KioskScan) -> AppResult<KioskScanResult>;
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{id::UserId, login_throttle::LoginAttempt};
+
+#[mockall::automock]
+#[async_trait]
+pub trait LoginThrottleRepository: Send + Sync {
+    // 待ち時間中、またはロック中の場合は AppError::TooManyRequests を返す
+    // 試行できる場合は、認証の結果が出る前にその試行を失敗として数えておく
+    async fn check(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // check で数えた失敗の回数に応じて、待ち時間やロックを設定する
+    async fn record_failure(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // ログインに成功したら、そのメールアドレスの失敗回数をリセットし、check で数えた分を取り消す
+    async fn record_success(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // 2 段階認証のコードの誤りも、そのユーザーのメールアドレスでのログインの失敗として数える
+    async fn record_two_factor_failure(&self, user_id: UserId) -> AppResult<()>;
+    // 管理者がユーザーのロックを解除する
+    async fn unlock(&self, user_id: UserId) -> AppResult<()>;
+}
@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::mail::Mail;
+
+#[mockall::automock]
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> AppResult<()>;
+}
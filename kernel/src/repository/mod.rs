@@ -1,5 +1,18 @@
 pub mod auth;
 pub mod book;
+pub mod calendar;
 pub mod checkout;
 pub mod health;
+pub mod invitation;
+pub mod kiosk;
+pub mod login_throttle;
+pub mod mailer;
+pub mod oidc;
+pub mod password_reset;
+pub mod personal_access_token;
+pub mod role;
+pub mod setup;
+pub mod signup;
+pub mod statistics;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    oidc::{OidcAuthorizationRequest, OidcCallback, OidcIdentity},
+    role::Role,
+    user::User,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait OidcRepository: Send + Sync {
+    // 認可コードフローを開始する。state と PKCE の code_verifier は認可コードを受け取るまで保存しておく
+    // state は、ログインを開始したブラウザに保存させる値と紐付ける
+    async fn begin_login(&self) -> AppResult<OidcAuthorizationRequest>;
+    // 認可コードをトークンに交換し、ID トークンを検証する
+    async fn complete_login(&self, callback: OidcCallback) -> AppResult<OidcIdentity>;
+    // IdP のユーザーに紐付いたユーザーを返す
+    // 紐付いていない場合は確認済みのメールアドレスで紐付け、設定に応じてユーザーを作成する
+    async fn find_or_provision_user(&self, identity: &OidcIdentity) -> AppResult<User>;
+    // IdP のグループに対応する権限。グループと権限の対応を設定していない場合は None
+    fn role_for_groups(&self, groups: &[String]) -> Option<Role>;
+}
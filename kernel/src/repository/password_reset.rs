@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    password_reset::{
+        event::{ForcePasswordReset, ResetPassword},
+        PasswordResetRequest, PasswordResetToken,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    // メールアドレスに対応するユーザーがいれば、再設定用のトークンを発行する
+    async fn issue_token(&self, email: &str) -> AppResult<Option<PasswordResetRequest>>;
+    // 指定したユーザーの再設定用のトークンを発行する
+    async fn issue_token_for_user(&self, user_id: UserId) -> AppResult<PasswordResetToken>;
+    // トークンに対応するユーザーのメールアドレスを返す。新しいパスワードの検証に使う
+    async fn find_email(&self, token: &PasswordResetToken) -> AppResult<Option<String>>;
+    // トークンに対応するユーザーのパスワードを再設定する
+    async fn reset_password(&self, event: ResetPassword) -> AppResult<UserId>;
+    // 仮のパスワードを設定し、次回ログイン時にパスワードの再設定を求める
+    async fn force_reset(&self, event: ForcePasswordReset) -> AppResult<()>;
+    // パスワードの再設定が求められているかを返す
+    async fn is_password_change_required(&self, user_id: UserId) -> AppResult<bool>;
+}
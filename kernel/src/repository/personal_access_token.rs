@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    personal_access_token::{
+        event::{CreatePersonalAccessToken, DeletePersonalAccessToken},
+        CreatedPersonalAccessToken, PersonalAccessToken, PersonalAccessTokenSecret,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait PersonalAccessTokenRepository: Send + Sync {
+    async fn create(
+        &self,
+        event: CreatePersonalAccessToken,
+    ) -> AppResult<CreatedPersonalAccessToken>;
+    async fn find_by_user(&self, user_id: UserId) -> AppResult<Vec<PersonalAccessToken>>;
+    async fn delete(&self, event: DeletePersonalAccessToken) -> AppResult<()>;
+    // 有効期限内のトークンを返す。見つかった場合は最終利用日時を更新する
+    async fn authenticate(
+        &self,
+        secret: &PersonalAccessTokenSecret,
+    ) -> AppResult<Option<PersonalAccessToken>>;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::role::{
+    event::{CreateRole, DeleteRole, UpdateRolePermissions},
+    Permission, Role, RolePermissions,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    async fn find_all(&self) -> AppResult<Vec<RolePermissions>>;
+    // リクエストごとに参照するため、キャッシュした値を返す
+    async fn find_permissions(&self, role: &Role) -> AppResult<Vec<Permission>>;
+    async fn create(&self, event: CreateRole) -> AppResult<RolePermissions>;
+    async fn update_permissions(&self, event: UpdateRolePermissions) -> AppResult<()>;
+    // 組み込みのロールと、ユーザーが割り当てられているロールは削除できない
+    async fn delete(&self, event: DeleteRole) -> AppResult<()>;
+}
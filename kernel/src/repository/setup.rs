@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::user::{event::CreateUser, User};
+
+#[mockall::automock]
+#[async_trait]
+pub trait SetupRepository: Send + Sync {
+    // 管理者のユーザーが1人もおらず、初期設定が必要な場合は true を返す
+    async fn is_required(&self) -> AppResult<bool>;
+    // 最初の管理者を作成する。すでに管理者がいる場合は作成せずにエラーを返す
+    async fn create_initial_admin(&self, event: CreateUser) -> AppResult<User>;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    signup::{EmailVerificationToken, PendingUser},
+    user::event::CreateUser,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait SignupRepository: Send + Sync {
+    // メールアドレスが未確認のユーザーを作成し、確認用のトークンを発行する
+    // 同じメールアドレスの未確認のユーザーがいる場合は、トークンを発行し直して以前のトークンを無効にする
+    // 確認済みのユーザーがいる場合は何もせず、None を返す
+    async fn register(&self, event: CreateUser) -> AppResult<Option<PendingUser>>;
+    // トークンに対応するユーザーのメールアドレスを確認済みにし、申し込まれた名前とパスワードを反映する
+    async fn verify_email(&self, token: &EmailVerificationToken) -> AppResult<UserId>;
+}
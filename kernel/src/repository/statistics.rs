@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::statistics::{LibraryStatistics, StatisticsOptions};
+
+#[mockall::automock]
+#[async_trait]
+pub trait StatisticsRepository: Send + Sync {
+    async fn summarize(&self, options: StatisticsOptions) -> AppResult<LibraryStatistics>;
+}
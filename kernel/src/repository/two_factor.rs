@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    two_factor::{
+        RecoveryCode, TotpEnrollment, TwoFactorChallenge, TwoFactorChallengePurpose,
+        TwoFactorVerified,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait TwoFactorRepository: Send + Sync {
+    async fn is_enabled(&self, user_id: UserId) -> AppResult<bool>;
+    // 新しい共有鍵を生成する。有効にする前であれば何度でもやり直せる
+    async fn begin_enrollment(&self, user_id: UserId) -> AppResult<TotpEnrollment>;
+    // 認証アプリが生成したコードを確認して有効にし、リカバリーコードを発行する
+    async fn activate(&self, user_id: UserId, code: &str) -> AppResult<Vec<RecoveryCode>>;
+    async fn disable(&self, user_id: UserId, code: &str) -> AppResult<()>;
+    async fn issue_challenge(
+        &self,
+        user_id: UserId,
+        purpose: TwoFactorChallengePurpose,
+    ) -> AppResult<TwoFactorChallenge>;
+    // 成功するとチャレンジは無効になる。失敗が続いた場合もチャレンジを無効にする
+    // チャレンジをまたいで失敗が続いたユーザーには、一定の期間 AppError::TooManyRequests を返す
+    async fn verify_challenge(
+        &self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> AppResult<TwoFactorVerified>;
+}
@@ -3,9 +3,14 @@ use shared::error::AppResult;
 
 use crate::model::{
     id::UserId,
+    list::PaginatedList,
     user::{
-        event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
-        User,
+        event::{
+            CreateUser, ProvisionUser, SyncRoleMembers, SyncUserProfile,
+            UpdateLoanStatisticsSharing, UpdateUserPassword, UpdateUserProfile, UpdateUserRole,
+            UpdateUserStatus,
+        },
+        EmailChangeToken, PendingEmailChange, User, UserListOptions,
     },
 };
 
@@ -13,9 +18,32 @@ use crate::model::{
 #[async_trait]
 pub trait UserRepository: Send + Sync {
     async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>>;
-    async fn find_all(&self) -> AppResult<Vec<User>>;
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
+    async fn find_all(&self, options: UserListOptions) -> AppResult<PaginatedList<User>>;
+    // 指定したメールアドレスのうち、すでに登録されているものを返す（大文字・小文字は区別しない）
+    async fn find_existing_emails(&self, emails: Vec<String>) -> AppResult<Vec<String>>;
     async fn create(&self, event: CreateUser) -> AppResult<User>;
+    // 作成したユーザーは、パスワードを設定するまでパスワードでログインできない
+    async fn provision(&self, event: ProvisionUser) -> AppResult<User>;
+    // メールアドレスが変更される場合は、確認待ちの変更を返す
+    async fn update_profile(
+        &self,
+        event: UpdateUserProfile,
+    ) -> AppResult<Option<PendingEmailChange>>;
+    async fn confirm_email_change(&self, token: &EmailChangeToken) -> AppResult<UserId>;
+    // 管理元の値をそのまま反映するため、メールアドレスの確認は行わない
+    async fn sync_profile(&self, event: SyncUserProfile) -> AppResult<User>;
+    // 以下の3つの操作は、成功するとそのユーザーのすべてのセッションを無効にする
+    // （状態の変更は Active 以外にした場合のみ）
+    // ロールと状態の変更では、有効な管理者が1人もいなくなる変更はできない
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()>;
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()>;
-    async fn delete(&self, event: DeleteUser) -> AppResult<()>;
+    // 複数のユーザーのロールをまとめて変更する。存在しないユーザーが含まれる場合は何も変更しない
+    // ロールを変更したユーザーのセッションは無効にする
+    async fn sync_role_members(&self, event: SyncRoleMembers) -> AppResult<()>;
+    async fn update_status(&self, event: UpdateUserStatus) -> AppResult<()>;
+    async fn update_loan_statistics_sharing(
+        &self,
+        event: UpdateLoanStatisticsSharing,
+    ) -> AppResult<()>;
 }
@@ -2,18 +2,47 @@ use std::sync::Arc;
 
 use adapter::{
     database::ConnectionPool,
+    mailer::{FileMailer, SmtpMailer},
     redis::RedisClient,
     repository::{
-        auth::AuthRepositoryImpl, book::BookRespositoryImpl, checkout::CheckouRepositoryImpl,
-        health::HealthCheckRepositoryImpl, user::UserRepsitoryImpl,
+        auth::{
+            jwt::{JwtAuthRepositoryImpl, JwtKeys},
+            AuthRepositoryImpl, SessionStore,
+        },
+        book::BookRespositoryImpl,
+        calendar::CalendarFeedRepositoryImpl,
+        checkout::CheckouRepositoryImpl,
+        health::HealthCheckRepositoryImpl,
+        invitation::InvitationRepositoryImpl,
+        kiosk::KioskRepositoryImpl,
+        login_throttle::LoginThrottleRepositoryImpl,
+        oidc::OidcRepositoryImpl,
+        password_reset::PasswordResetRepositoryImpl,
+        personal_access_token::PersonalAccessTokenRepositoryImpl,
+        role::RoleRepositoryImpl,
+        setup::SetupRepositoryImpl,
+        signup::SignupRepositoryImpl,
+        statistics::StatisticsRepositoryImpl,
+        two_factor::TwoFactorRepositoryImpl,
+        user::UserRepsitoryImpl,
     },
 };
+use kernel::model::user::password::PasswordPolicy;
+use kernel::policy::{Policy, RolePolicy};
 use kernel::repository::{
-    auth::AuthRepository, book::BookRepository, checkout::CheckouRepository,
-    health::HealthCheckRepository, user::UserRepository,
+    auth::AuthRepository, book::BookRepository, calendar::CalendarFeedRepository,
+    checkout::CheckouRepository, health::HealthCheckRepository, invitation::InvitationRepository,
+    kiosk::KioskRepository, login_throttle::LoginThrottleRepository, mailer::Mailer,
+    oidc::OidcRepository, password_reset::PasswordResetRepository,
+    personal_access_token::PersonalAccessTokenRepository, role::RoleRepository,
+    setup::SetupRepository, signup::SignupRepository, statistics::StatisticsRepository,
+    two_factor::TwoFactorRepository, user::UserRepository,
 };
 use mockall::predicate::*;
-use shared::config::AppConfig;
+use shared::{
+    config::{AccessTokenFormat, AppConfig, MailTransport},
+    error::AppResult,
+};
 
 #[derive(Clone)]
 pub struct AppRegistryImpl {
@@ -22,6 +51,22 @@ pub struct AppRegistryImpl {
     auth_repository: Arc<dyn AuthRepository>,
     user_repository: Arc<dyn UserRepository>,
     checkout_repository: Arc<dyn CheckouRepository>,
+    statistics_repository: Arc<dyn StatisticsRepository>,
+    calendar_feed_repository: Arc<dyn CalendarFeedRepository>,
+    kiosk_repository: Arc<dyn KioskRepository>,
+    signup_repository: Arc<dyn SignupRepository>,
+    password_reset_repository: Arc<dyn PasswordResetRepository>,
+    invitation_repository: Arc<dyn InvitationRepository>,
+    login_throttle_repository: Arc<dyn LoginThrottleRepository>,
+    two_factor_repository: Arc<dyn TwoFactorRepository>,
+    personal_access_token_repository: Arc<dyn PersonalAccessTokenRepository>,
+    oidc_repository: Option<Arc<dyn OidcRepository>>,
+    role_repository: Arc<dyn RoleRepository>,
+    setup_repository: Arc<dyn SetupRepository>,
+    policy: Arc<dyn Policy>,
+    password_policy: Arc<PasswordPolicy>,
+    mailer: Arc<dyn Mailer>,
+    app_config: Arc<AppConfig>,
 }
 
 impl AppRegistryImpl {
@@ -29,23 +74,118 @@ impl AppRegistryImpl {
         pool: ConnectionPool,
         redis_client: Arc<RedisClient>,
         app_config: AppConfig,
-    ) -> Self {
+    ) -> AppResult<Self> {
         let health_check_repository = Arc::new(HealthCheckRepositoryImpl::new(pool.clone()));
         let book_repository = Arc::new(BookRespositoryImpl::new(pool.clone()));
-        let auth_repository = Arc::new(AuthRepositoryImpl::new(
+        let sessions = SessionStore::new(redis_client.clone(), app_config.auth.refresh_ttl);
+        let auth_repository: Arc<dyn AuthRepository> = match &app_config.auth.token_format {
+            AccessTokenFormat::Opaque => Arc::new(AuthRepositoryImpl::new(
+                pool.clone(),
+                redis_client.clone(),
+                app_config.auth.ttl,
+                sessions,
+            )),
+            AccessTokenFormat::Jwt {
+                keys_dir,
+                signing_key_id,
+                issuer,
+            } => Arc::new(JwtAuthRepositoryImpl::new(
+                pool.clone(),
+                redis_client.clone(),
+                JwtKeys::load(keys_dir, signing_key_id)?,
+                issuer.clone(),
+                app_config.auth.ttl,
+                sessions,
+            )),
+        };
+        let user_repository = Arc::new(UserRepsitoryImpl::new(
             pool.clone(),
+            auth_repository.clone(),
             redis_client.clone(),
-            app_config.auth.ttl,
+            app_config.signup.verification_ttl,
         ));
-        let user_repository = Arc::new(UserRepsitoryImpl::new(pool.clone()));
         let checkout_repository = Arc::new(CheckouRepositoryImpl::new(pool.clone()));
-        Self {
+        let statistics_repository = Arc::new(StatisticsRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            app_config.statistics.cache_ttl,
+        ));
+        let calendar_feed_repository = Arc::new(CalendarFeedRepositoryImpl::new(pool.clone()));
+        let kiosk_repository = Arc::new(KioskRepositoryImpl::new(pool.clone()));
+        let signup_repository = Arc::new(SignupRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            app_config.signup.verification_ttl,
+        ));
+        let password_reset_repository = Arc::new(PasswordResetRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            app_config.password_reset.ttl,
+        ));
+        let invitation_repository = Arc::new(InvitationRepositoryImpl::new(
+            pool.clone(),
+            app_config.invitation.ttl,
+        ));
+        let login_throttle_repository = Arc::new(LoginThrottleRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            app_config.login_throttle.clone(),
+        ));
+        let two_factor_repository = Arc::new(TwoFactorRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            login_throttle_repository.clone(),
+            app_config.two_factor.issuer.clone(),
+            app_config.two_factor.challenge_ttl,
+            app_config.two_factor.max_failures,
+            app_config.two_factor.lockout,
+        ));
+        let personal_access_token_repository =
+            Arc::new(PersonalAccessTokenRepositoryImpl::new(pool.clone()));
+        let oidc_repository: Option<Arc<dyn OidcRepository>> = match &app_config.oidc {
+            Some(oidc) => Some(Arc::new(OidcRepositoryImpl::new(
+                pool.clone(),
+                redis_client.clone(),
+                oidc.clone(),
+            ))),
+            None => None,
+        };
+        let role_repository = Arc::new(RoleRepositoryImpl::new(pool.clone(), redis_client.clone()));
+        let setup_repository = Arc::new(SetupRepositoryImpl::new(pool.clone()));
+        let mailer: Arc<dyn Mailer> = match &app_config.mailer.transport {
+            MailTransport::File { dir } => {
+                Arc::new(FileMailer::new(app_config.mailer.from.clone(), dir))
+            }
+            MailTransport::Smtp { host, port } => {
+                Arc::new(SmtpMailer::new(app_config.mailer.from.clone(), host, *port))
+            }
+        };
+        Ok(Self {
             health_check_repository,
             book_repository,
             auth_repository,
             user_repository,
             checkout_repository,
-        }
+            statistics_repository,
+            calendar_feed_repository,
+            kiosk_repository,
+            signup_repository,
+            password_reset_repository,
+            invitation_repository,
+            login_throttle_repository,
+            two_factor_repository,
+            personal_access_token_repository,
+            oidc_repository,
+            role_repository,
+            setup_repository,
+            policy: Arc::new(RolePolicy),
+            password_policy: Arc::new(PasswordPolicy::new(
+                app_config.password_policy.min_length,
+                app_config.password_policy.common_passwords.clone(),
+            )),
+            mailer,
+            app_config: Arc::new(app_config),
+        })
     }
 }
 
@@ -56,6 +196,23 @@ pub trait AppRegistryExt {
     fn auth_repository(&self) -> Arc<dyn AuthRepository>;
     fn user_repository(&self) -> Arc<dyn UserRepository>;
     fn checkout_repository(&self) -> Arc<dyn CheckouRepository>;
+    fn statistics_repository(&self) -> Arc<dyn StatisticsRepository>;
+    fn calendar_feed_repository(&self) -> Arc<dyn CalendarFeedRepository>;
+    fn kiosk_repository(&self) -> Arc<dyn KioskRepository>;
+    fn signup_repository(&self) -> Arc<dyn SignupRepository>;
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository>;
+    fn invitation_repository(&self) -> Arc<dyn InvitationRepository>;
+    fn login_throttle_repository(&self) -> Arc<dyn LoginThrottleRepository>;
+    fn two_factor_repository(&self) -> Arc<dyn TwoFactorRepository>;
+    fn personal_access_token_repository(&self) -> Arc<dyn PersonalAccessTokenRepository>;
+    // シングルサインオンが無効の場合は None
+    fn oidc_repository(&self) -> Option<Arc<dyn OidcRepository>>;
+    fn role_repository(&self) -> Arc<dyn RoleRepository>;
+    fn setup_repository(&self) -> Arc<dyn SetupRepository>;
+    fn policy(&self) -> Arc<dyn Policy>;
+    fn password_policy(&self) -> Arc<PasswordPolicy>;
+    fn mailer(&self) -> Arc<dyn Mailer>;
+    fn app_config(&self) -> Arc<AppConfig>;
 }
 
 impl AppRegistryExt for AppRegistryImpl {
@@ -78,6 +235,70 @@ impl AppRegistryExt for AppRegistryImpl {
     fn checkout_repository(&self) -> Arc<dyn CheckouRepository> {
         self.checkout_repository.clone()
     }
+
+    fn statistics_repository(&self) -> Arc<dyn StatisticsRepository> {
+        self.statistics_repository.clone()
+    }
+
+    fn calendar_feed_repository(&self) -> Arc<dyn CalendarFeedRepository> {
+        self.calendar_feed_repository.clone()
+    }
+
+    fn kiosk_repository(&self) -> Arc<dyn KioskRepository> {
+        self.kiosk_repository.clone()
+    }
+
+    fn signup_repository(&self) -> Arc<dyn SignupRepository> {
+        self.signup_repository.clone()
+    }
+
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository> {
+        self.password_reset_repository.clone()
+    }
+
+    fn invitation_repository(&self) -> Arc<dyn InvitationRepository> {
+        self.invitation_repository.clone()
+    }
+
+    fn login_throttle_repository(&self) -> Arc<dyn LoginThrottleRepository> {
+        self.login_throttle_repository.clone()
+    }
+
+    fn two_factor_repository(&self) -> Arc<dyn TwoFactorRepository> {
+        self.two_factor_repository.clone()
+    }
+
+    fn personal_access_token_repository(&self) -> Arc<dyn PersonalAccessTokenRepository> {
+        self.personal_access_token_repository.clone()
+    }
+
+    fn oidc_repository(&self) -> Option<Arc<dyn OidcRepository>> {
+        self.oidc_repository.clone()
+    }
+
+    fn role_repository(&self) -> Arc<dyn RoleRepository> {
+        self.role_repository.clone()
+    }
+
+    fn setup_repository(&self) -> Arc<dyn SetupRepository> {
+        self.setup_repository.clone()
+    }
+
+    fn policy(&self) -> Arc<dyn Policy> {
+        self.policy.clone()
+    }
+
+    fn password_policy(&self) -> Arc<PasswordPolicy> {
+        self.password_policy.clone()
+    }
+
+    fn mailer(&self) -> Arc<dyn Mailer> {
+        self.mailer.clone()
+    }
+
+    fn app_config(&self) -> Arc<AppConfig> {
+        self.app_config.clone()
+    }
 }
 
 pub type AppRegistry = Arc<dyn AppRegistryExt + Send + Sync + 'static>;
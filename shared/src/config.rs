@@ -4,6 +4,23 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub auth: AuthConfig,
+    pub statistics: StatisticsConfig,
+    pub checkout: CheckoutConfig,
+    pub frontend: FrontendConfig,
+    pub signup: SignupConfig,
+    pub mailer: MailerConfig,
+    pub password_reset: PasswordResetConfig,
+    pub password_policy: PasswordPolicyConfig,
+    pub invitation: InvitationConfig,
+    pub login_throttle: LoginThrottleConfig,
+    pub proxy: ProxyConfig,
+    pub two_factor: TwoFactorConfig,
+    // OpenID Connect によるシングルサインオン。無効の場合は None
+    pub oidc: Option<OidcConfig>,
+    // SCIM によるユーザーのプロビジョニング。無効の場合は None
+    pub scim: Option<ScimConfig>,
+    // 起動時に作成する最初の管理者。指定しない場合は None
+    pub initial_admin: Option<InitialAdminConfig>,
 }
 
 impl AppConfig {
@@ -14,6 +31,8 @@ impl AppConfig {
             username: std::env::var("DATABASE_USERNAME")?,
             password: std::env::var("DATABASE_PASSWORD")?,
             database: std::env::var("DATABASE_NAME")?,
+            max_transaction_retries: std::env::var("DATABASE_MAX_TRANSACTION_RETRIES")?
+                .parse::<u32>()?,
         };
         let redis = RedisConfig {
             host: std::env::var("REDIS_HOST")?,
@@ -21,11 +40,153 @@ impl AppConfig {
         };
         let auth = AuthConfig {
             ttl: std::env::var("AUTH_TOKEN_TTL")?.parse::<u64>()?,
+            refresh_ttl: std::env::var("AUTH_REFRESH_TOKEN_TTL")?.parse::<u64>()?,
+            token_format: match std::env::var("AUTH_TOKEN_FORMAT")?.as_str() {
+                "opaque" => AccessTokenFormat::Opaque,
+                "jwt" => AccessTokenFormat::Jwt {
+                    keys_dir: std::env::var("AUTH_JWT_KEYS_DIR")?,
+                    signing_key_id: std::env::var("AUTH_JWT_SIGNING_KEY_ID")?,
+                    issuer: std::env::var("AUTH_JWT_ISSUER")?,
+                },
+                other => anyhow::bail!("unknown AUTH_TOKEN_FORMAT: {}", other),
+            },
+            password_login_enabled: std::env::var("PASSWORD_LOGIN_ENABLED")?.parse::<bool>()?,
         };
+        let statistics = StatisticsConfig {
+            cache_ttl: std::env::var("STATISTICS_CACHE_TTL")?.parse::<u64>()?,
+        };
+        let checkout = CheckoutConfig {
+            loan_period_days: std::env::var("CHECKOUT_LOAN_PERIOD_DAYS")?.parse::<i64>()?,
+        };
+        let frontend = FrontendConfig {
+            base_url: std::env::var("FRONTEND_BASE_URL")?,
+        };
+        let signup = SignupConfig {
+            enabled: std::env::var("SIGNUP_ENABLED")?.parse::<bool>()?,
+            // カンマ区切りで指定する。空の場合はドメインを制限しない
+            allowed_email_domains: std::env::var("SIGNUP_ALLOWED_EMAIL_DOMAINS")?
+                .split(',')
+                .map(|domain| domain.trim().to_lowercase())
+                .filter(|domain| !domain.is_empty())
+                .collect(),
+            verification_ttl: std::env::var("SIGNUP_VERIFICATION_TTL")?.parse::<u64>()?,
+        };
+        let mailer = MailerConfig {
+            from: std::env::var("MAILER_FROM")?,
+            transport: match std::env::var("MAILER_TRANSPORT")?.as_str() {
+                "smtp" => MailTransport::Smtp {
+                    host: std::env::var("SMTP_HOST")?,
+                    port: std::env::var("SMTP_PORT")?.parse::<u16>()?,
+                },
+                "file" => MailTransport::File {
+                    dir: std::env::var("MAILER_FILE_DIR")?,
+                },
+                other => anyhow::bail!("unknown MAILER_TRANSPORT: {}", other),
+            },
+        };
+        let password_reset = PasswordResetConfig {
+            ttl: std::env::var("PASSWORD_RESET_TTL")?.parse::<u64>()?,
+        };
+        // 1 行に 1 つのパスワードを書いたファイルを指定する。空の場合は一覧による制限をしない
+        let common_passwords_file = std::env::var("PASSWORD_COMMON_PASSWORDS_FILE")?;
+        let common_passwords = if common_passwords_file.is_empty() {
+            Vec::new()
+        } else {
+            std::fs::read_to_string(&common_passwords_file)?
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect()
+        };
+        let password_policy = PasswordPolicyConfig {
+            min_length: std::env::var("PASSWORD_MIN_LENGTH")?.parse::<usize>()?,
+            common_passwords,
+        };
+        let invitation = InvitationConfig {
+            ttl: std::env::var("INVITATION_TTL")?.parse::<u64>()?,
+        };
+        let login_throttle = LoginThrottleConfig {
+            max_failures_per_email: std::env::var("LOGIN_MAX_FAILURES_PER_EMAIL")?
+                .parse::<u64>()?,
+            max_failures_per_ip: std::env::var("LOGIN_MAX_FAILURES_PER_IP")?.parse::<u64>()?,
+            backoff_base: std::env::var("LOGIN_BACKOFF_BASE")?.parse::<u64>()?,
+            lockout: std::env::var("LOGIN_LOCKOUT_DURATION")?.parse::<u64>()?,
+        };
+        let proxy = ProxyConfig {
+            trusted_hops: std::env::var("TRUSTED_PROXY_HOPS")?.parse::<usize>()?,
+        };
+        let two_factor = TwoFactorConfig {
+            issuer: std::env::var("TWO_FACTOR_ISSUER")?,
+            challenge_ttl: std::env::var("TWO_FACTOR_CHALLENGE_TTL")?.parse::<u64>()?,
+            required_for_admin: std::env::var("TWO_FACTOR_REQUIRED_FOR_ADMIN")?.parse::<bool>()?,
+            max_failures: std::env::var("TWO_FACTOR_MAX_FAILURES")?.parse::<u64>()?,
+            lockout: std::env::var("TWO_FACTOR_LOCKOUT_DURATION")?.parse::<u64>()?,
+        };
+        let oidc = if std::env::var("OIDC_ENABLED")?.parse::<bool>()? {
+            Some(OidcConfig {
+                issuer_url: std::env::var("OIDC_ISSUER_URL")?,
+                client_id: std::env::var("OIDC_CLIENT_ID")?,
+                // 空の場合はパブリッククライアントとして PKCE のみで認可コードを交換する
+                client_secret: Some(std::env::var("OIDC_CLIENT_SECRET")?)
+                    .filter(|secret| !secret.is_empty()),
+                redirect_uri: std::env::var("OIDC_REDIRECT_URI")?,
+                scopes: std::env::var("OIDC_SCOPES")?,
+                groups_claim: std::env::var("OIDC_GROUPS_CLAIM")?,
+                // `グループ名=ロール名` をカンマ区切りで指定する。先に書いたものを優先する。空の場合はロールを同期しない
+                group_roles: std::env::var("OIDC_GROUP_ROLES")?
+                    .split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(group, role)| (group.trim().to_string(), role.trim().to_string()))
+                    .collect(),
+                auto_provision: std::env::var("OIDC_AUTO_PROVISION")?.parse::<bool>()?,
+                state_ttl: std::env::var("OIDC_STATE_TTL")?.parse::<u64>()?,
+            })
+        } else {
+            None
+        };
+        let scim = if std::env::var("SCIM_ENABLED")?.parse::<bool>()? {
+            let bearer_token = std::env::var("SCIM_BEARER_TOKEN")?;
+            // 推測されやすいトークンで外部からユーザーを操作されないよう、短いトークンは受け付けない
+            if bearer_token.len() < 32 {
+                anyhow::bail!("SCIM_BEARER_TOKEN must be at least 32 characters");
+            }
+            Some(ScimConfig { bearer_token })
+        } else {
+            None
+        };
+        // 自動でデプロイする環境向けに、初期設定の API を使わずに最初の管理者を作成する
+        // メールアドレスが空の場合は作成しない
+        let initial_admin_email = std::env::var("INITIAL_ADMIN_EMAIL")?;
+        let initial_admin = if initial_admin_email.is_empty() {
+            None
+        } else {
+            Some(InitialAdminConfig {
+                name: std::env::var("INITIAL_ADMIN_NAME")?,
+                email: initial_admin_email,
+                password: std::env::var("INITIAL_ADMIN_PASSWORD")?,
+            })
+        };
+        if !auth.password_login_enabled && oidc.is_none() {
+            anyhow::bail!("either PASSWORD_LOGIN_ENABLED or OIDC_ENABLED must be true");
+        }
         Ok(Self {
             database,
             redis,
             auth,
+            statistics,
+            checkout,
+            frontend,
+            signup,
+            mailer,
+            password_reset,
+            password_policy,
+            invitation,
+            login_throttle,
+            proxy,
+            two_factor,
+            oidc,
+            scim,
+            initial_admin,
         })
     }
 }
@@ -36,6 +197,8 @@ pub struct DatabaseConfig {
     pub username: String,
     pub password: String,
     pub database: String,
+    // シリアライズ失敗・デッドロック時にトランザクションをやり直す最大回数
+    pub max_transaction_retries: u32,
 }
 
 pub struct RedisConfig {
@@ -44,5 +207,147 @@ pub struct RedisConfig {
 }
 
 pub struct AuthConfig {
+    // アクセストークンの有効期間（秒）
     pub ttl: u64,
+    // リフレッシュトークンの有効期間（秒）。トークンを置き換えるたびに延長される
+    pub refresh_ttl: u64,
+    pub token_format: AccessTokenFormat,
+    // メールアドレスとパスワードでのログインを受け付けるかどうか
+    // シングルサインオンのみを使う場合は無効にする
+    pub password_login_enabled: bool,
+}
+
+pub enum AccessTokenFormat {
+    // ランダムな文字列を発行し、Redis でユーザーとの対応を管理する
+    Opaque,
+    // Ed25519 で署名した JWT を発行する
+    // keys_dir には `{kid}.pub.pem` と、署名に使う鍵の `{signing_key_id}.pem` を置く
+    Jwt {
+        keys_dir: String,
+        signing_key_id: String,
+        issuer: String,
+    },
+}
+
+pub struct StatisticsConfig {
+    pub cache_ttl: u64,
+}
+
+pub struct CheckoutConfig {
+    // 貸出日から返却期限までの日数
+    pub loan_period_days: i64,
+}
+
+pub struct FrontendConfig {
+    // メールやカレンダーに記載するリンクの生成に使う
+    pub base_url: String,
+}
+
+pub struct SignupConfig {
+    // 管理者以外によるユーザー登録を受け付けるかどうか
+    pub enabled: bool,
+    pub allowed_email_domains: Vec<String>,
+    // メールアドレス確認用トークンの有効期間（秒）。メールアドレスの変更の確認にも使う
+    pub verification_ttl: u64,
+}
+
+impl SignupConfig {
+    pub fn is_allowed_email(&self, email: &str) -> bool {
+        if self.allowed_email_domains.is_empty() {
+            return true;
+        }
+        email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_lowercase())
+            .is_some_and(|domain| self.allowed_email_domains.contains(&domain))
+    }
+}
+
+pub struct PasswordResetConfig {
+    // パスワード再設定用トークンの有効期間（秒）
+    pub ttl: u64,
+}
+
+pub struct PasswordPolicyConfig {
+    // 文字数で数える
+    pub min_length: usize,
+    // 漏えいしたパスワードや、よく使われるパスワードの一覧
+    pub common_passwords: Vec<String>,
+}
+
+pub struct InvitationConfig {
+    // 招待の有効期間（秒）。期限が切れた招待は再送できる
+    pub ttl: u64,
+}
+
+#[derive(Clone)]
+pub struct LoginThrottleConfig {
+    // 続けてこの回数だけ失敗すると、lockout 秒の間ログインできなくする
+    pub max_failures_per_email: u64,
+    pub max_failures_per_ip: u64,
+    // メールアドレスごとに、失敗するたびに次に試行できるまでの待ち時間（秒）を倍にしていく
+    pub backoff_base: u64,
+    // ロックする期間（秒）。失敗回数もこの期間が過ぎるとリセットされる
+    pub lockout: u64,
+}
+
+pub struct ProxyConfig {
+    // アプリケーションの手前にある、信頼できるリバースプロキシの段数。
+    // 0 の場合は X-Forwarded-For を使わず、接続元のアドレスをそのまま使う
+    pub trusted_hops: usize,
+}
+
+pub struct TwoFactorConfig {
+    // 認証アプリに表示されるサービス名
+    pub issuer: String,
+    // パスワードでの認証後、2 段階目の認証を待つ期間（秒）
+    pub challenge_ttl: u64,
+    // 管理者に TOTP の登録を必須とするかどうか
+    pub required_for_admin: bool,
+    // チャレンジをまたいで続けてこの回数だけコードを誤ると、lockout 秒の間 2 段階認証を行えなくする
+    pub max_failures: u64,
+    pub lockout: u64,
+}
+
+#[derive(Clone)]
+pub struct OidcConfig {
+    // ディスカバリー（/.well-known/openid-configuration）の取得に使う IdP の issuer
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    // IdP に登録したリダイレクト URI。フロントエンドで認可コードを受け取り、API に送る
+    pub redirect_uri: String,
+    // スペース区切りで指定する。openid は必須
+    pub scopes: String,
+    // ID トークンでグループの一覧を表すクレーム名
+    pub groups_claim: String,
+    pub group_roles: Vec<(String, String)>,
+    // IdP で認証されたユーザーが存在しない場合に、ユーザーを作成するかどうか
+    pub auto_provision: bool,
+    // 認可リクエストを開始してから、認可コードを受け取るまでの期間（秒）
+    pub state_ttl: u64,
+}
+
+pub struct ScimConfig {
+    // IdP の SCIM クライアントに設定する、SCIM 専用のベアラートークン
+    pub bearer_token: String,
+}
+
+pub struct InitialAdminConfig {
+    pub name: String,
+    pub email: String,
+    // 管理者がすでにいる場合は使われない。作成後は環境変数から取り除いてよい
+    pub password: String,
+}
+
+pub struct MailerConfig {
+    pub from: String,
+    pub transport: MailTransport,
+}
+
+pub enum MailTransport {
+    // 開発環境などで、送信するメールをファイルとして書き出す
+    File { dir: String },
+    // 認証なしで SMTP サーバーに送信する
+    Smtp { host: String, port: u16 },
 }
@@ -1,4 +1,7 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +11,8 @@ pub enum AppError {
     #[error("{0}")]
     EntityNotFound(String),
     #[error("{0}")]
+    EntityConflict(String),
+    #[error("{0}")]
     ValidationError(#[from] garde::Report),
     #[error("トランザクションを実行できませんでした。")]
     TransactionError(#[source] sqlx::Error),
@@ -19,6 +24,8 @@ pub enum AppError {
     KeyValuesStoreError(#[from] redis::RedisError),
     #[error("{0}")]
     BcryptError(#[from] bcrypt::BcryptError),
+    #[error("パスワードハッシュの処理中にエラーが発生しました: {0}")]
+    PasswordHashError(String),
     #[error("{0}")]
     ConvertToUuidError(#[from] uuid::Error),
     #[error("ログインに失敗しました")]
@@ -29,10 +36,29 @@ pub enum AppError {
     ForbiddenOperation,
     #[error("{0}")]
     ConversionEntityError(String),
+    #[error("他の処理と競合したため完了できませんでした。時間をおいて再度お試しください。")]
+    TransactionConflict,
+    #[error("メールを送信できませんでした: {0}")]
+    MailDeliveryError(String),
+    #[error("JWT の処理中にエラーが発生しました: {0}")]
+    JwtError(String),
+    #[error("OpenID Connect プロバイダーとの通信中にエラーが発生しました: {0}")]
+    OidcError(String),
+    #[error("試行回数が多すぎます。{0} 秒後に再度お試しください")]
+    TooManyRequests(u64),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
+        // 次に試行できるまでの秒数を Retry-After ヘッダで伝える
+        if let AppError::TooManyRequests(retry_after) = self {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+            )
+                .into_response();
+        }
+
         let status_code = match self {
             AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
@@ -41,12 +67,18 @@ impl IntoResponse for AppError {
             }
             AppError::UnauthenticatedError | AppError::ForbiddenOperation => StatusCode::FORBIDDEN,
             AppError::UnauthorizedError => StatusCode::UNAUTHORIZED,
+            AppError::EntityConflict(_) | AppError::TransactionConflict => StatusCode::CONFLICT,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
             e @ (AppError::TransactionError(_)
             | AppError::SpecificOperationError(_)
             | AppError::NoRowsAffectedError(_)
             | AppError::KeyValuesStoreError(_)
             | AppError::BcryptError(_)
-            | AppError::ConversionEntityError(_)) => {
+            | AppError::PasswordHashError(_)
+            | AppError::ConversionEntityError(_)
+            | AppError::MailDeliveryError(_)
+            | AppError::JwtError(_)
+            | AppError::OidcError(_)) => {
                 tracing::error!(
                     error.cause_chain = ?e,
                     error.message = %e,
@@ -4,11 +4,16 @@ use std::sync::Arc;
 use adapter::database::connect_database_with;
 use adapter::redis::RedisClient;
 use anyhow::Result;
-use api::route::{auth, v1};
-
-use axum::{http::Method, Router};
-use registry::AppRegistryImpl;
+use api::model::setup::SetupRequest;
+use api::route::{auth, scim, v1};
+
+use axum::{
+    http::{header, HeaderValue, Method},
+    Router,
+};
+use registry::{AppRegistry, AppRegistryExt, AppRegistryImpl};
 use shared::config::AppConfig;
+use shared::error::AppError;
 use tokio::net::TcpListener;
 
 use shared::env::{which, Environment};
@@ -32,11 +37,19 @@ use utoipa::OpenApi;
 use utoipa_redoc::{Redoc, Servable};
 
 // cors 関数を追加
-fn cors() -> CorsLayer {
-    CorsLayer::new()
-        .allow_headers(cors::Any)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_origin(cors::Any)
+// シングルサインオンが有効な場合は、ログインを開始したブラウザの確認に Cookie を使うため、
+// フロントエンドからの Cookie 付きのリクエストのみを許可する
+fn cors(app_config: &AppConfig) -> Result<CorsLayer> {
+    let layer =
+        CorsLayer::new().allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE]);
+    if app_config.oidc.is_none() {
+        return Ok(layer.allow_headers(cors::Any).allow_origin(cors::Any));
+    }
+    let origin = HeaderValue::from_str(app_config.frontend.base_url.trim_end_matches('/'))?;
+    Ok(layer
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .allow_origin(origin)
+        .allow_credentials(true))
 }
 
 #[tokio::main]
@@ -91,10 +104,15 @@ async fn bootstrap() -> Result<()> {
     let app_config = AppConfig::new()?;
     let pool = connect_database_with(&app_config.database);
     let kv = Arc::new(RedisClient::new(&app_config.redis)?);
+    let cors = cors(&app_config)?;
 
-    let registry = Arc::new(AppRegistryImpl::new(pool, kv, app_config));
+    let registry: AppRegistry = Arc::new(AppRegistryImpl::new(pool, kv, app_config)?);
+    create_initial_admin(&registry).await?;
 
-    let router = Router::new().merge(v1::routes()).merge(auth::routes());
+    let router = Router::new()
+        .merge(v1::routes())
+        .merge(auth::routes())
+        .merge(scim::routes());
     #[cfg(debug_assertions)]
     let router = router.merge(Redoc::with_url("/docs", ApiDoc::openapi()));
 
@@ -109,21 +127,77 @@ async fn bootstrap() -> Result<()> {
                         .latency_unit(LatencyUnit::Millis),
                 ),
         )
-        .layer(cors())
+        .layer(cors)
         .with_state(registry);
 
     let addr = SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 8080);
     let listener = TcpListener::bind(addr).await?;
     tracing::info!("Listening on {}", addr);
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .context("Unexpected error happened in server")
-        .inspect_err(|e| {
-            tracing::error!(
-                error.cause_chain = ?e,error.message = %e, "Unexpected error"
-            )
-        })
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .context("Unexpected error happened in server")
+    .inspect_err(|e| {
+        tracing::error!(
+            error.cause_chain = ?e,error.message = %e, "Unexpected error"
+        )
+    })
+}
+
+// 環境変数で最初の管理者が指定されている場合に、起動時に作成する
+// 自動でデプロイする環境で使う。管理者がすでにいる場合は何もしない
+async fn create_initial_admin(registry: &AppRegistry) -> Result<()> {
+    // 複数のインスタンスが同時に起動した場合に、競合したインスタンスが確認からやり直す回数
+    const MAX_ATTEMPTS: u32 = 3;
+
+    let config = registry.app_config();
+    let Some(initial_admin) = &config.initial_admin else {
+        return Ok(());
+    };
+    if !registry.setup_repository().is_required().await? {
+        return Ok(());
+    }
+
+    let req = SetupRequest {
+        name: initial_admin.name.clone(),
+        email: initial_admin.email.clone(),
+        password: initial_admin.password.clone(),
+    };
+    req.validate_with(registry.password_policy())
+        .context("INITIAL_ADMIN_* is invalid")?;
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match registry
+            .setup_repository()
+            .create_initial_admin(req.clone().into())
+            .await
+        {
+            Ok(admin) => {
+                tracing::info!(user_id = %admin.id, "Initial admin has been created");
+                return Ok(());
+            }
+            // 他のインスタンスが先に作成した場合や、シリアライズ失敗のやり直しが上限に達した場合は、
+            // 管理者がいるかどうかを確認し、いなければ作成からやり直す
+            Err(e @ (AppError::EntityConflict(_) | AppError::TransactionConflict)) => {
+                if !registry.setup_repository().is_required().await? {
+                    tracing::info!(
+                        error.message = %e,
+                        "Initial admin has already been created by another instance"
+                    );
+                    return Ok(());
+                }
+                if attempts >= MAX_ATTEMPTS {
+                    return Err(e.into());
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
 }
 
 async fn shutdown_signal() {